@@ -6,17 +6,25 @@
 use std::path::Path;
 use std::time::Instant;
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use owo_colors::{OwoColorize, Stream};
 use tracing::info;
 
-use syslua_lib::execute::{ApplyOptions, ExecuteConfig, apply};
+use tokio::runtime::Runtime;
+use tokio::task::JoinHandle;
 
+use syslua_lib::execute::{
+  ApplyError, ApplyOptions, ApplyResult, DagResult, EventSender, ExecuteConfig, FailedDependency, apply, apply_plan,
+  event_channel,
+};
+
+use crate::events::{combine, events_file_sender};
 use crate::output::{
   OutputFormat, format_duration, print_error, print_info, print_json, print_stat, print_success, print_warning,
-  symbols, truncate_hash,
+  render_progress, symbols, truncate_hash,
 };
 use syslua_lib::platform::paths;
+use syslua_lib::util::hash::ObjectHash;
 
 /// Execute the apply command.
 ///
@@ -29,23 +37,122 @@ use syslua_lib::platform::paths;
 /// - Saves new snapshot
 ///
 /// Prints a summary including counts of builds realized, binds applied/destroyed, and the snapshot ID.
-pub fn cmd_apply(file: &str, repair: bool, impure: bool, output: OutputFormat) -> Result<()> {
+/// With `keep_going`, independent builds and binds still run after a failure
+/// and every failure is listed. With `events_file`, lifecycle events are also
+/// written there as JSON lines.
+pub fn cmd_apply(
+  file: &str,
+  repair: bool,
+  impure: bool,
+  keep_going: bool,
+  events_file: Option<&Path>,
+  output: OutputFormat,
+) -> Result<()> {
   let start = Instant::now();
   let path = Path::new(file);
 
+  let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+  let (events, progress) = start_progress(&rt, output);
+  let events = combine(events, events_file_sender(events_file)?);
+
   let options = ApplyOptions {
-    execute: ExecuteConfig::default(),
+    execute: ExecuteConfig {
+      keep_going,
+      events,
+      ..ExecuteConfig::default()
+    },
     dry_run: false,
     repair,
     impure,
+    force: false,
   };
 
   // Run async apply
+  let result = rt.block_on(apply(path, &options));
+  drop(options);
+  finish_progress(&rt, progress);
+  let result = check_apply(result, output)?;
+
+  print_apply_result(&result, repair, start, output)?;
+
+  // Print plan directory
+  let snapshot_path = paths::snapshots_dir().join(format!("{}.json", result.snapshot.id));
+  info!(path = %snapshot_path.display(), "snapshot saved");
+
+  Ok(())
+}
+
+/// Execute the apply command against a saved plan.
+///
+/// Loads the manifest written by `sys plan` (by hash or path), verifies it still
+/// hashes to the plan hash, and applies it without evaluating any Lua. With
+/// `force`, the plan is applied even if the current snapshot changed since it
+/// was made.
+pub fn cmd_apply_plan(
+  plan: &str,
+  repair: bool,
+  force: bool,
+  keep_going: bool,
+  events_file: Option<&Path>,
+  output: OutputFormat,
+) -> Result<()> {
+  let start = Instant::now();
+
   let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
-  let result = rt.block_on(apply(path, &options)).context("Apply failed")?;
+  let (events, progress) = start_progress(&rt, output);
+  let events = combine(events, events_file_sender(events_file)?);
+
+  let options = ApplyOptions {
+    execute: ExecuteConfig {
+      keep_going,
+      events,
+      ..ExecuteConfig::default()
+    },
+    dry_run: false,
+    repair,
+    impure: false,
+    force,
+  };
+
+  let result = rt.block_on(apply_plan(plan, &options));
+  drop(options);
+  finish_progress(&rt, progress);
+  let result = check_apply(result, output)?;
+
+  print_apply_result(&result, repair, start, output)?;
+
+  let snapshot_path = paths::snapshots_dir().join(format!("{}.json", result.snapshot.id));
+  info!(path = %snapshot_path.display(), "snapshot saved");
+
+  Ok(())
+}
 
+/// Start rendering execution progress, unless the output is JSON.
+///
+/// # Returns
+///
+/// The sender to put in [`ExecuteConfig::events`] and the renderer task.
+fn start_progress(rt: &Runtime, output: OutputFormat) -> (Option<EventSender>, Option<JoinHandle<()>>) {
   if output.is_json() {
-    print_json(&result)?;
+    return (None, None);
+  }
+  let (tx, rx) = event_channel();
+  (Some(tx), Some(rt.spawn(render_progress(rx))))
+}
+
+/// Wait for the renderer to drain its events and clear the progress lines.
+///
+/// Every sender must have been dropped, or this never returns.
+fn finish_progress(rt: &Runtime, progress: Option<JoinHandle<()>>) {
+  if let Some(progress) = progress {
+    let _ = rt.block_on(progress);
+  }
+}
+
+/// Print the summary of an apply, including drift and failure details.
+fn print_apply_result(result: &ApplyResult, repair: bool, start: Instant, output: OutputFormat) -> Result<()> {
+  if output.is_json() {
+    print_json(result)?;
   } else {
     println!();
     print_success("Apply complete!");
@@ -87,18 +194,71 @@ pub fn cmd_apply(file: &str, repair: bool, impure: bool, output: OutputFormat) -
     }
 
     if !result.execution.is_success() {
-      if let Some((hash, ref err)) = result.execution.build_failed {
-        print_error(&format!("Build failed: {} - {}", truncate_hash(&hash.0), err));
-      }
-      if let Some((hash, ref err)) = result.execution.bind_failed {
-        print_error(&format!("Bind failed: {} - {}", truncate_hash(&hash.0), err));
+      print_execution_failures(&result.execution);
+    }
+  }
+
+  Ok(())
+}
+
+/// Report every failed build and bind before turning a failed execution into an error.
+fn check_apply(result: Result<ApplyResult, ApplyError>, output: OutputFormat) -> Result<ApplyResult> {
+  match result {
+    Err(ApplyError::ExecutionFailed(execution)) => {
+      if output.is_json() {
+        print_json(&execution)?;
+      } else {
+        print_execution_failures(&execution);
       }
+      bail!(
+        "Apply failed: {} build(s) and {} bind(s) failed",
+        execution.build_failed.len(),
+        execution.bind_failed.len()
+      );
     }
+    other => other.context("Apply failed"),
   }
+}
 
-  // Print plan directory
-  let snapshot_path = paths::snapshots_dir().join(format!("{}.json", result.snapshot.id));
-  info!(path = %snapshot_path.display(), "snapshot saved");
+/// Print each failed build and bind with its error and the nodes it caused to be skipped.
+fn print_execution_failures(execution: &DagResult) {
+  let builds = execution
+    .build_failed
+    .iter()
+    .map(|(hash, err)| (FailedDependency::Build(hash.clone()), err));
+  let binds = execution
+    .bind_failed
+    .iter()
+    .map(|(hash, err)| (FailedDependency::Bind(hash.clone()), err));
+  let mut failures: Vec<_> = builds.chain(binds).collect();
+  failures.sort_by_key(|(node, _)| node.to_string());
 
-  Ok(())
+  eprintln!();
+  for (node, err) in &failures {
+    let (kind, hash) = describe(node);
+    print_error(&format!("Failed {} {}: {}", kind, truncate_hash(&hash.0), err));
+    for skipped in execution.skipped_by(node) {
+      let (kind, hash) = describe(&skipped);
+      eprintln!(
+        "    {} skipped {} {}",
+        symbols::MINUS.if_supports_color(Stream::Stderr, |s| s.dimmed()),
+        kind,
+        truncate_hash(&hash.0)
+      );
+    }
+  }
+  if !failures.is_empty() {
+    eprintln!(
+      "\n{}",
+      "Run 'sys log <hash>' to see the full output of a failed build or bind."
+        .if_supports_color(Stream::Stderr, |s| s.dimmed())
+    );
+  }
+}
+
+fn describe(node: &FailedDependency) -> (&'static str, &ObjectHash) {
+  match node {
+    FailedDependency::Build(hash) => ("build", hash),
+    FailedDependency::Bind(hash) => ("bind", hash),
+  }
 }
@@ -0,0 +1,107 @@
+//! Implementation of the `sys build` command.
+//!
+//! This command realizes builds from a config into the store without applying
+//! any binds or recording a snapshot.
+
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use owo_colors::OwoColorize;
+use serde_json::Value as JsonValue;
+
+use syslua_lib::execute::{BuildFailure, BuildSelector, ExecuteConfig, RealizeError, RealizeOptions, realize};
+
+use crate::output::{
+  OutputFormat, format_duration, print_error, print_json, print_stat, print_success, symbols, truncate_hash,
+};
+
+/// Execute the build command.
+///
+/// Evaluates the config and realizes the build selected by `id` or
+/// `hash_prefix` (or every build if neither is given), together with its
+/// dependencies. The resolved outputs of the selected builds are printed.
+///
+/// With `rebuild`, the selected builds are redone even if a completed output
+/// already exists in the store. With `keep_going`, builds that do not depend
+/// on a failed one are still realized and every failure is listed.
+pub fn cmd_build(
+  file: &str,
+  id: Option<String>,
+  hash_prefix: Option<String>,
+  rebuild: bool,
+  impure: bool,
+  keep_going: bool,
+  output: OutputFormat,
+) -> Result<()> {
+  let start = Instant::now();
+
+  let selector = match (id, hash_prefix) {
+    (Some(id), _) => Some(BuildSelector::Id(id)),
+    (None, Some(prefix)) => Some(BuildSelector::HashPrefix(prefix)),
+    (None, None) => None,
+  };
+
+  let options = RealizeOptions {
+    execute: ExecuteConfig {
+      keep_going,
+      ..ExecuteConfig::default()
+    },
+    impure,
+    selector,
+    rebuild,
+  };
+
+  let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+  let result = match rt.block_on(realize(Path::new(file), &options)) {
+    Err(RealizeError::BuildsFailed(failures)) => {
+      if output.is_json() {
+        print_json(&failures)?;
+      } else {
+        print_failures(&failures);
+      }
+      bail!("Build failed: {} build(s) failed", failures.len());
+    }
+    other => other.context("Build failed")?,
+  };
+
+  if output.is_json() {
+    print_json(&result)?;
+    return Ok(());
+  }
+
+  println!();
+  print_success(&format!("Built {} build(s)", result.builds.len()));
+  for build in &result.builds {
+    let name = build.id.as_deref().unwrap_or_else(|| truncate_hash(&build.hash.0));
+    println!();
+    println!("{} {}", name.bold(), truncate_hash(&build.hash.0).dimmed());
+    print_stat("Store path", &build.store_path.display().to_string());
+
+    let mut outputs: Vec<_> = build.outputs.iter().collect();
+    outputs.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, value) in outputs {
+      match value {
+        JsonValue::String(s) => print_stat(name, s),
+        other => print_stat(name, &other.to_string()),
+      }
+    }
+  }
+  println!();
+  print_stat("Dependencies", &result.dependencies.to_string());
+  print_stat("Duration", &format_duration(start.elapsed()));
+
+  Ok(())
+}
+
+/// Print each failed build with its error and the builds it caused to be skipped.
+fn print_failures(failures: &[BuildFailure]) {
+  eprintln!();
+  for failure in failures {
+    let name = failure.id.as_deref().unwrap_or_else(|| truncate_hash(&failure.hash.0));
+    print_error(&format!("Failed {}: {}", name, failure.error));
+    for hash in &failure.skipped {
+      eprintln!("    {} skipped {}", symbols::MINUS.dimmed(), truncate_hash(&hash.0));
+    }
+  }
+}
@@ -3,6 +3,7 @@
 //! This command destroys all binds from the current snapshot, effectively
 //! removing everything syslua has applied.
 
+use std::path::Path;
 use std::time::Instant;
 
 use anyhow::{Context, Result};
@@ -12,6 +13,7 @@ use tracing::info;
 use syslua_lib::execute::{DestroyOptions, ExecuteConfig, destroy};
 use syslua_lib::platform::paths::{data_dir, store_dir};
 
+use crate::events::events_file_sender;
 use crate::output::{OutputFormat, format_duration, print_json, print_stat, symbols};
 
 /// Execute the destroy command.
@@ -23,7 +25,8 @@ use crate::output::{OutputFormat, format_duration, print_json, print_stat, symbo
 /// - Clears the current snapshot pointer
 ///
 /// Prints a summary including counts of binds destroyed and builds orphaned.
-pub fn cmd_destroy(dry_run: bool, output: OutputFormat) -> Result<()> {
+/// With `events_file`, every destroyed bind is also reported there as JSON lines.
+pub fn cmd_destroy(dry_run: bool, events_file: Option<&Path>, output: OutputFormat) -> Result<()> {
   let start = Instant::now();
 
   // Log environment info for debugging
@@ -46,7 +49,10 @@ pub fn cmd_destroy(dry_run: bool, output: OutputFormat) -> Result<()> {
   }
 
   let options = DestroyOptions {
-    execute: ExecuteConfig::default(),
+    execute: ExecuteConfig {
+      events: events_file_sender(events_file)?,
+      ..ExecuteConfig::default()
+    },
     dry_run,
   };
 
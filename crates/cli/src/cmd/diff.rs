@@ -300,10 +300,50 @@ fn print_actions(label: &str, actions: &[Action]) {
 fn format_action(action: &Action) -> String {
   match action {
     Action::Exec(opts) => format_exec(opts),
-    Action::FetchUrl { url, sha256 } => {
-      let short_sha = truncate_hash(sha256);
-      format!("fetch_url: {} (sha256: {}...)", url, short_sha)
+    Action::FetchUrl(opts) => {
+      let short_hash = truncate_hash(&opts.hash);
+      let mut line = format!("fetch_url: {} (hash: {}...)", opts.url, short_hash);
+      if !opts.mirrors.is_empty() {
+        line.push_str(&format!(" +{} mirrors", opts.mirrors.len()));
+      }
+      line
+    }
+    Action::FetchGit { url, rev, sha256 } => {
+      format!(
+        "fetch_git: {} @ {} (sha256: {}...)",
+        url,
+        truncate_hash(rev),
+        truncate_hash(sha256)
+      )
+    }
+    Action::Unpack {
+      archive,
+      format,
+      strip_components,
+    } => {
+      let mut line = format!("unpack: {}", archive);
+      if let Some(format) = format {
+        line.push_str(&format!(" ({})", format.as_str()));
+      }
+      if *strip_components > 0 {
+        line.push_str(&format!(" --strip-components={}", strip_components));
+      }
+      line
+    }
+    Action::WriteFile { path, content, mode } => {
+      let mut line = format!("write_file: {} ({} bytes)", path, content.len());
+      if let Some(mode) = mode {
+        line.push_str(&format!(" mode {:o}", mode));
+      }
+      line
     }
+    Action::Copy { src, dest } => format!("copy: {} -> {}", src, dest),
+    Action::Symlink { target, link } => format!("symlink: {} -> {}", link, target),
+    Action::Mkdir { path, mode: Some(mode) } => format!("mkdir: {} mode {:o}", path, mode),
+    Action::Mkdir { path, mode: None } => format!("mkdir: {}", path),
+    Action::Chmod { path, mode } => format!("chmod: {} {:o}", path, mode),
+    Action::Remove { path } => format!("remove: {}", path),
+    Action::Source { name, sha256 } => format!("source: {} (sha256: {}...)", name, truncate_hash(sha256)),
   }
 }
 
@@ -0,0 +1,137 @@
+//! Implementation of the `sys drift` command.
+//!
+//! Runs the `check` callback of every bind in the current snapshot without
+//! evaluating the config, planning or applying anything. Drift is reported
+//! through a dedicated exit code so cron jobs and monitoring can alert on it.
+
+use std::fmt;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use syslua_lib::execute::{ExecuteConfig, check_unchanged_binds};
+use syslua_lib::snapshot::SnapshotStore;
+use syslua_lib::util::hash::ObjectHash;
+
+use crate::output::{OutputFormat, format_duration, print_info, print_json, print_stat, print_success, symbols};
+
+/// Exit code used when at least one bind has drifted.
+pub const DRIFT_EXIT_CODE: u8 = 2;
+
+/// Returned by [`cmd_drift`] when drift was found.
+///
+/// The report has already been printed; `main` maps this to
+/// [`DRIFT_EXIT_CODE`] instead of a generic failure.
+#[derive(Debug)]
+pub struct DriftDetected(pub usize);
+
+impl fmt::Display for DriftDetected {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} bind(s) drifted", self.0)
+  }
+}
+
+impl std::error::Error for DriftDetected {}
+
+#[derive(Serialize)]
+struct DriftEntry<'a> {
+  hash: &'a ObjectHash,
+  id: Option<&'a str>,
+  drifted: bool,
+  message: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct DriftReport<'a> {
+  snapshot_id: &'a str,
+  checked: usize,
+  drifted: usize,
+  binds: Vec<DriftEntry<'a>>,
+}
+
+/// Execute the drift command.
+///
+/// # Arguments
+///
+/// * `bind` - Only check the bind with this ID
+/// * `output` - Output format
+///
+/// # Returns
+///
+/// [`DriftDetected`] if any checked bind has drifted.
+pub fn cmd_drift(bind: Option<&str>, output: OutputFormat) -> Result<()> {
+  let start = Instant::now();
+
+  let snapshot = SnapshotStore::default_store()
+    .load_current()
+    .context("Failed to load current snapshot")?
+    .context("No current snapshot. Run 'sys apply' first.")?;
+  let manifest = &snapshot.manifest;
+
+  let hashes: Vec<ObjectHash> = manifest
+    .bindings
+    .iter()
+    .filter(|(_, def)| bind.is_none_or(|id| def.id.as_deref() == Some(id)))
+    .map(|(hash, _)| hash.clone())
+    .collect();
+  if let Some(id) = bind
+    && hashes.is_empty()
+  {
+    bail!("No bind with id '{}' in the current snapshot", id);
+  }
+
+  let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+  let results = rt
+    .block_on(check_unchanged_binds(&hashes, manifest, &ExecuteConfig::default()))
+    .context("Failed to check for drift")?;
+  let drifted = results.iter().filter(|r| r.result.drifted).count();
+
+  if output.is_json() {
+    let report = DriftReport {
+      snapshot_id: &snapshot.id,
+      checked: results.len(),
+      drifted,
+      binds: results
+        .iter()
+        .map(|r| DriftEntry {
+          hash: &r.hash,
+          id: r.id.as_deref(),
+          drifted: r.result.drifted,
+          message: r.result.message.as_deref(),
+        })
+        .collect(),
+    };
+    print_json(&report)?;
+  } else if results.is_empty() {
+    print_info("No binds with check callbacks to run");
+  } else {
+    if drifted == 0 {
+      print_success("No drift detected");
+    } else {
+      println!(
+        "{} {}",
+        symbols::WARNING.yellow(),
+        format!("Drift detected: {} bind(s)", drifted).yellow()
+      );
+      for result in results.iter().filter(|r| r.result.drifted) {
+        let id = result.id.as_deref().unwrap_or(&result.hash.0);
+        match result.result.message {
+          Some(ref msg) => println!("  {} {}: {}", symbols::MODIFY.yellow(), id, msg.dimmed()),
+          None => println!("  {} {}", symbols::MODIFY.yellow(), id),
+        }
+      }
+    }
+    println!();
+    print_stat("Snapshot", &snapshot.id);
+    print_stat("Binds checked", &results.len().to_string());
+    print_stat("Drifted", &drifted.to_string());
+    print_stat("Duration", &format_duration(start.elapsed()));
+  }
+
+  if drifted > 0 {
+    return Err(DriftDetected(drifted).into());
+  }
+  Ok(())
+}
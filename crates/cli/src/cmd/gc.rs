@@ -25,6 +25,8 @@ pub fn cmd_gc(dry_run: bool, output: OutputFormat) -> Result<()> {
     }
     print_stat("Builds removed", &result.stats.builds_deleted.to_string());
     print_stat("Inputs removed", &result.stats.inputs_deleted.to_string());
+    print_stat("Sources removed", &result.stats.sources_deleted.to_string());
+    print_stat("Downloads removed", &result.stats.downloads_deleted.to_string());
     print_stat("Space freed", &format_bytes(result.stats.total_bytes_freed()));
     print_stat("Duration", &format_duration(start.elapsed()));
   }
@@ -0,0 +1,70 @@
+//! Implementation of the `sys graph` command.
+//!
+//! This command exports the build/bind dependency graph of a config or a
+//! snapshot, with each node coloured by what applying it would do.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use syslua_lib::eval::{EvalOptions, evaluate_config};
+use syslua_lib::execute::DependencyGraph;
+use syslua_lib::platform::paths::store_dir;
+use syslua_lib::snapshot::{SnapshotStore, compute_diff};
+
+use crate::output::print_json;
+
+/// Graph output format.
+#[derive(Debug, Clone, Copy, Default, clap::ValueEnum)]
+pub enum GraphFormat {
+  /// Graphviz DOT (default)
+  #[default]
+  Dot,
+  /// Mermaid flowchart
+  Mermaid,
+  /// JSON nodes and edges
+  Json,
+}
+
+/// Execute the graph command.
+///
+/// Evaluates `file` (or loads the snapshot `snapshot`, by ID or tag) and
+/// prints its dependency graph. Nodes are categorised by the diff against the
+/// current snapshot, as `sys plan` would report it.
+pub fn cmd_graph(file: Option<&str>, snapshot: Option<&str>, impure: bool, format: GraphFormat) -> Result<()> {
+  let snapshot_store = SnapshotStore::default_store();
+  let current = snapshot_store
+    .load_current()
+    .context("Failed to load current snapshot")?;
+  let current_manifest = current.as_ref().map(|s| &s.manifest);
+
+  let manifest = match (file, snapshot) {
+    (_, Some(target)) => {
+      snapshot_store
+        .resolve_snapshot(target)
+        .with_context(|| format!("Failed to load snapshot: {}", target))?
+        .manifest
+    }
+    (Some(file), None) => evaluate_config(
+      Path::new(file),
+      &EvalOptions {
+        impure,
+        ..EvalOptions::default()
+      },
+    )
+    .with_context(|| format!("Failed to evaluate config: {}", file))?,
+    (None, None) => anyhow::bail!("either a config file or --snapshot is required"),
+  };
+
+  let diff = compute_diff(&manifest, current_manifest, &store_dir());
+  let graph =
+    DependencyGraph::from_manifest(&manifest, current_manifest, &diff).context("Failed to build dependency graph")?;
+
+  match format {
+    GraphFormat::Dot => print!("{}", graph.to_dot()),
+    GraphFormat::Mermaid => print!("{}", graph.to_mermaid()),
+    GraphFormat::Json => print_json(&graph)?,
+  }
+
+  Ok(())
+}
@@ -0,0 +1,83 @@
+//! Implementation of the `sys key` commands.
+//!
+//! Keys sign builds pushed to a binary cache. Machines that substitute from
+//! the cache list the public key in their trusted-keys file.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use serde::Serialize;
+
+use syslua_lib::cache::SecretKey;
+use syslua_lib::platform::paths::{keys_dir, trusted_keys_path};
+
+use crate::output::{OutputFormat, print_info, print_json, print_stat, print_success};
+
+#[derive(Subcommand, Debug)]
+pub enum KeyCommand {
+  /// Generate a new signing key pair
+  Generate {
+    /// Key name, recorded in every signature (e.g. "cache.example.com-1")
+    name: String,
+
+    /// Directory to write `<name>.secret` and `<name>.public` to
+    #[arg(long, value_name = "DIR")]
+    out_dir: Option<String>,
+
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+}
+
+#[derive(Debug, Serialize)]
+struct GenerateResult {
+  name: String,
+  public_key: String,
+  secret_key_file: PathBuf,
+  public_key_file: PathBuf,
+}
+
+pub fn cmd_key(command: KeyCommand) -> Result<()> {
+  match command {
+    KeyCommand::Generate { name, out_dir, output } => cmd_generate(&name, out_dir, output),
+  }
+}
+
+fn cmd_generate(name: &str, out_dir: Option<String>, output: OutputFormat) -> Result<()> {
+  let dir = out_dir.map(PathBuf::from).unwrap_or_else(keys_dir);
+  let secret_path = dir.join(format!("{}.secret", name));
+  let public_path = dir.join(format!("{}.public", name));
+  if secret_path.exists() {
+    bail!("{} already exists", secret_path.display());
+  }
+
+  let key = SecretKey::generate(name).context("Failed to generate key")?;
+  std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+  key.write(&secret_path).context("Failed to write secret key")?;
+  let public_key = key.public_key().to_string();
+  std::fs::write(&public_path, format!("{}\n", public_key)).context("Failed to write public key")?;
+
+  if output.is_json() {
+    print_json(&GenerateResult {
+      name: name.to_string(),
+      public_key,
+      secret_key_file: secret_path,
+      public_key_file: public_path,
+    })?;
+  } else {
+    print_success(&format!("Generated key '{}'", name));
+    print_stat("Secret key", &secret_path.display().to_string());
+    print_stat("Public key", &public_path.display().to_string());
+    println!();
+    println!("{}", public_key);
+    println!();
+    print_info(&format!(
+      "Add the public key to {} on machines that should trust this key",
+      trusted_keys_path().display()
+    ));
+  }
+
+  Ok(())
+}
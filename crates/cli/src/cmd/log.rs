@@ -0,0 +1,129 @@
+//! Implementation of the `sys log` command.
+//!
+//! Prints the logs that builds and binds leave in the store, one per action.
+//! The target is an ID from the current snapshot or a hash prefix, which also
+//! finds builds that failed and never made it into a snapshot.
+
+use std::fs;
+
+use anyhow::{Context, Result, bail};
+use owo_colors::OwoColorize;
+use serde::Serialize;
+
+use syslua_lib::action::log::{list_action_logs, logged_hashes};
+use syslua_lib::snapshot::SnapshotStore;
+use syslua_lib::util::hash::ObjectHash;
+
+use crate::output::{OutputFormat, print_json, truncate_hash};
+
+#[derive(Serialize)]
+struct LogEntry {
+  phase: &'static str,
+  action: usize,
+  path: String,
+  content: String,
+}
+
+#[derive(Serialize)]
+struct LogReport {
+  hash: ObjectHash,
+  logs: Vec<LogEntry>,
+}
+
+/// Execute the log command.
+///
+/// # Arguments
+///
+/// * `target` - ID of a build or bind in the current snapshot, or a hash prefix
+/// * `action` - Only print the log of the action with this index
+/// * `output` - Output format
+pub fn cmd_log(target: &str, action: Option<usize>, output: OutputFormat) -> Result<()> {
+  let hash = resolve_target(target)?;
+
+  let mut logs = list_action_logs(&hash).context("Failed to read action logs")?;
+  if let Some(index) = action {
+    logs.retain(|log| log.index == index);
+    if logs.is_empty() {
+      bail!("No log for action {} of {}", index, hash.0);
+    }
+  }
+
+  let mut entries = Vec::with_capacity(logs.len());
+  for log in logs {
+    let content = fs::read_to_string(&log.path).with_context(|| format!("Failed to read {}", log.path.display()))?;
+    entries.push(LogEntry {
+      phase: log.phase.as_str(),
+      action: log.index,
+      path: log.path.display().to_string(),
+      content,
+    });
+  }
+
+  if output.is_json() {
+    print_json(&LogReport { hash, logs: entries })?;
+    return Ok(());
+  }
+
+  for (i, entry) in entries.iter().enumerate() {
+    if i > 0 {
+      println!();
+    }
+    println!(
+      "{} {}",
+      format!("==> {} action {}", entry.phase, entry.action).bold(),
+      entry.path.dimmed()
+    );
+    print!("{}", entry.content);
+  }
+  Ok(())
+}
+
+/// Find the build or bind `target` refers to.
+///
+/// IDs are looked up in the current snapshot; anything else is matched as a
+/// prefix against the hashes that have logs.
+fn resolve_target(target: &str) -> Result<ObjectHash> {
+  let snapshot = SnapshotStore::default_store()
+    .load_current()
+    .context("Failed to load current snapshot")?;
+  if let Some(snapshot) = snapshot {
+    let manifest = &snapshot.manifest;
+    let by_id: Vec<&ObjectHash> = manifest
+      .builds
+      .iter()
+      .filter(|(_, def)| def.id.as_deref() == Some(target))
+      .map(|(hash, _)| hash)
+      .chain(
+        manifest
+          .bindings
+          .iter()
+          .filter(|(_, def)| def.id.as_deref() == Some(target))
+          .map(|(hash, _)| hash),
+      )
+      .collect();
+    match by_id.as_slice() {
+      [hash] => return Ok((*hash).clone()),
+      [] => {}
+      _ => bail!("'{}' matches more than one build or bind; pass a hash instead", target),
+    }
+  }
+
+  let matches: Vec<ObjectHash> = logged_hashes()
+    .context("Failed to read action logs")?
+    .into_iter()
+    .filter(|hash| hash.0.starts_with(target))
+    .collect();
+  match matches.as_slice() {
+    [hash] => Ok(hash.clone()),
+    [] => bail!("No logs for '{}'", target),
+    _ => bail!(
+      "'{}' is ambiguous: {}",
+      target,
+      matches
+        .iter()
+        .map(|hash| truncate_hash(&hash.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+    ),
+  }
+}
@@ -3,32 +3,62 @@
 //! Each submodule implements a single CLI command:
 //!
 //! - [`apply`] - Evaluate config and apply changes to the system
+//! - [`build`] - Realize builds from a config without applying binds
 //! - [`destroy`] - Remove all managed binds from the system
 //! - [`diff`] - Show differences between snapshots
+//! - [`drift`] - Run bind checks against the current snapshot
+//! - [`graph`] - Export the build/bind dependency graph
 //! - [`info`] - Display information about builds, binds, or inputs
 //! - [`init`] - Initialize a new syslua configuration
+//! - [`key`] - Generate keys for signing cached builds
+//! - [`log`] - Show the logged output of build and bind actions
 //! - [`plan`] - Show what changes would be made without applying
+//! - [`repl`] - Interactive Lua session with the config's inputs
+//! - [`rollback`] - Return the system to a previous snapshot
+//! - [`shell`] - Start a shell with build outputs on PATH
 //! - [`status`] - Show current system state vs expected state
+//! - [`store`] - Verify the store and push builds to a binary cache
 //! - [`update`] - Update input locks to latest versions
+//! - [`why`] - Explain why a build or bind is part of a config
 
 mod apply;
+mod build;
 mod destroy;
 mod diff;
+mod drift;
 mod gc;
+mod graph;
 mod info;
 mod init;
+pub mod key;
+mod log;
 mod plan;
+mod repl;
+mod rollback;
+mod shell;
 pub mod snapshot;
 mod status;
+pub mod store;
 mod update;
+mod why;
 
-pub use apply::cmd_apply;
+pub use apply::{cmd_apply, cmd_apply_plan};
+pub use build::cmd_build;
 pub use destroy::cmd_destroy;
 pub use diff::cmd_diff;
+pub use drift::{DRIFT_EXIT_CODE, DriftDetected, cmd_drift};
 pub use gc::cmd_gc;
+pub use graph::{GraphFormat, cmd_graph};
 pub use info::cmd_info;
 pub use init::cmd_init;
+pub use key::cmd_key;
+pub use log::cmd_log;
 pub use plan::cmd_plan;
+pub use repl::cmd_repl;
+pub use rollback::cmd_rollback;
+pub use shell::cmd_shell;
 pub use snapshot::cmd_snapshot;
 pub use status::cmd_status;
+pub use store::cmd_store;
 pub use update::cmd_update;
+pub use why::cmd_why;
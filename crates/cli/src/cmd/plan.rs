@@ -12,17 +12,22 @@ use owo_colors::OwoColorize;
 
 use syslua_lib::eval::{EvalOptions, evaluate_config};
 
+use crate::events::events_file_sender;
 use crate::output::{OutputFormat, format_duration, print_json, print_stat, symbols, truncate_hash};
-use syslua_lib::execute::{ExecuteConfig, check_unchanged_binds};
+use syslua_lib::execute::{ExecuteConfig, PLAN_INFO_FILENAME, PLAN_MANIFEST_FILENAME, PlanInfo, check_unchanged_binds};
 use syslua_lib::platform::paths::{plans_dir, store_dir};
 use syslua_lib::snapshot::{SnapshotStore, compute_diff};
 use syslua_lib::util::hash::Hashable;
 
-pub fn cmd_plan(file: &str, impure: bool, output: OutputFormat) -> Result<()> {
+pub fn cmd_plan(file: &str, impure: bool, events_file: Option<&Path>, output: OutputFormat) -> Result<()> {
   let start = Instant::now();
   let path = Path::new(file);
+  let events = events_file_sender(events_file)?;
 
-  let eval_options = EvalOptions { impure };
+  let eval_options = EvalOptions {
+    impure,
+    events: events.clone(),
+  };
   let manifest =
     evaluate_config(path, &eval_options).with_context(|| format!("Failed to evaluate config: {}", file))?;
 
@@ -31,7 +36,7 @@ pub fn cmd_plan(file: &str, impure: bool, output: OutputFormat) -> Result<()> {
   let plan_dir = plans_dir().join(&hash.0);
   fs::create_dir_all(&plan_dir).with_context(|| format!("Failed to create plan directory: {}", plan_dir.display()))?;
 
-  let manifest_path = plan_dir.join("manifest.json");
+  let manifest_path = plan_dir.join(PLAN_MANIFEST_FILENAME);
   let manifest_json = serde_json::to_string_pretty(&manifest).context("Failed to serialize manifest")?;
   fs::write(&manifest_path, &manifest_json)
     .with_context(|| format!("Failed to write manifest: {}", manifest_path.display()))?;
@@ -40,6 +45,16 @@ pub fn cmd_plan(file: &str, impure: bool, output: OutputFormat) -> Result<()> {
   let current_snapshot = snapshot_store
     .load_current()
     .context("Failed to load current snapshot")?;
+
+  // Applying the plan refuses to run once the current snapshot has moved on
+  let info = PlanInfo {
+    hash: hash.clone(),
+    config_path: Some(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())),
+    base_snapshot: current_snapshot.as_ref().map(|s| s.id.clone()),
+  };
+  let info_path = plan_dir.join(PLAN_INFO_FILENAME);
+  let info_json = serde_json::to_string_pretty(&info).context("Failed to serialize plan info")?;
+  fs::write(&info_path, &info_json).with_context(|| format!("Failed to write plan info: {}", info_path.display()))?;
   let current_manifest = current_snapshot.as_ref().map(|s| &s.manifest);
 
   let store_path = store_dir();
@@ -49,7 +64,10 @@ pub fn cmd_plan(file: &str, impure: bool, output: OutputFormat) -> Result<()> {
     // For JSON output, we need to check for drift first
     let drift_results = if !diff.binds_unchanged.is_empty() {
       let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
-      let config = ExecuteConfig::default();
+      let config = ExecuteConfig {
+        events: events.clone(),
+        ..ExecuteConfig::default()
+      };
       Some(
         rt.block_on(check_unchanged_binds(&diff.binds_unchanged, &manifest, &config))
           .context("Failed to check for drift")?,
@@ -97,7 +115,10 @@ pub fn cmd_plan(file: &str, impure: bool, output: OutputFormat) -> Result<()> {
 
     if !diff.binds_unchanged.is_empty() {
       let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
-      let config = ExecuteConfig::default();
+      let config = ExecuteConfig {
+        events: events.clone(),
+        ..ExecuteConfig::default()
+      };
 
       let drift_results = rt
         .block_on(check_unchanged_binds(&diff.binds_unchanged, &manifest, &config))
@@ -0,0 +1,156 @@
+//! Implementation of the `sys repl` command.
+//!
+//! This command starts an interactive Lua session with the `sys` global and a
+//! config's resolved inputs, for experimenting with modules without editing
+//! the config and re-running `sys plan`.
+
+use std::io::{self, BufRead, Write};
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use syslua_lib::eval::EvalOptions;
+use syslua_lib::manifest::Manifest;
+use syslua_lib::repl::{ReplOutput, ReplSession, is_incomplete_input};
+use syslua_lib::update::find_config_path;
+use syslua_lib::util::hash::ObjectHash;
+
+use crate::output::{print_error, print_info, print_json, symbols, truncate_hash};
+
+const HELP: &str = "\
+Enter Lua statements or expressions. Commands:
+  :manifest   Print the manifest of everything registered so far (JSON)
+  :builds     List registered builds
+  :binds      List registered binds
+  :setup      Run the config's setup(inputs)
+  :help       Show this help
+  :quit       Leave the REPL (or press Ctrl-D)";
+
+/// Execute the repl command.
+///
+/// Loads the config's inputs (without calling its `setup`) and reads Lua from
+/// stdin line by line. Unfinished chunks continue on the next line. After each
+/// chunk, the returned values and any builds or binds it registered are shown.
+pub fn cmd_repl(config: Option<&str>, impure: bool) -> Result<()> {
+  let config_path = match config {
+    Some(path) => Some(find_config_path(Some(path)).context("Failed to find config file")?),
+    None => find_config_path(None).ok(),
+  };
+
+  let session = ReplSession::new(
+    config_path.as_deref(),
+    &EvalOptions {
+      impure,
+      ..EvalOptions::default()
+    },
+  )
+  .context("Failed to start Lua session")?;
+
+  match &config_path {
+    Some(path) => print_info(&format!("Loaded inputs from {}", path.display())),
+    None => print_info("No config found; inputs are empty"),
+  }
+  println!("Type :help for commands");
+
+  let stdin = io::stdin();
+  let mut lines = stdin.lock().lines();
+  let mut buffer = String::new();
+
+  loop {
+    print!("{} ", if buffer.is_empty() { ">" } else { ">>" });
+    io::stdout().flush()?;
+
+    let Some(line) = lines.next() else {
+      println!();
+      break;
+    };
+    let line = line.context("Failed to read input")?;
+
+    if buffer.is_empty() {
+      match line.trim() {
+        "" => continue,
+        ":quit" | ":q" | ":exit" => break,
+        ":help" => {
+          println!("{}", HELP);
+          continue;
+        }
+        ":manifest" => {
+          print_json(&session.manifest())?;
+          continue;
+        }
+        ":builds" => {
+          let manifest = session.manifest();
+          print_builds(&manifest, manifest.builds.keys());
+          continue;
+        }
+        ":binds" => {
+          let manifest = session.manifest();
+          print_binds(&manifest, manifest.bindings.keys());
+          continue;
+        }
+        ":setup" => {
+          match session.run_setup() {
+            Ok(output) => print_output(&session, &output),
+            Err(e) => print_error(&e.to_string()),
+          }
+          continue;
+        }
+        cmd if cmd.starts_with(':') => {
+          print_error(&format!("unknown command '{}', see :help", cmd));
+          continue;
+        }
+        _ => {}
+      }
+    }
+
+    if !buffer.is_empty() {
+      buffer.push('\n');
+    }
+    buffer.push_str(&line);
+
+    match session.eval(&buffer) {
+      Ok(output) => print_output(&session, &output),
+      Err(e) if is_incomplete_input(&e) => continue,
+      Err(e) => print_error(&e.to_string()),
+    }
+    buffer.clear();
+  }
+
+  Ok(())
+}
+
+fn print_output(session: &ReplSession, output: &ReplOutput) {
+  for value in &output.values {
+    println!("{}", value);
+  }
+
+  if !output.builds.is_empty() || !output.binds.is_empty() {
+    let manifest = session.manifest();
+    print_builds(&manifest, &output.builds);
+    print_binds(&manifest, &output.binds);
+  }
+}
+
+fn print_builds<'a>(manifest: &Manifest, hashes: impl IntoIterator<Item = &'a ObjectHash>) {
+  for hash in hashes {
+    let id = manifest.builds.get(hash).and_then(|b| b.id.as_deref());
+    print_entry("build", id, hash);
+  }
+}
+
+fn print_binds<'a>(manifest: &Manifest, hashes: impl IntoIterator<Item = &'a ObjectHash>) {
+  for hash in hashes {
+    let id = manifest.bindings.get(hash).and_then(|b| b.id.as_deref());
+    print_entry("bind", id, hash);
+  }
+}
+
+fn print_entry(kind: &str, id: Option<&str>, hash: &ObjectHash) {
+  println!(
+    "{} {} {} {}",
+    symbols::ADD.green(),
+    kind.dimmed(),
+    id.unwrap_or("(no id)"),
+    truncate_hash(&hash.0).cyan()
+  );
+}
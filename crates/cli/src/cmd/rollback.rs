@@ -0,0 +1,65 @@
+//! Implementation of the `sys rollback` command.
+//!
+//! This command returns the system to a previously recorded snapshot without
+//! re-evaluating any Lua configuration.
+
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use syslua_lib::execute::{ExecuteConfig, RollbackOptions, rollback};
+
+use crate::output::{OutputFormat, format_duration, print_json, print_stat, print_success};
+
+/// Execute the rollback command.
+///
+/// Resolves `target` as a snapshot ID or tag and transitions the system to it:
+/// - Destroys binds that are not part of the target snapshot
+/// - Re-realizes target builds missing from the store
+/// - Re-applies or updates binds from the target snapshot
+/// - Marks the target snapshot as current
+///
+/// With `dry_run`, only the diff against the current state is reported.
+pub fn cmd_rollback(target: &str, dry_run: bool, output: OutputFormat) -> Result<()> {
+  let start = Instant::now();
+
+  let options = RollbackOptions {
+    execute: ExecuteConfig::default(),
+    dry_run,
+  };
+
+  let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+  let result = rt.block_on(rollback(target, &options)).context("Rollback failed")?;
+
+  if output.is_json() {
+    print_json(&result)?;
+    return Ok(());
+  }
+
+  println!();
+  if dry_run {
+    println!("{}", "Rollback dry run:".yellow());
+    print_stat("Target snapshot", &result.snapshot.id);
+    print_stat("Builds to realize", &result.diff.builds_to_realize.len().to_string());
+    print_stat("Binds to apply", &result.diff.binds_to_apply.len().to_string());
+    print_stat("Binds to update", &result.diff.binds_to_update.len().to_string());
+    print_stat("Binds to destroy", &result.diff.binds_to_destroy.len().to_string());
+    print_stat("Binds unchanged", &result.diff.binds_unchanged.len().to_string());
+    return Ok(());
+  }
+
+  print_success("Rollback complete!");
+  print_stat("Snapshot", &result.snapshot.id);
+  if let Some(ref previous) = result.previous_snapshot_id {
+    print_stat("Previous", previous);
+  }
+  print_stat("Builds realized", &result.execution.realized.len().to_string());
+  print_stat("Binds applied", &result.execution.applied.len().to_string());
+  print_stat("Binds updated", &result.binds_updated.to_string());
+  print_stat("Binds destroyed", &result.binds_destroyed.to_string());
+  print_stat("Binds unchanged", &result.diff.binds_unchanged.len().to_string());
+  print_stat("Duration", &format_duration(start.elapsed()));
+
+  Ok(())
+}
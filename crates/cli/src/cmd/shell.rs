@@ -0,0 +1,75 @@
+//! Implementation of the `sys shell` command.
+//!
+//! This command realizes builds from a config (or from package expressions) and
+//! starts a shell with their binaries on `PATH`, without applying any binds.
+
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use syslua_lib::execute::ExecuteConfig;
+use syslua_lib::shell::{ShellOptions, prepare_shell};
+use syslua_lib::update::find_config_path;
+
+use crate::output::print_info;
+
+/// Execute the shell command.
+///
+/// Evaluates the config (or only the given `packages`, using the config for
+/// inputs), realizes the resulting builds and spawns `$SHELL` with `PATH` and
+/// `MANPATH` pointing at their outputs. If `command` is non-empty it is run
+/// instead of an interactive shell, and a non-zero exit status is an error.
+///
+/// The realized builds stay registered as GC roots until the shell exits.
+pub fn cmd_shell(config: Option<&str>, packages: Vec<String>, impure: bool, command: Vec<String>) -> Result<()> {
+  let config_path = find_config_path(config).context("Failed to find config file")?;
+
+  let options = ShellOptions {
+    execute: ExecuteConfig::default(),
+    impure,
+    packages,
+  };
+
+  let rt = tokio::runtime::Runtime::new().context("Failed to create async runtime")?;
+  let env = rt
+    .block_on(prepare_shell(&config_path, &options))
+    .context("Failed to prepare shell environment")?;
+
+  let mut shell = match command.split_first() {
+    Some((program, args)) => {
+      let mut cmd = Command::new(program);
+      cmd.args(args);
+      cmd
+    }
+    None => {
+      print_info(&format!(
+        "Entering shell with {} build(s); exit to leave",
+        env.builds.len()
+      ));
+      Command::new(default_shell())
+    }
+  };
+
+  let status = shell.envs(env.env_vars()).status().context("Failed to start shell")?;
+
+  // Release the GC root only once the shell has exited.
+  drop(env);
+
+  if !command.is_empty() && !status.success() {
+    bail!("command exited with {}", status);
+  }
+
+  Ok(())
+}
+
+/// The user's preferred shell.
+fn default_shell() -> String {
+  #[cfg(windows)]
+  {
+    std::env::var("COMSPEC").unwrap_or_else(|_| "cmd.exe".to_string())
+  }
+  #[cfg(not(windows))]
+  {
+    std::env::var("SHELL").unwrap_or_else(|_| "/bin/sh".to_string())
+  }
+}
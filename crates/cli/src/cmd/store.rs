@@ -0,0 +1,177 @@
+//! Implementation of the `sys store` commands.
+//!
+//! - `verify` checks builds against the output hash in their markers
+//! - `push` copies builds, optionally signed, into a binary cache that other
+//!   machines can substitute from (see `SYSLUA_SUBSTITUTERS`)
+
+use std::path::Path;
+use std::time::Instant;
+
+use anyhow::{Context, Result, bail};
+use clap::Subcommand;
+use owo_colors::OwoColorize;
+
+use syslua_lib::cache::{SecretKey, push_store};
+use syslua_lib::execute::ExecuteConfig;
+use syslua_lib::store_lock::{LockMode, StoreLock};
+use syslua_lib::store_verify::{EntryStatus, VerifyOptions, VerifyReport, verify_store};
+
+use crate::output::{
+  OutputFormat, format_duration, print_error, print_json, print_stat, print_success, print_warning, truncate_hash,
+};
+
+#[derive(Subcommand, Debug)]
+pub enum StoreCommand {
+  /// Check every build in the store against its recorded output hash
+  Verify {
+    /// Re-realize corrupted builds that a snapshot still references
+    #[arg(long)]
+    repair: bool,
+
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+
+  /// Copy realized builds into a binary cache directory
+  Push {
+    /// Cache directory to push to (created if missing)
+    cache_dir: String,
+
+    /// Sign each build with this secret key file before pushing
+    #[arg(long, value_name = "FILE")]
+    sign_key: Option<String>,
+
+    /// Output format
+    #[arg(short = 'o', long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+}
+
+pub fn cmd_store(command: StoreCommand) -> Result<()> {
+  match command {
+    StoreCommand::Verify { repair, output } => cmd_verify(repair, output),
+    StoreCommand::Push {
+      cache_dir,
+      sign_key,
+      output,
+    } => cmd_push(Path::new(&cache_dir), sign_key.as_deref().map(Path::new), output),
+  }
+}
+
+/// Execute the store verify command.
+///
+/// Fails if corrupted builds remain after any repairs, so scripts can rely on
+/// the exit code; the report is printed either way.
+fn cmd_verify(repair: bool, output: OutputFormat) -> Result<()> {
+  let start = Instant::now();
+
+  let options = VerifyOptions {
+    repair,
+    execute: ExecuteConfig::default(),
+  };
+  let rt = tokio::runtime::Runtime::new()?;
+  let report = rt.block_on(verify_store(&options)).context("Failed to verify store")?;
+
+  if output.is_json() {
+    print_json(&report)?;
+  } else {
+    print_report(&report, repair);
+    print_stat("Duration", &format_duration(start.elapsed()));
+  }
+
+  if !report.is_clean() {
+    bail!("store contains corrupted builds");
+  }
+  Ok(())
+}
+
+/// Execute the store push command.
+///
+/// Builds already in the cache are left alone; builds that cannot be pushed are
+/// reported and the command fails after pushing the rest.
+fn cmd_push(cache_dir: &Path, sign_key: Option<&Path>, output: OutputFormat) -> Result<()> {
+  let start = Instant::now();
+
+  let key = sign_key
+    .map(|path| SecretKey::load(path).with_context(|| format!("Failed to load key {}", path.display())))
+    .transpose()?;
+
+  let _lock = StoreLock::acquire(LockMode::Shared, "push").context("Failed to acquire store lock")?;
+  let result = push_store(cache_dir, key.as_ref()).context("Failed to push to cache")?;
+
+  if output.is_json() {
+    print_json(&result)?;
+  } else {
+    for failure in &result.failed {
+      print_error(&format!("{}: {}", truncate_hash(&failure.hash.0), failure.error));
+    }
+    println!();
+    print_success(&format!("Pushed to {}", cache_dir.display()));
+    print_stat("Pushed", &result.pushed.len().to_string());
+    print_stat("Already cached", &result.cached.len().to_string());
+    print_stat("Failed", &result.failed.len().to_string());
+    print_stat("Duration", &format_duration(start.elapsed()));
+  }
+
+  if !result.failed.is_empty() {
+    bail!("{} build(s) could not be pushed", result.failed.len());
+  }
+  Ok(())
+}
+
+fn print_report(report: &VerifyReport, repair: bool) {
+  for entry in &report.problems {
+    let label = match entry.status {
+      EntryStatus::Ok => continue,
+      EntryStatus::Corrupted => format!("{:<10}", "corrupted").red().to_string(),
+      EntryStatus::Incomplete => format!("{:<10}", "incomplete").yellow().to_string(),
+      EntryStatus::Unverified => format!("{:<10}", "unverified").dimmed().to_string(),
+      EntryStatus::Unknown => format!("{:<10}", "unknown").yellow().to_string(),
+    };
+    let referenced = if entry.referenced { "" } else { " (unreferenced)" };
+    println!(
+      "  {} {}{}",
+      label,
+      truncate_hash(&entry.hash.0).cyan(),
+      referenced.dimmed()
+    );
+    if let (Some(expected), Some(actual)) = (&entry.expected, &entry.actual) {
+      println!(
+        "    expected {}, found {}",
+        truncate_hash(expected),
+        truncate_hash(actual)
+      );
+    }
+    if let Some(message) = &entry.message {
+      println!("    {}", message.dimmed());
+    }
+  }
+
+  for hash in &report.repaired {
+    print_success(&format!("Repaired {}", truncate_hash(&hash.0)));
+  }
+  for failure in &report.repair_failed {
+    print_error(&format!(
+      "Failed to repair {}: {}",
+      truncate_hash(&failure.hash.0),
+      failure.error
+    ));
+  }
+
+  println!();
+  let summary = &report.summary;
+  if report.is_clean() {
+    print_success("Store verified");
+  } else if repair {
+    print_warning("Store has corrupted builds that could not be repaired");
+  } else {
+    print_warning("Store has corrupted builds; run with --repair to rebuild them");
+  }
+  print_stat("Builds scanned", &summary.scanned.to_string());
+  print_stat("Intact", &summary.ok.to_string());
+  print_stat("Corrupted", &summary.corrupted.to_string());
+  print_stat("Incomplete", &summary.incomplete.to_string());
+  print_stat("Unverified", &summary.unverified.to_string());
+  print_stat("Unknown", &summary.unknown.to_string());
+}
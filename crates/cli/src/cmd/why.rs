@@ -0,0 +1,83 @@
+//! Implementation of the `sys why` command.
+//!
+//! This command explains why a build or bind is part of a config by listing
+//! every dependency path that leads to it.
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+
+use syslua_lib::eval::{EvalOptions, evaluate_config_with_sources};
+use syslua_lib::execute::{MAX_WHY_PATHS, NodeKind, WhyNode, explain};
+use syslua_lib::update::find_config_path;
+
+use crate::output::{OutputFormat, print_json, print_warning, symbols, truncate_hash};
+
+/// Execute the why command.
+///
+/// Evaluates the config (recording where each build and bind is registered),
+/// finds `target` by `id` or hash prefix and prints every path from a root
+/// bind or build down to it.
+pub fn cmd_why(target: &str, config: Option<&str>, impure: bool, output: OutputFormat) -> Result<()> {
+  let config_path = find_config_path(config).context("Failed to find config file")?;
+
+  let (manifest, sources) = evaluate_config_with_sources(
+    &config_path,
+    &EvalOptions {
+      impure,
+      ..EvalOptions::default()
+    },
+  )
+  .with_context(|| format!("Failed to evaluate config: {}", config_path.display()))?;
+
+  let result = explain(&manifest, &sources, target)?;
+
+  if output.is_json() {
+    print_json(&result)?;
+    return Ok(());
+  }
+
+  println!("{}", describe(&result.target));
+
+  let direct = result.paths.len() == 1 && result.paths[0].len() == 1;
+  if direct {
+    println!("  Registered directly by the config; nothing depends on it.");
+    return Ok(());
+  }
+
+  println!("  Required through {} path(s):", result.paths.len());
+  for path in &result.paths {
+    println!();
+    for (depth, node) in path.iter().enumerate() {
+      let indent = "  ".repeat(depth + 1);
+      if depth == 0 {
+        println!("{}{}", indent, describe(node));
+      } else {
+        println!("{}{} {}", indent, symbols::ARROW.dimmed(), describe(node));
+      }
+    }
+  }
+
+  if result.truncated {
+    println!();
+    print_warning(&format!("Only the first {} paths are shown", MAX_WHY_PATHS));
+  }
+
+  Ok(())
+}
+
+fn describe(node: &WhyNode) -> String {
+  let kind = match node.kind {
+    NodeKind::Build => "build",
+    NodeKind::Bind => "bind",
+  };
+  let mut line = format!(
+    "{} {} {}",
+    kind.dimmed(),
+    node.id.as_deref().unwrap_or("(no id)").bold(),
+    truncate_hash(&node.hash.0).cyan()
+  );
+  if let Some(ref source) = node.source {
+    line.push_str(&format!(" {}", source.to_string().dimmed()));
+  }
+  line
+}
@@ -0,0 +1,236 @@
+//! JSON-lines event stream written with `--events-file`.
+//!
+//! Every lifecycle event of an apply, plan or destroy becomes one JSON object
+//! on its own line, flushed as soon as it happens so the file can be followed
+//! while the command runs. Each object has the same core fields:
+//!
+//! ```text
+//! {"event":"node_failed","time":"...","kind":"build","hash":"...","id":"rg","duration_ms":812,"error":"..."}
+//! ```
+//!
+//! `hash`, `id`, `duration_ms` and `error` are `null` when they don't apply to
+//! an event. Command output and download progress are not part of the stream;
+//! they are in the action logs (`sys log`).
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tracing::debug;
+
+use syslua_lib::execute::dag::DagNode;
+use syslua_lib::execute::{EventSender, ExecuteEvent, ExecuteObserver};
+
+/// One line of the event stream.
+#[derive(Debug, Default, Serialize)]
+struct EventRecord {
+  event: &'static str,
+  time: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  kind: Option<&'static str>,
+  hash: Option<String>,
+  id: Option<String>,
+  duration_ms: Option<u64>,
+  error: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  config: Option<PathBuf>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  rev: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  path: Option<PathBuf>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  message: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  dependency: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  binds: Option<usize>,
+}
+
+/// Observer writing events to a file as JSON lines.
+pub struct JsonLinesObserver {
+  file: Mutex<LineWriter<File>>,
+  /// IDs seen when nodes were scheduled, for the events that only carry a node.
+  ids: Mutex<HashMap<DagNode, String>>,
+}
+
+impl JsonLinesObserver {
+  /// Create (or truncate) the event file at `path`.
+  pub fn create(path: &Path) -> Result<Self> {
+    let file = File::create(path).with_context(|| format!("Failed to create events file: {}", path.display()))?;
+    Ok(Self {
+      file: Mutex::new(LineWriter::new(file)),
+      ids: Mutex::new(HashMap::new()),
+    })
+  }
+
+  fn record(&self, event: &ExecuteEvent) -> Option<EventRecord> {
+    let mut ids = self.ids.lock().unwrap_or_else(|e| e.into_inner());
+    let mut node_record = |event: &'static str, node: &DagNode, id: Option<&String>| {
+      let (kind, hash) = match node {
+        DagNode::Build(hash) => ("build", hash),
+        DagNode::Bind(hash) => ("bind", hash),
+      };
+      if let Some(id) = id {
+        ids.insert(node.clone(), id.clone());
+      }
+      EventRecord {
+        event,
+        kind: Some(kind),
+        hash: Some(hash.0.clone()),
+        id: ids.get(node).cloned(),
+        ..EventRecord::default()
+      }
+    };
+
+    let record = match event {
+      ExecuteEvent::EvalStarted { config } => EventRecord {
+        event: "eval_started",
+        config: Some(config.clone()),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::EvalFinished {
+        config,
+        duration,
+        error,
+      } => EventRecord {
+        event: "eval_finished",
+        config: Some(config.clone()),
+        duration_ms: Some(millis(*duration)),
+        error: error.clone(),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::InputResolved { name, rev, path } => EventRecord {
+        event: "input_resolved",
+        id: Some(name.clone()),
+        rev: Some(rev.clone()),
+        path: Some(path.clone()),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::NodeScheduled { node, id } => node_record("node_scheduled", node, id.as_ref()),
+      ExecuteEvent::NodeStarted { node, id } => node_record("node_started", node, id.as_ref()),
+      ExecuteEvent::NodeFinished { node, duration, error } => {
+        let name = if error.is_some() {
+          "node_failed"
+        } else {
+          "node_succeeded"
+        };
+        EventRecord {
+          duration_ms: Some(millis(*duration)),
+          error: error.clone(),
+          ..node_record(name, node, None)
+        }
+      }
+      ExecuteEvent::NodeSkipped { node, dependency } => EventRecord {
+        dependency: Some(dependency.to_string()),
+        ..node_record("node_skipped", node, None)
+      },
+      ExecuteEvent::DriftDetected { hash, id, message } => EventRecord {
+        event: "drift_detected",
+        kind: Some("bind"),
+        hash: Some(hash.0.clone()),
+        id: id.clone(),
+        message: message.clone(),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::RollbackStarted { binds } => EventRecord {
+        event: "rollback_started",
+        binds: Some(*binds),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::RollbackFinished { duration, error } => EventRecord {
+        event: "rollback_finished",
+        duration_ms: Some(millis(*duration)),
+        error: error.clone(),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::SnapshotSaved { id } => EventRecord {
+        event: "snapshot_saved",
+        id: Some(id.clone()),
+        ..EventRecord::default()
+      },
+      ExecuteEvent::Output { .. } | ExecuteEvent::Download { .. } => return None,
+    };
+    Some(record)
+  }
+}
+
+impl ExecuteObserver for JsonLinesObserver {
+  fn on_event(&self, event: &ExecuteEvent) {
+    let Some(mut record) = self.record(event) else {
+      return;
+    };
+    record.time = humantime::format_rfc3339_millis(SystemTime::now()).to_string();
+    let Ok(line) = serde_json::to_string(&record) else {
+      return;
+    };
+    let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = writeln!(file, "{}", line) {
+      debug!(error = %e, "failed to write event");
+    }
+  }
+}
+
+/// Open the event stream requested with `--events-file`, if any.
+pub fn events_file_sender(path: Option<&Path>) -> Result<Option<EventSender>> {
+  path
+    .map(|path| JsonLinesObserver::create(path).map(EventSender::new))
+    .transpose()
+}
+
+/// Combine two optional event senders into one.
+pub fn combine(a: Option<EventSender>, b: Option<EventSender>) -> Option<EventSender> {
+  match (a, b) {
+    (Some(a), Some(b)) => Some(a.tee(b)),
+    (a, b) => a.or(b),
+  }
+}
+
+fn millis(duration: Duration) -> u64 {
+  duration.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use syslua_lib::util::hash::ObjectHash;
+  use tempfile::TempDir;
+
+  #[test]
+  fn node_events_carry_the_scheduled_id() {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("events.jsonl");
+    let observer = JsonLinesObserver::create(&path).unwrap();
+    let node = DagNode::Build(ObjectHash("abc".to_string()));
+
+    observer.on_event(&ExecuteEvent::NodeScheduled {
+      node: node.clone(),
+      id: Some("rg".to_string()),
+    });
+    observer.on_event(&ExecuteEvent::Output {
+      node: node.clone(),
+      stream: syslua_lib::execute::OutputStream::Stdout,
+      line: "ignored".to_string(),
+    });
+    observer.on_event(&ExecuteEvent::NodeFinished {
+      node,
+      duration: Duration::from_millis(1500),
+      error: Some("boom".to_string()),
+    });
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let events: Vec<serde_json::Value> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["event"], "node_scheduled");
+    assert_eq!(events[1]["event"], "node_failed");
+    assert_eq!(events[1]["kind"], "build");
+    assert_eq!(events[1]["hash"], "abc");
+    assert_eq!(events[1]["id"], "rg");
+    assert_eq!(events[1]["duration_ms"], 1500);
+    assert_eq!(events[1]["error"], "boom");
+  }
+}
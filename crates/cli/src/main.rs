@@ -1,14 +1,19 @@
 mod cmd;
+mod events;
 mod output;
 mod prompts;
 
+use std::path::PathBuf;
 use std::process::ExitCode;
 
 use clap::{Parser, Subcommand};
+use cmd::GraphFormat;
 use cmd::{
-  cmd_apply, cmd_destroy, cmd_diff, cmd_gc, cmd_info, cmd_init, cmd_plan, cmd_snapshot, cmd_status, cmd_update,
+  cmd_apply, cmd_apply_plan, cmd_build, cmd_destroy, cmd_diff, cmd_drift, cmd_gc, cmd_graph, cmd_info, cmd_init,
+  cmd_key, cmd_log, cmd_plan, cmd_repl, cmd_rollback, cmd_shell, cmd_snapshot, cmd_status, cmd_store, cmd_update,
+  cmd_why,
 };
-use output::OutputFormat;
+use output::{LogWriter, OutputFormat};
 use tracing::Level;
 use tracing_subscriber::{Layer, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -86,13 +91,26 @@ enum Commands {
   },
   /// Evaluate a config and apply changes to the system
   Apply {
-    file: String,
+    #[arg(required_unless_present = "plan")]
+    file: Option<String>,
+    /// Apply a saved plan (hash or path) instead of evaluating a config
+    #[arg(long, value_name = "HASH|PATH", conflicts_with_all = ["file", "impure"])]
+    plan: Option<String>,
     /// Check unchanged binds for drift and repair if needed
     #[arg(long)]
     repair: bool,
+    /// Apply the plan even if the current snapshot changed since it was made
+    #[arg(long, requires = "plan")]
+    force: bool,
     /// Allow impure Lua libs (io, os). Breaks determinism.
     #[arg(long)]
     impure: bool,
+    /// Keep running independent builds and binds after a failure and report every failure
+    #[arg(long)]
+    keep_going: bool,
+    /// Write lifecycle events to this file as JSON lines
+    #[arg(long, value_name = "PATH")]
+    events_file: Option<PathBuf>,
     /// Output format
     #[arg(short, long, value_enum, default_value = "text")]
     output: OutputFormat,
@@ -103,6 +121,31 @@ enum Commands {
     /// Allow impure Lua libs (io, os). Breaks determinism.
     #[arg(long)]
     impure: bool,
+    /// Write lifecycle events to this file as JSON lines
+    #[arg(long, value_name = "PATH")]
+    events_file: Option<PathBuf>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+  /// Realize builds from a config without applying binds
+  Build {
+    file: String,
+    /// Build ID to realize (default: all builds)
+    #[arg(long, conflicts_with = "hash")]
+    id: Option<String>,
+    /// Hash (or unique hash prefix) of the build to realize
+    #[arg(long, value_name = "PREFIX")]
+    hash: Option<String>,
+    /// Rebuild the selected build even if it is already in the store
+    #[arg(long)]
+    rebuild: bool,
+    /// Allow impure Lua libs (io, os). Breaks determinism.
+    #[arg(long)]
+    impure: bool,
+    /// Keep realizing independent builds after a failure and report every failure
+    #[arg(long)]
+    keep_going: bool,
     /// Output format
     #[arg(short, long, value_enum, default_value = "text")]
     output: OutputFormat,
@@ -112,6 +155,21 @@ enum Commands {
     /// Show what would be destroyed without making changes
     #[arg(long)]
     dry_run: bool,
+    /// Write lifecycle events to this file as JSON lines
+    #[arg(long, value_name = "PATH")]
+    events_file: Option<PathBuf>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+  /// Roll the system back to a previous snapshot
+  Rollback {
+    /// Snapshot ID or tag to roll back to
+    #[arg(value_name = "SNAPSHOT")]
+    target: String,
+    /// Show what would change without making changes
+    #[arg(long)]
+    dry_run: bool,
     /// Output format
     #[arg(short, long, value_enum, default_value = "text")]
     output: OutputFormat,
@@ -158,6 +216,28 @@ enum Commands {
     #[arg(short, long, value_enum, default_value = "text")]
     output: OutputFormat,
   },
+  /// Run bind checks against the current snapshot and report drift
+  ///
+  /// Exits with code 2 when any bind has drifted.
+  Drift {
+    /// Only check the bind with this ID
+    #[arg(long)]
+    bind: Option<String>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
+  /// Show the output of a build's or bind's actions
+  Log {
+    /// ID of a build or bind in the current snapshot, or a hash prefix
+    target: String,
+    /// Only show the log of the action with this index
+    #[arg(long, value_name = "N")]
+    action: Option<usize>,
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
   /// Clean up unused builds and inputs from the store
   Gc {
     /// Show what would be removed without making changes
@@ -167,11 +247,74 @@ enum Commands {
     #[arg(short, long, value_enum, default_value = "text")]
     output: OutputFormat,
   },
+  /// Export the build/bind dependency graph of a config or snapshot
+  Graph {
+    #[arg(required_unless_present = "snapshot")]
+    file: Option<String>,
+    /// Graph a snapshot (ID or tag) instead of evaluating a config
+    #[arg(long, value_name = "SNAPSHOT", conflicts_with_all = ["file", "impure"])]
+    snapshot: Option<String>,
+    /// Allow impure Lua libs (io, os). Breaks determinism.
+    #[arg(long)]
+    impure: bool,
+    /// Graph format
+    #[arg(short, long, value_enum, default_value = "dot")]
+    format: GraphFormat,
+  },
+  /// Start a shell with build outputs on PATH, without applying binds
+  Shell {
+    /// Path to config file (default: ./init.lua or ~/.config/syslua/init.lua)
+    #[arg(value_name = "CONFIG")]
+    config: Option<String>,
+    /// Package expression to realize instead of the config's builds (can be repeated)
+    #[arg(short, long = "package", value_name = "EXPR")]
+    packages: Vec<String>,
+    /// Allow impure Lua libs (io, os). Breaks determinism.
+    #[arg(long)]
+    impure: bool,
+    /// Command to run instead of an interactive shell
+    #[arg(last = true, value_name = "COMMAND")]
+    command: Vec<String>,
+  },
+  /// Start an interactive Lua session with the config's inputs loaded
+  Repl {
+    /// Path to config file (default: ./init.lua or ~/.config/syslua/init.lua)
+    #[arg(value_name = "CONFIG")]
+    config: Option<String>,
+    /// Allow impure Lua libs (io, os). Breaks determinism.
+    #[arg(long)]
+    impure: bool,
+  },
+  /// Explain why a build or bind is part of a config
+  Why {
+    /// Build or bind ID, or hash prefix
+    #[arg(value_name = "BUILD_OR_BIND")]
+    target: String,
+    /// Path to config file (default: ./init.lua or ~/.config/syslua/init.lua)
+    #[arg(value_name = "CONFIG")]
+    config: Option<String>,
+    /// Allow impure Lua libs (io, os). Breaks determinism.
+    #[arg(long)]
+    impure: bool,
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    output: OutputFormat,
+  },
   /// Manage snapshots
   Snapshot {
     #[command(subcommand)]
     command: cmd::snapshot::SnapshotCommand,
   },
+  /// Manage keys for signing cached builds
+  Key {
+    #[command(subcommand)]
+    command: cmd::key::KeyCommand,
+  },
+  /// Inspect and maintain the store
+  Store {
+    #[command(subcommand)]
+    command: cmd::store::StoreCommand,
+  },
 }
 
 fn main() -> ExitCode {
@@ -192,6 +335,7 @@ fn main() -> ExitCode {
         tracing_subscriber::registry()
           .with(
             fmt::layer()
+              .with_writer(|| LogWriter)
               .with_target(true)
               .with_filter(tracing_subscriber::filter::LevelFilter::from_level(level)),
           )
@@ -200,6 +344,7 @@ fn main() -> ExitCode {
         tracing_subscriber::registry()
           .with(
             fmt::layer()
+              .with_writer(|| LogWriter)
               .without_time()
               .with_target(false)
               .with_filter(tracing_subscriber::filter::LevelFilter::from_level(level)),
@@ -211,6 +356,7 @@ fn main() -> ExitCode {
       tracing_subscriber::registry()
         .with(
           fmt::layer()
+            .with_writer(|| LogWriter)
             .json()
             .with_file(true)
             .with_line_number(true)
@@ -225,12 +371,49 @@ fn main() -> ExitCode {
     Commands::Init { path } => cmd_init(&path),
     Commands::Apply {
       file,
+      plan,
       repair,
+      force,
+      impure,
+      keep_going,
+      events_file,
+      output,
+    } => match plan {
+      Some(plan) => cmd_apply_plan(&plan, repair, force, keep_going, events_file.as_deref(), output),
+      None => cmd_apply(
+        file.as_deref().unwrap_or_default(),
+        repair,
+        impure,
+        keep_going,
+        events_file.as_deref(),
+        output,
+      ),
+    },
+    Commands::Plan {
+      file,
       impure,
+      events_file,
+      output,
+    } => cmd_plan(&file, impure, events_file.as_deref(), output),
+    Commands::Build {
+      file,
+      id,
+      hash,
+      rebuild,
+      impure,
+      keep_going,
+      output,
+    } => cmd_build(&file, id, hash, rebuild, impure, keep_going, output),
+    Commands::Destroy {
+      dry_run,
+      events_file,
       output,
-    } => cmd_apply(&file, repair, impure, output),
-    Commands::Plan { file, impure, output } => cmd_plan(&file, impure, output),
-    Commands::Destroy { dry_run, output } => cmd_destroy(dry_run, output),
+    } => cmd_destroy(dry_run, events_file.as_deref(), output),
+    Commands::Rollback {
+      target,
+      dry_run,
+      output,
+    } => cmd_rollback(&target, dry_run, output),
     Commands::Diff {
       snapshot_a,
       snapshot_b,
@@ -247,12 +430,36 @@ fn main() -> ExitCode {
       Ok(())
     }
     Commands::Status { verbose, output } => cmd_status(verbose, output),
+    Commands::Drift { bind, output } => cmd_drift(bind.as_deref(), output),
+    Commands::Log { target, action, output } => cmd_log(&target, action, output),
     Commands::Gc { dry_run, output } => cmd_gc(dry_run, output),
+    Commands::Graph {
+      file,
+      snapshot,
+      impure,
+      format,
+    } => cmd_graph(file.as_deref(), snapshot.as_deref(), impure, format),
+    Commands::Shell {
+      config,
+      packages,
+      impure,
+      command,
+    } => cmd_shell(config.as_deref(), packages, impure, command),
+    Commands::Repl { config, impure } => cmd_repl(config.as_deref(), impure),
+    Commands::Why {
+      target,
+      config,
+      impure,
+      output,
+    } => cmd_why(&target, config.as_deref(), impure, output),
     Commands::Snapshot { command } => cmd_snapshot(command),
+    Commands::Key { command } => cmd_key(command),
+    Commands::Store { command } => cmd_store(command),
   };
 
   match result {
     Ok(()) => ExitCode::SUCCESS,
+    Err(err) if err.is::<cmd::DriftDetected>() => ExitCode::from(cmd::DRIFT_EXIT_CODE),
     Err(err) => {
       eprintln!("Error: {err:?}");
       ExitCode::FAILURE
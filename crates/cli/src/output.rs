@@ -1,13 +1,21 @@
 //! CLI output formatting utilities.
 //!
 //! Provides consistent formatting for terminal output including colored status
-//! messages, human-readable byte/duration formatting, and Unicode symbols.
+//! messages, human-readable byte/duration formatting, Unicode symbols, and the
+//! live progress display of a running apply.
 
-use std::time::Duration;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use clap::ValueEnum;
 use owo_colors::{OwoColorize, Stream};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use syslua_lib::execute::ExecuteEvent;
+use syslua_lib::execute::dag::DagNode;
 
 #[derive(Debug, Clone, Copy, Default, ValueEnum)]
 pub enum OutputFormat {
@@ -118,9 +126,249 @@ pub fn print_json<T: serde::Serialize>(value: &T) -> anyhow::Result<()> {
   Ok(())
 }
 
+/// Number of progress lines currently drawn at the bottom of the terminal.
+///
+/// Shared between the progress renderer and [`LogWriter`] so log messages
+/// never end up in the middle of the live region.
+static LIVE_LINES: Mutex<usize> = Mutex::new(0);
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+const DEFAULT_WIDTH: usize = 80;
+
+/// Stdout writer for log messages that keeps the progress display intact.
+///
+/// Clears the live progress lines before writing; the renderer draws them
+/// again on its next tick.
+pub struct LogWriter;
+
+impl Write for LogWriter {
+  fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    let mut live = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stdout = io::stdout().lock();
+    clear_live_lines(&mut stdout, &mut live)?;
+    stdout.write(buf)
+  }
+
+  fn flush(&mut self) -> io::Result<()> {
+    io::stdout().flush()
+  }
+}
+
+fn clear_live_lines(out: &mut impl Write, live: &mut usize) -> io::Result<()> {
+  if *live > 0 {
+    write!(out, "\x1b[{}A\r\x1b[J", *live)?;
+    *live = 0;
+  }
+  Ok(())
+}
+
+/// Show the progress of an execution until its event channel closes.
+///
+/// On a terminal, every running build and bind gets a line that is redrawn in
+/// place, showing its elapsed time, download progress and the last line its
+/// command printed. Finished nodes are printed above that region. When stdout
+/// is not a terminal, each event is printed as a plain line instead.
+pub async fn render_progress(mut events: UnboundedReceiver<ExecuteEvent>) {
+  let mut progress = Progress::new(io::stdout().is_terminal());
+  let mut tick = tokio::time::interval(REDRAW_INTERVAL);
+  loop {
+    tokio::select! {
+      event = events.recv() => match event {
+        Some(event) => progress.handle(event),
+        None => break,
+      },
+      _ = tick.tick(), if progress.live => progress.redraw(),
+    }
+  }
+  progress.clear();
+}
+
+struct RunningNode {
+  node: DagNode,
+  label: String,
+  started: Instant,
+  download: Option<(u64, Option<u64>)>,
+  last_line: Option<String>,
+}
+
+struct Progress {
+  live: bool,
+  width: usize,
+  running: Vec<RunningNode>,
+  labels: HashMap<DagNode, String>,
+}
+
+impl Progress {
+  fn new(live: bool) -> Self {
+    let width = std::env::var("COLUMNS")
+      .ok()
+      .and_then(|columns| columns.parse().ok())
+      .unwrap_or(DEFAULT_WIDTH);
+    Self {
+      live,
+      width,
+      running: Vec::new(),
+      labels: HashMap::new(),
+    }
+  }
+
+  fn handle(&mut self, event: ExecuteEvent) {
+    match event {
+      ExecuteEvent::NodeStarted { node, id } => {
+        let label = node_label(&node, id.as_deref());
+        if !self.live {
+          self.print_line(&format!(
+            "{} {}",
+            symbols::ARROW.if_supports_color(Stream::Stdout, |s| s.blue()),
+            label
+          ));
+        }
+        self.labels.insert(node.clone(), label.clone());
+        self.running.push(RunningNode {
+          node,
+          label,
+          started: Instant::now(),
+          download: None,
+          last_line: None,
+        });
+      }
+      ExecuteEvent::Output { node, line, .. } => {
+        if !self.live {
+          self.print_line(&format!(
+            "  {} {}",
+            format!("{} |", self.label(&node)).if_supports_color(Stream::Stdout, |s| s.dimmed()),
+            line
+          ));
+        } else if let Some(running) = self.running.iter_mut().find(|r| r.node == node) {
+          running.last_line = Some(line);
+        }
+      }
+      ExecuteEvent::Download {
+        node,
+        downloaded,
+        total,
+        ..
+      } => {
+        if let Some(running) = self.running.iter_mut().find(|r| r.node == node) {
+          running.download = Some((downloaded, total));
+        }
+      }
+      ExecuteEvent::NodeFinished { node, duration, error } => {
+        self.running.retain(|r| r.node != node);
+        let label = self.label(&node);
+        let line = match error {
+          None => format!(
+            "{} {} {}",
+            symbols::SUCCESS.if_supports_color(Stream::Stdout, |s| s.green()),
+            label,
+            format!("({})", format_duration(duration)).if_supports_color(Stream::Stdout, |s| s.dimmed())
+          ),
+          Some(error) => format!(
+            "{} {}: {}",
+            symbols::ERROR.if_supports_color(Stream::Stdout, |s| s.red()),
+            label,
+            error.lines().next().unwrap_or_default()
+          ),
+        };
+        self.print_line(&line);
+      }
+      ExecuteEvent::NodeSkipped { node, .. } => {
+        let line = format!(
+          "{} {} skipped",
+          symbols::MINUS.if_supports_color(Stream::Stdout, |s| s.yellow()),
+          self.label(&node)
+        );
+        self.print_line(&line);
+      }
+      _ => {}
+    }
+  }
+
+  fn label(&self, node: &DagNode) -> String {
+    self.labels.get(node).cloned().unwrap_or_else(|| node_label(node, None))
+  }
+
+  /// Print a line above the live region.
+  fn print_line(&self, line: &str) {
+    let mut live = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stdout = io::stdout().lock();
+    let _ = clear_live_lines(&mut stdout, &mut live);
+    let _ = writeln!(stdout, "{}", line);
+  }
+
+  fn redraw(&self) {
+    let mut live = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stdout = io::stdout().lock();
+    let _ = clear_live_lines(&mut stdout, &mut live);
+    for running in &self.running {
+      let _ = writeln!(stdout, "{}", self.status_line(running));
+    }
+    let _ = stdout.flush();
+    *live = self.running.len();
+  }
+
+  fn clear(&self) {
+    let mut live = LIVE_LINES.lock().unwrap_or_else(|e| e.into_inner());
+    let mut stdout = io::stdout().lock();
+    let _ = clear_live_lines(&mut stdout, &mut live);
+    let _ = stdout.flush();
+  }
+
+  /// One line of the live region, cut to the terminal width so it never wraps.
+  fn status_line(&self, running: &RunningNode) -> String {
+    let elapsed = running.started.elapsed();
+    let frame = SPINNER[(elapsed.as_millis() / REDRAW_INTERVAL.as_millis()) as usize % SPINNER.len()];
+    let mut status = format!("{} {} {}", frame, running.label, format_duration(elapsed));
+    match running.download {
+      Some((downloaded, Some(total))) => {
+        status.push_str(&format!(" {} / {}", format_bytes(downloaded), format_bytes(total)))
+      }
+      Some((downloaded, None)) => status.push_str(&format!(" {}", format_bytes(downloaded))),
+      None => {}
+    }
+    let status = truncate_width(&status, self.width.saturating_sub(1));
+
+    let room = self.width.saturating_sub(status.chars().count() + 3);
+    match &running.last_line {
+      Some(line) if room > 0 => {
+        let tail: String = line.chars().filter(|c| !c.is_control()).collect();
+        format!(
+          "{}  {}",
+          status,
+          truncate_width(tail.trim(), room).if_supports_color(Stream::Stdout, |s| s.dimmed())
+        )
+      }
+      _ => status,
+    }
+  }
+}
+
+/// `build <id>` or `bind <id>`, falling back to the shortened hash.
+fn node_label(node: &DagNode, id: Option<&str>) -> String {
+  let (kind, hash) = match node {
+    DagNode::Build(hash) => ("build", hash),
+    DagNode::Bind(hash) => ("bind", hash),
+  };
+  format!("{} {}", kind, id.unwrap_or_else(|| truncate_hash(&hash.0)))
+}
+
+fn truncate_width(text: &str, width: usize) -> String {
+  if text.chars().count() <= width {
+    return text.to_string();
+  }
+  if width == 0 {
+    return String::new();
+  }
+  let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+  truncated.push('…');
+  truncated
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
+  use syslua_lib::util::hash::ObjectHash;
 
   #[test]
   fn test_truncate_hash() {
@@ -138,6 +386,20 @@ mod tests {
     assert_eq!(format_bytes(1073741824), "1.0 GB");
   }
 
+  #[test]
+  fn test_node_label() {
+    let hash = ObjectHash("abcdef1234567890".to_string());
+    assert_eq!(node_label(&DagNode::Build(hash.clone()), Some("rg")), "build rg");
+    assert_eq!(node_label(&DagNode::Bind(hash), None), "bind abcdef123456");
+  }
+
+  #[test]
+  fn test_truncate_width() {
+    assert_eq!(truncate_width("hello", 5), "hello");
+    assert_eq!(truncate_width("hello world", 6), "hello…");
+    assert_eq!(truncate_width("hello", 0), "");
+  }
+
   #[test]
   fn test_format_duration() {
     assert_eq!(format_duration(Duration::from_millis(50)), "50ms");
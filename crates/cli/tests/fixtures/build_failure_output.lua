@@ -0,0 +1,25 @@
+--- Build that writes to stdout and stderr before failing.
+--- Tests that action output is logged and the end of stderr is reported.
+
+return {
+  inputs = {},
+  setup = function(_)
+    sys.build({
+      id = 'noisy-build',
+      create = function(_, ctx)
+        if sys.os == 'windows' then
+          ctx:exec({
+            bin = 'powershell.exe',
+            args = { '-NoProfile', '-NonInteractive', '-Command', 'Write-Output "configuring"; [Console]::Error.WriteLine("compiler exploded"); exit 2' },
+          })
+        else
+          ctx:exec({
+            bin = '/bin/sh',
+            args = { '-c', 'echo configuring; echo "compiler exploded" >&2; exit 2' },
+          })
+        end
+        return { out = ctx.out }
+      end,
+    })
+  end,
+}
@@ -0,0 +1,62 @@
+--- Independent failing builds for keep-going tests.
+---
+--- 'broken-a' and 'broken-b' both fail, 'needs-broken-a' depends on 'broken-a'
+--- and must be skipped, and 'healthy' succeeds on its own.
+
+local function sh(ctx, script)
+  if sys.os == 'windows' then
+    return ctx:exec({
+      bin = 'powershell.exe',
+      args = { '-NoProfile', '-NonInteractive', '-Command', script },
+      env = { PATH = sys.getenv('SystemDrive') .. '\\Windows\\System32;' .. sys.getenv('SystemDrive') .. '\\Windows' },
+    })
+  else
+    return ctx:exec({
+      bin = '/bin/sh',
+      args = { '-c', script },
+      env = { PATH = '/bin:/usr/bin' },
+    })
+  end
+end
+
+return {
+  inputs = {},
+  setup = function(_)
+    local broken_a = sys.build({
+      id = 'broken-a',
+      create = function(_, ctx)
+        sh(ctx, 'exit 1')
+        return { out = ctx.out }
+      end,
+    })
+
+    sys.build({
+      id = 'broken-b',
+      create = function(_, ctx)
+        sh(ctx, 'exit 2')
+        return { out = ctx.out }
+      end,
+    })
+
+    sys.build({
+      id = 'needs-broken-a',
+      inputs = { dep = broken_a },
+      create = function(_, ctx)
+        sh(ctx, 'exit 0')
+        return { out = ctx.out }
+      end,
+    })
+
+    sys.build({
+      id = 'healthy',
+      create = function(_, ctx)
+        if sys.os == 'windows' then
+          sh(ctx, 'Set-Content -Path "' .. ctx.out .. '\\ok.txt" -Value "ok"')
+        else
+          sh(ctx, 'echo ok > ' .. ctx.out .. '/ok.txt')
+        end
+        return { out = ctx.out }
+      end,
+    })
+  end,
+}
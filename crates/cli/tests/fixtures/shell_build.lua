@@ -0,0 +1,37 @@
+--- Build exposing a binary plus a bind that must never run under `sys shell`.
+
+local TEST_DIR = sys.getenv('TEST_OUTPUT_DIR')
+
+return {
+  inputs = {},
+  setup = function(_)
+    local hello = sys.build({
+      id = 'hello-shell',
+      create = function(_, ctx)
+        ctx:exec({
+          bin = '/bin/sh',
+          args = {
+            '-c',
+            'mkdir -p ' .. ctx.out .. '/bin && printf "#!/bin/sh\\necho hello from shell\\n" > '
+              .. ctx.out
+              .. '/bin/hello-shell && chmod +x '
+              .. ctx.out
+              .. '/bin/hello-shell',
+          },
+          env = { PATH = '/bin:/usr/bin' },
+        })
+        return { out = ctx.out }
+      end,
+    })
+
+    sys.bind({
+      id = 'shell-marker',
+      inputs = { hello = hello },
+      create = function(_, ctx)
+        ctx:exec({ bin = '/bin/sh', args = { '-c', 'touch ' .. TEST_DIR .. '/bind-ran.txt' } })
+        return {}
+      end,
+      destroy = function(_, _) end,
+    })
+  end,
+}
@@ -171,3 +171,114 @@ fn drift_does_not_affect_exit_code() {
     .success()
     .stderr(predicate::str::contains("Drift detected"));
 }
+
+#[test]
+fn apply_keep_going_lists_every_failure() {
+  let env = TestEnv::from_fixture("keep_going.lua");
+
+  env
+    .sys_cmd()
+    .args(["apply", "--keep-going"])
+    .arg(&env.config_path)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Failed build").count(2))
+    .stderr(predicate::str::contains("skipped build").count(1))
+    .stderr(predicate::str::contains("2 build(s) and 0 bind(s) failed"));
+}
+
+#[test]
+fn apply_prints_progress_lines_without_terminal() {
+  let env = TestEnv::from_fixture("build_failure_output.lua");
+
+  env
+    .sys_cmd()
+    .arg("apply")
+    .arg(&env.config_path)
+    .assert()
+    .failure()
+    .stdout(predicate::str::contains("build noisy-build | configuring"))
+    .stdout(predicate::str::contains("build noisy-build: command failed"));
+}
+
+#[test]
+fn apply_json_output_has_no_progress() {
+  let env = TestEnv::from_fixture("build_with_exec.lua");
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "apply", "-o", "json"])
+    .arg(&env.config_path)
+    .output()
+    .expect("failed to run apply");
+  assert!(output.status.success());
+
+  let _: serde_json::Value = serde_json::from_slice(&output.stdout).expect("apply output should be JSON");
+}
+
+/// Names of the events in a JSON-lines event file.
+fn event_names(path: &std::path::Path) -> Vec<String> {
+  std::fs::read_to_string(path)
+    .expect("events file should exist")
+    .lines()
+    .map(|line| {
+      let event: serde_json::Value = serde_json::from_str(line).expect("each line should be JSON");
+      event["event"].as_str().unwrap().to_string()
+    })
+    .collect()
+}
+
+#[test]
+fn apply_writes_events_file() {
+  let env = TestEnv::from_fixture("build_with_exec.lua");
+  let events_path = env.output_path().join("events.jsonl");
+
+  env
+    .sys_cmd()
+    .arg("apply")
+    .arg(&env.config_path)
+    .arg("--events-file")
+    .arg(&events_path)
+    .assert()
+    .success();
+
+  assert_eq!(
+    event_names(&events_path),
+    [
+      "eval_started",
+      "eval_finished",
+      "node_scheduled",
+      "node_started",
+      "node_succeeded",
+      "snapshot_saved"
+    ]
+  );
+}
+
+#[test]
+fn apply_events_file_reports_failures() {
+  let env = TestEnv::from_fixture("build_failure_output.lua");
+  let events_path = env.output_path().join("events.jsonl");
+
+  env
+    .sys_cmd()
+    .arg("apply")
+    .arg(&env.config_path)
+    .arg("--events-file")
+    .arg(&events_path)
+    .assert()
+    .failure();
+
+  let content = std::fs::read_to_string(&events_path).unwrap();
+  let failed: serde_json::Value = content
+    .lines()
+    .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+    .find(|event| event["event"] == "node_failed")
+    .expect("the failed build should be reported");
+  assert_eq!(failed["kind"], "build");
+  assert_eq!(failed["id"], "noisy-build");
+  assert!(failed["hash"].is_string());
+  assert!(failed["duration_ms"].is_u64());
+  assert!(failed["error"].as_str().unwrap().contains("exit code"));
+  assert!(!event_names(&events_path).contains(&"snapshot_saved".to_string()));
+}
@@ -0,0 +1,123 @@
+//! Build command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+fn build_json(env: &TestEnv, args: &[&str]) -> serde_json::Value {
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "build"])
+    .arg(&env.config_path)
+    .args(args)
+    .args(["-o", "json"])
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "build failed: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  serde_json::from_slice(&output.stdout).expect("valid JSON")
+}
+
+#[test]
+fn build_by_id_realizes_dependencies() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  let parsed = build_json(&env, &["--id", "processor-1.0.0"]);
+  let builds = parsed["builds"].as_array().unwrap();
+  assert_eq!(builds.len(), 1);
+  assert_eq!(builds[0]["id"], "processor-1.0.0");
+  assert_eq!(parsed["dependencies"], 1);
+
+  let store_path = builds[0]["store_path"].as_str().unwrap();
+  assert!(std::path::Path::new(store_path).exists());
+
+  let output = env.sys_cmd().args(["snapshot", "list", "-o", "json"]).output().unwrap();
+  let snapshots: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert!(snapshots["current"].is_null(), "build must not create a snapshot");
+}
+
+#[test]
+fn build_by_hash_prefix() {
+  let env = TestEnv::from_fixture("build_only.lua");
+
+  let all = build_json(&env, &[]);
+  let hash = all["builds"][0]["hash"].as_str().unwrap().to_string();
+
+  let parsed = build_json(&env, &["--hash", &hash[..8]]);
+  assert_eq!(parsed["builds"][0]["hash"], hash.as_str());
+  assert_eq!(parsed["builds"][0]["id"], "simple-build-1.0.0");
+}
+
+#[test]
+fn build_rebuild_replaces_cached_output() {
+  let env = TestEnv::from_fixture("build_only.lua");
+
+  let parsed = build_json(&env, &["--id", "simple-build-1.0.0"]);
+  let store_path = std::path::PathBuf::from(parsed["builds"][0]["store_path"].as_str().unwrap());
+  std::fs::write(store_path.join("extra.txt"), "stale").unwrap();
+
+  build_json(&env, &["--id", "simple-build-1.0.0", "--rebuild"]);
+  assert!(
+    !store_path.join("extra.txt").exists(),
+    "rebuild should start from scratch"
+  );
+  assert!(store_path.join("hello.txt").exists());
+}
+
+#[test]
+fn build_unknown_id_fails() {
+  let env = TestEnv::from_fixture("build_only.lua");
+
+  env
+    .sys_cmd()
+    .arg("build")
+    .arg(&env.config_path)
+    .args(["--id", "missing"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("no build matches id 'missing'"));
+}
+
+#[test]
+fn build_keep_going_reports_every_failure() {
+  let env = TestEnv::from_fixture("keep_going.lua");
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "build", "--keep-going", "-o", "json"])
+    .arg(&env.config_path)
+    .output()
+    .unwrap();
+  assert!(!output.status.success());
+
+  let failures: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  let mut failed: Vec<(&str, usize)> = failures
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|f| (f["id"].as_str().unwrap(), f["skipped"].as_array().unwrap().len()))
+    .collect();
+  failed.sort();
+  assert_eq!(failed, vec![("broken-a", 1), ("broken-b", 0)]);
+
+  let healthy = std::fs::read_dir(env.root_path().join("store").join("build"))
+    .unwrap()
+    .any(|entry| entry.unwrap().path().join("ok.txt").exists());
+  assert!(healthy, "independent build should still be realized");
+}
+
+#[test]
+fn build_stops_at_first_failure_by_default() {
+  let env = TestEnv::from_fixture("keep_going.lua");
+
+  env
+    .sys_cmd()
+    .arg("build")
+    .arg(&env.config_path)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Failed broken-").not());
+}
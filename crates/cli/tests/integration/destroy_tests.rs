@@ -98,3 +98,27 @@ fn destroy_dry_run_shows_plan() {
     "marker file should be removed after actual destroy"
   );
 }
+
+#[test]
+fn destroy_writes_events_file() {
+  let env = TestEnv::from_fixture("bind_create.lua");
+  let events_path = env.output_path().join("events.jsonl");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+  env
+    .sys_cmd()
+    .args(["destroy", "--events-file"])
+    .arg(&events_path)
+    .assert()
+    .success();
+
+  let events: Vec<serde_json::Value> = std::fs::read_to_string(&events_path)
+    .unwrap()
+    .lines()
+    .map(|line| serde_json::from_str(line).unwrap())
+    .collect();
+  assert_eq!(events.len(), 2);
+  assert_eq!(events[0]["event"], "node_started");
+  assert_eq!(events[1]["event"], "node_succeeded");
+  assert_eq!(events[1]["id"], "test-bind");
+}
@@ -0,0 +1,85 @@
+//! Drift command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+#[test]
+fn drift_requires_snapshot() {
+  let env = TestEnv::empty();
+
+  env
+    .sys_cmd()
+    .arg("drift")
+    .assert()
+    .failure()
+    .code(1)
+    .stderr(predicate::str::contains("No current snapshot"));
+}
+
+#[test]
+fn drift_reports_clean_binds() {
+  let env = TestEnv::from_fixture("bind_check.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  env
+    .sys_cmd()
+    .arg("drift")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("No drift detected"));
+}
+
+#[test]
+fn drift_exits_with_distinct_code() {
+  let env = TestEnv::from_fixture("bind_check.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+  std::fs::remove_file(env.output_path().join("check-marker.txt")).expect("failed to delete marker file");
+
+  env
+    .sys_cmd()
+    .arg("drift")
+    .assert()
+    .code(2)
+    .stdout(predicate::str::contains("Drift detected: 1 bind(s)"))
+    .stdout(predicate::str::contains("check-test"))
+    .stdout(predicate::str::contains("file does not exist"));
+}
+
+#[test]
+fn drift_json_output() {
+  let env = TestEnv::from_fixture("bind_check.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+  std::fs::remove_file(env.output_path().join("check-marker.txt")).expect("failed to delete marker file");
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "drift", "--bind", "check-test", "-o", "json"])
+    .output()
+    .expect("failed to run drift");
+  assert_eq!(output.status.code(), Some(2));
+
+  let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("drift output should be JSON");
+  assert_eq!(report["checked"], 1);
+  assert_eq!(report["drifted"], 1);
+  assert_eq!(report["binds"][0]["id"], "check-test");
+  assert_eq!(report["binds"][0]["drifted"], true);
+  assert_eq!(report["binds"][0]["message"], "file does not exist");
+}
+
+#[test]
+fn drift_unknown_bind_fails() {
+  let env = TestEnv::from_fixture("bind_check.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  env
+    .sys_cmd()
+    .args(["drift", "--bind", "missing"])
+    .assert()
+    .code(1)
+    .stderr(predicate::str::contains("No bind with id 'missing'"));
+}
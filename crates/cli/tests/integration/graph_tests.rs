@@ -0,0 +1,84 @@
+//! Graph command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+fn graph_json(env: &TestEnv, args: &[&str]) -> serde_json::Value {
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "graph"])
+    .args(args)
+    .args(["-f", "json"])
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "graph failed: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  serde_json::from_slice(&output.stdout).expect("valid JSON")
+}
+
+#[test]
+fn graph_dot_shows_build_dependencies() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  env
+    .sys_cmd()
+    .args(["-l", "error", "graph"])
+    .arg(&env.config_path)
+    .assert()
+    .success()
+    .stdout(predicate::str::starts_with("digraph syslua {"))
+    .stdout(predicate::str::contains("data-1.0.0"))
+    .stdout(predicate::str::contains("processor-1.0.0"))
+    .stdout(predicate::str::contains("\" -> \""));
+}
+
+#[test]
+fn graph_mermaid_output() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  env
+    .sys_cmd()
+    .args(["-l", "error", "graph"])
+    .arg(&env.config_path)
+    .args(["-f", "mermaid"])
+    .assert()
+    .success()
+    .stdout(predicate::str::starts_with("flowchart LR"))
+    .stdout(predicate::str::contains(":::to_realize"));
+}
+
+#[test]
+fn graph_json_reflects_store_state() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+  let config = env.config_path.to_string_lossy().to_string();
+
+  let before = graph_json(&env, &[&config]);
+  assert_eq!(before["nodes"].as_array().unwrap().len(), 2);
+  assert_eq!(before["edges"].as_array().unwrap().len(), 1);
+  assert!(
+    before["nodes"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .all(|n| n["state"] == "to_realize")
+  );
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  let output = env.sys_cmd().args(["snapshot", "list", "-o", "json"]).output().unwrap();
+  let snapshots: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  let current = snapshots["current"].as_str().unwrap().to_string();
+
+  let after = graph_json(&env, &["--snapshot", &current]);
+  assert!(
+    after["nodes"]
+      .as_array()
+      .unwrap()
+      .iter()
+      .all(|n| n["state"] == "cached")
+  );
+}
@@ -0,0 +1,90 @@
+//! Log command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+#[test]
+fn log_shows_every_action_of_a_build() {
+  let env = TestEnv::from_fixture("build_with_exec.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  env
+    .sys_cmd()
+    .args(["log", "hello-1.0.0"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("create action 0"))
+    .stdout(predicate::str::contains("create action 2"))
+    .stdout(predicate::str::contains("exit: exit status: 0"));
+}
+
+#[test]
+fn log_filters_by_action() {
+  let env = TestEnv::from_fixture("build_with_exec.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "log", "hello-1.0.0", "--action", "1", "-o", "json"])
+    .output()
+    .expect("failed to run log");
+  assert!(output.status.success());
+
+  let report: serde_json::Value = serde_json::from_slice(&output.stdout).expect("log output should be JSON");
+  let logs = report["logs"].as_array().unwrap();
+  assert_eq!(logs.len(), 1);
+  assert_eq!(logs[0]["phase"], "create");
+  assert_eq!(logs[0]["action"], 1);
+  assert!(logs[0]["content"].as_str().unwrap().contains("exec: "));
+}
+
+#[test]
+fn log_explains_failed_build() {
+  let env = TestEnv::from_fixture("build_failure_output.lua");
+
+  // The tail of stderr is part of the error, so no rerun is needed
+  env
+    .sys_cmd()
+    .arg("apply")
+    .arg(&env.config_path)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("compiler exploded"))
+    .stderr(predicate::str::contains("sys log"));
+
+  // The failed build is not in any snapshot, so look it up by hash
+  let log_dir = env.root_path().join("store").join("log");
+  let hash = std::fs::read_dir(&log_dir)
+    .unwrap()
+    .next()
+    .expect("failed build should have logs")
+    .unwrap()
+    .file_name()
+    .into_string()
+    .unwrap();
+
+  env
+    .sys_cmd()
+    .args(["log", &hash[..8]])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("stdout: configuring"))
+    .stdout(predicate::str::contains("stderr: compiler exploded"));
+}
+
+#[test]
+fn log_unknown_target_fails() {
+  let env = TestEnv::from_fixture("build_with_exec.lua");
+
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  env
+    .sys_cmd()
+    .args(["log", "no-such-build"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("No logs for 'no-such-build'"));
+}
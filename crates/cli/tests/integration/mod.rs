@@ -1,12 +1,20 @@
 pub mod apply_tests;
+pub mod build_tests;
 pub mod common;
 pub mod destroy_tests;
+pub mod drift_tests;
 pub mod gc_tests;
+pub mod graph_tests;
 pub mod inputs_tests;
+pub mod log_tests;
 pub mod pkgs_tests;
 pub mod plan_tests;
+pub mod repl_tests;
 pub mod rollback_tests;
 pub mod script_tests;
+pub mod shell_tests;
 pub mod snapshot_tests;
+pub mod store_tests;
 pub mod update_tests;
+pub mod why_tests;
 pub mod windows_tests;
@@ -55,3 +55,90 @@ fn plan_bind_shows_count() {
     .success()
     .stdout(predicate::str::contains("Binds: 1"));
 }
+
+/// Run `sys plan` and return the plan hash from its JSON output.
+fn plan_hash(env: &TestEnv) -> String {
+  let output = env
+    .sys_cmd()
+    .args(["plan", "-l", "error", "-o", "json"])
+    .arg(&env.config_path)
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  parsed["plan_hash"].as_str().expect("plan hash").to_string()
+}
+
+#[test]
+fn apply_saved_plan_without_config() {
+  let env = TestEnv::from_fixture("build_only.lua");
+  let hash = plan_hash(&env);
+
+  // The plan must be applied without evaluating the config again.
+  std::fs::remove_file(&env.config_path).unwrap();
+
+  env
+    .sys_cmd()
+    .args(["apply", "--plan", &hash])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Apply complete!"));
+
+  let output = env.sys_cmd().args(["snapshot", "list", "-o", "json"]).output().unwrap();
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert!(parsed["current"].is_string());
+}
+
+#[test]
+fn apply_tampered_plan_fails() {
+  let env = TestEnv::from_fixture("build_only.lua");
+  let hash = plan_hash(&env);
+
+  let manifest_path = env.root_path().join("plans").join(&hash).join("manifest.json");
+  let mut manifest: serde_json::Value =
+    serde_json::from_str(&std::fs::read_to_string(&manifest_path).unwrap()).unwrap();
+  manifest["builds"] = serde_json::json!({});
+  std::fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+  env
+    .sys_cmd()
+    .args(["apply", "--plan", &hash])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("plan hash mismatch"));
+}
+
+#[test]
+fn apply_unknown_plan_fails() {
+  let env = TestEnv::empty();
+
+  env
+    .sys_cmd()
+    .args(["apply", "--plan", "0123456789abcdef0123"])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("plan not found"));
+}
+
+#[test]
+fn apply_outdated_plan_requires_force() {
+  let env = TestEnv::from_fixture("build_only.lua");
+  let hash = plan_hash(&env);
+
+  // Another apply moves the current snapshot on after the plan was made
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  env
+    .sys_cmd()
+    .args(["apply", "--plan", &hash])
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("run `sys plan` again"));
+
+  env
+    .sys_cmd()
+    .args(["apply", "--plan", &hash, "--force"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Apply complete!"));
+}
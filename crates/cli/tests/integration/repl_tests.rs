@@ -0,0 +1,35 @@
+//! REPL command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+#[test]
+fn repl_evaluates_input_and_runs_setup() {
+  let env = TestEnv::from_fixture("build_only.lua");
+
+  env
+    .sys_cmd()
+    .args(["-l", "error", "repl"])
+    .arg(&env.config_path)
+    .write_stdin("1 +\n  41\n:setup\n:builds\n:quit\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("42"))
+    .stdout(predicate::str::contains("simple-build-1.0.0"));
+}
+
+#[test]
+fn repl_reports_lua_errors_and_continues() {
+  let env = TestEnv::from_fixture("build_only.lua");
+
+  env
+    .sys_cmd()
+    .args(["-l", "error", "repl"])
+    .arg(&env.config_path)
+    .write_stdin("error('boom')\nreturn 'still here'\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("\"still here\""))
+    .stderr(predicate::str::contains("boom"));
+}
@@ -40,3 +40,74 @@ fn build_failure_skips_dependent_binds() {
 
   assert!(!marker_file.exists(), "dependent bind should not have run");
 }
+
+/// Apply the `rollback_bind_failure.lua` fixture in the given phase and return the new snapshot ID.
+fn apply_phase(env: &TestEnv, phase: &str) -> String {
+  let output = env
+    .sys_cmd()
+    .args(["apply", "--impure", "-l", "error", "-o", "json"])
+    .arg(&env.config_path)
+    .env("TEST_PHASE", phase)
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "apply failed: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  parsed["snapshot"]["id"].as_str().expect("snapshot ID").to_string()
+}
+
+#[test]
+fn rollback_command_restores_tagged_snapshot() {
+  let env = TestEnv::from_fixture("rollback_bind_failure.lua");
+  let marker_file = env.output_path().join("original.txt");
+
+  let initial_id = apply_phase(&env, "initial");
+  env
+    .sys_cmd()
+    .args(["snapshot", "tag", &initial_id, "good"])
+    .assert()
+    .success();
+
+  apply_phase(&env, "empty");
+  assert!(
+    !marker_file.exists(),
+    "original.txt should be destroyed by second apply"
+  );
+
+  env.sys_cmd().args(["rollback", "good"]).assert().success();
+  assert!(marker_file.exists(), "original.txt should be re-created by rollback");
+
+  let output = env.sys_cmd().args(["snapshot", "list", "-o", "json"]).output().unwrap();
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert_eq!(parsed["current"].as_str(), Some(initial_id.as_str()));
+}
+
+#[test]
+fn rollback_dry_run_reports_diff_without_changes() {
+  let env = TestEnv::from_fixture("rollback_bind_failure.lua");
+  let marker_file = env.output_path().join("original.txt");
+
+  let initial_id = apply_phase(&env, "initial");
+  apply_phase(&env, "empty");
+
+  let output = env
+    .sys_cmd()
+    .args(["rollback", &initial_id, "--dry-run", "-l", "error", "-o", "json"])
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert_eq!(parsed["snapshot"]["id"].as_str(), Some(initial_id.as_str()));
+  assert_eq!(parsed["diff"]["binds_to_apply"].as_array().map(|a| a.len()), Some(1));
+  assert!(!marker_file.exists(), "dry run should not re-create binds");
+}
+
+#[test]
+fn rollback_unknown_snapshot_fails() {
+  let env = TestEnv::from_fixture("minimal.lua");
+  env.sys_cmd().args(["rollback", "does-not-exist"]).assert().failure();
+}
@@ -0,0 +1,47 @@
+//! Shell command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+#[test]
+#[cfg(unix)]
+fn shell_runs_command_with_build_on_path() {
+  let env = TestEnv::from_fixture("shell_build.lua");
+
+  env
+    .sys_cmd()
+    .arg("shell")
+    .arg(&env.config_path)
+    .args(["--", "hello-shell"])
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("hello from shell"));
+
+  assert!(
+    !env.output_path().join("bind-ran.txt").exists(),
+    "binds must not run in a shell"
+  );
+
+  let output = env.sys_cmd().args(["snapshot", "list", "-o", "json"]).output().unwrap();
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert!(parsed["current"].is_null(), "shell must not create a snapshot");
+
+  let roots = env.root_path().join("store").join("gcroots");
+  let leftover = std::fs::read_dir(&roots).map(|d| d.count()).unwrap_or(0);
+  assert_eq!(leftover, 0, "gc root should be released after the shell exits");
+}
+
+#[test]
+#[cfg(unix)]
+fn shell_propagates_command_failure() {
+  let env = TestEnv::from_fixture("shell_build.lua");
+
+  env
+    .sys_cmd()
+    .arg("shell")
+    .arg(&env.config_path)
+    .args(["--", "sh", "-c", "exit 3"])
+    .assert()
+    .failure();
+}
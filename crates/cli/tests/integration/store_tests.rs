@@ -0,0 +1,196 @@
+//! Store command integration tests.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+fn verify_json(env: &TestEnv, args: &[&str]) -> (bool, serde_json::Value) {
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "store", "verify", "-o", "json"])
+    .args(args)
+    .output()
+    .unwrap();
+  let report = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  (output.status.success(), report)
+}
+
+/// Apply the build-only fixture and return the path of its build output.
+fn apply_build(env: &TestEnv) -> PathBuf {
+  env.sys_cmd().arg("apply").arg(&env.config_path).assert().success();
+
+  build_output(env)
+}
+
+#[test]
+fn verify_empty_store_succeeds() {
+  let env = TestEnv::empty();
+
+  let (success, report) = verify_json(&env, &[]);
+  assert!(success);
+  assert_eq!(report["summary"]["scanned"], 0);
+  assert!(report["problems"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn verify_detects_corrupted_build() {
+  let env = TestEnv::from_fixture("build_only.lua");
+  let build = apply_build(&env);
+
+  let (success, report) = verify_json(&env, &[]);
+  assert!(success, "intact store should verify: {report}");
+  assert_eq!(report["summary"]["ok"], 1);
+
+  std::fs::write(build.join("hello.txt"), "tampered\n").unwrap();
+
+  let (success, report) = verify_json(&env, &[]);
+  assert!(!success);
+  assert_eq!(report["summary"]["corrupted"], 1);
+  let problem = &report["problems"][0];
+  assert_eq!(problem["status"], "corrupted");
+  assert_eq!(problem["referenced"], true);
+}
+
+#[test]
+fn verify_repair_rebuilds_referenced_build() {
+  let env = TestEnv::from_fixture("build_only.lua");
+  let build = apply_build(&env);
+  std::fs::write(build.join("hello.txt"), "tampered\n").unwrap();
+
+  let (success, report) = verify_json(&env, &["--repair"]);
+  assert!(success, "repair should succeed: {report}");
+  assert_eq!(report["repaired"].as_array().unwrap().len(), 1);
+
+  let content = std::fs::read_to_string(build.join("hello.txt")).unwrap();
+  assert_eq!(content.trim(), "hello");
+
+  let (success, report) = verify_json(&env, &[]);
+  assert!(success);
+  assert_eq!(report["summary"]["corrupted"], 0);
+}
+
+/// Serve `root` as static files over HTTP for the rest of the test process.
+fn serve(root: PathBuf) -> String {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let addr = listener.local_addr().unwrap();
+  std::thread::spawn(move || {
+    for mut stream in listener.incoming().flatten() {
+      let mut line = String::new();
+      if BufReader::new(&stream).read_line(&mut line).is_err() {
+        continue;
+      }
+      let path = line.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+      let _ = match std::fs::read(root.join(path)) {
+        Ok(body) => write!(
+          stream,
+          "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+          body.len()
+        )
+        .and_then(|_| stream.write_all(&body)),
+        Err(_) => write!(
+          stream,
+          "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        ),
+      };
+    }
+  });
+  format!("http://{}", addr)
+}
+
+/// Sign and push the build-only fixture's build from a fresh environment into
+/// `cache`, returning the signing key's public key.
+fn push_fixture(cache: &Path) -> String {
+  let env = TestEnv::from_fixture("build_only.lua");
+  env.sys_cmd().arg("build").arg(&env.config_path).assert().success();
+
+  let output = env
+    .sys_cmd()
+    .args(["key", "generate", "test-cache-1", "-o", "json"])
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  let key: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "store", "push", "-o", "json", "--sign-key"])
+    .arg(key["secret_key_file"].as_str().unwrap())
+    .arg(cache)
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+  let result: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+  assert_eq!(result["pushed"].as_array().unwrap().len(), 1);
+
+  key["public_key"].as_str().unwrap().to_string()
+}
+
+/// A fresh environment that trusts `public_key`.
+fn trusting_env(public_key: &str) -> TestEnv {
+  let env = TestEnv::from_fixture("build_only.lua");
+  std::fs::write(env.root_path().join("trusted-keys"), format!("{}\n", public_key)).unwrap();
+  env
+}
+
+fn build_with(env: &TestEnv, substituter: &str) -> assert_cmd::assert::Assert {
+  env
+    .sys_cmd()
+    .env("SYSLUA_SUBSTITUTERS", substituter)
+    .args(["-l", "info", "build"])
+    .arg(&env.config_path)
+    .assert()
+    .success()
+}
+
+fn build_substituted(env: &TestEnv, substituter: &str) {
+  build_with(env, substituter).stdout(predicates::str::contains("substituted build from cache"));
+
+  let output = build_output(env);
+  assert_eq!(
+    std::fs::read_to_string(output.join("hello.txt")).unwrap().trim(),
+    "hello"
+  );
+}
+
+/// The build-only fixture's output directory in the store.
+fn build_output(env: &TestEnv) -> PathBuf {
+  std::fs::read_dir(env.root_path().join("store").join("build"))
+    .unwrap()
+    .map(|e| e.unwrap().path())
+    .find(|p| p.join("hello.txt").exists())
+    .expect("build output in store")
+}
+
+#[test]
+fn push_and_substitute_from_directory() {
+  let cache = tempfile::TempDir::new().unwrap();
+  let public_key = push_fixture(cache.path());
+
+  let env = trusting_env(&public_key);
+  build_substituted(&env, cache.path().to_str().unwrap());
+}
+
+#[test]
+fn substitute_from_http_cache() {
+  let cache = tempfile::TempDir::new().unwrap();
+  let public_key = push_fixture(cache.path());
+  let url = serve(cache.path().to_path_buf());
+
+  let env = trusting_env(&public_key);
+  build_substituted(&env, &url);
+}
+
+#[test]
+fn untrusted_cache_falls_back_to_building() {
+  let cache = tempfile::TempDir::new().unwrap();
+  push_fixture(cache.path());
+
+  let env = TestEnv::from_fixture("build_only.lua");
+  build_with(&env, cache.path().to_str().unwrap())
+    .stdout(predicates::str::contains("substituted build from cache").not());
+  assert!(build_output(&env).join("hello.txt").exists());
+}
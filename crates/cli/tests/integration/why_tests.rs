@@ -0,0 +1,66 @@
+//! Why command integration tests.
+
+use predicates::prelude::*;
+
+use super::common::TestEnv;
+
+#[test]
+fn why_lists_paths_with_sources() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  let output = env
+    .sys_cmd()
+    .args(["-l", "error", "why", "data-1.0.0"])
+    .arg(&env.config_path)
+    .args(["-o", "json"])
+    .output()
+    .unwrap();
+  assert!(
+    output.status.success(),
+    "why failed: {}",
+    String::from_utf8_lossy(&output.stderr)
+  );
+  let parsed: serde_json::Value = serde_json::from_slice(&output.stdout).expect("valid JSON");
+
+  assert_eq!(parsed["target"]["id"], "data-1.0.0");
+  let paths = parsed["paths"].as_array().unwrap();
+  assert_eq!(paths.len(), 1);
+  let ids: Vec<&str> = paths[0]
+    .as_array()
+    .unwrap()
+    .iter()
+    .map(|n| n["id"].as_str().unwrap())
+    .collect();
+  assert_eq!(ids, vec!["processor-1.0.0", "data-1.0.0"]);
+
+  let source = &parsed["target"]["source"];
+  assert!(source["file"].as_str().unwrap().ends_with("init.lua"));
+  assert!(source["line"].as_u64().is_some());
+}
+
+#[test]
+fn why_text_output() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  env
+    .sys_cmd()
+    .args(["-l", "error", "why", "data-1.0.0"])
+    .arg(&env.config_path)
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Required through 1 path(s)"))
+    .stdout(predicate::str::contains("processor-1.0.0"));
+}
+
+#[test]
+fn why_unknown_target_fails() {
+  let env = TestEnv::from_fixture("multi_build.lua");
+
+  env
+    .sys_cmd()
+    .args(["why", "nope"])
+    .arg(&env.config_path)
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("no build or bind matches 'nope'"));
+}
@@ -4,14 +4,25 @@
 //! following Nix-inspired principles.
 
 use std::collections::BTreeMap;
+use std::io;
 use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::time::{Duration, Instant};
 
 use mlua::prelude::*;
 use serde::{Deserialize, Serialize};
-use tokio::process::Command;
-use tracing::{debug, info};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tracing::{debug, info, warn};
 
+use super::whole_number;
+use crate::action::log::ActionLog;
+use crate::execute::events::OutputStream;
 use crate::execute::types::ExecuteError;
+use crate::platform::sandbox::Sandbox;
+
+/// Number of stderr lines kept in the error of a failed command.
+const STDERR_TAIL_LINES: usize = 10;
 
 /// Options for executing a shell command in a build.
 ///
@@ -42,6 +53,15 @@ pub struct ExecOpts {
   pub env: Option<BTreeMap<String, String>>,
   /// Optional working directory.
   pub cwd: Option<String>,
+  /// Kill the command if it runs longer than this many seconds.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub timeout: Option<u64>,
+  /// Maximum address space of the command in bytes (`RLIMIT_AS`, Unix only).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub memory_limit: Option<u64>,
+  /// Maximum CPU time of the command in seconds (`RLIMIT_CPU`, Unix only).
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub cpu_time_limit: Option<u64>,
 }
 
 impl ExecOpts {
@@ -52,6 +72,9 @@ impl ExecOpts {
       args: None,
       env: None,
       cwd: None,
+      timeout: None,
+      memory_limit: None,
+      cpu_time_limit: None,
     }
   }
 
@@ -72,6 +95,44 @@ impl ExecOpts {
     self.cwd = Some(cwd.to_string());
     self
   }
+
+  /// Set the timeout in seconds.
+  pub fn with_timeout(mut self, seconds: u64) -> Self {
+    self.timeout = Some(seconds);
+    self
+  }
+
+  /// Set the memory limit in bytes.
+  pub fn with_memory_limit(mut self, bytes: u64) -> Self {
+    self.memory_limit = Some(bytes);
+    self
+  }
+
+  /// Set the CPU time limit in seconds.
+  pub fn with_cpu_time_limit(mut self, seconds: u64) -> Self {
+    self.cpu_time_limit = Some(seconds);
+    self
+  }
+
+  /// The limits the command runs under.
+  pub fn limits(&self) -> ExecLimits {
+    ExecLimits {
+      timeout: self.timeout.map(Duration::from_secs),
+      memory_limit: self.memory_limit,
+      cpu_time_limit: self.cpu_time_limit,
+    }
+  }
+}
+
+/// Resource limits for a single command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ExecLimits {
+  /// Wall-clock time after which the command's process group is killed.
+  pub timeout: Option<Duration>,
+  /// Maximum address space in bytes.
+  pub memory_limit: Option<u64>,
+  /// Maximum CPU time in seconds.
+  pub cpu_time_limit: Option<u64>,
 }
 
 impl From<&str> for ExecOpts {
@@ -91,8 +152,14 @@ pub fn parse_exec_opts(opts: LuaValue, args: Option<LuaValue>) -> LuaResult<Exec
       let args: Option<Vec<String>> = table.get("args")?;
       let cwd: Option<String> = table.get("cwd")?;
       let env: Option<LuaTable> = table.get("env")?;
+      let timeout = whole_number(&table, "exec", "timeout", "seconds")?;
+      let memory_limit = whole_number(&table, "exec", "memory_limit", "bytes")?;
+      let cpu_time_limit = whole_number(&table, "exec", "cpu_time_limit", "seconds")?;
 
       let mut opts = ExecOpts::new(&bin);
+      opts.timeout = timeout;
+      opts.memory_limit = memory_limit;
+      opts.cpu_time_limit = cpu_time_limit;
 
       let mut args_vec = Vec::new();
       if let Some(a) = args {
@@ -147,24 +214,45 @@ pub fn parse_exec_opts(opts: LuaValue, args: Option<LuaValue>) -> LuaResult<Exec
 /// - Sets TMPDIR/TMP/TEMP/TEMPDIR to a temp directory within out_dir
 /// - Sets `out` to the output directory
 /// - Merges user-specified environment variables
+/// - On Linux, confines the command to `sandbox` if one is given (see
+///   [`crate::platform::sandbox`])
+/// - On Unix, applies the memory and CPU time `limits` as rlimits
+/// - Kills the command and its process group once the timeout in `limits` expires
 ///
 /// # Arguments
 ///
 /// * `opts` - The command options to execute
 /// * `out_dir` - The build's output directory
+/// * `limits` - Timeout and resource limits
+/// * `sandbox` - The sandbox to run in, `None` to run unconfined
+/// * `log` - The action log that stdout and stderr are copied to
 ///
 /// # Returns
 ///
-/// The stdout of the command on success (trimmed).
+/// The stdout of the command on success (trimmed). On failure, the error
+/// carries the last lines of stderr.
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_cmd(
   cmd: &str,
   args: Option<&Vec<String>>,
   env: Option<&BTreeMap<String, String>>,
   cwd: Option<&str>,
   out_dir: &Path,
+  limits: ExecLimits,
+  sandbox: Option<&Sandbox>,
+  log: Option<&ActionLog>,
 ) -> Result<String, ExecuteError> {
   info!(cmd = %cmd, "executing command");
 
+  if let Some(log) = log {
+    let mut line = cmd.to_string();
+    for arg in args.into_iter().flatten() {
+      line.push(' ');
+      line.push_str(arg);
+    }
+    log.line("exec", &line);
+  }
+
   // Create temp directory for the build
   let tmp_dir = out_dir.join("tmp");
   tokio::fs::create_dir_all(&tmp_dir).await?;
@@ -222,14 +310,77 @@ pub async fn execute_cmd(
     }
   }
 
-  debug!(cmd = %cmd,  working_dir = ?working_dir, "spawning process");
+  // The sandbox root must outlive the command
+  #[cfg(target_os = "linux")]
+  let _sandbox_root = match sandbox {
+    Some(sandbox) => Some(
+      sandbox
+        .confine(&mut command, working_dir)
+        .map_err(|e| ExecuteError::Sandbox { message: e.to_string() })?,
+    ),
+    None => None,
+  };
+
+  #[cfg(unix)]
+  set_rlimits(&mut command, &limits);
+
+  // A timed command leads its own process group, so that whatever it started
+  // can be killed along with it
+  if limits.timeout.is_some() {
+    #[cfg(unix)]
+    command.process_group(0);
+    command.kill_on_drop(true);
+  }
+
+  debug!(cmd = %cmd,  working_dir = ?working_dir, sandboxed = sandbox.is_some(), limits = ?limits, "spawning process");
 
-  let output = command.output().await?;
+  let start = Instant::now();
+  let mut child = match command
+    .stdin(Stdio::null())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+  {
+    Ok(child) => child,
+    Err(e) if sandbox.is_some() && cfg!(target_os = "linux") => {
+      return Err(ExecuteError::Sandbox {
+        message: format!("failed to start {}: {}", cmd, e),
+      });
+    }
+    Err(e) => return Err(e.into()),
+  };
+
+  let pid = child.id();
+  let run = capture_output(&mut child, log);
+  let (status, stdout, stderr) = match limits.timeout {
+    None => run.await?,
+    Some(timeout) => match tokio::time::timeout(timeout, run).await {
+      Ok(output) => output?,
+      Err(_) => {
+        let elapsed = start.elapsed();
+        warn!(cmd = %cmd, elapsed = ?elapsed, "command timed out, killing it");
+        if let Some(log) = log {
+          log.line("exit", &format!("timed out after {}s", elapsed.as_secs()));
+        }
+        #[cfg(unix)]
+        if let Some(pid) = pid {
+          kill_process_group(pid);
+        }
+        #[cfg(not(unix))]
+        let _ = pid;
+        return Err(ExecuteError::Timeout {
+          cmd: cmd.to_string(),
+          elapsed,
+        });
+      }
+    },
+  };
 
-  if !output.status.success() {
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
+  if let Some(log) = log {
+    log.line("exit", &status.to_string());
+  }
 
+  if !status.success() {
     // Log output for debugging
     if !stderr.is_empty() {
       debug!(stderr = %stderr, "command stderr");
@@ -240,11 +391,12 @@ pub async fn execute_cmd(
 
     return Err(ExecuteError::CmdFailed {
       cmd: cmd.to_string(),
-      code: output.status.code(),
+      code: status.code(),
+      stderr: last_lines(&stderr, STDERR_TAIL_LINES),
     });
   }
 
-  let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+  let stdout = stdout.trim().to_string();
 
   if !stdout.is_empty() {
     debug!(stdout = %stdout, "command output");
@@ -253,6 +405,88 @@ pub async fn execute_cmd(
   Ok(stdout)
 }
 
+/// Read stdout and stderr of `child` until both are closed, copying each line
+/// to `log`, then wait for it to exit.
+async fn capture_output(child: &mut Child, log: Option<&ActionLog>) -> io::Result<(ExitStatus, String, String)> {
+  let stdout = child.stdout.take().expect("stdout is piped");
+  let stderr = child.stderr.take().expect("stderr is piped");
+  let (stdout, stderr) = tokio::try_join!(
+    read_lines(stdout, OutputStream::Stdout, log),
+    read_lines(stderr, OutputStream::Stderr, log)
+  )?;
+  let status = child.wait().await?;
+  Ok((status, stdout, stderr))
+}
+
+async fn read_lines(pipe: impl AsyncRead + Unpin, stream: OutputStream, log: Option<&ActionLog>) -> io::Result<String> {
+  let mut reader = BufReader::new(pipe);
+  let mut collected = Vec::new();
+  let mut line = Vec::new();
+  loop {
+    line.clear();
+    if reader.read_until(b'\n', &mut line).await? == 0 {
+      break;
+    }
+    if let Some(log) = log {
+      log.output(stream, String::from_utf8_lossy(&line).trim_end_matches(['\n', '\r']));
+    }
+    collected.extend_from_slice(&line);
+  }
+  Ok(String::from_utf8_lossy(&collected).into_owned())
+}
+
+/// The last `count` non-empty lines of `text`.
+fn last_lines(text: &str, count: usize) -> String {
+  let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+  lines[lines.len().saturating_sub(count)..].join("\n")
+}
+
+/// Apply the memory and CPU time limits in the child before it runs.
+#[cfg(unix)]
+fn set_rlimits(command: &mut Command, limits: &ExecLimits) {
+  use rustix::process::{Resource, Rlimit, setrlimit};
+
+  let memory_limit = limits.memory_limit;
+  let cpu_time_limit = limits.cpu_time_limit;
+  if memory_limit.is_none() && cpu_time_limit.is_none() {
+    return;
+  }
+
+  // SAFETY: setrlimit is async-signal-safe and nothing is allocated.
+  unsafe {
+    command.pre_exec(move || {
+      if let Some(bytes) = memory_limit {
+        let limit = Rlimit {
+          current: Some(bytes),
+          maximum: Some(bytes),
+        };
+        setrlimit(Resource::As, limit)?;
+      }
+      if let Some(seconds) = cpu_time_limit {
+        let limit = Rlimit {
+          current: Some(seconds),
+          maximum: Some(seconds),
+        };
+        setrlimit(Resource::Cpu, limit)?;
+      }
+      Ok(())
+    });
+  }
+}
+
+/// Kill every process in the group led by `pid`.
+#[cfg(unix)]
+fn kill_process_group(pid: u32) {
+  use rustix::process::{Pid, Signal};
+
+  let Some(pgid) = i32::try_from(pid).ok().and_then(Pid::from_raw) else {
+    return;
+  };
+  if let Err(e) = rustix::process::kill_process_group(pgid, Signal::KILL) {
+    debug!(pid, error = %e, "failed to kill process group");
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -265,7 +499,9 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = echo_msg("hello");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result, "hello");
   }
@@ -279,7 +515,18 @@ mod tests {
     env.insert("MY_VAR".to_string(), "my_value".to_string());
 
     let (cmd, args) = shell_echo_env("MY_VAR");
-    let result = execute_cmd(cmd, Some(&args), Some(&env), None, out_dir).await.unwrap();
+    let result = execute_cmd(
+      cmd,
+      Some(&args),
+      Some(&env),
+      None,
+      out_dir,
+      ExecLimits::default(),
+      None,
+      None,
+    )
+    .await
+    .unwrap();
 
     assert_eq!(result, "my_value");
   }
@@ -290,7 +537,9 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = shell_echo_env("out");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result, out_dir.to_string_lossy());
   }
@@ -301,7 +550,9 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = shell_echo_env("PATH");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     #[cfg(unix)]
     assert_eq!(result, "/path-not-set");
@@ -321,7 +572,9 @@ mod tests {
 
     // SystemRoot should be preserved for Windows to function properly
     let (cmd, args) = shell_echo_env("SystemRoot");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     // SystemRoot is typically C:\Windows or similar
     assert!(!result.is_empty(), "SystemRoot should be preserved");
@@ -338,7 +591,9 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = shell_echo_env("SOURCE_DATE_EPOCH");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result, "315532800");
   }
@@ -349,11 +604,58 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = shell_cmd("exit 1");
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await;
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None).await;
 
     assert!(matches!(result, Err(ExecuteError::CmdFailed { code: Some(1), .. })));
   }
 
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn execute_command_failure_keeps_stderr_tail_and_log() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path();
+    let log_path = out_dir.join("logs").join("create-0.log");
+    let log = ActionLog::create(&log_path).unwrap();
+
+    let (cmd, args) = shell_cmd("echo building; for i in $(/usr/bin/seq 1 15); do echo \"line $i\" >&2; done; exit 3");
+    let result = execute_cmd(
+      cmd,
+      Some(&args),
+      None,
+      None,
+      out_dir,
+      ExecLimits::default(),
+      None,
+      Some(&log),
+    )
+    .await;
+
+    let err = result.unwrap_err();
+    match &err {
+      ExecuteError::CmdFailed { code, stderr, .. } => {
+        assert_eq!(*code, Some(3));
+        assert_eq!(stderr.lines().count(), STDERR_TAIL_LINES);
+        assert_eq!(stderr.lines().next(), Some("line 6"));
+        assert_eq!(stderr.lines().last(), Some("line 15"));
+      }
+      other => panic!("expected CmdFailed, got {:?}", other),
+    }
+    assert!(err.to_string().ends_with("\n    line 15"), "{}", err);
+
+    let content = std::fs::read_to_string(&log_path).unwrap();
+    assert!(
+      content
+        .lines()
+        .next()
+        .unwrap()
+        .contains(" exec: /bin/sh -c echo building;")
+    );
+    assert!(content.contains(" stdout: building\n"));
+    assert!(content.contains(" stderr: line 1\n"));
+    assert!(content.contains(" stderr: line 15\n"));
+    assert!(content.trim_end().ends_with("exit status: 3"), "{}", content);
+  }
+
   #[tokio::test]
   async fn execute_command_with_cwd() {
     let temp_dir = TempDir::new().unwrap();
@@ -365,9 +667,18 @@ mod tests {
 
     // Run a command that creates a marker file in the cwd
     let (cmd, args) = touch_file("cwd_marker");
-    execute_cmd(cmd, Some(&args), None, Some(sub_dir.to_str().unwrap()), out_dir)
-      .await
-      .unwrap();
+    execute_cmd(
+      cmd,
+      Some(&args),
+      None,
+      Some(sub_dir.to_str().unwrap()),
+      out_dir,
+      ExecLimits::default(),
+      None,
+      None,
+    )
+    .await
+    .unwrap();
 
     // Verify the marker file was created in the subdirectory (proving cwd was set correctly)
     assert!(
@@ -382,7 +693,9 @@ mod tests {
     let out_dir = temp_dir.path();
 
     let (cmd, args) = shell_echo_env("TMPDIR");
-    execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     // Verify tmp directory was created
     assert!(out_dir.join("tmp").exists());
@@ -401,7 +714,9 @@ mod tests {
     "#;
 
     let (cmd, args) = shell_cmd(script);
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result, "3");
   }
@@ -417,7 +732,9 @@ mod tests {
     let script = "echo first && echo 3";
 
     let (cmd, args) = shell_cmd(script);
-    let result = execute_cmd(cmd, Some(&args), None, None, out_dir).await.unwrap();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, ExecLimits::default(), None, None)
+      .await
+      .unwrap();
 
     // cmd.exe should execute both commands, output ends with "3"
     assert!(
@@ -426,4 +743,104 @@ mod tests {
       result
     );
   }
+
+  #[test]
+  fn parse_exec_opts_reads_limits() {
+    let lua = Lua::new();
+    let table: LuaTable = lua
+      .load(r#"{ bin = "/bin/make", timeout = 600, memory_limit = 1073741824, cpu_time_limit = 120 }"#)
+      .eval()
+      .unwrap();
+
+    let opts = parse_exec_opts(LuaValue::Table(table), None).unwrap();
+    assert_eq!(opts.timeout, Some(600));
+    assert_eq!(opts.memory_limit, Some(1 << 30));
+    assert_eq!(opts.cpu_time_limit, Some(120));
+
+    let table: LuaTable = lua.load(r#"{ bin = "/bin/make", timeout = 1.5 }"#).eval().unwrap();
+    assert!(parse_exec_opts(LuaValue::Table(table), None).is_err());
+  }
+
+  #[test]
+  fn limits_change_the_action_hash_only_when_set() {
+    let plain = serde_json::to_string(&ExecOpts::new("make")).unwrap();
+    assert!(
+      !plain.contains("timeout"),
+      "unset limits must not be serialized: {}",
+      plain
+    );
+
+    let timed = serde_json::to_string(&ExecOpts::new("make").with_timeout(5)).unwrap();
+    assert_ne!(plain, timed);
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn execute_command_timeout_kills_process_group() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path();
+
+    // The background sleep keeps stdout open after the shell is killed
+    let (cmd, args) = shell_cmd("/bin/sleep 30 & echo $! > bg.pid; /bin/sleep 30");
+    let limits = ExecOpts::new(cmd).with_timeout(1).limits();
+    let start = Instant::now();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, limits, None, None).await;
+
+    match result {
+      Err(ExecuteError::Timeout { elapsed, .. }) => assert!(elapsed >= Duration::from_secs(1)),
+      other => panic!("expected timeout, got {:?}", other),
+    }
+    assert!(start.elapsed() < Duration::from_secs(10));
+
+    #[cfg(target_os = "linux")]
+    {
+      let pid = std::fs::read_to_string(out_dir.join("bg.pid")).unwrap();
+      let stat = std::path::PathBuf::from(format!("/proc/{}/stat", pid.trim()));
+      let mut alive = true;
+      for _ in 0..50 {
+        // Gone, or a zombie waiting to be reaped
+        alive = std::fs::read_to_string(&stat).is_ok_and(|stat| !stat.contains(") Z "));
+        if !alive {
+          break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+      }
+      assert!(!alive, "background process survived the timeout");
+    }
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn execute_command_memory_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path();
+
+    let (cmd, args) = shell_cmd("x=$(/usr/bin/head -c 200000000 /dev/zero | /usr/bin/tr '\\0' a); echo done");
+    let limits = ExecOpts::new(cmd).with_memory_limit(64 << 20).limits();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, limits, None, None).await;
+
+    assert!(
+      matches!(result, Err(ExecuteError::CmdFailed { .. })),
+      "expected failure, got {:?}",
+      result
+    );
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn execute_command_cpu_time_limit() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path();
+
+    let (cmd, args) = shell_cmd("while :; do :; done");
+    let limits = ExecOpts::new(cmd).with_cpu_time_limit(1).with_timeout(30).limits();
+    let result = execute_cmd(cmd, Some(&args), None, None, out_dir, limits, None, None).await;
+
+    // Killed by SIGXCPU, so there is no exit code
+    assert!(
+      matches!(result, Err(ExecuteError::CmdFailed { code: None, .. })),
+      "expected the CPU limit to kill the command, got {:?}",
+      result
+    );
+  }
 }
@@ -0,0 +1,385 @@
+//! FetchGit action implementation.
+//!
+//! Checks out one exact commit of a git repository into the output directory,
+//! without `.git` metadata, using the in-process git client that also fetches
+//! inputs. Builds don't need `git` on the host or on `PATH`.
+//!
+//! Repositories are cached next to the inputs, one per URL:
+//!
+//! ```text
+//! ~/.cache/syslua/inputs/fetch-git-<hash of url>/
+//! ```
+//!
+//! A commit that is already in the cache is checked out without contacting
+//! the remote. The checked-out tree is verified against the expected hash,
+//! computed with [`hash_directory`], before it is moved into the output
+//! directory.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use mlua::prelude::*;
+use tracing::{debug, info};
+
+use crate::consts::OBJ_HASH_PREFIX_LEN;
+use crate::execute::types::ExecuteError;
+use crate::inputs::fetch::fetch_git;
+use crate::platform::paths::cache_dir;
+use crate::util::hash::{hash_bytes, hash_directory};
+
+/// Serializes fetches of the same repository by concurrent builds.
+static REPO_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Parse the arguments of `ctx:fetch_git`.
+///
+/// Accepts `(url, rev, sha256)` or a single table with `url`, `rev` and
+/// `sha256`. `rev` must be a full commit hash, so the output is fixed.
+///
+/// # Returns
+///
+/// The URL, the commit and the expected hash of the checked-out tree.
+pub fn parse_fetch_git_args(
+  url: LuaValue,
+  rev: Option<String>,
+  sha256: Option<String>,
+) -> LuaResult<(String, String, String)> {
+  let (url, rev, sha256) = match url {
+    LuaValue::String(s) => (s.to_str()?.to_string(), rev, sha256),
+    LuaValue::Table(table) => (
+      table
+        .get::<Option<String>>("url")?
+        .ok_or_else(|| LuaError::external("fetch_git() requires a 'url'"))?,
+      table.get::<Option<String>>("rev")?,
+      table.get::<Option<String>>("sha256")?,
+    ),
+    _ => {
+      return Err(LuaError::external(
+        "fetch_git() expects a URL or a table with 'url', 'rev' and 'sha256' fields",
+      ));
+    }
+  };
+
+  let rev = rev.ok_or_else(|| LuaError::external("fetch_git() requires a 'rev'"))?;
+  if !is_commit_hash(&rev) {
+    return Err(LuaError::external(format!(
+      "fetch_git(): 'rev' must be a full commit hash, got '{}'",
+      rev
+    )));
+  }
+  let sha256 = sha256.ok_or_else(|| LuaError::external("fetch_git() requires a 'sha256'"))?;
+  if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+    return Err(LuaError::external(format!(
+      "fetch_git(): 'sha256' must be 64 hex characters, got '{}'",
+      sha256
+    )));
+  }
+
+  Ok((url, rev.to_ascii_lowercase(), sha256.to_ascii_lowercase()))
+}
+
+/// Whether `rev` is a full SHA-1 or SHA-256 commit hash.
+fn is_commit_hash(rev: &str) -> bool {
+  matches!(rev.len(), 40 | 64) && rev.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Execute a FetchGit action.
+///
+/// # Arguments
+///
+/// * `url` - The repository URL, with placeholders already resolved
+/// * `rev` - The commit to check out
+/// * `sha256` - Expected [`hash_directory`] hash of the checked-out tree
+/// * `out_dir` - Directory the tree is checked out into
+///
+/// # Returns
+///
+/// The directory the tree was checked out into.
+pub async fn execute_fetch_git(url: &str, rev: &str, sha256: &str, out_dir: &Path) -> Result<PathBuf, ExecuteError> {
+  let cache_dir = cache_dir().join("inputs");
+  let (url, rev, sha256, out_dir) = (
+    url.to_string(),
+    rev.to_string(),
+    sha256.to_string(),
+    out_dir.to_path_buf(),
+  );
+
+  tokio::task::spawn_blocking(move || fetch_git_tree(&url, &rev, &sha256, &cache_dir, &out_dir))
+    .await
+    .map_err(|e| ExecuteError::TaskFailed { message: e.to_string() })?
+}
+
+/// Check out `rev` of `url` into `out_dir`, using the repository cache in `cache_dir`.
+fn fetch_git_tree(
+  url: &str,
+  rev: &str,
+  sha256: &str,
+  cache_dir: &Path,
+  out_dir: &Path,
+) -> Result<PathBuf, ExecuteError> {
+  let fetch_failed = |message: String| ExecuteError::FetchFailed {
+    url: url.to_string(),
+    message,
+  };
+
+  info!(url, rev, "fetching git commit");
+
+  let name = format!("fetch-git-{}", &hash_bytes(url.as_bytes()).0[..OBJ_HASH_PREFIX_LEN]);
+  let repo_path = cache_dir.join(&name);
+  let lock = REPO_LOCKS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .entry(repo_path.clone())
+    .or_default()
+    .clone();
+  let _guard = lock.lock().unwrap_or_else(|e| e.into_inner());
+
+  let id = gix::ObjectId::from_hex(rev.as_bytes()).map_err(|e| fetch_failed(e.to_string()))?;
+  let cached = repo_path.join(".git").exists()
+    && gix::open(&repo_path)
+      .map(|repo| repo.find_commit(id).is_ok())
+      .unwrap_or(false);
+  if cached {
+    debug!(path = %repo_path.display(), rev, "commit already in cache");
+  } else {
+    fetch_git(&name, url, Some(rev), cache_dir).map_err(|e| fetch_failed(e.to_string()))?;
+  }
+
+  let repo = gix::open(&repo_path).map_err(|e| fetch_failed(e.to_string()))?;
+  let commit = repo
+    .find_commit(id)
+    .map_err(|e| fetch_failed(format!("commit {} not found: {}", rev, e)))?;
+  let tree = commit.tree().map_err(|e| fetch_failed(e.to_string()))?;
+
+  // Check out next to the output so the tree can be verified on its own, then moved
+  fs::create_dir_all(out_dir)?;
+  let staging = tempfile::Builder::new().prefix(".fetch-git-").tempdir_in(out_dir)?;
+  write_tree(&repo, &tree, staging.path()).map_err(|e| fetch_failed(format!("checkout of {} failed: {}", rev, e)))?;
+
+  let actual = hash_directory(staging.path(), &[]).map_err(|e| fetch_failed(e.to_string()))?;
+  if actual.0 != sha256 {
+    return Err(ExecuteError::HashMismatch {
+      url: format!("{}#{}", url, rev),
+      expected: sha256.to_string(),
+      actual: actual.0,
+    });
+  }
+
+  for entry in fs::read_dir(staging.path())? {
+    let entry = entry?;
+    fs::rename(entry.path(), out_dir.join(entry.file_name()))?;
+  }
+
+  info!(url, rev, path = %out_dir.display(), "checked out git commit");
+  Ok(out_dir.to_path_buf())
+}
+
+/// Write the entries of `tree` into the directory `dest`.
+///
+/// Files get mode `0644`, or `0755` if they are executable in git. Submodules
+/// become empty directories.
+fn write_tree(repo: &gix::Repository, tree: &gix::Tree<'_>, dest: &Path) -> io::Result<()> {
+  for entry in tree.iter() {
+    let entry = entry.map_err(io::Error::other)?;
+    let name = entry.filename().to_string();
+    if name.is_empty() || name == "." || name == ".." || name == ".git" || name.contains(['/', '\\']) {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("refusing to write tree entry '{}'", name),
+      ));
+    }
+
+    let path = dest.join(&name);
+    let mode = entry.mode();
+    if mode.is_tree() {
+      fs::create_dir(&path)?;
+      let subtree = repo.find_tree(entry.oid().to_owned()).map_err(io::Error::other)?;
+      write_tree(repo, &subtree, &path)?;
+    } else if mode.is_commit() {
+      fs::create_dir(&path)?;
+    } else {
+      let blob = repo.find_blob(entry.oid().to_owned()).map_err(io::Error::other)?;
+      if mode.is_link() {
+        write_link(&blob.data, &path)?;
+      } else {
+        fs::write(&path, &blob.data)?;
+        set_mode(&path, if mode.is_executable() { 0o755 } else { 0o644 })?;
+      }
+    }
+  }
+  Ok(())
+}
+
+#[cfg(unix)]
+fn write_link(target: &[u8], path: &Path) -> io::Result<()> {
+  use std::os::unix::ffi::OsStrExt;
+  std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(target), path)
+}
+
+/// Without symlink support the link becomes a file holding its target, as
+/// `git` does with `core.symlinks=false`.
+#[cfg(not(unix))]
+fn write_link(target: &[u8], path: &Path) -> io::Result<()> {
+  fs::write(path, target)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::process::Command;
+  use tempfile::TempDir;
+
+  fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+      .args(["-c", "user.email=test@example.com", "-c", "user.name=Test"])
+      .args(args)
+      .current_dir(dir)
+      .output()
+      .expect("failed to run git");
+    assert!(output.status.success(), "git {:?} failed: {:?}", args, output);
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+  }
+
+  /// Create a bare repository with two commits.
+  ///
+  /// # Returns
+  ///
+  /// The `file://` URL of the bare repository and both commits, oldest first.
+  fn bare_repo(root: &Path) -> (String, String, String) {
+    let work = root.join("work");
+    fs::create_dir_all(work.join("src")).unwrap();
+    git(&work, &["init", "-q"]);
+    fs::write(work.join("README.md"), "v1\n").unwrap();
+    fs::write(work.join("src/run.sh"), "#!/bin/sh\n").unwrap();
+    git(&work, &["add", "."]);
+    git(&work, &["update-index", "--chmod=+x", "src/run.sh"]);
+    git(&work, &["commit", "-q", "-m", "v1"]);
+    let first = git(&work, &["rev-parse", "HEAD"]);
+    fs::write(work.join("README.md"), "v2\n").unwrap();
+    git(&work, &["commit", "-q", "-am", "v2"]);
+    let second = git(&work, &["rev-parse", "HEAD"]);
+
+    let bare = root.join("repo.git");
+    git(
+      root,
+      &["clone", "-q", "--bare", work.to_str().unwrap(), bare.to_str().unwrap()],
+    );
+    (format!("file://{}", bare.display()), first, second)
+  }
+
+  /// The hash of the tree of `rev`, from a plain checkout.
+  fn tree_hash(root: &Path, url: &str, rev: &str) -> String {
+    let checkout = root.join(format!("checkout-{}", rev));
+    git(root, &["clone", "-q", url, checkout.to_str().unwrap()]);
+    git(&checkout, &["checkout", "-q", rev]);
+    hash_directory(&checkout, &[".git"]).unwrap().0
+  }
+
+  #[test]
+  fn checks_out_exact_commit_without_git_metadata() {
+    let temp = TempDir::new().unwrap();
+    let (url, first, _) = bare_repo(temp.path());
+    let sha256 = tree_hash(temp.path(), &url, &first);
+    let out = temp.path().join("out");
+
+    let path = fetch_git_tree(&url, &first, &sha256, &temp.path().join("cache"), &out).unwrap();
+
+    assert_eq!(path, out);
+    assert_eq!(fs::read_to_string(out.join("README.md")).unwrap(), "v1\n");
+    assert!(!out.join(".git").exists());
+    assert_eq!(
+      fs::read_dir(&out).unwrap().count(),
+      2,
+      "no staging directory left behind"
+    );
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let mode = fs::metadata(out.join("src/run.sh")).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o755);
+    }
+  }
+
+  #[test]
+  fn rejects_tree_with_wrong_hash() {
+    let temp = TempDir::new().unwrap();
+    let (url, _, second) = bare_repo(temp.path());
+    let out = temp.path().join("out");
+
+    let result = fetch_git_tree(&url, &second, &"0".repeat(64), &temp.path().join("cache"), &out);
+
+    assert!(
+      matches!(&result, Err(ExecuteError::HashMismatch { actual, .. }) if *actual == tree_hash(temp.path(), &url, &second)),
+      "expected hash mismatch, got {:?}",
+      result
+    );
+    assert!(!out.join("README.md").exists());
+  }
+
+  #[test]
+  fn reuses_cached_repository() {
+    let temp = TempDir::new().unwrap();
+    let (url, first, second) = bare_repo(temp.path());
+    let cache = temp.path().join("cache");
+    let (first_hash, second_hash) = (
+      tree_hash(temp.path(), &url, &first),
+      tree_hash(temp.path(), &url, &second),
+    );
+
+    fetch_git_tree(&url, &second, &second_hash, &cache, &temp.path().join("out1")).unwrap();
+    fs::remove_dir_all(temp.path().join("repo.git")).unwrap();
+
+    // Both commits were fetched with the first checkout; the remote is gone now
+    let out = temp.path().join("out2");
+    fetch_git_tree(&url, &first, &first_hash, &cache, &out).unwrap();
+    assert_eq!(fs::read_to_string(out.join("README.md")).unwrap(), "v1\n");
+  }
+
+  #[test]
+  fn unknown_commit_fails() {
+    let temp = TempDir::new().unwrap();
+    let (url, _, _) = bare_repo(temp.path());
+
+    let result = fetch_git_tree(
+      &url,
+      &"1".repeat(40),
+      &"0".repeat(64),
+      &temp.path().join("cache"),
+      &temp.path().join("out"),
+    );
+
+    assert!(
+      matches!(result, Err(ExecuteError::FetchFailed { .. })),
+      "got {:?}",
+      result
+    );
+  }
+
+  #[test]
+  fn parse_requires_full_commit_and_hash() {
+    let lua = Lua::new();
+    let url = LuaValue::String(lua.create_string("https://example.com/repo.git").unwrap());
+    let commit = "A".repeat(40);
+
+    let (_, rev, sha256) = parse_fetch_git_args(url.clone(), Some(commit.clone()), Some("F".repeat(64))).unwrap();
+    assert_eq!(rev, "a".repeat(40));
+    assert_eq!(sha256, "f".repeat(64));
+
+    assert!(parse_fetch_git_args(url.clone(), Some("main".to_string()), Some("f".repeat(64))).is_err());
+    assert!(parse_fetch_git_args(url.clone(), Some(commit.clone()), Some("abc".to_string())).is_err());
+    assert!(parse_fetch_git_args(url, None, Some("f".repeat(64))).is_err());
+  }
+}
@@ -1,103 +1,549 @@
 //! FetchUrl action implementation.
 //!
-//! This module handles downloading files from URLs with SHA256 verification.
+//! Downloads are streamed to disk while they are hashed and kept in a
+//! store-wide cache keyed by their hash, so a file is fetched once no matter
+//! how many builds use it:
+//!
+//! ```text
+//! <store>/downloads/<sha256>        # Verified download
+//! <store>/downloads/<sha256>.part   # Download in progress, resumed with HTTP Range
+//! <store>/downloads/<sha256>.lock   # Locked by the process downloading the file
+//! <store>/downloads/sha512-<hex>    # Downloads verified with SHA-512
+//! ```
+//!
+//! Each build gets its own copy of the file in `out_dir/downloads/`.
+//!
+//! A fetch can list mirrors that are tried in order when the main URL fails.
+//! Transient failures (connection errors, timeouts, `408`, `429` and `5xx`
+//! responses) are retried with exponential backoff before moving on to the
+//! next URL.
 
+use std::collections::HashMap;
+use std::fmt;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
 
-use sha2::{Digest, Sha256};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use mlua::prelude::*;
+use reqwest::StatusCode;
+use reqwest::header::{CONTENT_RANGE, RANGE};
+use serde::{Deserialize, Serialize};
+use sha2::digest::DynDigest;
+use sha2::{Digest, Sha256, Sha512};
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
-use tracing::{debug, info};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{debug, info, warn};
 
+use super::whole_number;
+use crate::action::log::ActionLog;
 use crate::execute::types::ExecuteError;
+use crate::platform::paths::store_dir;
+use crate::store_lock::{LockMode, try_lock};
+
+/// Directory in the store holding the download cache.
+pub const DOWNLOADS_DIR: &str = "downloads";
+
+/// Suffix of a download that has not finished yet.
+pub const PARTIAL_SUFFIX: &str = ".part";
+
+/// Suffix of the file locked for the whole download.
+pub const LOCK_SUFFIX: &str = ".lock";
+
+/// How often a fetch checks whether another process finished the download.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Retries of each URL after a transient failure, unless the action sets its own.
+pub const DEFAULT_RETRIES: u32 = 2;
+
+/// Seconds to wait before the first retry, unless the action sets its own.
+pub const DEFAULT_RETRY_DELAY: u64 = 1;
+
+/// Serializes downloads of the same file by concurrent builds.
+///
+/// Other processes are kept out by the lock file next to the download.
+static DOWNLOAD_LOCKS: LazyLock<Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>> =
+  LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Options for fetching a file in a build.
+///
+/// The hash is serialized as `sha256` and the other options are left out
+/// while unset, so actions written before mirrors and retries existed keep
+/// their hashes.
+///
+/// # Example
+///
+/// ```ignore
+/// ctx.fetch_url(
+///     FetchUrlOpts::new("https://example.com/rg.tar.gz", "sha256-...")
+///         .with_mirrors(vec!["https://mirror.example.com/rg.tar.gz".to_string()])
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FetchUrlOpts {
+  /// The URL to download.
+  pub url: String,
+  /// Expected hash of the file: lowercase SHA-256 hex, or an SRI hash
+  /// (`sha256-<base64>`, `sha512-<base64>`).
+  #[serde(rename = "sha256")]
+  pub hash: String,
+  /// URLs tried in order after `url` fails.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub mirrors: Vec<String>,
+  /// How often each URL is retried after a transient failure.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub retries: Option<u32>,
+  /// Seconds to wait before the first retry; doubled for each further one.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub retry_delay: Option<u64>,
+}
+
+impl FetchUrlOpts {
+  pub fn new(url: &str, hash: &str) -> Self {
+    Self {
+      url: url.to_string(),
+      hash: hash.to_string(),
+      mirrors: Vec::new(),
+      retries: None,
+      retry_delay: None,
+    }
+  }
+
+  pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+    self.mirrors = mirrors;
+    self
+  }
+
+  /// The main URL followed by the mirrors.
+  pub fn urls(&self) -> impl Iterator<Item = &str> {
+    std::iter::once(self.url.as_str()).chain(self.mirrors.iter().map(String::as_str))
+  }
+}
+
+/// Parse the arguments of `ctx:fetch_url`.
+///
+/// Accepts `(url, hash)` or a single table with `url`, `hash` (or `sha256`),
+/// `mirrors`, `retries` and `retry_delay`.
+pub fn parse_fetch_url_opts(opts: LuaValue, hash: Option<String>) -> LuaResult<FetchUrlOpts> {
+  match opts {
+    LuaValue::String(url) => {
+      let hash = hash.ok_or_else(|| LuaError::external("fetch_url() expects a hash after the url"))?;
+      Ok(FetchUrlOpts::new(&url.to_str()?, &hash))
+    }
+    LuaValue::Table(table) => {
+      let url: String = table
+        .get::<Option<String>>("url")?
+        .ok_or_else(|| LuaError::external("fetch_url() requires a 'url'"))?;
+      let hash = match table.get::<Option<String>>("hash")? {
+        Some(hash) => hash,
+        None => table
+          .get::<Option<String>>("sha256")?
+          .ok_or_else(|| LuaError::external("fetch_url() requires a 'hash'"))?,
+      };
+      let mirrors: Option<Vec<String>> = table.get("mirrors")?;
+      let retries = whole_number(&table, "fetch_url", "retries", "retries")?;
+      let retry_delay = whole_number(&table, "fetch_url", "retry_delay", "seconds")?;
+
+      let mut opts = FetchUrlOpts::new(&url, &hash).with_mirrors(mirrors.unwrap_or_default());
+      opts.retries = retries
+        .map(u32::try_from)
+        .transpose()
+        .map_err(|_| LuaError::external("fetch_url 'retries' is too large"))?;
+      opts.retry_delay = retry_delay;
+      Ok(opts)
+    }
+    _ => Err(LuaError::external(
+      "fetch_url() expects a url or a table with a 'url' field",
+    )),
+  }
+}
+
+/// Hash algorithms a download can be verified with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+  Sha256,
+  Sha512,
+}
+
+impl HashAlgorithm {
+  fn name(self) -> &'static str {
+    match self {
+      HashAlgorithm::Sha256 => "sha256",
+      HashAlgorithm::Sha512 => "sha512",
+    }
+  }
+
+  fn digest_len(self) -> usize {
+    match self {
+      HashAlgorithm::Sha256 => 32,
+      HashAlgorithm::Sha512 => 64,
+    }
+  }
+
+  fn hasher(self) -> Box<dyn DynDigest + Send> {
+    match self {
+      HashAlgorithm::Sha256 => Box::new(Sha256::new()),
+      HashAlgorithm::Sha512 => Box::new(Sha512::new()),
+    }
+  }
+}
+
+/// The expected hash of a download.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Integrity {
+  pub algorithm: HashAlgorithm,
+  pub digest: Vec<u8>,
+  /// Whether the hash was written in SRI form; mismatches are reported the same way.
+  sri: bool,
+}
+
+impl Integrity {
+  /// Parse SHA-256 hex or an SRI hash (`sha256-<base64>`, `sha512-<base64>`).
+  pub fn parse(hash: &str) -> Result<Self, String> {
+    let hash = hash.trim();
+    if let Some((algorithm, encoded)) = hash.split_once('-') {
+      let algorithm = match algorithm {
+        "sha256" => HashAlgorithm::Sha256,
+        "sha512" => HashAlgorithm::Sha512,
+        _ => return Err(format!("unsupported hash algorithm '{}'", algorithm)),
+      };
+      let digest = BASE64
+        .decode(encoded)
+        .map_err(|e| format!("invalid {} hash '{}': {}", algorithm.name(), hash, e))?;
+      if digest.len() != algorithm.digest_len() {
+        return Err(format!("invalid {} hash '{}': wrong length", algorithm.name(), hash));
+      }
+      return Ok(Self {
+        algorithm,
+        digest,
+        sri: true,
+      });
+    }
+
+    match hex::decode(hash) {
+      Ok(digest) if digest.len() == HashAlgorithm::Sha256.digest_len() => Ok(Self {
+        algorithm: HashAlgorithm::Sha256,
+        digest,
+        sri: false,
+      }),
+      _ => Err(format!("invalid sha256 '{}'", hash)),
+    }
+  }
+
+  /// Name of the file in the download cache.
+  ///
+  /// SHA-256 downloads are named by their hex digest alone, other algorithms
+  /// carry their name as a prefix.
+  pub fn cache_key(&self) -> String {
+    match self.algorithm {
+      HashAlgorithm::Sha256 => hex::encode(&self.digest),
+      algorithm => format!("{}-{}", algorithm.name(), hex::encode(&self.digest)),
+    }
+  }
+
+  /// Format `digest` the way this hash was written.
+  fn format(&self, digest: &[u8]) -> String {
+    if self.sri {
+      format!("{}-{}", self.algorithm.name(), BASE64.encode(digest))
+    } else {
+      hex::encode(digest)
+    }
+  }
+}
+
+impl fmt::Display for Integrity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.format(&self.digest))
+  }
+}
+
+/// Path of the cached download with the given cache key (see [`Integrity::cache_key`]).
+pub fn download_cache_path(key: &str) -> PathBuf {
+  store_dir().join(DOWNLOADS_DIR).join(key)
+}
 
 /// Execute a FetchUrl action.
 ///
-/// Downloads the file from the given URL to a temporary location within `out_dir`,
-/// verifies the SHA256 hash, and returns the path to the downloaded file.
+/// Makes sure the download cache has the file (see [`fetch_to_cache`]) and
+/// copies it into `out_dir/downloads/`, so the build can change its copy freely.
 ///
 /// # Arguments
 ///
-/// * `url` - The URL to download from
-/// * `expected_sha256` - The expected SHA256 hash (lowercase hex)
+/// * `opts` - The fetch options, with placeholders already resolved
 /// * `out_dir` - The output directory for the build (file is stored in `out_dir/downloads/`)
+/// * `log` - The action log that download progress is reported to
 ///
 /// # Returns
 ///
 /// The path to the downloaded file on success.
-pub async fn execute_fetch_url(url: &str, expected_sha256: &str, out_dir: &Path) -> Result<PathBuf, ExecuteError> {
-  info!(url = %url, "fetching URL");
+pub async fn execute_fetch_url(
+  opts: &FetchUrlOpts,
+  out_dir: &Path,
+  log: Option<&ActionLog>,
+) -> Result<PathBuf, ExecuteError> {
+  info!(url = %opts.url, "fetching URL");
+
+  let cached = fetch_to_cache(opts, log).await?;
 
-  // Create downloads directory
   let downloads_dir = out_dir.join("downloads");
   fs::create_dir_all(&downloads_dir).await?;
+  let dest_path = downloads_dir.join(url_to_filename(&opts.url));
+  fs::copy(&cached, &dest_path).await?;
+
+  Ok(dest_path)
+}
+
+/// Make sure the download cache has the file `opts` describes, fetching it
+/// if it does not.
+///
+/// The download holds an exclusive lock on `<key>.lock`, so a build in another
+/// process fetching the same file waits for it and then uses the result.
+/// The file is streamed to `<key>.part` while it is hashed and only moved to
+/// its final name once the hash matches. A partial file left by an interrupted
+/// download is resumed with an HTTP Range request; servers that don't support
+/// ranges send the whole file again.
+///
+/// Each URL is retried after transient failures; any other failure, including
+/// a hash mismatch, moves on to the next mirror.
+///
+/// # Returns
+///
+/// The path of the verified file in the cache, or the error of the last
+/// attempt if every URL failed.
+pub async fn fetch_to_cache(opts: &FetchUrlOpts, log: Option<&ActionLog>) -> Result<PathBuf, ExecuteError> {
+  let integrity = Integrity::parse(&opts.hash).map_err(|message| ExecuteError::FetchFailed {
+    url: opts.url.clone(),
+    message,
+  })?;
+  let key = integrity.cache_key();
+
+  let lock = DOWNLOAD_LOCKS
+    .lock()
+    .unwrap_or_else(|e| e.into_inner())
+    .entry(key.clone())
+    .or_default()
+    .clone();
+  let _guard = lock.lock().await;
+
+  let cached = download_cache_path(&key);
+  if fs::try_exists(&cached).await? {
+    info!(path = ?cached, "using cached download");
+    return Ok(cached);
+  }
+  if let Some(parent) = cached.parent() {
+    fs::create_dir_all(parent).await?;
+  }
+
+  let _file_lock = lock_download(&cached, log).await?;
+  if fs::try_exists(&cached).await? {
+    info!(path = ?cached, "using download finished by another process");
+    return Ok(cached);
+  }
+
+  let partial = PathBuf::from(format!("{}{}", cached.display(), PARTIAL_SUFFIX));
+  let retries = opts.retries.unwrap_or(DEFAULT_RETRIES);
+  let delay = Duration::from_secs(opts.retry_delay.unwrap_or(DEFAULT_RETRY_DELAY));
+  let mut last_error = None;
+
+  for (i, url) in opts.urls().enumerate() {
+    if i > 0
+      && let Some(log) = log
+    {
+      log.line("fetch", &format!("trying mirror {}", url));
+    }
+
+    for attempt in 0..=retries {
+      if attempt > 0 {
+        let wait = delay * 2u32.saturating_pow(attempt - 1);
+        if let Some(log) = log {
+          log.line("fetch", &format!("retry {}/{} in {:?}", attempt, retries, wait));
+        }
+        tokio::time::sleep(wait).await;
+      }
 
-  // Derive filename from URL
-  let filename = url_to_filename(url);
-  let dest_path = downloads_dir.join(&filename);
-
-  // Check if file already exists with correct hash (cache hit)
-  if dest_path.exists() {
-    debug!(path = ?dest_path, "checking cached file");
-    if let Ok(actual_hash) = hash_file(&dest_path).await {
-      if actual_hash == expected_sha256 {
-        info!(path = ?dest_path, "using cached file");
-        return Ok(dest_path);
+      let failure = match download(url, &partial, integrity.algorithm, log).await {
+        Ok((digest, size)) if digest == integrity.digest => {
+          fs::rename(&partial, &cached).await?;
+          info!(path = ?cached, size, "download complete");
+          return Ok(cached);
+        }
+        Ok((digest, _)) => {
+          let _ = fs::remove_file(&partial).await;
+          DownloadFailure::permanent(ExecuteError::HashMismatch {
+            url: url.to_string(),
+            expected: integrity.to_string(),
+            actual: integrity.format(&digest),
+          })
+        }
+        Err(failure) => failure,
+      };
+
+      warn!(url = %url, attempt, error = %failure.error, "download failed");
+      if let Some(log) = log {
+        log.line("error", &failure.error.to_string());
+      }
+      let transient = failure.transient;
+      last_error = Some(failure.error);
+      if !transient {
+        break;
       }
-      debug!(expected = %expected_sha256, actual = %actual_hash, "cached file hash mismatch, re-downloading");
     }
   }
 
-  // Download the file
-  let response = reqwest::get(url).await.map_err(|e| ExecuteError::FetchFailed {
-    url: url.to_string(),
-    message: e.to_string(),
-  })?;
+  Err(last_error.expect("a fetch has at least one URL"))
+}
 
-  if !response.status().is_success() {
-    return Err(ExecuteError::FetchFailed {
-      url: url.to_string(),
-      message: format!("HTTP {}", response.status()),
-    });
+/// Lock `<cached>.lock` exclusively, waiting while another process holds it.
+///
+/// The lock is released when the returned file is closed.
+async fn lock_download(cached: &Path, log: Option<&ActionLog>) -> Result<std::fs::File, ExecuteError> {
+  let path = PathBuf::from(format!("{}{}", cached.display(), LOCK_SUFFIX));
+  let file = std::fs::OpenOptions::new()
+    .read(true)
+    .write(true)
+    .create(true)
+    .truncate(false)
+    .open(&path)?;
+
+  let mut waiting = false;
+  loop {
+    match try_lock(&file, LockMode::Exclusive) {
+      Ok(()) => return Ok(file),
+      Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+        if !waiting {
+          waiting = true;
+          debug!(path = ?path, "waiting for another process downloading the file");
+          if let Some(log) = log {
+            log.line("fetch", "waiting for another process downloading the file");
+          }
+        }
+        tokio::time::sleep(LOCK_POLL_INTERVAL).await;
+      }
+      Err(e) => return Err(e.into()),
+    }
   }
+}
 
-  let bytes = response.bytes().await.map_err(|e| ExecuteError::FetchFailed {
-    url: url.to_string(),
-    message: e.to_string(),
-  })?;
+/// A failed download attempt.
+struct DownloadFailure {
+  error: ExecuteError,
+  /// Whether trying the same URL again may succeed.
+  transient: bool,
+}
+
+impl DownloadFailure {
+  fn permanent(error: ExecuteError) -> Self {
+    Self {
+      error,
+      transient: false,
+    }
+  }
+
+  fn http(url: &str, error: reqwest::Error) -> Self {
+    let transient = error.is_connect() || error.is_timeout() || error.is_request() || error.is_body();
+    Self {
+      error: ExecuteError::FetchFailed {
+        url: url.to_string(),
+        message: error.to_string(),
+      },
+      transient,
+    }
+  }
+
+  fn status(url: &str, status: StatusCode) -> Self {
+    let transient =
+      status.is_server_error() || status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::TOO_MANY_REQUESTS;
+    Self {
+      error: ExecuteError::FetchFailed {
+        url: url.to_string(),
+        message: format!("HTTP {}", status),
+      },
+      transient,
+    }
+  }
+}
 
-  // Compute hash while writing
-  let actual_hash = {
-    let mut hasher = Sha256::new();
-    hasher.update(&bytes);
-    hex::encode(hasher.finalize())
+impl From<std::io::Error> for DownloadFailure {
+  fn from(error: std::io::Error) -> Self {
+    Self::permanent(error.into())
+  }
+}
+
+/// Download `url` into `partial`, continuing from the bytes it already has.
+///
+/// # Returns
+///
+/// The digest and size of the complete file.
+async fn download(
+  url: &str,
+  partial: &Path,
+  algorithm: HashAlgorithm,
+  log: Option<&ActionLog>,
+) -> Result<(Vec<u8>, u64), DownloadFailure> {
+  let offset = match fs::metadata(partial).await {
+    Ok(metadata) => metadata.len(),
+    Err(_) => 0,
   };
 
-  // Verify hash before writing
-  if actual_hash != expected_sha256 {
-    return Err(ExecuteError::HashMismatch {
-      url: url.to_string(),
-      expected: expected_sha256.to_string(),
-      actual: actual_hash,
-    });
+  let mut request = reqwest::Client::new().get(url);
+  if offset > 0 {
+    request = request.header(RANGE, format!("bytes={}-", offset));
   }
+  let mut response = request.send().await.map_err(|e| DownloadFailure::http(url, e))?;
+  let status = response.status();
 
-  // Write to file
-  let mut file = fs::File::create(&dest_path).await?;
-  file.write_all(&bytes).await?;
+  let mut hasher = algorithm.hasher();
+  if offset > 0 && status == StatusCode::RANGE_NOT_SATISFIABLE {
+    // Nothing left to send: the partial file is already complete
+    hash_into(hasher.as_mut(), partial).await?;
+    return Ok((hasher.finalize().into_vec(), offset));
+  }
+  if !status.is_success() {
+    return Err(DownloadFailure::status(url, status));
+  }
+
+  let resumed = offset > 0 && status == StatusCode::PARTIAL_CONTENT && range_start(&response) == Some(offset);
+  let (mut file, mut downloaded) = if resumed {
+    debug!(url = %url, offset, "resuming download");
+    hash_into(hasher.as_mut(), partial).await?;
+    (fs::OpenOptions::new().append(true).open(partial).await?, offset)
+  } else {
+    (fs::File::create(partial).await?, 0)
+  };
+
+  let total = response.content_length().map(|len| len + downloaded);
+  while let Some(chunk) = response.chunk().await.map_err(|e| DownloadFailure::http(url, e))? {
+    hasher.update(&chunk);
+    file.write_all(&chunk).await?;
+    downloaded += chunk.len() as u64;
+    if let Some(log) = log {
+      log.download(url, downloaded, total);
+    }
+  }
   file.flush().await?;
 
-  info!(path = ?dest_path, size = bytes.len(), "download complete");
+  Ok((hasher.finalize().into_vec(), downloaded))
+}
 
-  Ok(dest_path)
+/// First byte of a `206 Partial Content` response, from its Content-Range.
+fn range_start(response: &reqwest::Response) -> Option<u64> {
+  let range = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+  let (start, _) = range.strip_prefix("bytes ")?.split_once('-')?;
+  start.parse().ok()
 }
 
-/// Compute SHA256 hash of a file.
-async fn hash_file(path: &Path) -> Result<String, std::io::Error> {
-  let bytes = fs::read(path).await?;
-  let mut hasher = Sha256::new();
-  hasher.update(&bytes);
-  Ok(hex::encode(hasher.finalize()))
+/// Feed the contents of a file to `hasher` without reading it into memory.
+async fn hash_into(hasher: &mut (dyn DynDigest + Send), path: &Path) -> Result<(), std::io::Error> {
+  let mut file = fs::File::open(path).await?;
+  let mut buf = vec![0; 64 * 1024];
+  loop {
+    let n = file.read(&mut buf).await?;
+    if n == 0 {
+      return Ok(());
+    }
+    hasher.update(&buf[..n]);
+  }
 }
 
 /// Convert a URL to a safe filename.
@@ -129,7 +575,7 @@ fn url_to_filename(url: &str) -> String {
 
   // Fallback: hash the URL
   let mut hasher = Sha256::new();
-  hasher.update(url.as_bytes());
+  Digest::update(&mut hasher, url.as_bytes());
   format!("download_{}", &hex::encode(hasher.finalize())[..16])
 }
 
@@ -174,4 +620,328 @@ mod tests {
 
   // Integration tests that require network would go in a separate test module
   // with #[ignore] or behind a feature flag
+
+  use std::io::{BufRead, BufReader, Write};
+  use std::net::TcpListener;
+
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  /// Serve `body` over HTTP on a local port, honoring `Range: bytes=N-`.
+  ///
+  /// Returns the URL of the file and the Range header of every request made.
+  fn serve(body: &'static [u8]) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+    serve_failing(body, "", 0)
+  }
+
+  /// Like [`serve`], but answers the first `failures` requests with `status`.
+  fn serve_failing(
+    body: &'static [u8],
+    status: &'static str,
+    failures: usize,
+  ) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/file.tar.gz", listener.local_addr().unwrap());
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+
+    std::thread::spawn(move || {
+      for stream in listener.incoming() {
+        let mut stream = stream.unwrap();
+        let mut range = None;
+        for line in BufReader::new(&stream).lines() {
+          let line = line.unwrap();
+          if line.is_empty() {
+            break;
+          }
+          if let Some(value) = line.strip_prefix("range: ").or_else(|| line.strip_prefix("Range: ")) {
+            range = Some(value.to_string());
+          }
+        }
+        let count = {
+          let mut seen = seen.lock().unwrap();
+          seen.push(range.clone());
+          seen.len()
+        };
+        if count <= failures {
+          let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+          );
+          continue;
+        }
+
+        let start: usize = range
+          .as_deref()
+          .and_then(|r| r.strip_prefix("bytes=")?.strip_suffix('-')?.parse().ok())
+          .unwrap_or(0);
+        let status = if start > 0 { "206 Partial Content" } else { "200 OK" };
+        let part = &body[start..];
+        let _ = write!(
+          stream,
+          "HTTP/1.1 {}\r\nContent-Length: {}\r\nContent-Range: bytes {}-{}/{}\r\nConnection: close\r\n\r\n",
+          status,
+          part.len(),
+          start,
+          body.len() - 1,
+          body.len()
+        );
+        let _ = stream.write_all(part);
+      }
+    });
+
+    (url, requests)
+  }
+
+  fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+  }
+
+  fn opts(url: &str, hash: &str) -> FetchUrlOpts {
+    FetchUrlOpts {
+      retry_delay: Some(0),
+      ..FetchUrlOpts::new(url, hash)
+    }
+  }
+
+  /// Run `f` on a runtime with the store in a temp directory.
+  fn with_temp_store<F, Fut>(f: F)
+  where
+    F: FnOnce(TempDir) -> Fut,
+    Fut: std::future::Future<Output = ()>,
+  {
+    let temp = TempDir::new().unwrap();
+    let store = temp.path().join("store");
+    temp_env::with_var("SYSLUA_STORE", Some(&store), || {
+      tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(f(temp))
+    });
+  }
+
+  const BODY: &[u8] = b"a toolchain tarball that is much larger in real life";
+
+  #[test]
+  #[serial]
+  fn fetch_downloads_once_for_every_build() {
+    with_temp_store(|temp| async move {
+      let (url, requests) = serve(BODY);
+      let sha = sha256_hex(BODY);
+
+      let first = execute_fetch_url(&opts(&url, &sha), &temp.path().join("a"), None)
+        .await
+        .unwrap();
+      let second = execute_fetch_url(&opts(&url, &sha), &temp.path().join("b"), None)
+        .await
+        .unwrap();
+
+      assert_eq!(requests.lock().unwrap().len(), 1);
+      assert_eq!(std::fs::read(&first).unwrap(), BODY);
+      assert_eq!(std::fs::read(&second).unwrap(), BODY);
+      assert_ne!(first, second);
+      assert_eq!(std::fs::read(download_cache_path(&sha)).unwrap(), BODY);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_resumes_partial_download() {
+    with_temp_store(|temp| async move {
+      let (url, requests) = serve(BODY);
+      let sha = sha256_hex(BODY);
+
+      let cached = download_cache_path(&sha);
+      std::fs::create_dir_all(cached.parent().unwrap()).unwrap();
+      std::fs::write(format!("{}{}", cached.display(), PARTIAL_SUFFIX), &BODY[..10]).unwrap();
+
+      let path = execute_fetch_url(&opts(&url, &sha), temp.path(), None).await.unwrap();
+
+      assert_eq!(*requests.lock().unwrap(), vec![Some("bytes=10-".to_string())]);
+      assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_waits_for_download_locked_by_another_process() {
+    with_temp_store(|temp| async move {
+      let (url, requests) = serve(BODY);
+      let sha = sha256_hex(BODY);
+
+      let cached = download_cache_path(&sha);
+      std::fs::create_dir_all(cached.parent().unwrap()).unwrap();
+      let lock_path = format!("{}{}", cached.display(), LOCK_SUFFIX);
+      let held = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .unwrap();
+      try_lock(&held, LockMode::Exclusive).unwrap();
+
+      let out = temp.path().to_path_buf();
+      let fetch = tokio::spawn(async move { execute_fetch_url(&opts(&url, &sha), &out, None).await });
+      tokio::time::sleep(LOCK_POLL_INTERVAL * 2).await;
+      assert!(requests.lock().unwrap().is_empty(), "the fetch waits for the lock");
+
+      // The other process finishes the download and releases the lock
+      std::fs::write(&cached, BODY).unwrap();
+      drop(held);
+
+      let path = fetch.await.unwrap().unwrap();
+      assert!(requests.lock().unwrap().is_empty());
+      assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_discards_download_with_wrong_hash() {
+    with_temp_store(|temp| async move {
+      let (url, _) = serve(BODY);
+      let sha = sha256_hex(b"something else");
+
+      let result = execute_fetch_url(&opts(&url, &sha), temp.path(), None).await;
+
+      assert!(matches!(result, Err(ExecuteError::HashMismatch { .. })), "{:?}", result);
+      let cached = download_cache_path(&sha);
+      assert!(!cached.exists());
+      assert!(!Path::new(&format!("{}{}", cached.display(), PARTIAL_SUFFIX)).exists());
+    });
+  }
+
+  #[tokio::test]
+  async fn fetch_rejects_invalid_sha256() {
+    let result = fetch_to_cache(&opts("http://127.0.0.1:1/file", "../../etc"), None).await;
+    assert!(matches!(result, Err(ExecuteError::FetchFailed { .. })));
+  }
+  #[test]
+  #[serial]
+  fn fetch_retries_transient_failures() {
+    with_temp_store(|temp| async move {
+      let (url, requests) = serve_failing(BODY, "503 Service Unavailable", 2);
+
+      let path = execute_fetch_url(&opts(&url, &sha256_hex(BODY)), temp.path(), None)
+        .await
+        .unwrap();
+
+      assert_eq!(requests.lock().unwrap().len(), 3);
+      assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_gives_up_after_retries() {
+    with_temp_store(|temp| async move {
+      let (url, requests) = serve_failing(BODY, "503 Service Unavailable", 10);
+      let opts = FetchUrlOpts {
+        retries: Some(1),
+        ..opts(&url, &sha256_hex(BODY))
+      };
+
+      let result = execute_fetch_url(&opts, temp.path(), None).await;
+
+      assert!(matches!(result, Err(ExecuteError::FetchFailed { .. })), "{:?}", result);
+      assert_eq!(requests.lock().unwrap().len(), 2);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_falls_back_to_mirrors() {
+    with_temp_store(|temp| async move {
+      let (missing, missing_requests) = serve_failing(BODY, "404 Not Found", usize::MAX);
+      let (broken, _) = serve(b"not the file");
+      let (mirror, _) = serve(BODY);
+      let opts = opts(&missing, &sha256_hex(BODY)).with_mirrors(vec![broken, mirror]);
+
+      let path = execute_fetch_url(&opts, temp.path(), None).await.unwrap();
+
+      // A 404 is not retried
+      assert_eq!(missing_requests.lock().unwrap().len(), 1);
+      assert_eq!(std::fs::read(&path).unwrap(), BODY);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn fetch_verifies_sri_hashes() {
+    with_temp_store(|temp| async move {
+      let (url, _) = serve(BODY);
+      let sri = format!("sha512-{}", BASE64.encode(Sha512::digest(BODY)));
+
+      let path = execute_fetch_url(&opts(&url, &sri), temp.path(), None).await.unwrap();
+
+      assert_eq!(std::fs::read(&path).unwrap(), BODY);
+      let key = format!("sha512-{}", hex::encode(Sha512::digest(BODY)));
+      assert!(download_cache_path(&key).exists());
+
+      let wrong = format!("sha512-{}", BASE64.encode(Sha512::digest(b"other")));
+      let result = execute_fetch_url(&opts(&url, &wrong), temp.path(), None).await;
+      match result {
+        Err(ExecuteError::HashMismatch { expected, actual, .. }) => {
+          assert_eq!(expected, wrong);
+          assert_eq!(actual, sri);
+        }
+        other => panic!("expected hash mismatch, got {:?}", other),
+      }
+    });
+  }
+
+  #[test]
+  fn integrity_parses_hex_and_sri() {
+    let hex = sha256_hex(BODY);
+    let sri = format!("sha256-{}", BASE64.encode(Sha256::digest(BODY)));
+    assert_eq!(
+      Integrity::parse(&hex).unwrap().digest,
+      Integrity::parse(&sri).unwrap().digest
+    );
+    assert_eq!(Integrity::parse(&sri).unwrap().cache_key(), hex);
+    assert_eq!(Integrity::parse(&sri).unwrap().to_string(), sri);
+    assert_eq!(Integrity::parse(&hex.to_uppercase()).unwrap().to_string(), hex);
+
+    assert!(Integrity::parse("md5-1B2M2Y8AsgTpgAmY7PhCfg==").is_err());
+    assert!(Integrity::parse("sha512-AAAA").is_err());
+    assert!(Integrity::parse("abc123").is_err());
+  }
+
+  #[test]
+  fn serialization_is_unchanged_without_new_options() {
+    let action = crate::action::Action::FetchUrl(FetchUrlOpts::new("https://example.com/a.tar.gz", "abc"));
+    assert_eq!(
+      serde_json::to_string(&action).unwrap(),
+      r#"{"FetchUrl":{"url":"https://example.com/a.tar.gz","sha256":"abc"}}"#
+    );
+  }
+
+  #[test]
+  fn parse_table_form() {
+    let lua = Lua::new();
+    let table: LuaValue = lua
+      .load(
+        r#"{
+          url = "https://example.com/a.tar.gz",
+          hash = "sha256-abc",
+          mirrors = { "https://mirror.example.com/a.tar.gz" },
+          retries = 5,
+          retry_delay = 3,
+        }"#,
+      )
+      .eval()
+      .unwrap();
+
+    let opts = parse_fetch_url_opts(table, None).unwrap();
+    assert_eq!(opts.url, "https://example.com/a.tar.gz");
+    assert_eq!(opts.hash, "sha256-abc");
+    assert_eq!(opts.mirrors, vec!["https://mirror.example.com/a.tar.gz".to_string()]);
+    assert_eq!(opts.retries, Some(5));
+    assert_eq!(opts.retry_delay, Some(3));
+
+    let url = LuaValue::String(lua.create_string("https://example.com/a.tar.gz").unwrap());
+    assert!(parse_fetch_url_opts(url, None).is_err());
+  }
 }
@@ -0,0 +1,492 @@
+//! File action implementations.
+//!
+//! `write_file`, `copy`, `symlink`, `mkdir`, `chmod` and `remove` change the
+//! filesystem directly instead of going through a shell, so paths and contents
+//! never need quoting and no `/bin/sh` or PowerShell is required.
+//!
+//! Relative paths are resolved against the output directory of the build or
+//! bind. The actions can run again over their own results, which is what
+//! rollbacks and re-applies do: files are replaced atomically, an existing
+//! symlink is replaced, existing directories are kept and `remove` succeeds if
+//! the path is already gone. That makes `remove` a reliable `destroy` for all
+//! of them.
+//!
+//! Binds change the host, so their file actions can touch any path. The file
+//! actions of a build run in-process, outside the exec sandbox, and are held
+//! to a [`FileScope`] instead: they write only below the build's output and
+//! read only from it, from the outputs of its dependencies and from host paths
+//! the build declares in `sandbox.paths`.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use mlua::prelude::*;
+use tracing::info;
+use walkdir::WalkDir;
+
+use crate::action::ActionCtx;
+use crate::action::log::ActionLog;
+use crate::execute::types::ExecuteError;
+use crate::platform::link::link_dir;
+
+/// Where the file actions of a build may write and read.
+#[derive(Debug, Clone)]
+pub struct FileScope {
+  out_dir: PathBuf,
+  readable: Vec<PathBuf>,
+}
+
+impl FileScope {
+  /// A scope that writes and reads only below `out_dir`.
+  pub fn new(out_dir: impl Into<PathBuf>) -> Self {
+    let out_dir = out_dir.into();
+    Self {
+      readable: vec![out_dir.clone()],
+      out_dir,
+    }
+  }
+
+  /// Allow reading below `path` as well.
+  pub fn with_readable(mut self, path: impl Into<PathBuf>) -> Self {
+    self.readable.push(path.into());
+    self
+  }
+
+  /// Fail unless writing `path` stays below the output directory.
+  ///
+  /// `replaces_link` is set for actions that replace a symlink at `path`
+  /// instead of following it; otherwise a final symlink must stay inside too.
+  pub fn check_write(&self, path: &Path, replaces_link: bool) -> io::Result<()> {
+    let path = normalize(path);
+    let out_dir = normalize(&self.out_dir);
+    // Follow the symlinks that already exist, except one the action replaces
+    let resolved = match path.parent() {
+      Some(parent) if replaces_link && fs::symlink_metadata(&path).is_ok_and(|m| m.is_symlink()) => {
+        resolve_existing(parent)?
+      }
+      _ => resolve_existing(&path)?,
+    };
+    if path.starts_with(&out_dir) && resolved.starts_with(resolve_existing(&out_dir)?) {
+      Ok(())
+    } else {
+      Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "builds can only write below their output directory",
+      ))
+    }
+  }
+
+  /// Fail unless `path` is below the output directory, a dependency or a
+  /// declared host path.
+  pub fn check_read(&self, path: &Path) -> io::Result<()> {
+    let resolved = dunce::canonicalize(path)?;
+    let readable = self
+      .readable
+      .iter()
+      .any(|root| resolved.starts_with(dunce::canonicalize(root).unwrap_or_else(|_| normalize(root))));
+    if readable {
+      Ok(())
+    } else {
+      Err(io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        "builds can only read their output, their dependencies and paths declared in `sandbox.paths`",
+      ))
+    }
+  }
+}
+
+/// Resolve `.` and `..` components without touching the filesystem.
+fn normalize(path: &Path) -> PathBuf {
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      std::path::Component::CurDir => {}
+      std::path::Component::ParentDir => {
+        normalized.pop();
+      }
+      other => normalized.push(other),
+    }
+  }
+  normalized
+}
+
+/// Canonicalize the longest existing prefix of `path` and append the rest, so
+/// symlinks that already exist are followed.
+fn resolve_existing(path: &Path) -> io::Result<PathBuf> {
+  let mut existing = path;
+  let mut rest = Vec::new();
+  loop {
+    match dunce::canonicalize(existing) {
+      Ok(resolved) => return Ok(rest.iter().rev().fold(resolved, |path, part| path.join(part))),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => match (existing.parent(), existing.file_name()) {
+        (Some(parent), Some(name)) => {
+          rest.push(name.to_os_string());
+          existing = parent;
+        }
+        _ => return Ok(path.to_path_buf()),
+      },
+      Err(e) => return Err(e),
+    }
+  }
+}
+
+/// Register the file methods on a build or bind context.
+pub fn add_file_methods<T, M>(methods: &mut M)
+where
+  T: AsMut<ActionCtx> + 'static,
+  M: LuaUserDataMethods<T>,
+{
+  methods.add_method_mut(
+    "write_file",
+    |_, this, (path, content, opts): (String, String, Option<LuaTable>)| {
+      let mode = match opts {
+        Some(opts) => opts.get::<Option<LuaValue>>("mode")?.map(parse_mode).transpose()?,
+        None => None,
+      };
+      Ok(this.as_mut().write_file(&path, &content, mode))
+    },
+  );
+
+  methods.add_method_mut("copy", |_, this, (src, dest): (String, String)| {
+    Ok(this.as_mut().copy(&src, &dest))
+  });
+
+  methods.add_method_mut("symlink", |_, this, (target, link): (String, String)| {
+    Ok(this.as_mut().symlink(&target, &link))
+  });
+
+  methods.add_method_mut("mkdir", |_, this, (path, opts): (String, Option<LuaTable>)| {
+    let mode = match opts {
+      Some(opts) => opts.get::<Option<LuaValue>>("mode")?.map(parse_mode).transpose()?,
+      None => None,
+    };
+    Ok(this.as_mut().mkdir(&path, mode))
+  });
+
+  methods.add_method_mut("chmod", |_, this, (path, mode): (String, LuaValue)| {
+    let mode = parse_mode(mode)?;
+    Ok(this.as_mut().chmod(&path, mode))
+  });
+
+  methods.add_method_mut("remove", |_, this, path: String| Ok(this.as_mut().remove(&path)));
+}
+
+/// Parse a file mode given as a number (`tonumber('755', 8)`) or an octal
+/// string (`'755'`, `'0644'`).
+pub fn parse_mode(value: LuaValue) -> LuaResult<u32> {
+  let mode = match &value {
+    LuaValue::Integer(n) => u32::try_from(*n).ok(),
+    LuaValue::Number(n) if n.fract() == 0.0 && *n >= 0.0 => Some(*n as u32),
+    LuaValue::String(s) => u32::from_str_radix(&s.to_str()?, 8).ok(),
+    _ => None,
+  };
+  match mode {
+    Some(mode) if mode <= 0o7777 => Ok(mode),
+    _ => Err(LuaError::external(format!(
+      "invalid file mode {:?}: expected an octal string like '644' or a number",
+      value
+    ))),
+  }
+}
+
+/// Run a file action off the async runtime.
+///
+/// # Arguments
+///
+/// * `action` - Name of the action, for errors and logs
+/// * `path` - The path the action changes; also its output
+/// * `log` - The log of the build or bind, if one is kept
+/// * `op` - The filesystem operation, called with `path`
+///
+/// # Returns
+///
+/// `path` once the operation succeeded.
+pub async fn execute_file_action(
+  action: &'static str,
+  path: PathBuf,
+  log: Option<&ActionLog>,
+  op: impl FnOnce(&Path) -> io::Result<()> + Send + 'static,
+) -> Result<PathBuf, ExecuteError> {
+  info!(action, path = %path.display(), "running file action");
+  if let Some(log) = log {
+    log.line(action, &path.display().to_string());
+  }
+
+  let (result, path) = tokio::task::spawn_blocking(move || (op(&path), path))
+    .await
+    .map_err(|e| ExecuteError::TaskFailed { message: e.to_string() })?;
+  if let Err(e) = result {
+    let error = ExecuteError::FileFailed {
+      action: action.to_string(),
+      message: format!("{}: {}", path.display(), e),
+    };
+    if let Some(log) = log {
+      log.line("error", &error.to_string());
+    }
+    return Err(error);
+  }
+  Ok(path)
+}
+
+/// Write `content` to `path`, creating its parent directories.
+///
+/// The content goes to a temporary file that is renamed over `path`, so a
+/// symlink at `path` is replaced rather than written through.
+pub fn write_file(path: &Path, content: &str, mode: Option<u32>) -> io::Result<()> {
+  let parent = create_parent(path)?;
+  let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+  io::Write::write_all(&mut temp, content.as_bytes())?;
+  if let Some(mode) = mode {
+    set_mode(temp.path(), mode)?;
+  }
+  temp.persist(path).map_err(|e| e.error)?;
+  Ok(())
+}
+
+/// Copy a file or directory tree from `src` to `dest`.
+///
+/// Symlinks inside a copied directory are copied as symlinks. An existing
+/// directory at `dest` is merged into; a symlink where the copy has a
+/// directory is replaced, never written through.
+pub fn copy(src: &Path, dest: &Path) -> io::Result<()> {
+  create_parent(dest)?;
+  if !fs::metadata(src)?.is_dir() {
+    remove_link(dest)?;
+    fs::copy(src, dest)?;
+    return Ok(());
+  }
+
+  for entry in WalkDir::new(src) {
+    let entry = entry?;
+    let relative = entry.path().strip_prefix(src).map_err(io::Error::other)?;
+    let target = dest.join(relative);
+    let file_type = entry.file_type();
+    if file_type.is_dir() {
+      remove_link(&target)?;
+      fs::create_dir_all(&target)?;
+    } else if file_type.is_symlink() {
+      remove_link(&target)?;
+      let link_target = fs::read_link(entry.path())?;
+      create_symlink(&link_target, &target)?;
+    } else {
+      remove_link(&target)?;
+      fs::copy(entry.path(), &target)?;
+    }
+  }
+  Ok(())
+}
+
+/// Create a symlink at `link` pointing to `target`, replacing an existing symlink.
+///
+/// Anything else at `link` is left alone and fails the action.
+pub fn symlink(target: &Path, link: &Path) -> io::Result<()> {
+  create_parent(link)?;
+  match fs::symlink_metadata(link) {
+    Ok(metadata) if metadata.is_symlink() => remove_symlink(link)?,
+    Ok(_) => {
+      return Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "path exists and is not a symlink",
+      ));
+    }
+    Err(_) => {}
+  }
+  create_symlink(target, link)
+}
+
+/// Create a directory and its parents.
+pub fn mkdir(path: &Path, mode: Option<u32>) -> io::Result<()> {
+  fs::create_dir_all(path)?;
+  match mode {
+    Some(mode) => set_mode(path, mode),
+    None => Ok(()),
+  }
+}
+
+/// Change the mode of `path`.
+pub fn chmod(path: &Path, mode: u32) -> io::Result<()> {
+  set_mode(path, mode)
+}
+
+/// Remove a file, symlink or directory tree. A missing path is not an error.
+///
+/// Symlinks are removed themselves, never what they point to.
+pub fn remove(path: &Path) -> io::Result<()> {
+  let metadata = match fs::symlink_metadata(path) {
+    Ok(metadata) => metadata,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+    Err(e) => return Err(e),
+  };
+  if metadata.is_symlink() {
+    remove_symlink(path)
+  } else if metadata.is_dir() {
+    fs::remove_dir_all(path)
+  } else {
+    fs::remove_file(path)
+  }
+}
+
+/// Create the parent directory of `path` and return it.
+fn create_parent(path: &Path) -> io::Result<&Path> {
+  let parent = path
+    .parent()
+    .filter(|parent| !parent.as_os_str().is_empty())
+    .unwrap_or(Path::new("."));
+  fs::create_dir_all(parent)?;
+  Ok(parent)
+}
+
+/// Remove a symlink at `path` so it is not written through.
+fn remove_link(path: &Path) -> io::Result<()> {
+  match fs::symlink_metadata(path) {
+    Ok(metadata) if metadata.is_symlink() => remove_symlink(path),
+    _ => Ok(()),
+  }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+/// Only the write bits have a meaning on Windows: without any, the file is read-only.
+#[cfg(not(unix))]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  let mut permissions = fs::metadata(path)?.permissions();
+  permissions.set_readonly(mode & 0o222 == 0);
+  fs::set_permissions(path, permissions)
+}
+
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+  // Relative targets are relative to the link's directory
+  let resolved = match link.parent() {
+    Some(parent) => parent.join(target),
+    None => target.to_path_buf(),
+  };
+  if cfg!(windows) && resolved.is_dir() {
+    return link_dir(target, link);
+  }
+  #[cfg(unix)]
+  return std::os::unix::fs::symlink(target, link);
+  #[cfg(windows)]
+  return std::os::windows::fs::symlink_file(target, link);
+}
+
+#[cfg(unix)]
+fn remove_symlink(path: &Path) -> io::Result<()> {
+  fs::remove_file(path)
+}
+
+/// Directory symlinks and junctions are directories to Windows.
+#[cfg(windows)]
+fn remove_symlink(path: &Path) -> io::Result<()> {
+  fs::remove_file(path).or_else(|_| fs::remove_dir(path))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tempfile::TempDir;
+
+  #[test]
+  fn write_file_replaces_symlink_instead_of_following_it() {
+    let temp = TempDir::new().unwrap();
+    let original = temp.path().join("original");
+    let path = temp.path().join("config/app.toml");
+    fs::write(&original, "keep me").unwrap();
+    symlink(&original, &path).unwrap();
+
+    write_file(&path, "new = true\n", None).unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new = true\n");
+    assert!(!fs::symlink_metadata(&path).unwrap().is_symlink());
+    assert_eq!(fs::read_to_string(&original).unwrap(), "keep me");
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn write_file_and_mkdir_set_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+    let script = temp.path().join("bin/hello");
+    write_file(&script, "#!/bin/sh\necho hi\n", Some(0o755)).unwrap();
+    mkdir(&temp.path().join("private"), Some(0o700)).unwrap();
+
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o7777;
+    assert_eq!(mode(&script), 0o755);
+    assert_eq!(mode(&temp.path().join("private")), 0o700);
+
+    chmod(&script, 0o600).unwrap();
+    assert_eq!(mode(&script), 0o600);
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn copy_copies_trees_with_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let src = temp.path().join("src");
+    fs::create_dir_all(src.join("nested")).unwrap();
+    fs::write(src.join("nested/file.txt"), "hello").unwrap();
+    std::os::unix::fs::symlink("nested/file.txt", src.join("link")).unwrap();
+    let dest = temp.path().join("dest");
+
+    copy(&src, &dest).unwrap();
+    // Copying again over the result succeeds
+    copy(&src, &dest).unwrap();
+
+    assert_eq!(fs::read_to_string(dest.join("nested/file.txt")).unwrap(), "hello");
+    assert_eq!(fs::read_link(dest.join("link")).unwrap(), Path::new("nested/file.txt"));
+  }
+
+  #[test]
+  fn symlink_replaces_links_but_not_files() {
+    let temp = TempDir::new().unwrap();
+    let link = temp.path().join("link");
+    let file = temp.path().join("file");
+    fs::write(&file, "data").unwrap();
+
+    symlink(Path::new("a"), &link).unwrap();
+    symlink(Path::new("b"), &link).unwrap();
+    assert_eq!(fs::read_link(&link).unwrap(), Path::new("b"));
+
+    let err = symlink(Path::new("b"), &file).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "data");
+  }
+
+  #[test]
+  fn remove_is_idempotent_and_does_not_follow_links() {
+    let temp = TempDir::new().unwrap();
+    let dir = temp.path().join("dir");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/file"), "x").unwrap();
+    let link = temp.path().join("link");
+    symlink(&dir, &link).unwrap();
+
+    remove(&link).unwrap();
+    assert!(dir.join("sub/file").exists());
+
+    remove(&dir).unwrap();
+    assert!(!dir.exists());
+    remove(&dir).unwrap();
+  }
+
+  #[test]
+  fn parse_mode_accepts_octal_strings_and_numbers() {
+    let lua = Lua::new();
+    assert_eq!(
+      parse_mode(LuaValue::String(lua.create_string("755").unwrap())).unwrap(),
+      0o755
+    );
+    assert_eq!(
+      parse_mode(LuaValue::String(lua.create_string("0644").unwrap())).unwrap(),
+      0o644
+    );
+    assert_eq!(parse_mode(LuaValue::Integer(0o600)).unwrap(), 0o600);
+    assert!(parse_mode(LuaValue::String(lua.create_string("rwx").unwrap())).is_err());
+    assert!(parse_mode(LuaValue::Integer(0o17777)).is_err());
+  }
+}
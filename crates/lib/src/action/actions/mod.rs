@@ -3,7 +3,30 @@
 //! This module contains the concrete implementations for each action type:
 //!
 //! - [`exec`] - Shell command execution with environment and working directory support
-//! - [`fetch_url`] - HTTP/HTTPS file download with SHA256 integrity verification
+//! - [`fetch_url`] - HTTP/HTTPS file download with SHA-256/SHA-512 integrity verification
+//! - [`fetch_git`] - Checking out an exact git commit without host tools
+//! - [`unpack`] - Deterministic extraction of tar, tar.gz, tar.xz and zip archives
+//! - [`file`] - Writing, copying, linking and removing files without a shell
+//! - [`source`] - Copying sources imported by `sys.source` out of the store
+
+use mlua::prelude::*;
 
 pub mod exec;
+pub mod fetch_git;
 pub mod fetch_url;
+pub mod file;
+pub mod source;
+pub mod unpack;
+
+/// Read an optional non-negative whole number from an action's options table.
+pub(crate) fn whole_number(table: &LuaTable, action: &str, key: &str, unit: &str) -> LuaResult<Option<u64>> {
+  match table.get::<LuaValue>(key)? {
+    LuaValue::Nil => Ok(None),
+    LuaValue::Integer(n) if n >= 0 => Ok(Some(n as u64)),
+    LuaValue::Number(n) if n >= 0.0 && n.fract() == 0.0 => Ok(Some(n as u64)),
+    _ => Err(LuaError::external(format!(
+      "{} '{}' must be a whole number of {}",
+      action, key, unit
+    ))),
+  }
+}
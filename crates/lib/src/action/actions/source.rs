@@ -0,0 +1,91 @@
+//! Source action implementation.
+//!
+//! Copies a file or directory that `sys.source` imported into the store while
+//! the config was evaluated into the output directory. The content was hashed
+//! on import, so it is not verified again here.
+
+use std::path::{Path, PathBuf};
+
+use crate::execute::types::ExecuteError;
+use crate::source::source_path;
+
+use super::file;
+
+/// Copy the imported source `sha256` to `out_dir/name`.
+///
+/// # Arguments
+///
+/// * `name` - Name of the copy in the output directory
+/// * `sha256` - Hash of the imported source
+/// * `out_dir` - The build's output directory
+///
+/// # Returns
+///
+/// The path of the copy.
+pub async fn execute_source(name: &str, sha256: &str, out_dir: &Path) -> Result<PathBuf, ExecuteError> {
+  let src = source_path(sha256);
+  if std::fs::symlink_metadata(&src).is_err() {
+    return Err(ExecuteError::SourceNotFound {
+      name: name.to_string(),
+      sha256: sha256.to_string(),
+    });
+  }
+
+  let dest = out_dir.join(name);
+  tokio::task::spawn_blocking(move || {
+    let result = file::copy(&src, &dest);
+    (result, dest)
+  })
+  .await
+  .map_err(|e| ExecuteError::TaskFailed { message: e.to_string() })
+  .and_then(|(result, dest)| {
+    result.map(|()| dest.clone()).map_err(|e| ExecuteError::FileFailed {
+      action: "source".to_string(),
+      message: format!("{}: {}", dest.display(), e),
+    })
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  fn run_in_store<T>(store: &Path, f: impl std::future::Future<Output = T>) -> T {
+    temp_env::with_var("SYSLUA_STORE", Some(store), || {
+      tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(f)
+    })
+  }
+
+  #[test]
+  #[serial]
+  fn copies_imported_source_into_out_dir() {
+    let temp = TempDir::new().unwrap();
+    let store = temp.path().join("store");
+    let imported = store.join("sources").join("abc");
+    std::fs::create_dir_all(imported.join("lua")).unwrap();
+    std::fs::write(imported.join("lua/init.lua"), "return {}").unwrap();
+    let out = temp.path().join("out");
+    std::fs::create_dir(&out).unwrap();
+
+    let path = run_in_store(&store, execute_source("nvim", "abc", &out)).unwrap();
+
+    assert_eq!(path, out.join("nvim"));
+    assert_eq!(std::fs::read_to_string(path.join("lua/init.lua")).unwrap(), "return {}");
+  }
+
+  #[test]
+  #[serial]
+  fn missing_source_is_an_error() {
+    let temp = TempDir::new().unwrap();
+
+    let result = run_in_store(temp.path(), execute_source("nvim", "abc", temp.path()));
+
+    assert!(matches!(result, Err(ExecuteError::SourceNotFound { .. })));
+  }
+}
@@ -0,0 +1,669 @@
+//! Unpack action implementation.
+//!
+//! Extracts tar, tar.gz, tar.xz and zip archives in-process, so builds don't
+//! depend on `tar`, `unzip` or PowerShell being installed on the host.
+//!
+//! Extraction is deterministic: ownership is not restored, files are written
+//! with mode `0644` (`0755` if any execute bit was set in the archive),
+//! directories with `0755`, and files and directories get the same fixed
+//! modification time. Entries with absolute paths or `..` components, links
+//! pointing outside the destination, and entries that would be written through
+//! a symlink extracted earlier fail the action.
+
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use flate2::read::GzDecoder;
+use lzma_rust2::XzReader;
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::whole_number;
+use crate::execute::types::ExecuteError;
+
+/// Modification time given to every extracted file and directory.
+///
+/// One second past the epoch rather than zero, which some tools treat as
+/// "no timestamp".
+const NORMALIZED_MTIME: Duration = Duration::from_secs(1);
+
+/// Archive formats the [`Unpack`](crate::action::Action::Unpack) action can extract.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArchiveFormat {
+  #[serde(rename = "tar")]
+  Tar,
+  #[serde(rename = "tar.gz")]
+  TarGz,
+  #[serde(rename = "tar.xz")]
+  TarXz,
+  #[serde(rename = "zip")]
+  Zip,
+}
+
+impl ArchiveFormat {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      ArchiveFormat::Tar => "tar",
+      ArchiveFormat::TarGz => "tar.gz",
+      ArchiveFormat::TarXz => "tar.xz",
+      ArchiveFormat::Zip => "zip",
+    }
+  }
+
+  /// Parse a format name as used in Lua (`"tar.gz"`, `"tgz"`, `"zip"`, ...).
+  pub fn parse(name: &str) -> Option<Self> {
+    match name {
+      "tar" => Some(ArchiveFormat::Tar),
+      "tar.gz" | "tgz" => Some(ArchiveFormat::TarGz),
+      "tar.xz" | "txz" => Some(ArchiveFormat::TarXz),
+      "zip" => Some(ArchiveFormat::Zip),
+      _ => None,
+    }
+  }
+
+  /// Detect the format of an archive from its first bytes.
+  fn detect(path: &Path) -> io::Result<Option<Self>> {
+    let mut header = Vec::with_capacity(262);
+    File::open(path)?.take(262).read_to_end(&mut header)?;
+
+    let format = if header.starts_with(&[0x1f, 0x8b]) {
+      Some(ArchiveFormat::TarGz)
+    } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+      Some(ArchiveFormat::TarXz)
+    } else if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+      Some(ArchiveFormat::Zip)
+    } else if header.get(257..262) == Some(b"ustar") {
+      Some(ArchiveFormat::Tar)
+    } else {
+      None
+    };
+    Ok(format)
+  }
+}
+
+/// Parse the arguments of `ctx:unpack`.
+///
+/// Accepts `(archive, opts?)` or a single table with `archive`, where the
+/// options are `format` and `strip_components`.
+///
+/// # Returns
+///
+/// The archive, its format (`None` to detect it when unpacking) and the number
+/// of leading path components to strip.
+pub fn parse_unpack_args(archive: LuaValue, opts: Option<LuaTable>) -> LuaResult<(String, Option<ArchiveFormat>, u32)> {
+  let (archive, opts) = match archive {
+    LuaValue::String(s) => (s.to_str()?.to_string(), opts),
+    LuaValue::Table(table) => {
+      let archive: String = table
+        .get::<Option<String>>("archive")?
+        .ok_or_else(|| LuaError::external("unpack() requires an 'archive'"))?;
+      (archive, Some(table))
+    }
+    _ => {
+      return Err(LuaError::external(
+        "unpack() expects an archive or a table with an 'archive' field",
+      ));
+    }
+  };
+
+  let Some(opts) = opts else {
+    return Ok((archive, None, 0));
+  };
+  let format = match opts.get::<Option<String>>("format")? {
+    Some(name) => Some(
+      ArchiveFormat::parse(&name)
+        .ok_or_else(|| LuaError::external(format!("unpack(): unsupported archive format '{}'", name)))?,
+    ),
+    None => None,
+  };
+  let strip_components = whole_number(&opts, "unpack", "strip_components", "path components")?.unwrap_or(0);
+  let strip_components =
+    u32::try_from(strip_components).map_err(|_| LuaError::external("unpack 'strip_components' is too large"))?;
+  Ok((archive, format, strip_components))
+}
+
+/// Execute an Unpack action.
+///
+/// # Arguments
+///
+/// * `archive` - Path of the archive, with placeholders already resolved
+/// * `format` - Format of the archive, `None` to detect it from its contents
+/// * `strip_components` - Number of leading path components removed from every entry
+/// * `out_dir` - Directory the archive is extracted into
+///
+/// # Returns
+///
+/// The directory the archive was extracted into.
+pub async fn execute_unpack(
+  archive: &Path,
+  format: Option<ArchiveFormat>,
+  strip_components: u32,
+  out_dir: &Path,
+) -> Result<PathBuf, ExecuteError> {
+  info!(archive = %archive.display(), "unpacking archive");
+
+  let archive = archive.to_path_buf();
+  let dest = out_dir.to_path_buf();
+  let task_archive = archive.clone();
+  let result = tokio::task::spawn_blocking(move || unpack(&task_archive, format, strip_components, &dest))
+    .await
+    .map_err(|e| ExecuteError::TaskFailed { message: e.to_string() })?;
+
+  result.map_err(|e| ExecuteError::UnpackFailed {
+    archive: archive.display().to_string(),
+    message: e.to_string(),
+  })?;
+  Ok(out_dir.to_path_buf())
+}
+
+fn unpack(archive: &Path, format: Option<ArchiveFormat>, strip_components: u32, dest: &Path) -> io::Result<()> {
+  let format = match format {
+    Some(format) => format,
+    None => ArchiveFormat::detect(archive)?.ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::InvalidData,
+        "unknown archive format; set `format` explicitly",
+      )
+    })?,
+  };
+
+  fs::create_dir_all(dest)?;
+  let mut extractor = Extractor::new(dest, strip_components);
+  let file = BufReader::new(File::open(archive)?);
+  match format {
+    ArchiveFormat::Tar => extractor.tar(file)?,
+    ArchiveFormat::TarGz => extractor.tar(GzDecoder::new(file))?,
+    ArchiveFormat::TarXz => extractor.tar(XzReader::new(file, true))?,
+    ArchiveFormat::Zip => extractor.zip(file)?,
+  }
+  extractor.finish()
+}
+
+/// Writes the entries of an archive below a destination directory.
+struct Extractor<'a> {
+  dest: &'a Path,
+  strip_components: usize,
+  /// Directories created or written to, normalized once everything is extracted.
+  dirs: BTreeSet<PathBuf>,
+}
+
+impl<'a> Extractor<'a> {
+  fn new(dest: &'a Path, strip_components: u32) -> Self {
+    Self {
+      dest,
+      strip_components: strip_components as usize,
+      dirs: BTreeSet::new(),
+    }
+  }
+
+  fn tar(&mut self, reader: impl Read) -> io::Result<()> {
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+      let mut entry = entry?;
+      let Some(path) = self.entry_path(&entry.path()?)? else {
+        continue;
+      };
+
+      let header = entry.header();
+      let kind = header.entry_type();
+      if kind.is_dir() {
+        self.dir(&path)?;
+      } else if kind.is_file() {
+        let executable = header.mode()? & 0o111 != 0;
+        self.file(&path, &mut entry, executable)?;
+      } else if kind.is_symlink() {
+        let target = entry
+          .link_name()?
+          .ok_or_else(|| unsafe_entry(&path, "symlink without a target"))?;
+        self.symlink(&path, &target)?;
+      } else if kind.is_hard_link() {
+        let target = entry
+          .link_name()?
+          .ok_or_else(|| unsafe_entry(&path, "hard link without a target"))?;
+        let target = self
+          .entry_path(&target)?
+          .ok_or_else(|| unsafe_entry(&path, "hard link to a stripped entry"))?;
+        self.hard_link(&path, &target)?;
+      }
+      // Devices, FIFOs and other special files are skipped
+    }
+    Ok(())
+  }
+
+  fn zip(&mut self, reader: impl Read + Seek) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(reader).map_err(io::Error::other)?;
+    for index in 0..archive.len() {
+      let mut entry = archive.by_index(index).map_err(io::Error::other)?;
+      let Some(path) = self.entry_path(Path::new(entry.name()))? else {
+        continue;
+      };
+
+      if entry.is_dir() {
+        self.dir(&path)?;
+      } else if entry.is_symlink() {
+        let mut target = String::new();
+        entry.read_to_string(&mut target)?;
+        self.symlink(&path, Path::new(&target))?;
+      } else {
+        let executable = entry.unix_mode().is_some_and(|mode| mode & 0o111 != 0);
+        self.file(&path, &mut entry, executable)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Path of an entry relative to the destination, after stripping leading
+  /// components.
+  ///
+  /// # Returns
+  ///
+  /// `None` for entries that are stripped away entirely, an error for paths
+  /// that could leave the destination.
+  fn entry_path(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+    let mut parts = Vec::new();
+    for component in path.components() {
+      match component {
+        Component::Normal(part) => parts.push(part),
+        Component::CurDir => {}
+        Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+          return Err(unsafe_entry(path, "path leaves the destination"));
+        }
+      }
+    }
+    if parts.len() <= self.strip_components {
+      return Ok(None);
+    }
+    Ok(Some(parts[self.strip_components..].iter().collect()))
+  }
+
+  fn dir(&mut self, path: &Path) -> io::Result<()> {
+    self.check_parents(path)?;
+    let full = self.dest.join(path);
+    fs::create_dir_all(&full)?;
+    self.track_parents(path);
+    self.dirs.insert(full);
+    Ok(())
+  }
+
+  fn file(&mut self, path: &Path, contents: &mut impl Read, executable: bool) -> io::Result<()> {
+    let full = self.prepare(path)?;
+    let mut file = File::create(&full)?;
+    io::copy(contents, &mut file)?;
+    file.set_modified(SystemTime::UNIX_EPOCH + NORMALIZED_MTIME)?;
+    set_mode(&full, if executable { 0o755 } else { 0o644 })
+  }
+
+  fn symlink(&mut self, path: &Path, target: &Path) -> io::Result<()> {
+    // Resolve the target lexically from the link's directory; it must not
+    // climb above the destination. `..` is only allowed before the first
+    // named component: after one, it could climb out of another symlink
+    // (`x/..` with `x -> ..`), which lexical resolution can't see.
+    let mut depth = path.components().count() - 1;
+    let mut descended = false;
+    for component in target.components() {
+      match component {
+        Component::Normal(_) => {
+          depth += 1;
+          descended = true;
+        }
+        Component::CurDir => {}
+        Component::ParentDir if depth > 0 && !descended => depth -= 1,
+        _ => return Err(unsafe_entry(path, "symlink points outside the destination")),
+      }
+    }
+
+    let full = self.prepare(path)?;
+    create_symlink(target, &full)
+  }
+
+  fn hard_link(&mut self, path: &Path, target: &Path) -> io::Result<()> {
+    self.check_parents(target)?;
+    let source = self.dest.join(target);
+    // A symlink's target was checked relative to where the symlink is; a
+    // hard link to it elsewhere could point anywhere
+    if fs::symlink_metadata(&source).is_ok_and(|m| m.file_type().is_symlink()) {
+      return Err(unsafe_entry(path, "hard link to a symlink"));
+    }
+    let full = self.prepare(path)?;
+    fs::hard_link(source, full)
+  }
+
+  /// Create the parent directories of an entry and remove whatever is at its
+  /// path, so a link left by an earlier entry is replaced rather than followed.
+  fn prepare(&mut self, path: &Path) -> io::Result<PathBuf> {
+    self.check_parents(path)?;
+    let full = self.dest.join(path);
+    if let Some(parent) = full.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    self.track_parents(path);
+    match fs::symlink_metadata(&full) {
+      Ok(metadata) if metadata.is_dir() => {
+        return Err(io::Error::new(
+          io::ErrorKind::AlreadyExists,
+          format!("{} is a directory", full.display()),
+        ));
+      }
+      Ok(_) => fs::remove_file(&full)?,
+      Err(_) => {}
+    }
+    Ok(full)
+  }
+
+  /// Fail if a directory above an entry is a symlink left by an earlier
+  /// entry; writing through it could land outside the destination.
+  fn check_parents(&self, path: &Path) -> io::Result<()> {
+    let mut dir = PathBuf::new();
+    for component in path.parent().into_iter().flat_map(Path::components) {
+      dir.push(component);
+      match fs::symlink_metadata(self.dest.join(&dir)) {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+          return Err(unsafe_entry(path, "a parent directory is a symlink"));
+        }
+        Ok(_) => {}
+        // Nothing further down exists yet
+        Err(_) => break,
+      }
+    }
+    Ok(())
+  }
+
+  fn track_parents(&mut self, path: &Path) {
+    let mut parent = path.parent();
+    while let Some(dir) = parent.filter(|dir| !dir.as_os_str().is_empty()) {
+      self.dirs.insert(self.dest.join(dir));
+      parent = dir.parent();
+    }
+  }
+
+  /// Normalize the directories once nothing is written into them anymore.
+  fn finish(self) -> io::Result<()> {
+    for dir in &self.dirs {
+      set_mode(dir, 0o755)?;
+      #[cfg(unix)]
+      File::open(dir)?.set_modified(SystemTime::UNIX_EPOCH + NORMALIZED_MTIME)?;
+    }
+    Ok(())
+  }
+}
+
+fn unsafe_entry(path: &Path, reason: &str) -> io::Error {
+  io::Error::new(
+    io::ErrorKind::InvalidData,
+    format!("unsafe archive entry {}: {}", path.display(), reason),
+  )
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+  Ok(())
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+  std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn create_symlink(target: &Path, link: &Path) -> io::Result<()> {
+  std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io::{Cursor, Write};
+
+  use tempfile::TempDir;
+
+  /// Build a tar archive from `(path, contents, mode)` entries.
+  fn tar_bytes(entries: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (path, contents, mode) in entries {
+      let mut header = tar::Header::new_gnu();
+      header.set_size(contents.len() as u64);
+      header.set_mode(*mode);
+      header.set_mtime(1_700_000_000);
+      header.set_entry_type(tar::EntryType::Regular);
+      builder.append_data(&mut header, path, *contents).unwrap();
+    }
+    builder.into_inner().unwrap()
+  }
+
+  fn tar_gz(entries: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(&tar_bytes(entries)).unwrap();
+    encoder.finish().unwrap()
+  }
+
+  fn write_archive(temp: &TempDir, name: &str, bytes: &[u8]) -> PathBuf {
+    let path = temp.path().join(name);
+    fs::write(&path, bytes).unwrap();
+    path
+  }
+
+  const ENTRIES: &[(&str, &[u8], u32)] = &[
+    ("rg-15.1.0/rg", b"#!/bin/sh\n", 0o700),
+    ("rg-15.1.0/doc/README.md", b"ripgrep\n", 0o600),
+  ];
+
+  #[tokio::test]
+  async fn unpacks_tar_gz_with_strip_components() {
+    let temp = TempDir::new().unwrap();
+    let archive = write_archive(&temp, "rg.tar.gz", &tar_gz(ENTRIES));
+    let out = temp.path().join("out");
+
+    let result = execute_unpack(&archive, Some(ArchiveFormat::TarGz), 1, &out)
+      .await
+      .unwrap();
+
+    assert_eq!(result, out);
+    assert_eq!(fs::read(out.join("rg")).unwrap(), b"#!/bin/sh\n");
+    assert_eq!(fs::read(out.join("doc/README.md")).unwrap(), b"ripgrep\n");
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn normalizes_permissions_and_mtimes() {
+    use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+    let temp = TempDir::new().unwrap();
+    let archive = write_archive(&temp, "rg.tar", &tar_bytes(ENTRIES));
+    let out = temp.path().join("out");
+
+    execute_unpack(&archive, None, 0, &out).await.unwrap();
+
+    for (path, mode) in [
+      ("rg-15.1.0/rg", 0o755),
+      ("rg-15.1.0/doc/README.md", 0o644),
+      ("rg-15.1.0/doc", 0o755),
+      ("rg-15.1.0", 0o755),
+    ] {
+      let metadata = fs::metadata(out.join(path)).unwrap();
+      assert_eq!(metadata.permissions().mode() & 0o777, mode, "{}", path);
+      assert_eq!(metadata.mtime(), 1, "{}", path);
+    }
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn detects_xz_and_zip() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp = TempDir::new().unwrap();
+
+    let mut xz = Vec::new();
+    let mut writer = lzma_rust2::XzWriter::new(&mut xz, lzma_rust2::XzOptions::default()).unwrap();
+    writer.write_all(&tar_bytes(ENTRIES)).unwrap();
+    writer.finish().unwrap();
+    let archive = write_archive(&temp, "rg.tar.xz", &xz);
+    execute_unpack(&archive, None, 1, &temp.path().join("xz"))
+      .await
+      .unwrap();
+    assert!(temp.path().join("xz/rg").exists());
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = zip::write::SimpleFileOptions::default().unix_permissions(0o755);
+    zip.start_file("rg-15.1.0/rg", options).unwrap();
+    zip.write_all(b"#!/bin/sh\n").unwrap();
+    let archive = write_archive(&temp, "rg.zip", &zip.finish().unwrap().into_inner());
+    execute_unpack(&archive, None, 1, &temp.path().join("zip"))
+      .await
+      .unwrap();
+    let metadata = fs::metadata(temp.path().join("zip/rg")).unwrap();
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o755);
+  }
+
+  #[tokio::test]
+  async fn rejects_path_traversal() {
+    let temp = TempDir::new().unwrap();
+    let out = temp.path().join("out");
+
+    // tar::Builder refuses `..`, so write the name into the header directly
+    let mut header = tar::Header::new_gnu();
+    header.as_gnu_mut().unwrap().name[..9].copy_from_slice(b"../escape");
+    header.set_size(1);
+    header.set_mode(0o644);
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_cksum();
+    let mut builder = tar::Builder::new(Vec::new());
+    builder.append(&header, &b"x"[..]).unwrap();
+    let archive = write_archive(&temp, "evil.tar", &builder.into_inner().unwrap());
+
+    let result = execute_unpack(&archive, None, 0, &out).await;
+
+    assert!(matches!(result, Err(ExecuteError::UnpackFailed { .. })), "{:?}", result);
+    assert!(!temp.path().join("escape").exists());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn rejects_symlinks_leaving_the_destination() {
+    let temp = TempDir::new().unwrap();
+    let out = temp.path().join("out");
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder.append_link(&mut header, "pkg/lib", "../../etc").unwrap();
+    let archive = write_archive(&temp, "evil.tar", &builder.into_inner().unwrap());
+
+    let result = execute_unpack(&archive, None, 0, &out).await;
+    assert!(matches!(result, Err(ExecuteError::UnpackFailed { .. })), "{:?}", result);
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder.append_link(&mut header, "pkg/bin/rg", "../lib/rg").unwrap();
+    let archive = write_archive(&temp, "ok.tar", &builder.into_inner().unwrap());
+
+    execute_unpack(&archive, None, 0, &out).await.unwrap();
+    assert_eq!(fs::read_link(out.join("pkg/bin/rg")).unwrap(), Path::new("../lib/rg"));
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn rejects_writes_through_chained_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let out = temp.path().join("build").join("out");
+
+    // Each link is harmless on its own, but `a/y` resolves to `a/x/..`, the
+    // parent of the destination
+    let mut builder = tar::Builder::new(Vec::new());
+    for (link, target) in [("a/x", ".."), ("a/y", "x/..")] {
+      let mut header = tar::Header::new_gnu();
+      header.set_entry_type(tar::EntryType::Symlink);
+      header.set_size(0);
+      builder.append_link(&mut header, link, target).unwrap();
+    }
+    let mut header = tar::Header::new_gnu();
+    header.set_size(1);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, "a/y/ESCAPED", &b"x"[..]).unwrap();
+    let archive = write_archive(&temp, "evil.tar", &builder.into_inner().unwrap());
+
+    let result = execute_unpack(&archive, None, 0, &out).await;
+
+    assert!(matches!(result, Err(ExecuteError::UnpackFailed { .. })), "{:?}", result);
+    assert!(!temp.path().join("build/ESCAPED").exists());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn rejects_entries_below_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let out = temp.path().join("out");
+
+    // The link stays inside the destination, but files are never written
+    // through one
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder.append_link(&mut header, "pkg/lib", "../other").unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_size(1);
+    header.set_mode(0o644);
+    builder.append_data(&mut header, "pkg/lib/file", &b"x"[..]).unwrap();
+    let archive = write_archive(&temp, "links.tar", &builder.into_inner().unwrap());
+
+    let result = execute_unpack(&archive, None, 0, &out).await;
+
+    assert!(matches!(result, Err(ExecuteError::UnpackFailed { .. })), "{:?}", result);
+    assert!(!out.join("other/file").exists());
+  }
+
+  #[tokio::test]
+  #[cfg(unix)]
+  async fn rejects_hard_links_below_symlinks() {
+    let temp = TempDir::new().unwrap();
+    let out = temp.path().join("out");
+
+    // `x/x` names `x` itself on disk, but only by going through a symlink
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Symlink);
+    header.set_size(0);
+    builder.append_link(&mut header, "x", ".").unwrap();
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(tar::EntryType::Link);
+    header.set_size(0);
+    builder.append_link(&mut header, "copy", "x/x").unwrap();
+    let archive = write_archive(&temp, "hard.tar", &builder.into_inner().unwrap());
+
+    let result = execute_unpack(&archive, None, 0, &out).await;
+
+    assert!(matches!(result, Err(ExecuteError::UnpackFailed { .. })), "{:?}", result);
+  }
+
+  #[test]
+  fn parses_lua_arguments() {
+    let lua = Lua::new();
+    let archive = LuaValue::String(lua.create_string("$${{action:0}}").unwrap());
+    let opts: LuaTable = lua.load("{ format = 'tgz', strip_components = 1 }").eval().unwrap();
+
+    assert_eq!(
+      parse_unpack_args(archive.clone(), Some(opts)).unwrap(),
+      ("$${{action:0}}".to_string(), Some(ArchiveFormat::TarGz), 1)
+    );
+    assert_eq!(
+      parse_unpack_args(archive.clone(), None).unwrap(),
+      ("$${{action:0}}".to_string(), None, 0)
+    );
+
+    let bad: LuaTable = lua.load("{ format = 'rar' }").eval().unwrap();
+    assert!(parse_unpack_args(archive, Some(bad)).is_err());
+  }
+}
@@ -0,0 +1,244 @@
+//! Persistent action logs.
+//!
+//! Every action of a build or bind writes its output to a log file in the
+//! store, so a failure can be inspected after the fact with `sys log`:
+//!
+//! ```text
+//! <store>/log/<hash>/<phase>-<index>.log
+//! ```
+//!
+//! Builds only have a `create` phase; binds also log their `update`,
+//! `destroy` and `check` actions. Each line of a log is prefixed with a
+//! timestamp and the stream it came from. Running an action again replaces
+//! its log.
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use tracing::{debug, warn};
+
+use crate::execute::events::{NodeEvents, OutputStream};
+use crate::platform::paths::store_dir;
+use crate::util::hash::ObjectHash;
+
+/// Directory in the store holding the action logs.
+pub const LOG_DIR: &str = "log";
+
+/// The set of actions of a build or bind an action belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ActionPhase {
+  Create,
+  Update,
+  Destroy,
+  Check,
+}
+
+impl ActionPhase {
+  const ALL: [ActionPhase; 4] = [
+    ActionPhase::Create,
+    ActionPhase::Update,
+    ActionPhase::Destroy,
+    ActionPhase::Check,
+  ];
+
+  pub fn as_str(self) -> &'static str {
+    match self {
+      ActionPhase::Create => "create",
+      ActionPhase::Update => "update",
+      ActionPhase::Destroy => "destroy",
+      ActionPhase::Check => "check",
+    }
+  }
+}
+
+impl fmt::Display for ActionPhase {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+/// A log file of a single action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFile {
+  pub phase: ActionPhase,
+  pub index: usize,
+  pub path: PathBuf,
+}
+
+/// Directory holding the action logs of a build or bind.
+pub fn log_dir_path(hash: &ObjectHash) -> PathBuf {
+  store_dir().join(LOG_DIR).join(&hash.0)
+}
+
+/// Path of the log of one action of a build or bind.
+pub fn action_log_path(hash: &ObjectHash, phase: ActionPhase, index: usize) -> PathBuf {
+  log_dir_path(hash).join(format!("{}-{}.log", phase, index))
+}
+
+/// Hashes of every build and bind with logs in the store.
+pub fn logged_hashes() -> io::Result<Vec<ObjectHash>> {
+  let entries = match fs::read_dir(store_dir().join(LOG_DIR)) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(e),
+  };
+
+  let mut hashes = Vec::new();
+  for entry in entries {
+    let entry = entry?;
+    if entry.file_type()?.is_dir()
+      && let Some(name) = entry.file_name().to_str()
+    {
+      hashes.push(ObjectHash(name.to_string()));
+    }
+  }
+  hashes.sort_by(|a, b| a.0.cmp(&b.0));
+  Ok(hashes)
+}
+
+/// List the action logs of a build or bind, ordered by phase and action index.
+///
+/// # Returns
+///
+/// An empty list if nothing was logged for `hash`.
+pub fn list_action_logs(hash: &ObjectHash) -> io::Result<Vec<LogFile>> {
+  let dir = log_dir_path(hash);
+  let entries = match fs::read_dir(&dir) {
+    Ok(entries) => entries,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+    Err(e) => return Err(e),
+  };
+
+  let mut logs = Vec::new();
+  for entry in entries {
+    let path = entry?.path();
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+      continue;
+    };
+    if let Some((phase, index)) = parse_log_name(name) {
+      logs.push(LogFile { phase, index, path });
+    }
+  }
+  logs.sort_by_key(|log| (log.phase, log.index));
+  Ok(logs)
+}
+
+fn parse_log_name(name: &str) -> Option<(ActionPhase, usize)> {
+  let (phase, index) = name.strip_suffix(".log")?.rsplit_once('-')?;
+  let phase = ActionPhase::ALL.into_iter().find(|p| p.as_str() == phase)?;
+  Some((phase, index.parse().ok()?))
+}
+
+/// Writer for the log of a running action.
+///
+/// Lines can be written from the tasks reading stdout and stderr at the same
+/// time. Command output and download progress are also forwarded to the
+/// execution's event channel, if there is one. Failures to write are logged
+/// and otherwise ignored; losing the log must not fail the action.
+#[derive(Debug)]
+pub struct ActionLog {
+  file: Option<Mutex<File>>,
+  events: Option<NodeEvents>,
+}
+
+impl ActionLog {
+  /// Create (or truncate) the log file at `path`, creating its directory.
+  pub fn create(path: &Path) -> io::Result<Self> {
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    Ok(Self {
+      file: Some(Mutex::new(File::create(path)?)),
+      events: None,
+    })
+  }
+
+  /// Open the log of an action at `path`, forwarding its output to `events`.
+  ///
+  /// If the file cannot be created, the action runs without one.
+  pub fn open(path: &Path, events: Option<NodeEvents>) -> Self {
+    let file = match Self::create(path) {
+      Ok(log) => log.file,
+      Err(e) => {
+        warn!(path = %path.display(), error = %e, "failed to create action log");
+        None
+      }
+    };
+    Self { file, events }
+  }
+
+  /// Append a timestamped line tagged with its source (`exec`, `exit`, ...).
+  pub fn line(&self, source: &str, text: &str) {
+    let Some(file) = &self.file else {
+      return;
+    };
+    let timestamp = humantime::format_rfc3339_millis(SystemTime::now());
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = writeln!(file, "{} {}: {}", timestamp, source, text) {
+      debug!(error = %e, "failed to write action log");
+    }
+  }
+
+  /// Record a line of command output.
+  pub fn output(&self, stream: OutputStream, text: &str) {
+    self.line(stream.as_str(), text);
+    if let Some(events) = &self.events {
+      events.output(stream, text);
+    }
+  }
+
+  /// Report download progress. Progress is not written to the file.
+  pub fn download(&self, url: &str, downloaded: u64, total: Option<u64>) {
+    if let Some(events) = &self.events {
+      events.download(url, downloaded, total);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  #[test]
+  #[serial]
+  fn logs_are_listed_in_phase_and_action_order() {
+    let temp = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_STORE", Some(temp.path()), || {
+      let hash = ObjectHash("abc123".to_string());
+      for (phase, index) in [
+        (ActionPhase::Destroy, 0),
+        (ActionPhase::Create, 10),
+        (ActionPhase::Create, 2),
+      ] {
+        ActionLog::create(&action_log_path(&hash, phase, index))
+          .unwrap()
+          .output(OutputStream::Stdout, "hello");
+      }
+      fs::write(log_dir_path(&hash).join("notes.txt"), "").unwrap();
+
+      let logs: Vec<_> = list_action_logs(&hash)
+        .unwrap()
+        .into_iter()
+        .map(|log| (log.phase, log.index))
+        .collect();
+      assert_eq!(
+        logs,
+        vec![
+          (ActionPhase::Create, 2),
+          (ActionPhase::Create, 10),
+          (ActionPhase::Destroy, 0)
+        ]
+      );
+
+      let content = fs::read_to_string(action_log_path(&hash, ActionPhase::Create, 2)).unwrap();
+      assert!(content.trim_end().ends_with("stdout: hello"), "{}", content);
+      assert!(list_action_logs(&ObjectHash("missing".to_string())).unwrap().is_empty());
+    });
+  }
+}
@@ -8,7 +8,12 @@
 //! # Action Types
 //!
 //! - [`Action::Exec`] - Execute a shell command with optional args, env, and cwd
-//! - [`Action::FetchUrl`] - Download a file from a URL with SHA256 verification
+//! - [`Action::FetchUrl`] - Download a file from a URL or its mirrors with hash verification
+//! - [`Action::FetchGit`] - Check out a git commit, verified by its tree hash
+//! - [`Action::Unpack`] - Extract an archive into the output directory
+//! - [`Action::WriteFile`], [`Action::Copy`], [`Action::Symlink`], [`Action::Mkdir`],
+//!   [`Action::Chmod`], [`Action::Remove`] - Change files without a shell
+//! - [`Action::Source`] - Copy a local source imported into the store
 //!
 //! # Placeholder Resolution
 //!
@@ -19,26 +24,58 @@
 //! - `${{bind:HASH:output}}` - Output from a dependency bind
 //!
 //! See [`crate::placeholder`] for the full placeholder system.
+//!
+//! # Logs
+//!
+//! The output of each action is written to a log in the store; see [`log`].
 
 pub mod actions;
+pub mod log;
 mod types;
 
 pub use types::*;
 
 use std::collections::BTreeMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::execute::types::{ActionResult, ExecuteError};
 use crate::placeholder::{self, Resolver};
+use crate::platform::sandbox::Sandbox;
 use actions::exec::ExecOpts;
 use actions::exec::execute_cmd;
-use actions::fetch_url::execute_fetch_url;
+use actions::fetch_git::execute_fetch_git;
+use actions::fetch_url::{FetchUrlOpts, execute_fetch_url};
+use actions::file::{self, FileScope, execute_file_action};
+use actions::source::execute_source;
+use actions::unpack::execute_unpack;
+use log::ActionLog;
 
 /// Names of built-in methods on BuildCtx that cannot be overwritten.
-pub const BUILTIN_BUILD_CTX_METHODS: &[&str] = &["exec", "fetch_url", "out"];
+pub const BUILTIN_BUILD_CTX_METHODS: &[&str] = &[
+  "chmod",
+  "copy",
+  "exec",
+  "fetch_git",
+  "fetch_url",
+  "mkdir",
+  "out",
+  "remove",
+  "symlink",
+  "unpack",
+  "write_file",
+];
 
 /// Names of built-in methods on BindCtx that cannot be overwritten.
-pub const BUILTIN_BIND_CTX_METHODS: &[&str] = &["exec", "out"];
+pub const BUILTIN_BIND_CTX_METHODS: &[&str] = &[
+  "chmod",
+  "copy",
+  "exec",
+  "mkdir",
+  "out",
+  "remove",
+  "symlink",
+  "write_file",
+];
 
 /// Execute a single build action.
 ///
@@ -50,6 +87,10 @@ pub const BUILTIN_BIND_CTX_METHODS: &[&str] = &["exec", "out"];
 /// * `action` - The action to execute
 /// * `resolver` - The placeholder resolver for this build
 /// * `out_dir` - The build's output directory
+/// * `sandbox` - The sandbox `Exec` actions run in, `None` to run unconfined
+/// * `scope` - Where file actions may write and read, `None` for binds, which
+///   may touch any path
+/// * `log` - The log that receives the action's output, `None` to not keep one
 ///
 /// # Returns
 ///
@@ -58,14 +99,183 @@ pub async fn execute_action(
   action: &Action,
   resolver: &impl Resolver,
   out_dir: &Path,
+  sandbox: Option<&Sandbox>,
+  scope: Option<&FileScope>,
+  log: Option<&ActionLog>,
 ) -> Result<ActionResult, ExecuteError> {
   match action {
-    Action::FetchUrl { url, sha256 } => {
-      // Resolve placeholders in URL (unusual but possible)
-      let resolved_url = placeholder::substitute(url, resolver)?;
-      let resolved_sha256 = placeholder::substitute(sha256, resolver)?;
+    Action::FetchUrl(opts) => {
+      // Resolve placeholders in URLs and hash (unusual but possible)
+      let mut mirrors = Vec::with_capacity(opts.mirrors.len());
+      for mirror in &opts.mirrors {
+        mirrors.push(placeholder::substitute(mirror, resolver)?);
+      }
+      let resolved = FetchUrlOpts {
+        url: placeholder::substitute(&opts.url, resolver)?,
+        hash: placeholder::substitute(&opts.hash, resolver)?,
+        mirrors,
+        ..opts.clone()
+      };
+
+      if let Some(log) = log {
+        log.line("fetch", &resolved.url);
+      }
+      let result = execute_fetch_url(&resolved, out_dir, log).await;
+      if let Some(log) = log {
+        match &result {
+          Ok(path) => log.line("fetch", &format!("saved to {}", path.display())),
+          Err(e) => log.line("error", &e.to_string()),
+        }
+      }
+      let path = result?;
+
+      Ok(ActionResult {
+        output: path.to_string_lossy().to_string(),
+      })
+    }
+
+    Action::FetchGit { url, rev, sha256 } => {
+      let url = placeholder::substitute(url, resolver)?;
+
+      if let Some(log) = log {
+        log.line("fetch", &format!("{} at {}", url, rev));
+      }
+      let result = execute_fetch_git(&url, rev, sha256, out_dir).await;
+      if let Some(log) = log
+        && let Err(e) = &result
+      {
+        log.line("error", &e.to_string());
+      }
+      let path = result?;
+
+      Ok(ActionResult {
+        output: path.to_string_lossy().to_string(),
+      })
+    }
+
+    Action::Unpack {
+      archive,
+      format,
+      strip_components,
+    } => {
+      let resolved_archive = placeholder::substitute(archive, resolver)?;
+
+      if let Some(log) = log {
+        log.line("unpack", &resolved_archive);
+      }
+      let readable = scope.map_or(Ok(()), |scope| scope.check_read(Path::new(&resolved_archive)));
+      let result = match readable {
+        Ok(()) => execute_unpack(Path::new(&resolved_archive), *format, *strip_components, out_dir).await,
+        Err(e) => Err(ExecuteError::UnpackFailed {
+          archive: resolved_archive.clone(),
+          message: e.to_string(),
+        }),
+      };
+      if let Some(log) = log
+        && let Err(e) = &result
+      {
+        log.line("error", &e.to_string());
+      }
+      let path = result?;
+
+      Ok(ActionResult {
+        output: path.to_string_lossy().to_string(),
+      })
+    }
 
-      let path = execute_fetch_url(&resolved_url, &resolved_sha256, out_dir).await?;
+    Action::WriteFile { path, content, mode } => {
+      let content = placeholder::substitute(content, resolver)?;
+      let mode = *mode;
+      let path = resolve_path(path, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("write_file", path, log, move |p| {
+          check_write(scope.as_ref(), p, true)?;
+          file::write_file(p, &content, mode)
+        })
+        .await,
+      )
+    }
+
+    Action::Copy { src, dest } => {
+      let src = resolve_path(src, out_dir, resolver)?;
+      let dest = resolve_path(dest, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("copy", dest, log, move |p| {
+          if let Some(scope) = &scope {
+            scope.check_read(&src)?;
+          }
+          check_write(scope.as_ref(), p, true)?;
+          file::copy(&src, p)
+        })
+        .await,
+      )
+    }
+
+    Action::Symlink { target, link } => {
+      // The target is kept as written: a relative target is relative to the link
+      let target = PathBuf::from(placeholder::substitute(target, resolver)?);
+      let link = resolve_path(link, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("symlink", link, log, move |p| {
+          check_write(scope.as_ref(), p, true)?;
+          file::symlink(&target, p)
+        })
+        .await,
+      )
+    }
+
+    Action::Mkdir { path, mode } => {
+      let mode = *mode;
+      let path = resolve_path(path, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("mkdir", path, log, move |p| {
+          check_write(scope.as_ref(), p, false)?;
+          file::mkdir(p, mode)
+        })
+        .await,
+      )
+    }
+
+    Action::Chmod { path, mode } => {
+      let mode = *mode;
+      let path = resolve_path(path, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("chmod", path, log, move |p| {
+          check_write(scope.as_ref(), p, false)?;
+          file::chmod(p, mode)
+        })
+        .await,
+      )
+    }
+
+    Action::Remove { path } => {
+      let path = resolve_path(path, out_dir, resolver)?;
+      let scope = scope.cloned();
+      file_result(
+        execute_file_action("remove", path, log, move |p| {
+          check_write(scope.as_ref(), p, true)?;
+          file::remove(p)
+        })
+        .await,
+      )
+    }
+
+    Action::Source { name, sha256 } => {
+      if let Some(log) = log {
+        log.line("source", &format!("{} ({})", name, sha256));
+      }
+      let result = execute_source(name, sha256, out_dir).await;
+      if let Some(log) = log
+        && let Err(e) = &result
+      {
+        log.line("error", &e.to_string());
+      }
+      let path = result?;
 
       Ok(ActionResult {
         output: path.to_string_lossy().to_string(),
@@ -78,6 +288,7 @@ pub async fn execute_action(
         args,
         env,
         cwd,
+        ..
       } = opts;
       // Resolve placeholders in command, env, and cwd
       let resolved_cmd = placeholder::substitute(cmd, resolver)?;
@@ -114,6 +325,9 @@ pub async fn execute_action(
         resolved_env.as_ref(),
         resolved_cwd.as_deref(),
         out_dir,
+        opts.limits(),
+        sandbox,
+        log,
       )
       .await?;
 
@@ -122,6 +336,26 @@ pub async fn execute_action(
   }
 }
 
+/// Resolve placeholders in a path; relative paths are relative to `out_dir`.
+fn resolve_path(path: &str, out_dir: &Path, resolver: &impl Resolver) -> Result<PathBuf, ExecuteError> {
+  Ok(out_dir.join(placeholder::substitute(path, resolver)?))
+}
+
+/// Check a file action's destination against the build's scope, if any.
+fn check_write(scope: Option<&FileScope>, path: &Path, replaces_link: bool) -> std::io::Result<()> {
+  match scope {
+    Some(scope) => scope.check_write(path, replaces_link),
+    None => Ok(()),
+  }
+}
+
+/// The result of a file action: the path it changed.
+fn file_result(result: Result<PathBuf, ExecuteError>) -> Result<ActionResult, ExecuteError> {
+  Ok(ActionResult {
+    output: result?.to_string_lossy().to_string(),
+  })
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -193,9 +427,14 @@ mod tests {
       args: Some(args),
       env: None,
       cwd: None,
+      timeout: None,
+      memory_limit: None,
+      cpu_time_limit: None,
     });
 
-    let result = execute_action(&action, &resolver, out_dir).await.unwrap();
+    let result = execute_action(&action, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result.output, "hello");
   }
@@ -212,9 +451,14 @@ mod tests {
       args: Some(args),
       env: None,
       cwd: None,
+      timeout: None,
+      memory_limit: None,
+      cpu_time_limit: None,
     });
 
-    let result = execute_action(&action, &resolver, out_dir).await.unwrap();
+    let result = execute_action(&action, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result.output, out_dir.to_string_lossy());
   }
@@ -231,13 +475,62 @@ mod tests {
       args: Some(args),
       env: None,
       cwd: None,
+      timeout: None,
+      memory_limit: None,
+      cpu_time_limit: None,
     });
 
-    let result = execute_action(&action, &resolver, out_dir).await.unwrap();
+    let result = execute_action(&action, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result.output, "/path/to/file.tar.gz");
   }
 
+  #[tokio::test]
+  async fn file_actions_resolve_relative_paths_against_out_dir() {
+    let temp_dir = TempDir::new().unwrap();
+    let out_dir = temp_dir.path();
+    let resolver = TestResolver::new(out_dir.to_str().unwrap()).with_action("world");
+
+    let write = Action::WriteFile {
+      path: "etc/greeting".to_string(),
+      content: "hello $${{action:0}}".to_string(),
+      mode: None,
+    };
+    let result = execute_action(&write, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
+    assert_eq!(result.output, out_dir.join("etc/greeting").to_string_lossy());
+    assert_eq!(
+      std::fs::read_to_string(out_dir.join("etc/greeting")).unwrap(),
+      "hello world"
+    );
+
+    let copy = Action::Copy {
+      src: "$${{out}}/etc".to_string(),
+      dest: "copy".to_string(),
+    };
+    execute_action(&copy, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
+    assert_eq!(
+      std::fs::read_to_string(out_dir.join("copy/greeting")).unwrap(),
+      "hello world"
+    );
+
+    let remove = Action::Remove {
+      path: "etc".to_string(),
+    };
+    execute_action(&remove, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
+    execute_action(&remove, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
+    assert!(!out_dir.join("etc").exists());
+  }
+
   #[tokio::test]
   async fn execute_cmd_with_env_placeholders() {
     let temp_dir = TempDir::new().unwrap();
@@ -253,9 +546,14 @@ mod tests {
       args: Some(args),
       env: Some(env),
       cwd: None,
+      timeout: None,
+      memory_limit: None,
+      cpu_time_limit: None,
     });
 
-    let result = execute_action(&action, &resolver, out_dir).await.unwrap();
+    let result = execute_action(&action, &resolver, out_dir, None, None, None)
+      .await
+      .unwrap();
 
     assert_eq!(result.output, out_dir.to_string_lossy());
   }
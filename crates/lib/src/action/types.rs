@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::action::actions::exec::ExecOpts;
+use crate::action::actions::fetch_url::FetchUrlOpts;
+use crate::action::actions::unpack::ArchiveFormat;
 
 /// Key for storing registered build ctx methods in Lua's registry.
 pub const BUILD_CTX_METHODS_REGISTRY_KEY: &str = "__syslua_build_ctx_methods";
@@ -16,6 +18,12 @@ pub const BIND_CTX_METHODS_REGISTRY_KEY: &str = "__syslua_bind_ctx_methods";
 /// # Variants
 ///
 /// - [`FetchUrl`](Action::FetchUrl): Download a file with integrity verification
+/// - [`FetchGit`](Action::FetchGit): Check out a git commit, verified by the hash of its tree
+/// - [`Unpack`](Action::Unpack): Extract an archive into the output directory
+/// - [`WriteFile`](Action::WriteFile), [`Copy`](Action::Copy), [`Symlink`](Action::Symlink),
+///   [`Mkdir`](Action::Mkdir), [`Chmod`](Action::Chmod), [`Remove`](Action::Remove):
+///   Change files directly, without a shell
+/// - [`Source`](Action::Source): Copy a source imported by `sys.source`
 /// - [`Exec`](Action::Exec): Execute a shell command
 ///
 /// # Placeholder Resolution
@@ -24,16 +32,75 @@ pub const BIND_CTX_METHODS_REGISTRY_KEY: &str = "__syslua_bind_ctx_methods";
 /// by subsequent actions via placeholders (e.g., `$${{action:0}}`).
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Action {
-  /// Fetch a URL with SHA-256 integrity verification.
+  /// Fetch a URL with integrity verification.
   ///
   /// This is a built-in action to avoid bootstrap problems (e.g., needing curl
   /// to build curl). The runtime handles the download directly.
   ///
   /// # Fields
   ///
-  /// - `url`: The URL to download
-  /// - `sha256`: Expected SHA-256 hash of the downloaded content (lowercase hex)
-  FetchUrl { url: String, sha256: String },
+  /// - `opts`: The URL and its mirrors, the expected hash and the retry policy
+  FetchUrl(FetchUrlOpts),
+  /// Check out one commit of a git repository into the output directory.
+  ///
+  /// # Fields
+  ///
+  /// - `url`: The repository to fetch from
+  /// - `rev`: The full hash of the commit
+  /// - `sha256`: Expected hash of the checked-out tree (see
+  ///   [`hash_directory`](crate::util::hash::hash_directory))
+  FetchGit { url: String, rev: String, sha256: String },
+  /// Extract an archive into the output directory.
+  ///
+  /// Built in for the same reason as `FetchUrl`: builds should not need
+  /// `tar` or `unzip` from the host.
+  ///
+  /// # Fields
+  ///
+  /// - `archive`: Path of the archive, usually the output of a `FetchUrl`
+  /// - `format`: Archive format, detected from the archive's contents if `None`
+  /// - `strip_components`: Number of leading path components removed from every entry
+  Unpack {
+    archive: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<ArchiveFormat>,
+    #[serde(default)]
+    strip_components: u32,
+  },
+  /// Write a file, creating its parent directories.
+  ///
+  /// # Fields
+  ///
+  /// - `path`: The file to write
+  /// - `content`: The content of the file
+  /// - `mode`: Permissions of the file (Unix mode bits)
+  WriteFile {
+    path: String,
+    content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+  },
+  /// Copy a file or directory tree.
+  Copy { src: String, dest: String },
+  /// Create a symlink at `link` pointing to `target`.
+  Symlink { target: String, link: String },
+  /// Create a directory and its parents.
+  Mkdir {
+    path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<u32>,
+  },
+  /// Change the permissions of a file or directory.
+  Chmod { path: String, mode: u32 },
+  /// Remove a file, symlink or directory tree, if it exists.
+  Remove { path: String },
+  /// Copy a source imported by `sys.source` out of the store.
+  ///
+  /// # Fields
+  ///
+  /// - `name`: Name of the copy in the output directory
+  /// - `sha256`: Hash of the imported content (see [`crate::source`])
+  Source { name: String, sha256: String },
   /// Execute a binary.
   ///
   /// # Fields
@@ -116,20 +183,131 @@ impl ActionCtx {
   ///
   /// # Arguments
   ///
-  /// - `url`: The URL to download
-  /// - `sha256`: Expected SHA-256 hash (lowercase hex) for integrity verification
+  /// - `opts`: The URL to download, its expected hash (SHA-256 hex or SRI) and
+  ///   optional mirrors and retry settings
   ///
   /// # Returns
   ///
   /// An opaque placeholder string (e.g., `$${{action:0}}`) that resolves to
   /// the downloaded file path at execution time.
-  pub fn fetch_url(&mut self, url: &str, sha256: &str) -> String {
-    self.record_action(Action::FetchUrl {
+  pub fn fetch_url(&mut self, opts: FetchUrlOpts) -> String {
+    self.record_action(Action::FetchUrl(opts))
+  }
+
+  /// Record checking out a git commit and return a placeholder for its output.
+  ///
+  /// The tree of the commit is checked out into the output directory, which
+  /// the returned placeholder resolves to at execution time.
+  ///
+  /// # Arguments
+  ///
+  /// - `url`: The repository to fetch from
+  /// - `rev`: The full hash of the commit
+  /// - `sha256`: Expected hash of the checked-out tree
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder string (e.g., `$${{action:0}}`).
+  pub fn fetch_git(&mut self, url: &str, rev: &str, sha256: &str) -> String {
+    self.record_action(Action::FetchGit {
       url: url.to_string(),
+      rev: rev.to_string(),
       sha256: sha256.to_string(),
     })
   }
 
+  /// Record an archive extraction and return a placeholder for its output.
+  ///
+  /// The archive is extracted into the output directory, which the returned
+  /// placeholder resolves to at execution time.
+  ///
+  /// # Arguments
+  ///
+  /// - `archive`: Path of the archive (typically a placeholder from [`fetch_url`](Self::fetch_url))
+  /// - `format`: Archive format, `None` to detect it from the archive
+  /// - `strip_components`: Number of leading path components to remove
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder string (e.g., `$${{action:1}}`).
+  pub fn unpack(&mut self, archive: &str, format: Option<ArchiveFormat>, strip_components: u32) -> String {
+    self.record_action(Action::Unpack {
+      archive: archive.to_string(),
+      format,
+      strip_components,
+    })
+  }
+
+  /// Record writing `content` to the file at `path`.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to the path of the file.
+  pub fn write_file(&mut self, path: &str, content: &str, mode: Option<u32>) -> String {
+    self.record_action(Action::WriteFile {
+      path: path.to_string(),
+      content: content.to_string(),
+      mode,
+    })
+  }
+
+  /// Record copying the file or directory `src` to `dest`.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to `dest`.
+  pub fn copy(&mut self, src: &str, dest: &str) -> String {
+    self.record_action(Action::Copy {
+      src: src.to_string(),
+      dest: dest.to_string(),
+    })
+  }
+
+  /// Record creating a symlink at `link` that points to `target`.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to `link`.
+  pub fn symlink(&mut self, target: &str, link: &str) -> String {
+    self.record_action(Action::Symlink {
+      target: target.to_string(),
+      link: link.to_string(),
+    })
+  }
+
+  /// Record creating the directory `path`.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to `path`.
+  pub fn mkdir(&mut self, path: &str, mode: Option<u32>) -> String {
+    self.record_action(Action::Mkdir {
+      path: path.to_string(),
+      mode,
+    })
+  }
+
+  /// Record changing the mode of `path`.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to `path`.
+  pub fn chmod(&mut self, path: &str, mode: u32) -> String {
+    self.record_action(Action::Chmod {
+      path: path.to_string(),
+      mode,
+    })
+  }
+
+  /// Record removing `path`; the reverse of the other file actions.
+  ///
+  /// # Returns
+  ///
+  /// An opaque placeholder that resolves to `path`.
+  pub fn remove(&mut self, path: &str) -> String {
+    self.record_action(Action::Remove { path: path.to_string() })
+  }
+
   /// Record a command execution action and return a placeholder for its output.
   ///
   /// The returned placeholder resolves to the command's stdout at execution time.
@@ -10,13 +10,39 @@ use serde_json::Value as JsonValue;
 use tempfile::TempDir;
 use tracing::debug;
 
+use crate::action::log::{ActionLog, ActionPhase, action_log_path};
 use crate::action::{Action, execute_action};
 use crate::bind::BindDef;
+use crate::execute::dag::DagNode;
+use crate::execute::events::EventSender;
 use crate::execute::resolver::BindCtxResolver;
 use crate::execute::types::{ActionResult, BindResult, ExecuteError};
 use crate::placeholder;
 use crate::util::hash::ObjectHash;
 
+/// Where the actions of a bind report their output.
+///
+/// Each action writes its log to the store (see [`crate::action::log`]); with
+/// `events`, its output is also streamed to the execution's event channel.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BindLogs<'a> {
+  pub events: Option<&'a EventSender>,
+}
+
+impl<'a> BindLogs<'a> {
+  pub fn new(events: Option<&'a EventSender>) -> Self {
+    Self { events }
+  }
+
+  /// Open the log of one action of the bind `hash`.
+  fn open(&self, hash: &ObjectHash, phase: ActionPhase, index: usize) -> ActionLog {
+    ActionLog::open(
+      &action_log_path(hash, phase, index),
+      self.events.map(|events| events.for_node(DagNode::Bind(hash.clone()))),
+    )
+  }
+}
+
 /// Apply a single bind.
 ///
 /// This executes all apply_actions in the bind definition and produces the
@@ -27,6 +53,7 @@ use crate::util::hash::ObjectHash;
 /// * `hash` - The bind hash
 /// * `bind_def` - The bind definition
 /// * `resolver` - A resolver that can resolve placeholders (including completed builds/binds)
+/// * `logs` - Where the bind's actions log their output, if anywhere
 ///
 /// # Returns
 ///
@@ -35,6 +62,7 @@ pub async fn apply_bind(
   hash: &ObjectHash,
   bind_def: &BindDef,
   resolver: &BindCtxResolver<'_>,
+  logs: Option<&BindLogs<'_>>,
 ) -> Result<BindResult, ExecuteError> {
   debug!(hash = %hash.0, "applying bind");
 
@@ -46,8 +74,16 @@ pub async fn apply_bind(
   let mut bind_resolver = resolver.with_out_dir(out_dir.to_string_lossy().to_string());
 
   // Execute actions in order
-  let (action_results, outputs) =
-    execute_bind_actions(&bind_def.create_actions, &mut bind_resolver, bind_def, out_dir).await?;
+  let (action_results, outputs) = execute_bind_actions(
+    hash,
+    ActionPhase::Create,
+    logs,
+    &bind_def.create_actions,
+    &mut bind_resolver,
+    bind_def,
+    out_dir,
+  )
+  .await?;
 
   debug!(hash = %hash.0, "bind applied");
 
@@ -71,6 +107,7 @@ pub async fn apply_bind(
 /// * `bind_def` - The bind definition
 /// * `bind_result` - The result from when the bind was applied (provides outputs)
 /// * `resolver` - A resolver for placeholder resolution
+/// * `logs` - Where the bind's actions log their output, if anywhere
 ///
 /// # Returns
 ///
@@ -80,6 +117,7 @@ pub async fn destroy_bind(
   bind_def: &BindDef,
   bind_result: &BindResult,
   resolver: &BindCtxResolver<'_>,
+  logs: Option<&BindLogs<'_>>,
 ) -> Result<(), ExecuteError> {
   let destroy_actions = &bind_def.destroy_actions;
   let _ = bind_result; // TODO: May be used in future for referencing applied outputs
@@ -94,7 +132,7 @@ pub async fn destroy_bind(
   let mut bind_resolver = resolver.with_out_dir(out_dir.to_string_lossy().to_string());
 
   // Execute destroy actions
-  let _ = execute_bind_actions_raw(destroy_actions, &mut bind_resolver, out_dir).await?;
+  let _ = execute_bind_actions_raw(hash, logs, destroy_actions, &mut bind_resolver, out_dir).await?;
 
   debug!(hash = %hash.0, "bind destroyed");
 
@@ -113,6 +151,7 @@ pub async fn destroy_bind(
 /// * `new_bind_def` - The new bind definition (must have update_actions)
 /// * `old_bind_result` - The result from when the bind was originally applied
 /// * `resolver` - A resolver for placeholder resolution
+/// * `logs` - Where the bind's actions log their output, if anywhere
 ///
 /// # Returns
 ///
@@ -123,6 +162,7 @@ pub async fn update_bind(
   new_bind_def: &BindDef,
   old_bind_result: &BindResult,
   resolver: &BindCtxResolver<'_>,
+  logs: Option<&BindLogs<'_>>,
 ) -> Result<BindResult, ExecuteError> {
   let _ = old_bind_result; // TODO: May be used in future for referencing old outputs
   debug!(old_hash = %old_hash.0, new_hash = %new_hash.0, "updating bind");
@@ -138,13 +178,22 @@ pub async fn update_bind(
     .ok_or_else(|| ExecuteError::CmdFailed {
       cmd: "update_bind called without update_actions".to_string(),
       code: None,
+      stderr: String::new(),
     })?;
 
   // Create a child resolver with its own out_dir and action_results
   let mut bind_resolver = resolver.with_out_dir(out_dir.to_string_lossy().to_string());
 
-  let (action_results, outputs) =
-    execute_bind_actions(update_actions, &mut bind_resolver, new_bind_def, out_dir).await?;
+  let (action_results, outputs) = execute_bind_actions(
+    new_hash,
+    ActionPhase::Update,
+    logs,
+    update_actions,
+    &mut bind_resolver,
+    new_bind_def,
+    out_dir,
+  )
+  .await?;
 
   debug!(old_hash = %old_hash.0, new_hash = %new_hash.0, "bind updated");
 
@@ -166,6 +215,7 @@ pub async fn check_bind(
   bind_def: &BindDef,
   bind_result: &BindResult,
   resolver: &BindCtxResolver<'_>,
+  logs: Option<&BindLogs<'_>>,
 ) -> Result<Option<crate::bind::BindCheckResult>, ExecuteError> {
   let _ = bind_result; // TODO: May be used in future for referencing applied outputs
   let Some(ref check_actions) = bind_def.check_actions else {
@@ -184,7 +234,7 @@ pub async fn check_bind(
   let mut check_resolver = resolver.with_out_dir(out_dir.to_string_lossy().to_string());
 
   // Execute check actions (this populates action_results in check_resolver)
-  execute_bind_check_actions(check_actions, &mut check_resolver, out_dir).await?;
+  execute_bind_check_actions(hash, logs, check_actions, &mut check_resolver, out_dir).await?;
 
   // Resolve check outputs using the resolver (now has action results)
   let drifted_str = placeholder::substitute(&check_outputs.drifted, &check_resolver)?;
@@ -201,6 +251,8 @@ pub async fn check_bind(
 }
 
 async fn execute_bind_check_actions(
+  hash: &ObjectHash,
+  logs: Option<&BindLogs<'_>>,
   actions: &[Action],
   resolver: &mut BindCtxResolver<'_>,
   out_dir: &Path,
@@ -210,7 +262,8 @@ async fn execute_bind_check_actions(
   for (idx, action) in actions.iter().enumerate() {
     debug!(action_idx = idx, "executing check action");
 
-    let result = execute_action(action, resolver, out_dir).await?;
+    let log = logs.map(|logs| logs.open(hash, ActionPhase::Check, idx));
+    let result = execute_action(action, resolver, out_dir, None, None, log.as_ref()).await?;
 
     resolver.push_action_result(result.output.clone());
     action_results.push(result);
@@ -221,6 +274,9 @@ async fn execute_bind_check_actions(
 
 /// Execute bind actions and resolve outputs.
 async fn execute_bind_actions(
+  hash: &ObjectHash,
+  phase: ActionPhase,
+  logs: Option<&BindLogs<'_>>,
   actions: &[Action],
   resolver: &mut BindCtxResolver<'_>,
   bind_def: &BindDef,
@@ -231,7 +287,8 @@ async fn execute_bind_actions(
   for (idx, action) in actions.iter().enumerate() {
     debug!(action_idx = idx, "executing bind action");
 
-    let result = execute_action(action, resolver, out_dir).await?;
+    let log = logs.map(|logs| logs.open(hash, phase, idx));
+    let result = execute_action(action, resolver, out_dir, None, None, log.as_ref()).await?;
 
     // Record the result for subsequent actions
     resolver.push_action_result(result.output.clone());
@@ -246,6 +303,8 @@ async fn execute_bind_actions(
 
 /// Execute bind actions without output resolution (used for destroy).
 async fn execute_bind_actions_raw(
+  hash: &ObjectHash,
+  logs: Option<&BindLogs<'_>>,
   actions: &[Action],
   resolver: &mut BindCtxResolver<'_>,
   out_dir: &Path,
@@ -255,7 +314,8 @@ async fn execute_bind_actions_raw(
   for (idx, action) in actions.iter().enumerate() {
     debug!(action_idx = idx, "executing destroy action");
 
-    let result = execute_action(action, resolver, out_dir).await?;
+    let log = logs.map(|logs| logs.open(hash, ActionPhase::Destroy, idx));
+    let result = execute_action(action, resolver, out_dir, None, None, log.as_ref()).await?;
 
     resolver.push_action_result(result.output.clone());
     action_results.push(result);
@@ -296,11 +356,30 @@ mod tests {
   use std::vec;
 
   use super::*;
+  use crate::action::log::list_action_logs;
   use crate::execute::types::BuildResult;
   use crate::manifest::Manifest;
   use crate::util::testutil::{echo_msg, shell_cmd};
   use crate::{action::actions::exec::ExecOpts, util::hash::Hashable};
 
+  /// Run an async test against a temporary store, which receives the action logs.
+  fn with_temp_store<F, Fut, T>(f: F) -> T
+  where
+    F: FnOnce() -> Fut,
+    Fut: std::future::Future<Output = T>,
+  {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let store_path = temp_dir.path().join("store");
+
+    temp_env::with_var("SYSLUA_STORE", Some(store_path.to_str().unwrap()), || {
+      tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap()
+        .block_on(f())
+    })
+  }
+
   /// Create a test resolver with empty collections.
   fn test_resolver() -> (
     HashMap<ObjectHash, BuildResult>,
@@ -321,6 +400,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -336,7 +418,7 @@ mod tests {
     let (builds, binds, manifest) = test_resolver();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     assert_eq!(result.action_results.len(), 1);
     assert_eq!(result.action_results[0].output, "applied");
@@ -358,6 +440,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -368,7 +453,7 @@ mod tests {
     let (builds, binds, manifest) = test_resolver();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     assert_eq!(result.outputs["link"], JsonValue::String("/path/to/link".to_string()));
   }
@@ -389,6 +474,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -399,7 +487,7 @@ mod tests {
     let (builds, binds, manifest) = test_resolver();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     // The output should be a temp directory path (a non-empty string)
     match &result.outputs["dir"] {
@@ -429,6 +517,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -450,7 +541,7 @@ mod tests {
     let manifest = Manifest::default();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     assert_eq!(result.action_results[0].output, "/store/obj/myapp/bin");
   }
@@ -472,6 +563,9 @@ mod tests {
         args: Some(apply_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![Action::Exec(ExecOpts {
@@ -479,6 +573,9 @@ mod tests {
         args: Some(destroy_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       check_actions: None,
       check_outputs: None,
@@ -488,14 +585,48 @@ mod tests {
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
     // First apply
-    let bind_result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let bind_result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     // Then destroy
-    let destroy_result = destroy_bind(&hash, &bind_def, &bind_result, &resolver).await;
+    let destroy_result = destroy_bind(&hash, &bind_def, &bind_result, &resolver, None).await;
 
     assert!(destroy_result.is_ok());
   }
 
+  #[test]
+  fn bind_actions_are_logged_per_phase() {
+    with_temp_store(|| async {
+      let (apply_cmd, apply_args) = echo_msg("applied");
+      let (destroy_cmd, destroy_args) = echo_msg("destroyed");
+      let bind_def = BindDef {
+        id: None,
+        inputs: None,
+        outputs: None,
+        create_actions: vec![Action::Exec(ExecOpts::new(apply_cmd).with_args(apply_args))],
+        update_actions: None,
+        destroy_actions: vec![Action::Exec(ExecOpts::new(destroy_cmd).with_args(destroy_args))],
+        check_actions: None,
+        check_outputs: None,
+      };
+      let hash = bind_def.compute_hash().unwrap();
+      let (builds, binds, manifest) = test_resolver();
+      let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
+
+      let bind_result = apply_bind(&hash, &bind_def, &resolver, Some(&BindLogs::default()))
+        .await
+        .unwrap();
+      destroy_bind(&hash, &bind_def, &bind_result, &resolver, Some(&BindLogs::default()))
+        .await
+        .unwrap();
+
+      let logs = list_action_logs(&hash).unwrap();
+      let phases: Vec<_> = logs.iter().map(|log| (log.phase, log.index)).collect();
+      assert_eq!(phases, vec![(ActionPhase::Create, 0), (ActionPhase::Destroy, 0)]);
+      let destroy_log = std::fs::read_to_string(&logs[1].path).unwrap();
+      assert!(destroy_log.contains("stdout: destroyed"), "{}", destroy_log);
+    })
+  }
+
   #[tokio::test]
   async fn destroy_bind_without_actions() {
     let bind_def = make_simple_bind();
@@ -509,7 +640,7 @@ mod tests {
     };
 
     // Destroy should succeed even with no destroy_actions
-    let result = destroy_bind(&hash, &bind_def, &bind_result, &resolver).await;
+    let result = destroy_bind(&hash, &bind_def, &bind_result, &resolver, None).await;
     assert!(result.is_ok());
   }
 
@@ -525,6 +656,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -535,7 +669,7 @@ mod tests {
     let (builds, binds, manifest) = test_resolver();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await;
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await;
 
     assert!(matches!(result, Err(ExecuteError::CmdFailed { .. })));
   }
@@ -559,18 +693,27 @@ mod tests {
           args: Some(args1),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
         Action::Exec(ExecOpts {
           bin: cmd2.to_string(),
           args: Some(args2),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
         Action::Exec(ExecOpts {
           bin: cmd3.to_string(),
           args: Some(args3),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
       ],
       update_actions: None,
@@ -582,7 +725,7 @@ mod tests {
     let (builds, binds, manifest) = test_resolver();
     let resolver = BindCtxResolver::new(&builds, &binds, &manifest, "/tmp".to_string());
 
-    let result = apply_bind(&hash, &bind_def, &resolver).await.unwrap();
+    let result = apply_bind(&hash, &bind_def, &resolver, None).await.unwrap();
 
     assert_eq!(result.action_results.len(), 3);
     assert_eq!(result.action_results[0].output, "step1");
@@ -608,12 +751,18 @@ mod tests {
         args: Some(create_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: Some(vec![Action::Exec(ExecOpts {
         bin: update_cmd.to_string(),
         args: Some(update_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]),
       destroy_actions: vec![],
       check_actions: None,
@@ -632,7 +781,7 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver)
+    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver, None)
       .await
       .unwrap();
 
@@ -659,12 +808,18 @@ mod tests {
         args: Some(create_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: Some(vec![Action::Exec(ExecOpts {
         bin: update_cmd.to_string(),
         args: Some(update_args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]),
       destroy_actions: vec![],
       check_actions: None,
@@ -682,7 +837,7 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver)
+    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver, None)
       .await
       .unwrap();
 
@@ -702,6 +857,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None, // No update actions!
       destroy_actions: vec![],
@@ -718,7 +876,7 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver).await;
+    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver, None).await;
 
     assert!(matches!(result, Err(ExecuteError::CmdFailed { .. })));
   }
@@ -741,6 +899,9 @@ mod tests {
         args: Some(args1.clone()),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: Some(vec![
         Action::Exec(ExecOpts {
@@ -748,18 +909,27 @@ mod tests {
           args: Some(args1),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
         Action::Exec(ExecOpts {
           bin: cmd2.to_string(),
           args: Some(args2),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
         Action::Exec(ExecOpts {
           bin: cmd3.to_string(),
           args: Some(args3),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
       ]),
       destroy_actions: vec![],
@@ -778,7 +948,7 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver)
+    let result = update_bind(&old_hash, &new_hash, &bind_def, &old_bind_result, &resolver, None)
       .await
       .unwrap();
 
@@ -800,7 +970,9 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = check_bind(&hash, &bind_def, &bind_result, &resolver).await.unwrap();
+    let result = check_bind(&hash, &bind_def, &bind_result, &resolver, None)
+      .await
+      .unwrap();
 
     assert!(result.is_none());
   }
@@ -823,6 +995,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]),
       check_outputs: Some(BindCheckOutputs {
         drifted: "$${{action:0}}".to_string(),
@@ -837,7 +1012,9 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = check_bind(&hash, &bind_def, &bind_result, &resolver).await.unwrap();
+    let result = check_bind(&hash, &bind_def, &bind_result, &resolver, None)
+      .await
+      .unwrap();
 
     assert!(result.is_some());
     let check_result = result.unwrap();
@@ -863,6 +1040,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]),
       check_outputs: Some(BindCheckOutputs {
         drifted: "$${{action:0}}".to_string(),
@@ -877,7 +1057,9 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = check_bind(&hash, &bind_def, &bind_result, &resolver).await.unwrap();
+    let result = check_bind(&hash, &bind_def, &bind_result, &resolver, None)
+      .await
+      .unwrap();
 
     assert!(result.is_some());
     let check_result = result.unwrap();
@@ -905,12 +1087,18 @@ mod tests {
           args: Some(args1),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
         Action::Exec(ExecOpts {
           bin: cmd2.to_string(),
           args: Some(args2),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         }),
       ]),
       check_outputs: Some(BindCheckOutputs {
@@ -926,7 +1114,9 @@ mod tests {
       action_results: vec![],
     };
 
-    let result = check_bind(&hash, &bind_def, &bind_result, &resolver).await.unwrap();
+    let result = check_bind(&hash, &bind_def, &bind_result, &resolver, None)
+      .await
+      .unwrap();
 
     assert!(result.is_some());
     let check_result = result.unwrap();
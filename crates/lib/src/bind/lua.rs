@@ -1,7 +1,7 @@
 //! Lua bindings for `sys.bind{}`.
 //!
 //! This module provides:
-//! - `BindCtx` as LuaUserData with methods like `exec`
+//! - `BindCtx` as LuaUserData with methods like `exec` and `write_file`
 //! - `register_sys_bind()` to register the `sys.bind` function
 
 use std::cell::RefCell;
@@ -12,9 +12,11 @@ use mlua::prelude::*;
 
 use crate::action::BIND_CTX_METHODS_REGISTRY_KEY;
 use crate::action::actions::exec::parse_exec_opts;
+use crate::action::actions::file::add_file_methods;
 use crate::bind::{BindInputsDef, BindRef, BindSpec};
 use crate::build::BUILD_REF_TYPE;
 use crate::build::lua::build_hash_to_lua;
+use crate::lua::sources;
 use crate::manifest::Manifest;
 use crate::util::hash::ObjectHash;
 
@@ -34,6 +36,8 @@ impl LuaUserData for BindCtx {
       Ok(this.exec(cmd_opts))
     });
 
+    add_file_methods(methods);
+
     // Fallback for custom registered methods (bind-specific registry)
     methods.add_meta_method(mlua::MetaMethod::Index, |lua, _this, key: String| {
       let registry: LuaTable = lua.named_registry_value(BIND_CTX_METHODS_REGISTRY_KEY)?;
@@ -252,6 +256,7 @@ pub fn register_sys_bind(lua: &Lua, sys_table: &LuaTable, manifest: Rc<RefCell<M
 
       manifest.bindings.insert(bind_ref.hash.clone(), bind_def.clone());
     }
+    sources::record_bind(lua, &bind_ref.hash);
 
     lua.pack(bind_ref)
   })?;
@@ -1209,5 +1214,54 @@ mod tests {
 
       Ok(())
     }
+
+    #[test]
+    fn file_actions_are_recorded() -> LuaResult<()> {
+      let (lua, manifest) = create_test_lua_with_manifest()?;
+
+      lua
+        .load(
+          r#"
+                sys.bind({
+                    id = "file-actions",
+                    create = function(inputs, ctx)
+                        ctx:write_file("/etc/app.conf", "key = 1\n", { mode = "600" })
+                        ctx:symlink(ctx.out, "/etc/app.d")
+                        return { path = "/etc/app.conf" }
+                    end,
+                    destroy = function(outputs, ctx)
+                        ctx:remove(outputs.path)
+                    end,
+                })
+            "#,
+        )
+        .exec()?;
+
+      let manifest = manifest.borrow();
+      let (_, bind_def) = manifest.bindings.iter().next().unwrap();
+
+      assert_eq!(
+        bind_def.create_actions,
+        vec![
+          Action::WriteFile {
+            path: "/etc/app.conf".to_string(),
+            content: "key = 1\n".to_string(),
+            mode: Some(0o600),
+          },
+          Action::Symlink {
+            target: "$${{out}}".to_string(),
+            link: "/etc/app.d".to_string(),
+          },
+        ]
+      );
+      assert_eq!(
+        bind_def.destroy_actions,
+        vec![Action::Remove {
+          path: "/etc/app.conf".to_string(),
+        }]
+      );
+
+      Ok(())
+    }
   }
 }
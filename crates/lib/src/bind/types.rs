@@ -458,7 +458,8 @@ impl BindDef {
 
 /// Context for bind `create`, `update`, and `destroy` functions.
 ///
-/// Provides `exec` and `out` for recording bind actions.
+/// Provides `exec`, the file actions (`write_file`, `copy`, ...), and `out`
+/// for recording bind actions.
 /// Note: `fetch_url` is intentionally not available in binds - binds should
 /// only modify system state using build outputs, not download new content.
 #[derive(Default)]
@@ -491,6 +492,12 @@ impl BindCtx {
   }
 }
 
+impl AsMut<ActionCtx> for BindCtx {
+  fn as_mut(&mut self) -> &mut ActionCtx {
+    &mut self.0
+  }
+}
+
 /// Marker type name for BindRef metatables in Lua.
 ///
 /// This constant is used to identify Lua userdata that represents a reference
@@ -562,6 +569,9 @@ mod tests {
           args: None,
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         update_actions: None,
         destroy_actions: vec![],
@@ -597,6 +607,9 @@ mod tests {
         args: None,
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       }));
 
       assert_ne!(def1.compute_hash().unwrap(), def2.compute_hash().unwrap());
@@ -614,6 +627,9 @@ mod tests {
         args: None,
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })];
 
       assert_ne!(def1.compute_hash().unwrap(), def2.compute_hash().unwrap());
@@ -631,12 +647,18 @@ mod tests {
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             bin: "step2".to_string(),
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         update_actions: None,
@@ -655,12 +677,18 @@ mod tests {
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             bin: "step1".to_string(),
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         update_actions: None,
@@ -689,24 +717,36 @@ mod tests {
           args: None,
           env: Some(env),
           cwd: Some("/home".to_string()),
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         update_actions: Some(vec![Action::Exec(ExecOpts {
           bin: "echo updated".to_string(),
           args: None,
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })]),
         destroy_actions: vec![Action::Exec(ExecOpts {
           bin: "rm /dest".to_string(),
           args: None,
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         check_actions: Some(vec![Action::Exec(ExecOpts {
           bin: "test".to_string(),
           args: Some(vec!["-L".to_string(), "/dest".to_string()]),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })]),
         check_outputs: Some(BindCheckOutputs {
           drifted: "$${{action:0}}".to_string(),
@@ -730,6 +770,9 @@ mod tests {
         args: Some(vec!["-f".to_string(), "/some/path".to_string()]),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]);
       def2.check_outputs = Some(BindCheckOutputs {
         drifted: "$${{action:0}}".to_string(),
@@ -3,23 +3,29 @@
 //! This module handles executing all actions for a single build and
 //! producing the final BuildResult.
 
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 use tokio::fs;
 use tracing::{debug, warn};
 
-use crate::build::BuildDef;
-use crate::build::store::build_dir_path;
+use crate::build::store::{build_dir_path, local_build_dir_path};
+use crate::build::{BuildDef, BuildSandbox};
+use crate::cache::{TrustedKeys, substitute};
 use crate::manifest::Manifest;
 use crate::placeholder;
+use crate::platform::paths::trusted_keys_path;
+use crate::platform::sandbox::{DEFAULT_SANDBOX_PATHS, Sandbox};
 
+use crate::action::actions::file::FileScope;
 use crate::action::execute_action;
+use crate::action::log::{ActionLog, ActionPhase, action_log_path};
+use crate::execute::dag::{DagNode, extract_build_dependencies};
 use crate::execute::resolver::BuildCtxResolver;
 use crate::execute::types::{ActionResult, BindResult, BuildResult, ExecuteConfig, ExecuteError};
-use crate::util::hash::{ObjectHash, hash_directory};
+use crate::util::hash::{ContentHash, DirHashError, ObjectHash, hash_directory};
 
 /// Marker file name indicating a build completed successfully.
 pub const BUILD_COMPLETE_MARKER: &str = ".syslua-complete";
@@ -27,7 +33,8 @@ pub const BUILD_COMPLETE_MARKER: &str = ".syslua-complete";
 /// Files/directories excluded when hashing build outputs.
 /// - BUILD_COMPLETE_MARKER: The marker itself (written after hash)
 /// - "tmp": Build temp directory (may have leftovers)
-const BUILD_HASH_EXCLUSIONS: &[&str] = &[".syslua-complete", "tmp"];
+/// - SIGNATURES_FILE: Signatures over the hash itself
+pub(crate) const BUILD_HASH_EXCLUSIONS: &[&str] = &[".syslua-complete", "tmp", ".syslua-signatures"];
 
 /// Marker file content structure.
 #[derive(Debug, Serialize, Deserialize)]
@@ -41,11 +48,16 @@ pub struct BuildMarker {
   pub output_hash: Option<String>,
 }
 
+/// Hash the outputs of a build the same way its marker's `output_hash` was computed.
+pub fn compute_build_hash(store_path: &Path) -> Result<ContentHash, DirHashError> {
+  hash_directory(store_path, BUILD_HASH_EXCLUSIONS)
+}
+
 /// Write the build completion marker with output hash.
 /// Called after build succeeds, before returning BuildResult.
 async fn write_build_complete_marker(store_path: &Path) -> Result<(), ExecuteError> {
   // Compute hash of build outputs (excluding marker and tmp)
-  let output_hash = hash_directory(store_path, BUILD_HASH_EXCLUSIONS)?;
+  let output_hash = compute_build_hash(store_path)?;
 
   let marker = BuildMarker {
     version: 1,
@@ -92,7 +104,7 @@ fn verify_build_hash(store_path: &Path, marker: &BuildMarker) -> bool {
     return true;
   };
 
-  match hash_directory(store_path, BUILD_HASH_EXCLUSIONS) {
+  match compute_build_hash(store_path) {
     Ok(current_hash) => {
       if current_hash.0 == *stored_hash {
         true
@@ -117,6 +129,87 @@ fn verify_build_hash(store_path: &Path, marker: &BuildMarker) -> bool {
   }
 }
 
+/// The sandbox a build's `exec` actions run in, or `None` if it is disabled.
+///
+/// The build can write to its own output directory and read the store paths
+/// of every build it depends on, directly or through other builds, along with
+/// its host path allowlist.
+fn build_sandbox(
+  build_def: &BuildDef,
+  store_path: &Path,
+  completed_builds: &HashMap<ObjectHash, BuildResult>,
+  manifest: &Manifest,
+) -> Option<Sandbox> {
+  let host_paths: Vec<&str> = match &build_def.sandbox {
+    Some(BuildSandbox::Disabled) => return None,
+    Some(BuildSandbox::Paths(paths)) => paths.iter().map(String::as_str).collect(),
+    None => DEFAULT_SANDBOX_PATHS.to_vec(),
+  };
+  let sandbox = host_paths
+    .into_iter()
+    .fold(Sandbox::new(), |sandbox, path| sandbox.with_read_only(path))
+    .with_writable(store_path);
+
+  Some(
+    dependency_paths(build_def, completed_builds, manifest)
+      .into_iter()
+      .fold(sandbox, Sandbox::with_read_only),
+  )
+}
+
+/// Where the build's file actions may write and read: its output, the
+/// outputs of its dependencies and the host paths it declares. Unlike the
+/// sandbox, this applies whether or not the sandbox is enabled.
+fn build_file_scope(
+  build_def: &BuildDef,
+  store_path: &Path,
+  completed_builds: &HashMap<ObjectHash, BuildResult>,
+  manifest: &Manifest,
+) -> FileScope {
+  let declared: &[String] = match &build_def.sandbox {
+    Some(BuildSandbox::Paths(paths)) => paths,
+    _ => &[],
+  };
+  dependency_paths(build_def, completed_builds, manifest)
+    .into_iter()
+    .chain(declared.iter().map(PathBuf::from))
+    .fold(FileScope::new(store_path), FileScope::with_readable)
+}
+
+/// Store paths of all dependencies of a build, direct and transitive.
+fn dependency_paths(
+  build_def: &BuildDef,
+  completed_builds: &HashMap<ObjectHash, BuildResult>,
+  manifest: &Manifest,
+) -> Vec<PathBuf> {
+  let mut paths = Vec::new();
+  let mut pending: Vec<ObjectHash> = direct_dependencies(build_def);
+  let mut seen = HashSet::new();
+  while let Some(dep) = pending.pop() {
+    if !seen.insert(dep.clone()) {
+      continue;
+    }
+    paths.push(
+      completed_builds
+        .get(&dep)
+        .map(|result| result.store_path.clone())
+        .unwrap_or_else(|| build_dir_path(&dep)),
+    );
+    if let Some(dep_def) = manifest.builds.get(&dep) {
+      pending.extend(direct_dependencies(dep_def));
+    }
+  }
+  paths
+}
+
+fn direct_dependencies(build_def: &BuildDef) -> Vec<ObjectHash> {
+  build_def
+    .inputs
+    .as_ref()
+    .and_then(|inputs| extract_build_dependencies(inputs).ok())
+    .unwrap_or_default()
+}
+
 /// Realize a single build.
 ///
 /// This executes all actions in the build definition and produces the
@@ -139,15 +232,64 @@ pub async fn realize_build(
   completed_builds: &HashMap<ObjectHash, BuildResult>,
   manifest: &Manifest,
   config: &ExecuteConfig,
+) -> Result<BuildResult, ExecuteError> {
+  realize_build_inner(hash, build_def, completed_builds, manifest, config, false).await
+}
+
+/// Realize a single build, ignoring any cached output in the store.
+///
+/// Like [`realize_build()`], but an existing output directory is removed and
+/// the build's actions are always executed, even when its
+/// [`BUILD_COMPLETE_MARKER`] is valid. Outputs cached in a parent store are
+/// not reused either; the build is redone in the primary store.
+///
+/// # Arguments
+///
+/// * `hash` - The build hash
+/// * `build_def` - The build definition
+/// * `completed_builds` - Results of already-completed builds (for dependency resolution)
+/// * `manifest` - The full manifest (for looking up definitions)
+/// * `config` - Execution configuration
+///
+/// # Returns
+///
+/// The result of realizing the build.
+pub async fn rebuild_build(
+  hash: &ObjectHash,
+  build_def: &BuildDef,
+  completed_builds: &HashMap<ObjectHash, BuildResult>,
+  manifest: &Manifest,
+  config: &ExecuteConfig,
+) -> Result<BuildResult, ExecuteError> {
+  realize_build_inner(hash, build_def, completed_builds, manifest, config, true).await
+}
+
+async fn realize_build_inner(
+  hash: &ObjectHash,
+  build_def: &BuildDef,
+  completed_builds: &HashMap<ObjectHash, BuildResult>,
+  manifest: &Manifest,
+  config: &ExecuteConfig,
+  rebuild: bool,
 ) -> Result<BuildResult, ExecuteError> {
   debug!(
     id = ?build_def.id,
     hash = %hash.0,
+    rebuild,
     "realizing build"
   );
 
   // Compute the store path for this build
-  let store_path = build_dir_path(hash);
+  let store_path = if rebuild {
+    local_build_dir_path(hash)
+  } else {
+    build_dir_path(hash)
+  };
+
+  if rebuild && fs::symlink_metadata(&store_path).await.is_ok() {
+    debug!(path = ?store_path, "removing existing build for rebuild");
+    remove_build_dir(&store_path).await?;
+  }
 
   // Check if already built (cache hit)
   if store_path.exists() {
@@ -179,19 +321,45 @@ pub async fn realize_build(
     }
   }
 
+  // Fetch from a binary cache instead of building, if one has it
+  if !rebuild && try_substitute(hash, &store_path, config).await {
+    let outputs = resolve_outputs(build_def, &store_path, &[], completed_builds, manifest, config)?;
+    return Ok(BuildResult {
+      store_path,
+      outputs,
+      action_results: vec![],
+    });
+  }
+
   // Create the output directory
   fs::create_dir_all(&store_path).await?;
 
   // Create resolver for this build
   let mut resolver = BuildCtxResolver::new(completed_builds, manifest, store_path.to_string_lossy().to_string());
 
+  let sandbox = build_sandbox(build_def, &store_path, completed_builds, manifest);
+  let scope = build_file_scope(build_def, &store_path, completed_builds, manifest);
+
   // Execute actions in order
   let mut action_results = Vec::new();
 
   for (idx, action) in build_def.create_actions.iter().enumerate() {
     debug!(action_idx = idx, "executing action");
 
-    let result = execute_action(action, &resolver, &store_path).await?;
+    let events = config
+      .events
+      .as_ref()
+      .map(|events| events.for_node(DagNode::Build(hash.clone())));
+    let log = ActionLog::open(&action_log_path(hash, ActionPhase::Create, idx), events);
+    let result = execute_action(
+      action,
+      &resolver,
+      &store_path,
+      sandbox.as_ref(),
+      Some(&scope),
+      Some(&log),
+    )
+    .await?;
 
     // Record the result for subsequent actions
     resolver.push_action_result(result.output.clone());
@@ -297,6 +465,23 @@ pub async fn realize_build_with_resolver(
     }
   }
 
+  if try_substitute(hash, &store_path, config).await {
+    let outputs = resolve_outputs_with_resolver(
+      build_def,
+      &store_path,
+      &[],
+      completed_builds,
+      completed_binds,
+      manifest,
+      config,
+    )?;
+    return Ok(BuildResult {
+      store_path,
+      outputs,
+      action_results: vec![],
+    });
+  }
+
   // Create the output directory
   fs::create_dir_all(&store_path).await?;
 
@@ -304,13 +489,29 @@ pub async fn realize_build_with_resolver(
   let mut resolver = BuildCtxResolver::new(completed_builds, manifest, store_path.to_string_lossy().to_string());
   let _ = completed_binds; // Unused - builds cannot reference binds
 
+  let sandbox = build_sandbox(build_def, &store_path, completed_builds, manifest);
+  let scope = build_file_scope(build_def, &store_path, completed_builds, manifest);
+
   // Execute actions in order
   let mut action_results = Vec::new();
 
   for (idx, action) in build_def.create_actions.iter().enumerate() {
     debug!(action_idx = idx, "executing action");
 
-    let result = execute_action(action, &resolver, &store_path).await?;
+    let events = config
+      .events
+      .as_ref()
+      .map(|events| events.for_node(DagNode::Build(hash.clone())));
+    let log = ActionLog::open(&action_log_path(hash, ActionPhase::Create, idx), events);
+    let result = execute_action(
+      action,
+      &resolver,
+      &store_path,
+      sandbox.as_ref(),
+      Some(&scope),
+      Some(&log),
+    )
+    .await?;
 
     // Record the result for subsequent actions
     resolver.push_action_result(result.output.clone());
@@ -344,6 +545,33 @@ pub async fn realize_build_with_resolver(
   })
 }
 
+/// Fetch a build from the configured substituters, trusting the keys in the
+/// trusted-keys file.
+async fn try_substitute(hash: &ObjectHash, store_path: &Path, config: &ExecuteConfig) -> bool {
+  if config.substituters.is_empty() {
+    return false;
+  }
+  let trusted = match TrustedKeys::load(&trusted_keys_path()) {
+    Ok(trusted) => trusted,
+    Err(e) => {
+      warn!(error = %e, "failed to load trusted keys, not substituting");
+      return false;
+    }
+  };
+  substitute(hash, store_path, &config.substituters, &trusted).await
+}
+
+/// Remove a build directory, or the link to it if it points into a parent store.
+async fn remove_build_dir(store_path: &Path) -> Result<(), ExecuteError> {
+  let meta = fs::symlink_metadata(store_path).await?;
+  if meta.is_symlink() {
+    fs::remove_file(store_path).await?;
+  } else {
+    fs::remove_dir_all(store_path).await?;
+  }
+  Ok(())
+}
+
 /// Resolve the outputs from a build definition.
 ///
 /// This substitutes placeholders in string output values with actual paths.
@@ -439,6 +667,7 @@ fn resolve_outputs_with_resolver(
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::build::BuildInputs;
   use crate::util::testutil::{echo_msg, shell_cmd};
   use crate::{
     action::{Action, actions::exec::ExecOpts},
@@ -456,13 +685,22 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       outputs: None,
+      sandbox: None,
     }
   }
 
   fn test_config() -> ExecuteConfig {
-    ExecuteConfig { parallelism: 1 }
+    ExecuteConfig {
+      parallelism: 1,
+      substituters: vec![],
+      keep_going: false,
+      events: None,
+    }
   }
 
   /// Helper to set up a temp store and run a test.
@@ -530,6 +768,9 @@ mod tests {
           args: Some(args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: Some(
           [
@@ -539,6 +780,7 @@ mod tests {
           .into_iter()
           .collect(),
         ),
+        sandbox: None,
       };
       let hash = build_def.compute_hash().unwrap();
 
@@ -576,12 +818,18 @@ mod tests {
             args: Some(args1),
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             bin: cmd2.to_string(),
             args: Some(args2),
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             // Reference previous action output
@@ -589,6 +837,9 @@ mod tests {
             args: Some(args3),
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         outputs: Some(
@@ -596,6 +847,7 @@ mod tests {
             .into_iter()
             .collect(),
         ),
+        sandbox: None,
       };
       let hash = build_def.compute_hash().unwrap();
 
@@ -631,8 +883,12 @@ mod tests {
           args: Some(args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: None,
+        sandbox: None,
       };
       let hash = build_def.compute_hash().unwrap();
 
@@ -864,4 +1120,236 @@ mod tests {
       assert!(is_build_complete(&result2.store_path));
     });
   }
+
+  #[test]
+  fn rebuild_ignores_valid_cache() {
+    with_temp_store(|| async {
+      let build_def = make_simple_build();
+      let hash = build_def.compute_hash().unwrap();
+      let manifest = Manifest {
+        builds: [(hash.clone(), build_def.clone())].into_iter().collect(),
+        bindings: Default::default(),
+      };
+      let config = test_config();
+
+      let first = realize_build(&hash, &build_def, &HashMap::new(), &manifest, &config)
+        .await
+        .unwrap();
+      std::fs::write(first.store_path.join("stale.txt"), "old").unwrap();
+      write_build_complete_marker(&first.store_path).await.unwrap();
+
+      // A plain realize is a cache hit and runs no actions
+      let cached = realize_build(&hash, &build_def, &HashMap::new(), &manifest, &config)
+        .await
+        .unwrap();
+      assert!(cached.action_results.is_empty());
+
+      let rebuilt = rebuild_build(&hash, &build_def, &HashMap::new(), &manifest, &config)
+        .await
+        .unwrap();
+      assert_eq!(rebuilt.action_results.len(), 1);
+      assert!(!rebuilt.store_path.join("stale.txt").exists());
+      assert!(is_build_complete(&rebuilt.store_path));
+    });
+  }
+
+  /// A build running `script` through the shell in the given sandbox.
+  fn script_build(script: &str, inputs: Option<BuildInputs>, sandbox: Option<BuildSandbox>) -> BuildDef {
+    let (cmd, args) = shell_cmd(script);
+    BuildDef {
+      id: None,
+      inputs,
+      create_actions: vec![Action::Exec(ExecOpts {
+        bin: cmd.to_string(),
+        args: Some(args),
+        env: None,
+        cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
+      })],
+      outputs: None,
+      sandbox,
+    }
+  }
+
+  #[test]
+  #[cfg(target_os = "linux")]
+  fn sandboxed_build_sees_dependencies_but_not_host() {
+    if !crate::platform::sandbox::sandboxes_work() {
+      return;
+    }
+    with_temp_store(|| async {
+      let host = TempDir::new().unwrap();
+      let secret = host.path().join("secret");
+      std::fs::write(&secret, "host data").unwrap();
+
+      let dep = script_build("echo dependency > $out/lib", None, None);
+      let dep_hash = dep.compute_hash().unwrap();
+      let script = format!(
+        "/bin/cat $${{{{build:{}:out}}}}/lib && ! /bin/cat {}",
+        dep_hash.0,
+        secret.display()
+      );
+      let build = script_build(&script, Some(BuildInputs::Build(dep_hash.clone())), None);
+      let hash = build.compute_hash().unwrap();
+
+      let manifest = Manifest {
+        builds: [(dep_hash.clone(), dep.clone()), (hash.clone(), build.clone())]
+          .into_iter()
+          .collect(),
+        bindings: Default::default(),
+      };
+      let config = test_config();
+
+      let dep_result = realize_build(&dep_hash, &dep, &HashMap::new(), &manifest, &config)
+        .await
+        .unwrap();
+      let completed = HashMap::from([(dep_hash, dep_result)]);
+      let result = realize_build(&hash, &build, &completed, &manifest, &config)
+        .await
+        .unwrap();
+
+      assert_eq!(result.action_results[0].output, "dependency");
+    });
+  }
+
+  #[test]
+  #[cfg(target_os = "linux")]
+  fn sandbox_can_be_disabled() {
+    if !crate::platform::sandbox::sandboxes_work() {
+      return;
+    }
+    with_temp_store(|| async {
+      let host = TempDir::new().unwrap();
+      let secret = host.path().join("secret");
+      std::fs::write(&secret, "host data").unwrap();
+      let script = format!("/bin/cat {}", secret.display());
+      let config = test_config();
+
+      for (sandbox, readable) in [(None, false), (Some(BuildSandbox::Disabled), true)] {
+        let build = script_build(&script, None, sandbox);
+        let hash = build.compute_hash().unwrap();
+        let manifest = Manifest {
+          builds: [(hash.clone(), build.clone())].into_iter().collect(),
+          bindings: Default::default(),
+        };
+
+        let result = realize_build(&hash, &build, &HashMap::new(), &manifest, &config).await;
+        assert_eq!(result.is_ok(), readable, "sandbox {:?}: {:?}", build.sandbox, result);
+      }
+    });
+  }
+
+  fn file_build(actions: Vec<Action>, sandbox: Option<BuildSandbox>) -> BuildDef {
+    BuildDef {
+      id: None,
+      inputs: None,
+      create_actions: actions,
+      outputs: None,
+      sandbox,
+    }
+  }
+
+  async fn realize_alone(build: &BuildDef) -> Result<BuildResult, ExecuteError> {
+    let hash = build.compute_hash().unwrap();
+    let manifest = Manifest {
+      builds: [(hash.clone(), build.clone())].into_iter().collect(),
+      bindings: Default::default(),
+    };
+    realize_build(&hash, build, &HashMap::new(), &manifest, &test_config()).await
+  }
+
+  #[test]
+  fn file_actions_cannot_write_outside_the_build() {
+    with_temp_store(|| async {
+      let host = TempDir::new().unwrap();
+      let target = host.path().join("escaped");
+
+      for path in [target.display().to_string(), "../escaped".to_string()] {
+        let build = file_build(
+          vec![Action::WriteFile {
+            path: path.clone(),
+            content: "x".to_string(),
+            mode: None,
+          }],
+          Some(BuildSandbox::Disabled),
+        );
+
+        let result = realize_alone(&build).await;
+
+        assert!(
+          matches!(result, Err(ExecuteError::FileFailed { .. })),
+          "{}: {:?}",
+          path,
+          result
+        );
+      }
+      assert!(!target.exists());
+
+      std::fs::write(&target, "keep").unwrap();
+      let remove = file_build(
+        vec![Action::Remove {
+          path: target.display().to_string(),
+        }],
+        None,
+      );
+      assert!(realize_alone(&remove).await.is_err());
+      assert!(target.exists());
+    });
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn file_actions_do_not_write_through_symlinks_leaving_the_build() {
+    with_temp_store(|| async {
+      let host = TempDir::new().unwrap();
+      let build = file_build(
+        vec![
+          Action::Symlink {
+            target: host.path().display().to_string(),
+            link: "host".to_string(),
+          },
+          Action::WriteFile {
+            path: "host/escaped".to_string(),
+            content: "x".to_string(),
+            mode: None,
+          },
+        ],
+        None,
+      );
+
+      let result = realize_alone(&build).await;
+
+      assert!(matches!(result, Err(ExecuteError::FileFailed { .. })), "{:?}", result);
+      assert!(!host.path().join("escaped").exists());
+    });
+  }
+
+  #[test]
+  fn file_actions_read_only_declared_host_paths() {
+    with_temp_store(|| async {
+      let host = TempDir::new().unwrap();
+      let secret = host.path().join("secret");
+      std::fs::write(&secret, "host data").unwrap();
+      let copy = Action::Copy {
+        src: secret.display().to_string(),
+        dest: "secret".to_string(),
+      };
+
+      let undeclared = file_build(vec![copy.clone()], Some(BuildSandbox::Disabled));
+      let result = realize_alone(&undeclared).await;
+      assert!(matches!(result, Err(ExecuteError::FileFailed { .. })), "{:?}", result);
+
+      let declared = file_build(
+        vec![copy],
+        Some(BuildSandbox::Paths(vec![host.path().display().to_string()])),
+      );
+      let result = realize_alone(&declared).await.unwrap();
+      assert_eq!(
+        std::fs::read_to_string(result.store_path.join("secret")).unwrap(),
+        "host data"
+      );
+    });
+  }
 }
@@ -1,7 +1,7 @@
 //! Lua bindings for `sys.build{}`.
 //!
 //! This module provides:
-//! - `BuildCtx` as LuaUserData with methods like `fetch_url` and `exec`
+//! - `BuildCtx` as LuaUserData with methods like `fetch_url`, `unpack` and `exec`
 //! - `register_sys_build()` to register the `sys.build` function
 //! - Helper functions for converting between Lua values and Rust types
 
@@ -13,6 +13,11 @@ use mlua::prelude::*;
 
 use crate::action::BUILD_CTX_METHODS_REGISTRY_KEY;
 use crate::action::actions::exec::parse_exec_opts;
+use crate::action::actions::fetch_git::parse_fetch_git_args;
+use crate::action::actions::fetch_url::parse_fetch_url_opts;
+use crate::action::actions::file::add_file_methods;
+use crate::action::actions::unpack::parse_unpack_args;
+use crate::lua::sources;
 use crate::manifest::Manifest;
 use crate::outputs::lua::parse_outputs;
 use crate::{bind::BIND_REF_TYPE, util::hash::ObjectHash};
@@ -26,10 +31,26 @@ impl LuaUserData for BuildCtx {
   }
 
   fn add_methods<M: LuaUserDataMethods<Self>>(methods: &mut M) {
-    methods.add_method_mut("fetch_url", |_, this, (url, sha256): (String, String)| {
-      Ok(this.fetch_url(&url, &sha256))
+    methods.add_method_mut("fetch_url", |_, this, (opts, hash): (LuaValue, Option<String>)| {
+      let fetch_opts = parse_fetch_url_opts(opts, hash)?;
+      Ok(this.fetch_url(fetch_opts))
     });
 
+    methods.add_method_mut(
+      "fetch_git",
+      |_, this, (url, rev, sha256): (LuaValue, Option<String>, Option<String>)| {
+        let (url, rev, sha256) = parse_fetch_git_args(url, rev, sha256)?;
+        Ok(this.fetch_git(&url, &rev, &sha256))
+      },
+    );
+
+    methods.add_method_mut("unpack", |_, this, (archive, opts): (LuaValue, Option<LuaTable>)| {
+      let (archive, format, strip_components) = parse_unpack_args(archive, opts)?;
+      Ok(this.unpack(&archive, format, strip_components))
+    });
+
+    add_file_methods(methods);
+
     methods.add_method_mut("exec", |_, this, (opts, args): (LuaValue, Option<LuaValue>)| {
       let cmd_opts = parse_exec_opts(opts, args)?;
       Ok(this.exec(cmd_opts))
@@ -250,6 +271,7 @@ pub fn register_sys_build(lua: &Lua, sys_table: &LuaTable, manifest: Rc<RefCell<
 
       manifest.builds.insert(build_ref.hash.clone(), build_def);
     }
+    sources::record_build(lua, &build_ref.hash);
 
     lua.pack(build_ref)
   })?;
@@ -274,7 +296,7 @@ mod tests {
   }
 
   mod sys_build {
-    use crate::{action::Action, consts::OBJ_HASH_PREFIX_LEN};
+    use crate::{action::Action, build::BuildSandbox, consts::OBJ_HASH_PREFIX_LEN};
 
     use super::*;
 
@@ -917,5 +939,74 @@ mod tests {
 
       Ok(())
     }
+
+    #[test]
+    fn sandbox_settings_are_recorded() -> LuaResult<()> {
+      let (lua, manifest) = create_test_lua_with_manifest()?;
+
+      lua
+        .load(
+          r#"
+                for id, sandbox in pairs({
+                    default = true,
+                    unconfined = false,
+                    custom = { paths = { "/usr", "/etc/ssl" } },
+                }) do
+                    sys.build({
+                        id = id,
+                        sandbox = sandbox,
+                        create = function(inputs, ctx)
+                            ctx:exec("make")
+                            return { out = ctx.out }
+                        end,
+                    })
+                end
+            "#,
+        )
+        .exec()?;
+
+      let manifest = manifest.borrow();
+      let sandbox = |id: &str| {
+        manifest
+          .builds
+          .values()
+          .find(|def| def.id.as_deref() == Some(id))
+          .unwrap()
+          .sandbox
+          .clone()
+      };
+      assert_eq!(sandbox("default"), None);
+      assert_eq!(sandbox("unconfined"), Some(BuildSandbox::Disabled));
+      assert_eq!(
+        sandbox("custom"),
+        Some(BuildSandbox::Paths(vec!["/usr".to_string(), "/etc/ssl".to_string()]))
+      );
+
+      Ok(())
+    }
+
+    #[test]
+    fn sandbox_paths_must_be_absolute() -> LuaResult<()> {
+      let (lua, _) = create_test_lua_with_manifest()?;
+
+      let result = lua
+        .load(
+          r#"
+                sys.build({
+                    id = "relative",
+                    sandbox = { paths = { "usr/bin" } },
+                    create = function(inputs, ctx)
+                        return { out = ctx.out }
+                    end,
+                })
+            "#,
+        )
+        .exec();
+
+      let err = result.unwrap_err().to_string();
+      assert!(err.contains("must be absolute"), "unexpected error: {}", err);
+
+      Ok(())
+    }
   }
 }
@@ -14,9 +14,14 @@ pub fn build_dir_name(hash: &ObjectHash) -> String {
   hash.0.clone()
 }
 
+/// Path of a build in the primary store, without any parent-store fallback.
+pub fn local_build_dir_path(hash: &ObjectHash) -> PathBuf {
+  store_dir().join("build").join(build_dir_name(hash))
+}
+
 pub fn build_dir_path(hash: &ObjectHash) -> PathBuf {
   let dir_name = build_dir_name(hash);
-  let primary = store_dir().join("build").join(&dir_name);
+  let primary = local_build_dir_path(hash);
 
   // If exists in primary store, use it
   if primary.exists() {
@@ -16,7 +16,10 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
 use crate::{
-  action::{Action, ActionCtx, actions::exec::ExecOpts},
+  action::{
+    Action, ActionCtx,
+    actions::{exec::ExecOpts, fetch_url::FetchUrlOpts, unpack::ArchiveFormat},
+  },
   manifest::Manifest,
   util::hash::{Hashable, ObjectHash},
 };
@@ -63,6 +66,8 @@ pub struct BuildSpec {
   /// If true, allows replacing an existing build with the same ID.
   /// Defaults to false, which means duplicate IDs will error.
   pub replace: bool,
+  /// Sandbox settings; `None` runs sandboxed with the default host paths.
+  pub sandbox: Option<BuildSandbox>,
 }
 
 impl FromLua for BuildSpec {
@@ -84,16 +89,63 @@ impl FromLua for BuildSpec {
       .get("create")
       .map_err(|_| LuaError::external("build spec requires 'create' function"))?;
     let replace: bool = table.get("replace").unwrap_or(false);
+    let sandbox = BuildSandbox::from_lua_value(table.get("sandbox")?)?;
 
     Ok(BuildSpec {
       id,
       inputs,
       create,
       replace,
+      sandbox,
     })
   }
 }
 
+/// Sandbox settings of a build.
+///
+/// On Linux, the `exec` actions of a build run in a sandbox that can only see
+/// its output directory, its dependencies and an allowlist of host paths (see
+/// [`crate::platform::sandbox`]). Builds without settings use
+/// [`DEFAULT_SANDBOX_PATHS`](crate::platform::sandbox::DEFAULT_SANDBOX_PATHS).
+///
+/// In Lua:
+///
+/// - `sandbox = false` gives [`Disabled`](BuildSandbox::Disabled)
+/// - `sandbox = { paths = { "/bin", "/usr" } }` gives [`Paths`](BuildSandbox::Paths)
+/// - `sandbox = true` or no `sandbox` field keeps the defaults
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildSandbox {
+  /// Run `exec` actions unconfined.
+  Disabled,
+  /// Run sandboxed with these host paths visible instead of the defaults.
+  Paths(Vec<String>),
+}
+
+impl BuildSandbox {
+  fn from_lua_value(value: LuaValue) -> LuaResult<Option<Self>> {
+    match value {
+      LuaValue::Nil | LuaValue::Boolean(true) => Ok(None),
+      LuaValue::Boolean(false) => Ok(Some(BuildSandbox::Disabled)),
+      LuaValue::Table(table) => {
+        let paths: Vec<String> = table
+          .get("paths")
+          .map_err(|_| LuaError::external("build 'sandbox.paths' must be a list of strings"))?;
+        if let Some(path) = paths.iter().find(|path| !std::path::Path::new(path).is_absolute()) {
+          return Err(LuaError::external(format!(
+            "build sandbox path must be absolute: {}",
+            path
+          )));
+        }
+        Ok(Some(BuildSandbox::Paths(paths)))
+      }
+      other => Err(LuaError::external(format!(
+        "build 'sandbox' must be a boolean or a table, got {}",
+        other.type_name()
+      ))),
+    }
+  }
+}
+
 /// A resolved, serializable input value.
 ///
 /// This is the manifest-side representation of inputs. All values are fully
@@ -199,6 +251,9 @@ pub struct BuildDef {
   pub outputs: Option<BTreeMap<String, JsonValue>>,
   /// The sequence of actions to execute during `create`.
   pub create_actions: Vec<Action>,
+  /// Sandbox settings, omitted from the manifest (and the hash) when default.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub sandbox: Option<BuildSandbox>,
 }
 
 impl Hashable for BuildDef {}
@@ -252,13 +307,15 @@ impl BuildDef {
       inputs,
       create_actions: ctx.into_actions(),
       outputs: Some(outputs),
+      sandbox: spec.sandbox,
     })
   }
 }
 
 /// Context for build `create` functions.
 ///
-/// Provides `fetch_url`, `exec`, and `out` for recording build actions.
+/// Provides `fetch_url`, `fetch_git`, `unpack`, `exec`, the file actions (`write_file`,
+/// `copy`, ...), and `out` for recording build actions.
 /// This is a newtype wrapper around [`ActionCtx`] that exposes the full
 /// set of build-specific methods.
 #[derive(Default)]
@@ -278,8 +335,18 @@ impl BuildCtx {
   /// Record a URL fetch action and return a placeholder for its output.
   ///
   /// This method is only available in build contexts, not bind contexts.
-  pub fn fetch_url(&mut self, url: &str, sha256: &str) -> String {
-    self.0.fetch_url(url, sha256)
+  pub fn fetch_url(&mut self, opts: FetchUrlOpts) -> String {
+    self.0.fetch_url(opts)
+  }
+
+  /// Record checking out a git commit into the output directory.
+  pub fn fetch_git(&mut self, url: &str, rev: &str, sha256: &str) -> String {
+    self.0.fetch_git(url, rev, sha256)
+  }
+
+  /// Record an archive extraction into the output directory.
+  pub fn unpack(&mut self, archive: &str, format: Option<ArchiveFormat>, strip_components: u32) -> String {
+    self.0.unpack(archive, format, strip_components)
   }
 
   /// Record a command execution action and return a placeholder for its output.
@@ -298,6 +365,12 @@ impl BuildCtx {
   }
 }
 
+impl AsMut<ActionCtx> for BuildCtx {
+  fn as_mut(&mut self) -> &mut ActionCtx {
+    &mut self.0
+  }
+}
+
 /// Marker type name for BuildRef metatables in Lua.
 ///
 /// This constant is used to identify Lua userdata that represents a reference
@@ -379,11 +452,12 @@ mod tests {
       BuildDef {
         id: Some("ripgrep-15.1.0".to_string()),
         inputs: None,
-        create_actions: vec![Action::FetchUrl {
-          url: "https://example.com/rg.tar.gz".to_string(),
-          sha256: "abc123".to_string(),
-        }],
+        create_actions: vec![Action::FetchUrl(FetchUrlOpts::new(
+          "https://example.com/rg.tar.gz",
+          "abc123",
+        ))],
         outputs: None,
+        sandbox: None,
       }
     }
 
@@ -424,6 +498,9 @@ mod tests {
         args: None,
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       }));
 
       assert_ne!(def1.compute_hash().unwrap(), def2.compute_hash().unwrap());
@@ -442,15 +519,22 @@ mod tests {
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             bin: "step2".to_string(),
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         outputs: None,
+        sandbox: None,
       };
 
       let def2 = BuildDef {
@@ -462,15 +546,22 @@ mod tests {
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
           Action::Exec(ExecOpts {
             bin: "step1".to_string(),
             args: None,
             env: None,
             cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         outputs: None,
+        sandbox: None,
       };
 
       assert_ne!(def1.compute_hash().unwrap(), def2.compute_hash().unwrap());
@@ -485,21 +576,22 @@ mod tests {
         id: Some("complex".to_string()),
         inputs: Some(BuildInputs::String("test".to_string())),
         create_actions: vec![
-          Action::FetchUrl {
-            url: "https://example.com/src.tar.gz".to_string(),
-            sha256: "abc123".to_string(),
-          },
+          Action::FetchUrl(FetchUrlOpts::new("https://example.com/src.tar.gz", "abc123")),
           Action::Exec(ExecOpts {
             bin: "make".to_string(),
             args: Some(vec!["install".to_string()]),
             env: Some(env),
             cwd: Some("/build".to_string()),
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
           }),
         ],
         outputs: Some(BTreeMap::from([(
           "out".to_string(),
           JsonValue::String("$${{action:1}}".to_string()),
         )])),
+        sandbox: None,
       };
 
       let json = serde_json::to_string(&def).unwrap();
@@ -0,0 +1,326 @@
+//! Binary cache of realized builds.
+//!
+//! A cache holds complete build directories keyed by [`ObjectHash`], so a build
+//! realized on one machine can be substituted on another instead of running its
+//! `create_actions` again. The layout is plain files, which lets the same cache
+//! be read from a directory or served by any static HTTP server:
+//!
+//! ```text
+//! <cache>/
+//! ├── <hash>.json     # CacheInfo: output hash and file listing
+//! └── <hash>/         # Build output tree, including its completion marker
+//! ```
+//!
+//! `<hash>.json` is written last, so a build is only visible once all of its
+//! files are in place. Substituted builds must carry a signature from a trusted
+//! key (see [`signing`]).
+
+pub mod push;
+pub mod signing;
+mod substitute;
+
+use std::collections::HashSet;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use crate::build::execute::{compute_build_hash, read_build_marker};
+use crate::util::hash::ObjectHash;
+
+pub use push::{PushFailure, PushResult, push_build, push_store};
+pub use signing::{BuildSignature, PublicKey, SIGNATURES_FILE, SecretKey, TrustedKeys, sign_build};
+pub use substitute::substitute;
+
+/// Environment variable listing substituters, separated by whitespace or commas.
+pub const SUBSTITUTERS_ENV: &str = "SYSLUA_SUBSTITUTERS";
+
+/// Current [`CacheInfo`] format version.
+pub const CACHE_INFO_VERSION: u32 = 1;
+
+/// Errors that can occur while reading or writing a binary cache.
+#[derive(Debug, Error)]
+pub enum CacheError {
+  #[error("I/O error: {0}")]
+  Io(#[from] io::Error),
+
+  #[error("build at {0} is not complete")]
+  Incomplete(PathBuf),
+
+  #[error("failed to hash build output: {0}")]
+  Hash(String),
+
+  #[error("request to {url} failed: {message}")]
+  Http { url: String, message: String },
+
+  #[error("invalid cache entry for {hash}: {message}")]
+  InvalidInfo { hash: ObjectHash, message: String },
+
+  #[error("cached output of {hash} does not match its hash (expected {expected}, got {actual})")]
+  HashMismatch {
+    hash: ObjectHash,
+    expected: String,
+    actual: String,
+  },
+
+  #[error("invalid substituter '{0}'")]
+  InvalidSubstituter(String),
+
+  #[error("invalid key: {0}")]
+  InvalidKey(String),
+
+  #[error("invalid signatures file: {0}")]
+  InvalidSignatures(String),
+
+  #[error("{0} is not signed by a trusted key")]
+  Untrusted(ObjectHash),
+}
+
+/// What kind of filesystem entry a [`CacheFile`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileKind {
+  File,
+  Dir,
+  Symlink,
+}
+
+/// One entry of a cached build directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheFile {
+  /// Path relative to the build directory, `/`-separated.
+  pub path: String,
+
+  pub kind: FileKind,
+
+  /// Whether a file has its executable bit set.
+  #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+  pub executable: bool,
+
+  /// Target of a symlink.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub target: Option<String>,
+}
+
+/// Description of a cached build, stored as `<hash>.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheInfo {
+  pub version: u32,
+
+  pub hash: ObjectHash,
+
+  /// The `output_hash` of the build's completion marker.
+  pub output_hash: String,
+
+  /// Every entry of the build directory, parents before children.
+  pub files: Vec<CacheFile>,
+}
+
+impl CacheInfo {
+  /// Check that the entry describes `hash` and stays inside its directory.
+  ///
+  /// Every entry's parent must be listed before it as a directory, so nothing
+  /// is written through a symlink from an earlier entry.
+  fn validate(&self, hash: &ObjectHash) -> Result<(), CacheError> {
+    let invalid = |message: String| CacheError::InvalidInfo {
+      hash: hash.clone(),
+      message,
+    };
+
+    if self.version != CACHE_INFO_VERSION {
+      return Err(invalid(format!("unsupported version {}", self.version)));
+    }
+    if &self.hash != hash {
+      return Err(invalid(format!("describes {}", self.hash)));
+    }
+    let mut dirs = HashSet::new();
+    for file in &self.files {
+      let components: Vec<_> = Path::new(&file.path).components().collect();
+      let normal = components.iter().all(|c| matches!(c, Component::Normal(_)));
+      // `a//b` and `a/./b` have the same components as `a/b`
+      if file.path.is_empty() || !normal || file.path.split('/').count() != components.len() {
+        return Err(invalid(format!("unsafe path '{}'", file.path)));
+      }
+      if let Some((parent, _)) = file.path.rsplit_once('/')
+        && !dirs.contains(parent)
+      {
+        return Err(invalid(format!(
+          "'{}' is not inside a directory listed before it",
+          file.path
+        )));
+      }
+      if file.kind == FileKind::Dir {
+        dirs.insert(file.path.as_str());
+      }
+      if file.kind == FileKind::Symlink && file.target.is_none() {
+        return Err(invalid(format!("symlink '{}' has no target", file.path)));
+      }
+    }
+    Ok(())
+  }
+}
+
+/// A binary cache that builds can be substituted from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Substituter {
+  /// A cache directory on the local filesystem.
+  Local(PathBuf),
+  /// A cache served over HTTP(S), given by its base URL.
+  Http(String),
+}
+
+impl FromStr for Substituter {
+  type Err = CacheError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    if s.starts_with("http://") || s.starts_with("https://") {
+      return Ok(Substituter::Http(s.trim_end_matches('/').to_string()));
+    }
+    let path = s.strip_prefix("file://").unwrap_or(s);
+    if path.is_empty() {
+      return Err(CacheError::InvalidSubstituter(s.to_string()));
+    }
+    Ok(Substituter::Local(PathBuf::from(path)))
+  }
+}
+
+impl std::fmt::Display for Substituter {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Substituter::Local(path) => write!(f, "{}", path.display()),
+      Substituter::Http(url) => write!(f, "{}", url),
+    }
+  }
+}
+
+/// Substituters configured through [`SUBSTITUTERS_ENV`].
+///
+/// Entries that cannot be parsed are skipped with a warning.
+pub fn substituters_from_env() -> Vec<Substituter> {
+  let Ok(value) = std::env::var(SUBSTITUTERS_ENV) else {
+    return Vec::new();
+  };
+
+  value
+    .split(|c: char| c.is_whitespace() || c == ',')
+    .filter(|s| !s.is_empty())
+    .filter_map(|s| match s.parse() {
+      Ok(substituter) => Some(substituter),
+      Err(e) => {
+        warn!(value = %s, error = %e, "ignoring substituter");
+        None
+      }
+    })
+    .collect()
+}
+
+/// The output hash of a complete build, after checking its contents still match.
+fn verified_output_hash(hash: &ObjectHash, build_path: &Path) -> Result<String, CacheError> {
+  let output_hash = read_build_marker(build_path)
+    .ok()
+    .flatten()
+    .and_then(|marker| marker.output_hash)
+    .ok_or_else(|| CacheError::Incomplete(build_path.to_path_buf()))?;
+  let actual = compute_build_hash(build_path).map_err(|e| CacheError::Hash(e.to_string()))?;
+  if actual.0 != output_hash {
+    return Err(CacheError::HashMismatch {
+      hash: hash.clone(),
+      expected: output_hash,
+      actual: actual.0,
+    });
+  }
+  Ok(output_hash)
+}
+
+/// File name of a build's [`CacheInfo`].
+fn info_file_name(hash: &ObjectHash) -> String {
+  format!("{}.json", hash.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_substituters() {
+    assert_eq!(
+      "https://cache.example.com/".parse::<Substituter>().unwrap(),
+      Substituter::Http("https://cache.example.com".to_string())
+    );
+    assert_eq!(
+      "file:///srv/cache".parse::<Substituter>().unwrap(),
+      Substituter::Local(PathBuf::from("/srv/cache"))
+    );
+    assert_eq!(
+      "/srv/cache".parse::<Substituter>().unwrap(),
+      Substituter::Local(PathBuf::from("/srv/cache"))
+    );
+    assert!("file://".parse::<Substituter>().is_err());
+  }
+
+  #[test]
+  fn validate_rejects_escaping_paths() {
+    let hash = ObjectHash("abc".to_string());
+    let mut info = CacheInfo {
+      version: CACHE_INFO_VERSION,
+      hash: hash.clone(),
+      output_hash: "00".to_string(),
+      files: vec![
+        CacheFile {
+          path: "bin".to_string(),
+          kind: FileKind::Dir,
+          executable: false,
+          target: None,
+        },
+        CacheFile {
+          path: "bin/tool".to_string(),
+          kind: FileKind::File,
+          executable: true,
+          target: None,
+        },
+      ],
+    };
+    assert!(info.validate(&hash).is_ok());
+
+    info.files[1].path = "../escape".to_string();
+    assert!(info.validate(&hash).is_err());
+
+    info.files[1].path = "/etc/passwd".to_string();
+    assert!(info.validate(&hash).is_err());
+
+    info.files[1].path = "bin/./tool".to_string();
+    assert!(info.validate(&hash).is_err());
+  }
+
+  #[test]
+  fn validate_rejects_entries_below_symlinks() {
+    let hash = ObjectHash("abc".to_string());
+    let entry = |path: &str, kind: FileKind| CacheFile {
+      path: path.to_string(),
+      kind,
+      executable: false,
+      target: (kind == FileKind::Symlink).then(|| "/home/u/.ssh".to_string()),
+    };
+    let mut info = CacheInfo {
+      version: CACHE_INFO_VERSION,
+      hash: hash.clone(),
+      output_hash: "00".to_string(),
+      files: vec![entry("x", FileKind::Dir), entry("x/authorized_keys", FileKind::File)],
+    };
+    assert!(info.validate(&hash).is_ok());
+
+    info.files[0] = entry("x", FileKind::Symlink);
+    assert!(info.validate(&hash).is_err());
+
+    info.files[1] = entry("x/sub", FileKind::Dir);
+    assert!(info.validate(&hash).is_err());
+
+    // Parents come first
+    info.files = vec![entry("x/authorized_keys", FileKind::File), entry("x", FileKind::Dir)];
+    assert!(info.validate(&hash).is_err());
+  }
+}
@@ -0,0 +1,252 @@
+//! Copying realized builds into a binary cache.
+
+use std::fs;
+use std::path::Path;
+
+use serde::Serialize;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+use crate::build::execute::{BUILD_COMPLETE_MARKER, BUILD_HASH_EXCLUSIONS};
+use crate::platform::paths::store_dir;
+use crate::util::hash::ObjectHash;
+
+use super::signing::{SIGNATURES_FILE, SecretKey, sign_build};
+use super::{CACHE_INFO_VERSION, CacheError, CacheFile, CacheInfo, FileKind, info_file_name, verified_output_hash};
+
+/// A build that could not be pushed.
+#[derive(Debug, Serialize)]
+pub struct PushFailure {
+  pub hash: ObjectHash,
+  pub error: String,
+}
+
+/// The result of pushing the store to a cache.
+#[derive(Debug, Default, Serialize)]
+pub struct PushResult {
+  /// Builds copied into the cache.
+  pub pushed: Vec<ObjectHash>,
+
+  /// Builds the cache already had.
+  pub cached: Vec<ObjectHash>,
+
+  /// Builds that were skipped because they are incomplete or damaged.
+  pub failed: Vec<PushFailure>,
+}
+
+/// Copy one realized build into `cache_dir`.
+///
+/// The build must have a completion marker with an output hash, and its
+/// contents must still match that hash.
+///
+/// # Arguments
+///
+/// * `hash` - The build hash
+/// * `build_path` - The build's directory in the store
+/// * `cache_dir` - Root of the cache
+///
+/// # Returns
+///
+/// `true` if the build was copied, `false` if the cache already had it. In
+/// that case only its signatures are refreshed.
+pub fn push_build(hash: &ObjectHash, build_path: &Path, cache_dir: &Path) -> Result<bool, CacheError> {
+  let info_path = cache_dir.join(info_file_name(hash));
+  if info_path.exists() {
+    debug!(hash = %hash.0, "build already cached");
+    update_signatures(hash, build_path, cache_dir)?;
+    return Ok(false);
+  }
+
+  let output_hash = verified_output_hash(hash, build_path)?;
+
+  fs::create_dir_all(cache_dir)?;
+  let staging = cache_dir.join(format!(".{}.partial", hash.0));
+  if staging.exists() {
+    fs::remove_dir_all(&staging)?;
+  }
+  fs::create_dir(&staging)?;
+
+  let files = copy_tree(build_path, &staging)?;
+
+  let dest = cache_dir.join(&hash.0);
+  if dest.exists() {
+    fs::remove_dir_all(&dest)?;
+  }
+  fs::rename(&staging, &dest)?;
+
+  // The info file goes last: its presence means the entry is complete.
+  let info = CacheInfo {
+    version: CACHE_INFO_VERSION,
+    hash: hash.clone(),
+    output_hash,
+    files,
+  };
+  write_info(cache_dir, &info)?;
+
+  debug!(hash = %hash.0, files = info.files.len(), "pushed build");
+  Ok(true)
+}
+
+/// Copy a build's signatures over those of its cached copy.
+fn update_signatures(hash: &ObjectHash, build_path: &Path, cache_dir: &Path) -> Result<(), CacheError> {
+  let signatures = build_path.join(SIGNATURES_FILE);
+  if !signatures.exists() {
+    return Ok(());
+  }
+
+  let content = fs::read(cache_dir.join(info_file_name(hash)))?;
+  let mut info: CacheInfo = serde_json::from_slice(&content).map_err(|e| CacheError::InvalidInfo {
+    hash: hash.clone(),
+    message: e.to_string(),
+  })?;
+  fs::copy(&signatures, cache_dir.join(&hash.0).join(SIGNATURES_FILE))?;
+
+  if !info.files.iter().any(|f| f.path == SIGNATURES_FILE) {
+    info.files.push(CacheFile {
+      path: SIGNATURES_FILE.to_string(),
+      kind: FileKind::File,
+      executable: false,
+      target: None,
+    });
+    write_info(cache_dir, &info)?;
+  }
+  Ok(())
+}
+
+/// Atomically write the `<hash>.json` of a cache entry.
+fn write_info(cache_dir: &Path, info: &CacheInfo) -> Result<(), CacheError> {
+  let name = info_file_name(&info.hash);
+  let content = serde_json::to_string_pretty(info).expect("failed to serialize cache info");
+  let tmp = cache_dir.join(format!(".{}.partial", name));
+  fs::write(&tmp, content)?;
+  fs::rename(&tmp, cache_dir.join(name))?;
+  Ok(())
+}
+
+/// Copy every complete build in the store into `cache_dir`.
+///
+/// With `key`, each build is signed in the store before it is copied, so the
+/// signature travels with it. Builds that cannot be pushed are reported in
+/// [`PushResult::failed`] rather than aborting the push.
+pub fn push_store(cache_dir: &Path, key: Option<&SecretKey>) -> Result<PushResult, CacheError> {
+  let build_dir = store_dir().join("build");
+  let mut result = PushResult::default();
+  if !build_dir.exists() {
+    return Ok(result);
+  }
+
+  let mut entries: Vec<_> = fs::read_dir(&build_dir)?.collect::<Result<_, _>>()?;
+  entries.sort_by_key(|e| e.file_name());
+
+  for entry in entries {
+    let path = entry.path();
+    if !path.is_dir() {
+      continue;
+    }
+    let hash = ObjectHash(entry.file_name().to_string_lossy().to_string());
+
+    let pushed = match key {
+      Some(key) => sign_build(&hash, &path, key).and_then(|_| push_build(&hash, &path, cache_dir)),
+      None => push_build(&hash, &path, cache_dir),
+    };
+    match pushed {
+      Ok(true) => result.pushed.push(hash),
+      Ok(false) => result.cached.push(hash),
+      Err(e) => {
+        warn!(hash = %hash.0, error = %e, "skipping build");
+        result.failed.push(PushFailure {
+          hash,
+          error: e.to_string(),
+        });
+      }
+    }
+  }
+
+  info!(
+    pushed = result.pushed.len(),
+    cached = result.cached.len(),
+    failed = result.failed.len(),
+    cache = %cache_dir.display(),
+    "push complete"
+  );
+  Ok(result)
+}
+
+/// Copy the contents of `src` into `dest`, returning the entries copied.
+fn copy_tree(src: &Path, dest: &Path) -> Result<Vec<CacheFile>, CacheError> {
+  let walker = WalkDir::new(src)
+    .min_depth(1)
+    .sort_by_file_name()
+    .into_iter()
+    .filter_entry(|e| {
+      let name = e.file_name().to_string_lossy();
+      name == BUILD_COMPLETE_MARKER || name == SIGNATURES_FILE || !BUILD_HASH_EXCLUSIONS.contains(&name.as_ref())
+    });
+
+  let mut files = Vec::new();
+  for entry in walker {
+    let entry = entry.map_err(|e| CacheError::Io(e.into()))?;
+    let rel = entry.path().strip_prefix(src).expect("walkdir yields children of src");
+    let rel_str = rel
+      .components()
+      .map(|c| c.as_os_str().to_string_lossy())
+      .collect::<Vec<_>>()
+      .join("/");
+    let target_path = dest.join(rel);
+    let file_type = entry.file_type();
+
+    let file = if file_type.is_dir() {
+      fs::create_dir(&target_path)?;
+      CacheFile {
+        path: rel_str,
+        kind: FileKind::Dir,
+        executable: false,
+        target: None,
+      }
+    } else if file_type.is_symlink() {
+      let target = fs::read_link(entry.path())?;
+      copy_symlink(&target, &target_path)?;
+      CacheFile {
+        path: rel_str,
+        kind: FileKind::Symlink,
+        executable: false,
+        target: Some(target.to_string_lossy().to_string()),
+      }
+    } else if file_type.is_file() {
+      fs::copy(entry.path(), &target_path)?;
+      CacheFile {
+        path: rel_str,
+        kind: FileKind::File,
+        executable: is_executable(&entry.metadata().map_err(|e| CacheError::Io(e.into()))?),
+        target: None,
+      }
+    } else {
+      // Sockets, devices and the like are not part of the build hash either
+      continue;
+    };
+    files.push(file);
+  }
+
+  Ok(files)
+}
+
+#[cfg(unix)]
+fn copy_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+  std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(target: &Path, link: &Path) -> std::io::Result<()> {
+  std::os::windows::fs::symlink_file(target, link)
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+  use std::os::unix::fs::PermissionsExt;
+  meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+  false
+}
@@ -0,0 +1,331 @@
+//! Ed25519 signatures over realized builds.
+//!
+//! A signature covers a build's [`ObjectHash`] together with the content hash
+//! of its outputs, so it vouches both for what was built and for every file
+//! produced. Signatures live in [`SIGNATURES_FILE`] next to the build's
+//! completion marker, and substituted builds are only accepted when one of them
+//! verifies against a key in the trusted-keys file.
+//!
+//! Keys are written as `<name>:<hex>`; a secret key holds the PKCS#8 document
+//! and a public key the raw 32-byte Ed25519 key.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use ring::rand::SystemRandom;
+use ring::signature::{ED25519, Ed25519KeyPair, KeyPair, UnparsedPublicKey};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::util::hash::ObjectHash;
+
+use super::{CacheError, verified_output_hash};
+
+/// File next to the completion marker holding a build's signatures.
+pub const SIGNATURES_FILE: &str = ".syslua-signatures";
+
+/// A named Ed25519 signing key.
+pub struct SecretKey {
+  name: String,
+  pkcs8: Vec<u8>,
+  pair: Ed25519KeyPair,
+}
+
+impl SecretKey {
+  /// Generate a new random key called `name`.
+  pub fn generate(name: &str) -> Result<Self, CacheError> {
+    validate_key_name(name)?;
+    let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+      .map_err(|_| CacheError::InvalidKey("failed to generate key".to_string()))?;
+    Self::from_pkcs8(name, document.as_ref())
+  }
+
+  /// Read a secret key from a file written by [`SecretKey::write`].
+  pub fn load(path: &Path) -> Result<Self, CacheError> {
+    fs::read_to_string(path)?.trim().parse()
+  }
+
+  /// Write the key to `path`, readable only by the owner on Unix.
+  pub fn write(&self, path: &Path) -> Result<(), CacheError> {
+    fs::write(path, format!("{}\n", self))?;
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// The public half of this key.
+  pub fn public_key(&self) -> PublicKey {
+    PublicKey {
+      name: self.name.clone(),
+      bytes: self.pair.public_key().as_ref().to_vec(),
+    }
+  }
+
+  /// Sign a build's hash and output hash.
+  pub fn sign(&self, hash: &ObjectHash, output_hash: &str) -> BuildSignature {
+    let signature = self.pair.sign(&signed_message(hash, output_hash));
+    BuildSignature {
+      key: self.name.clone(),
+      sig: hex::encode(signature.as_ref()),
+    }
+  }
+
+  fn from_pkcs8(name: &str, pkcs8: &[u8]) -> Result<Self, CacheError> {
+    let pair = Ed25519KeyPair::from_pkcs8(pkcs8).map_err(|e| CacheError::InvalidKey(e.to_string()))?;
+    Ok(Self {
+      name: name.to_string(),
+      pkcs8: pkcs8.to_vec(),
+      pair,
+    })
+  }
+}
+
+impl FromStr for SecretKey {
+  type Err = CacheError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (name, bytes) = split_key(s)?;
+    Self::from_pkcs8(name, &bytes)
+  }
+}
+
+impl fmt::Display for SecretKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.name, hex::encode(&self.pkcs8))
+  }
+}
+
+/// A named Ed25519 public key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicKey {
+  pub name: String,
+  bytes: Vec<u8>,
+}
+
+impl PublicKey {
+  /// Whether `signature` is this key's signature of `hash` and `output_hash`.
+  pub fn verify(&self, hash: &ObjectHash, output_hash: &str, signature: &BuildSignature) -> bool {
+    if signature.key != self.name {
+      return false;
+    }
+    let Ok(sig) = hex::decode(&signature.sig) else {
+      return false;
+    };
+    UnparsedPublicKey::new(&ED25519, &self.bytes)
+      .verify(&signed_message(hash, output_hash), &sig)
+      .is_ok()
+  }
+}
+
+impl FromStr for PublicKey {
+  type Err = CacheError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (name, bytes) = split_key(s)?;
+    if bytes.len() != 32 {
+      return Err(CacheError::InvalidKey(format!("public key '{}' is not 32 bytes", name)));
+    }
+    Ok(Self {
+      name: name.to_string(),
+      bytes,
+    })
+  }
+}
+
+impl fmt::Display for PublicKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.name, hex::encode(&self.bytes))
+  }
+}
+
+/// One signature of a build.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildSignature {
+  /// Name of the signing key.
+  pub key: String,
+  /// Hex-encoded Ed25519 signature.
+  pub sig: String,
+}
+
+/// Public keys whose signatures are accepted on substituted builds.
+#[derive(Debug, Clone, Default)]
+pub struct TrustedKeys {
+  keys: Vec<PublicKey>,
+}
+
+impl TrustedKeys {
+  pub fn new(keys: Vec<PublicKey>) -> Self {
+    Self { keys }
+  }
+
+  /// Read a trusted-keys file: one public key per line, `#` starts a comment.
+  ///
+  /// A missing file means no keys are trusted.
+  pub fn load(path: &Path) -> Result<Self, CacheError> {
+    let content = match fs::read_to_string(path) {
+      Ok(content) => content,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+      Err(e) => return Err(e.into()),
+    };
+
+    let keys = content
+      .lines()
+      .map(|line| line.split('#').next().unwrap_or("").trim())
+      .filter(|line| !line.is_empty())
+      .map(str::parse)
+      .collect::<Result<_, _>>()?;
+    Ok(Self { keys })
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.keys.is_empty()
+  }
+
+  /// Name of the first trusted key with a valid signature among `signatures`.
+  pub fn verify(&self, hash: &ObjectHash, output_hash: &str, signatures: &[BuildSignature]) -> Option<&str> {
+    self.keys.iter().find_map(|key| {
+      signatures
+        .iter()
+        .any(|sig| key.verify(hash, output_hash, sig))
+        .then_some(key.name.as_str())
+    })
+  }
+}
+
+/// Read the signatures stored with a build. A missing file means none.
+pub fn read_signatures(build_path: &Path) -> Result<Vec<BuildSignature>, CacheError> {
+  match fs::read_to_string(build_path.join(SIGNATURES_FILE)) {
+    Ok(content) => serde_json::from_str(&content).map_err(|e| CacheError::InvalidSignatures(e.to_string())),
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+    Err(e) => Err(e.into()),
+  }
+}
+
+/// Sign a complete build in place with `key`.
+///
+/// The outputs are re-hashed first, so a build that no longer matches its
+/// marker is never signed. Signing again with the same key replaces the old
+/// signature.
+pub fn sign_build(hash: &ObjectHash, build_path: &Path, key: &SecretKey) -> Result<BuildSignature, CacheError> {
+  let output_hash = verified_output_hash(hash, build_path)?;
+
+  let signature = key.sign(hash, &output_hash);
+  let mut signatures = read_signatures(build_path)?;
+  signatures.retain(|s| s.key != signature.key);
+  signatures.push(signature.clone());
+
+  let content = serde_json::to_string(&signatures).expect("failed to serialize signatures");
+  fs::write(build_path.join(SIGNATURES_FILE), format!("{}\n", content))?;
+  debug!(hash = %hash.0, key = %key.name, "signed build");
+  Ok(signature)
+}
+
+/// The bytes covered by a signature.
+fn signed_message(hash: &ObjectHash, output_hash: &str) -> Vec<u8> {
+  format!("syslua-build:{}:{}", hash.0, output_hash).into_bytes()
+}
+
+fn split_key(s: &str) -> Result<(&str, Vec<u8>), CacheError> {
+  let (name, encoded) = s
+    .trim()
+    .rsplit_once(':')
+    .ok_or_else(|| CacheError::InvalidKey("expected <name>:<hex>".to_string()))?;
+  validate_key_name(name)?;
+  let bytes = hex::decode(encoded).map_err(|e| CacheError::InvalidKey(format!("key '{}': {}", name, e)))?;
+  Ok((name, bytes))
+}
+
+fn validate_key_name(name: &str) -> Result<(), CacheError> {
+  if name.is_empty() || name.contains(':') || name.chars().any(char::is_whitespace) {
+    return Err(CacheError::InvalidKey(format!("invalid key name '{}'", name)));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use tempfile::TempDir;
+
+  use super::*;
+  use crate::build::execute::{BUILD_COMPLETE_MARKER, compute_build_hash};
+
+  #[test]
+  fn key_roundtrip_and_verify() {
+    let key = SecretKey::generate("test-1").unwrap();
+    let parsed: SecretKey = key.to_string().parse().unwrap();
+    assert_eq!(parsed.public_key(), key.public_key());
+
+    let public: PublicKey = key.public_key().to_string().parse().unwrap();
+    let hash = ObjectHash("abc".to_string());
+    let sig = key.sign(&hash, "00ff");
+
+    assert!(public.verify(&hash, "00ff", &sig));
+    assert!(!public.verify(&hash, "00fe", &sig));
+    assert!(!public.verify(&ObjectHash("abd".to_string()), "00ff", &sig));
+
+    let other = SecretKey::generate("test-1").unwrap();
+    assert!(!other.public_key().verify(&hash, "00ff", &sig));
+  }
+
+  #[test]
+  fn trusted_keys_file() {
+    let temp = TempDir::new().unwrap();
+    let key = SecretKey::generate("team").unwrap();
+    let path = temp.path().join("trusted-keys");
+    std::fs::write(&path, format!("# team cache\n{}\n\n", key.public_key())).unwrap();
+
+    let trusted = TrustedKeys::load(&path).unwrap();
+    let hash = ObjectHash("abc".to_string());
+    let sig = key.sign(&hash, "11");
+    assert_eq!(trusted.verify(&hash, "11", &[sig]), Some("team"));
+
+    assert!(TrustedKeys::load(&temp.path().join("missing")).unwrap().is_empty());
+    std::fs::write(&path, "not-a-key").unwrap();
+    assert!(TrustedKeys::load(&path).is_err());
+  }
+
+  #[test]
+  fn sign_build_writes_signature_file() {
+    let temp = TempDir::new().unwrap();
+    let build = temp.path().join("build");
+    std::fs::create_dir(&build).unwrap();
+    std::fs::write(build.join("out.txt"), "hello").unwrap();
+    let output_hash = compute_build_hash(&build).unwrap();
+    std::fs::write(
+      build.join(BUILD_COMPLETE_MARKER),
+      format!(
+        r#"{{"version":1,"status":"complete","output_hash":"{}"}}"#,
+        output_hash.0
+      ),
+    )
+    .unwrap();
+
+    let key = SecretKey::generate("signer").unwrap();
+    let hash = ObjectHash("abc".to_string());
+    sign_build(&hash, &build, &key).unwrap();
+    sign_build(&hash, &build, &key).unwrap();
+
+    let signatures = read_signatures(&build).unwrap();
+    assert_eq!(signatures.len(), 1, "re-signing replaces the old signature");
+    assert_eq!(
+      compute_build_hash(&build).unwrap(),
+      output_hash,
+      "signatures are not hashed"
+    );
+
+    std::fs::write(build.join("out.txt"), "tampered").unwrap();
+    assert!(matches!(
+      sign_build(&hash, &build, &key),
+      Err(CacheError::HashMismatch { .. })
+    ));
+  }
+}
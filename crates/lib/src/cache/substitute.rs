@@ -0,0 +1,479 @@
+//! Fetching builds from substituters.
+
+use std::path::Path;
+
+use tokio::fs;
+use tracing::{debug, info, warn};
+
+use crate::build::execute::{compute_build_hash, read_build_marker};
+use crate::util::hash::ObjectHash;
+
+use super::signing::{TrustedKeys, read_signatures};
+use super::{CacheError, CacheInfo, FileKind, Substituter, info_file_name};
+
+/// Try to place `hash` at `store_path` by fetching it from a substituter.
+///
+/// Substituters are tried in order. A fetched build is only moved into the
+/// store once its contents match the output hash recorded by the cache and by
+/// its marker, and that hash carries a signature from one of `trusted`; any
+/// failure is logged and the next substituter is tried.
+///
+/// # Arguments
+///
+/// * `hash` - The build hash
+/// * `store_path` - Where the build belongs in the store (must not exist)
+/// * `substituters` - Caches to try
+/// * `trusted` - Keys whose signatures are accepted
+///
+/// # Returns
+///
+/// `true` if the build is now in the store.
+pub async fn substitute(
+  hash: &ObjectHash,
+  store_path: &Path,
+  substituters: &[Substituter],
+  trusted: &TrustedKeys,
+) -> bool {
+  if substituters.is_empty() {
+    return false;
+  }
+  if trusted.is_empty() {
+    warn!(hash = %hash.0, "no trusted keys configured, not substituting");
+    return false;
+  }
+
+  let client = reqwest::Client::new();
+  for substituter in substituters {
+    match try_substitute(&client, substituter, hash, store_path, trusted).await {
+      Ok(true) => {
+        info!(hash = %hash.0, substituter = %substituter, "substituted build from cache");
+        return true;
+      }
+      Ok(false) => debug!(hash = %hash.0, substituter = %substituter, "build not in cache"),
+      Err(e) => warn!(hash = %hash.0, substituter = %substituter, error = %e, "substitution failed"),
+    }
+  }
+  false
+}
+
+async fn try_substitute(
+  client: &reqwest::Client,
+  substituter: &Substituter,
+  hash: &ObjectHash,
+  store_path: &Path,
+  trusted: &TrustedKeys,
+) -> Result<bool, CacheError> {
+  let Some(info) = fetch_info(client, substituter, hash).await? else {
+    return Ok(false);
+  };
+  info.validate(hash)?;
+
+  let parent = store_path.parent().expect("build paths have a parent");
+  fs::create_dir_all(parent).await?;
+  let staging = parent.join(format!(".{}.substitute", hash.0));
+  if fs::symlink_metadata(&staging).await.is_ok() {
+    fs::remove_dir_all(&staging).await?;
+  }
+  fs::create_dir(&staging).await?;
+
+  let result = materialize(client, substituter, &info, &staging, trusted).await;
+  if result.is_ok() {
+    fs::rename(&staging, store_path).await?;
+    return Ok(true);
+  }
+
+  let _ = fs::remove_dir_all(&staging).await;
+  result.map(|_| false)
+}
+
+/// Download every entry of `info` into `dest` and check the result.
+///
+/// Nothing is written through a symlink, even though [`CacheInfo::validate`]
+/// already rejects listings that would need it.
+async fn materialize(
+  client: &reqwest::Client,
+  substituter: &Substituter,
+  info: &CacheInfo,
+  dest: &Path,
+  trusted: &TrustedKeys,
+) -> Result<(), CacheError> {
+  for file in &info.files {
+    check_parents(dest, &file.path).await?;
+    let path = dest.join(&file.path);
+    match file.kind {
+      FileKind::Dir => fs::create_dir(&path).await?,
+      FileKind::File => {
+        let bytes = fetch_file(client, substituter, &info.hash, &file.path).await?;
+        write_new(&path, &bytes).await?;
+        if file.executable {
+          set_executable(&path).await?;
+        }
+      }
+      FileKind::Symlink => {
+        let target = file.target.as_deref().expect("validated symlink target");
+        create_symlink(target, &path).await?;
+      }
+    }
+  }
+
+  let actual = compute_build_hash(dest).map_err(|e| CacheError::Hash(e.to_string()))?;
+  let marker_hash = read_build_marker(dest).ok().flatten().and_then(|m| m.output_hash);
+  if actual.0 != info.output_hash || marker_hash.as_deref() != Some(info.output_hash.as_str()) {
+    return Err(CacheError::HashMismatch {
+      hash: info.hash.clone(),
+      expected: info.output_hash.clone(),
+      actual: actual.0,
+    });
+  }
+
+  // Verify against the hash just computed, so any changed file breaks the signature
+  let signatures = read_signatures(dest)?;
+  match trusted.verify(&info.hash, &actual.0, &signatures) {
+    Some(key) => {
+      debug!(hash = %info.hash.0, key, "signature verified");
+      Ok(())
+    }
+    None => Err(CacheError::Untrusted(info.hash.clone())),
+  }
+}
+
+async fn fetch_info(
+  client: &reqwest::Client,
+  substituter: &Substituter,
+  hash: &ObjectHash,
+) -> Result<Option<CacheInfo>, CacheError> {
+  let content = match substituter {
+    Substituter::Local(dir) => match fs::read(dir.join(info_file_name(hash))).await {
+      Ok(content) => content,
+      Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e.into()),
+    },
+    Substituter::Http(base) => {
+      let url = format!("{}/{}", base, info_file_name(hash));
+      match http_get(client, &url).await? {
+        Some(content) => content,
+        None => return Ok(None),
+      }
+    }
+  };
+
+  serde_json::from_slice(&content)
+    .map(Some)
+    .map_err(|e| CacheError::InvalidInfo {
+      hash: hash.clone(),
+      message: e.to_string(),
+    })
+}
+
+async fn fetch_file(
+  client: &reqwest::Client,
+  substituter: &Substituter,
+  hash: &ObjectHash,
+  path: &str,
+) -> Result<Vec<u8>, CacheError> {
+  match substituter {
+    Substituter::Local(dir) => Ok(fs::read(dir.join(&hash.0).join(path)).await?),
+    Substituter::Http(base) => {
+      let mut url = reqwest::Url::parse(base).map_err(|e| CacheError::Http {
+        url: base.clone(),
+        message: e.to_string(),
+      })?;
+      url
+        .path_segments_mut()
+        .map_err(|_| CacheError::InvalidSubstituter(base.clone()))?
+        .pop_if_empty()
+        .push(&hash.0)
+        .extend(path.split('/'));
+
+      let url = url.to_string();
+      http_get(client, &url).await?.ok_or(CacheError::Http {
+        url,
+        message: "HTTP 404 Not Found".to_string(),
+      })
+    }
+  }
+}
+
+/// GET `url`, returning `None` on 404.
+async fn http_get(client: &reqwest::Client, url: &str) -> Result<Option<Vec<u8>>, CacheError> {
+  let http_err = |message: String| CacheError::Http {
+    url: url.to_string(),
+    message,
+  };
+
+  let response = client.get(url).send().await.map_err(|e| http_err(e.to_string()))?;
+  if response.status() == reqwest::StatusCode::NOT_FOUND {
+    return Ok(None);
+  }
+  if !response.status().is_success() {
+    return Err(http_err(format!("HTTP {}", response.status())));
+  }
+  let bytes = response.bytes().await.map_err(|e| http_err(e.to_string()))?;
+  Ok(Some(bytes.to_vec()))
+}
+
+/// Fail if a directory above `path` in `dest` is a symlink.
+async fn check_parents(dest: &Path, path: &str) -> std::io::Result<()> {
+  let mut dir = dest.to_path_buf();
+  for component in Path::new(path).parent().into_iter().flat_map(Path::components) {
+    dir.push(component);
+    if fs::symlink_metadata(&dir).await?.file_type().is_symlink() {
+      return Err(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        format!("parent of '{}' is a symlink", path),
+      ));
+    }
+  }
+  Ok(())
+}
+
+/// Create `path` and write `bytes` to it, failing if it already exists or is
+/// a symlink.
+async fn write_new(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+  use tokio::io::AsyncWriteExt;
+
+  let mut options = fs::OpenOptions::new();
+  options.write(true).create_new(true);
+  #[cfg(unix)]
+  options.custom_flags(rustix::fs::OFlags::NOFOLLOW.bits() as i32);
+  let mut file = options.open(path).await?;
+  file.write_all(bytes).await?;
+  file.flush().await
+}
+
+#[cfg(unix)]
+async fn set_executable(path: &Path) -> std::io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  let mut perms = fs::metadata(path).await?.permissions();
+  perms.set_mode(perms.mode() | 0o111);
+  fs::set_permissions(path, perms).await
+}
+
+#[cfg(not(unix))]
+async fn set_executable(_path: &Path) -> std::io::Result<()> {
+  Ok(())
+}
+
+#[cfg(unix)]
+async fn create_symlink(target: &str, link: &Path) -> std::io::Result<()> {
+  fs::symlink(target, link).await
+}
+
+#[cfg(not(unix))]
+async fn create_symlink(target: &str, link: &Path) -> std::io::Result<()> {
+  fs::symlink_file(target, link).await
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::{BufRead, BufReader, Write};
+  use std::net::TcpListener;
+  use std::path::PathBuf;
+
+  use tempfile::TempDir;
+
+  use super::*;
+  use crate::build::execute::BUILD_COMPLETE_MARKER;
+  use crate::cache::signing::{SIGNATURES_FILE, SecretKey, sign_build};
+  use crate::cache::{CacheFile, push_build};
+
+  /// A fake build directory with a valid marker.
+  fn make_build(dir: &Path) -> PathBuf {
+    let build = dir.join("build");
+    std::fs::create_dir_all(build.join("bin")).unwrap();
+    std::fs::write(build.join("bin/tool"), "#!/bin/sh\necho hi\n").unwrap();
+    std::fs::write(build.join("README"), "docs").unwrap();
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      std::fs::set_permissions(build.join("bin/tool"), std::fs::Permissions::from_mode(0o755)).unwrap();
+      std::os::unix::fs::symlink("bin/tool", build.join("tool")).unwrap();
+    }
+
+    let hash = compute_build_hash(&build).unwrap();
+    std::fs::write(
+      build.join(BUILD_COMPLETE_MARKER),
+      format!(r#"{{"version":1,"status":"complete","output_hash":"{}"}}"#, hash.0),
+    )
+    .unwrap();
+    build
+  }
+
+  /// Serve `root` over HTTP on a random port for `requests` requests.
+  fn serve(root: PathBuf, requests: usize) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+      for stream in listener.incoming().take(requests) {
+        let mut stream = stream.unwrap();
+        let mut line = String::new();
+        BufReader::new(&stream).read_line(&mut line).unwrap();
+        let path = line.split_whitespace().nth(1).unwrap_or("/").trim_start_matches('/');
+        match std::fs::read(root.join(path)) {
+          Ok(body) => {
+            write!(
+              stream,
+              "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+              body.len()
+            )
+            .unwrap();
+            stream.write_all(&body).unwrap();
+          }
+          Err(_) => {
+            write!(
+              stream,
+              "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+          }
+        }
+      }
+    });
+    format!("http://{}", addr)
+  }
+
+  fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+  }
+
+  /// Sign and push a fake build, returning the cache and the key it trusts.
+  fn push_signed(temp: &Path) -> (ObjectHash, PathBuf, PathBuf, TrustedKeys) {
+    let build = make_build(temp);
+    let cache = temp.join("cache");
+    let hash = ObjectHash("abc123".to_string());
+    let key = SecretKey::generate("test-cache").unwrap();
+    sign_build(&hash, &build, &key).unwrap();
+    assert!(push_build(&hash, &build, &cache).unwrap());
+    (hash, build, cache, TrustedKeys::new(vec![key.public_key()]))
+  }
+
+  #[test]
+  fn push_then_substitute_from_directory() {
+    let temp = TempDir::new().unwrap();
+    let (hash, build, cache, trusted) = push_signed(temp.path());
+    assert!(!push_build(&hash, &build, &cache).unwrap(), "second push is a no-op");
+
+    let dest = temp.path().join("store/build/abc123");
+    let substituters = vec![Substituter::Local(cache)];
+    assert!(runtime().block_on(substitute(&hash, &dest, &substituters, &trusted)));
+
+    assert_eq!(std::fs::read_to_string(dest.join("README")).unwrap(), "docs");
+    assert_eq!(compute_build_hash(&dest).unwrap(), compute_build_hash(&build).unwrap());
+    assert!(dest.join(SIGNATURES_FILE).exists());
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+      let mode = std::fs::metadata(dest.join("bin/tool")).unwrap().permissions().mode();
+      assert_ne!(mode & 0o111, 0);
+    }
+  }
+
+  #[test]
+  fn substitute_from_http() {
+    let temp = TempDir::new().unwrap();
+    let (hash, build, cache, trusted) = push_signed(temp.path());
+
+    // info + marker + signatures + README + bin/tool
+    let url = serve(cache, 5);
+    let dest = temp.path().join("store/build/abc123");
+    assert!(runtime().block_on(substitute(&hash, &dest, &[Substituter::Http(url)], &trusted)));
+    assert_eq!(compute_build_hash(&dest).unwrap(), compute_build_hash(&build).unwrap());
+  }
+
+  #[test]
+  fn tampered_cache_entry_is_rejected() {
+    let temp = TempDir::new().unwrap();
+    let (hash, _, cache, trusted) = push_signed(temp.path());
+    std::fs::write(cache.join("abc123/README"), "tampered").unwrap();
+
+    let dest = temp.path().join("store/build/abc123");
+    let missing = Substituter::Local(temp.path().join("missing"));
+    let substituters = [missing, Substituter::Local(cache)];
+    assert!(!runtime().block_on(substitute(&hash, &dest, &substituters, &trusted)));
+    assert!(!dest.exists());
+    assert!(!temp.path().join("store/build/.abc123.substitute").exists());
+  }
+
+  #[test]
+  fn rehashed_tampering_fails_signature_check() {
+    let temp = TempDir::new().unwrap();
+    let (hash, _, cache, trusted) = push_signed(temp.path());
+
+    // Rewrite a file and every recorded hash to match; only the signature is left
+    let entry = cache.join("abc123");
+    std::fs::write(entry.join("README"), "tampered").unwrap();
+    let forged = compute_build_hash(&entry).unwrap().0;
+    std::fs::write(
+      entry.join(BUILD_COMPLETE_MARKER),
+      format!(r#"{{"version":1,"status":"complete","output_hash":"{}"}}"#, forged),
+    )
+    .unwrap();
+    let info_path = cache.join("abc123.json");
+    let mut info: CacheInfo = serde_json::from_slice(&std::fs::read(&info_path).unwrap()).unwrap();
+    info.output_hash = forged;
+    std::fs::write(&info_path, serde_json::to_string(&info).unwrap()).unwrap();
+
+    let dest = temp.path().join("store/build/abc123");
+    assert!(!runtime().block_on(substitute(&hash, &dest, &[Substituter::Local(cache)], &trusted)));
+    assert!(!dest.exists());
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn entries_below_symlinks_are_not_written() {
+    let temp = TempDir::new().unwrap();
+    let (hash, _, cache, trusted) = push_signed(temp.path());
+    let outside = temp.path().join("outside");
+    std::fs::create_dir(&outside).unwrap();
+
+    // A symlink out of the build, then a file and a directory through it
+    let entry = cache.join("abc123");
+    std::os::unix::fs::symlink(&outside, entry.join("x")).unwrap();
+    let info_path = cache.join("abc123.json");
+    let mut info: CacheInfo = serde_json::from_slice(&std::fs::read(&info_path).unwrap()).unwrap();
+    let file = |path: &str, kind: FileKind, target: Option<&Path>| CacheFile {
+      path: path.to_string(),
+      kind,
+      executable: false,
+      target: target.map(|t| t.to_string_lossy().to_string()),
+    };
+    info.files.push(file("x", FileKind::Symlink, Some(&outside)));
+    info.files.push(file("x/authorized_keys", FileKind::File, None));
+    info.files.push(file("x/sub", FileKind::Dir, None));
+    std::fs::write(&info_path, serde_json::to_string(&info).unwrap()).unwrap();
+
+    let dest = temp.path().join("store/build/abc123");
+    assert!(!runtime().block_on(substitute(&hash, &dest, &[Substituter::Local(cache.clone())], &trusted)));
+    assert!(!dest.exists());
+    assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+
+    // Even without validation, materializing stops at the symlink
+    let staging = temp.path().join("staging");
+    std::fs::create_dir(&staging).unwrap();
+    let result = runtime().block_on(materialize(
+      &reqwest::Client::new(),
+      &Substituter::Local(cache),
+      &info,
+      &staging,
+      &trusted,
+    ));
+    assert!(result.is_err());
+    assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+  }
+
+  #[test]
+  fn untrusted_or_unsigned_builds_are_rejected() {
+    let temp = TempDir::new().unwrap();
+    let (hash, _, cache, _) = push_signed(temp.path());
+    let dest = temp.path().join("store/build/abc123");
+    let substituters = [Substituter::Local(cache.clone())];
+
+    let stranger = TrustedKeys::new(vec![SecretKey::generate("test-cache").unwrap().public_key()]);
+    assert!(!runtime().block_on(substitute(&hash, &dest, &substituters, &stranger)));
+    assert!(!runtime().block_on(substitute(&hash, &dest, &substituters, &TrustedKeys::default())));
+    assert!(!dest.exists());
+  }
+}
@@ -7,16 +7,20 @@
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
+use std::time::Instant;
 
 use mlua::prelude::*;
 use tracing::{debug, info};
 
+use crate::execute::events::{EventSender, ExecuteEvent};
 use crate::init::update_luarc_inputs;
 use crate::inputs::resolve::{ResolveError, resolve_inputs, save_lock_file_if_changed};
 use crate::inputs::{InputDecl, InputDecls, InputOverride, ResolvedInput, ResolvedInputs};
 use crate::lua::runtime;
+use crate::lua::sources::{self, SourceMap};
 use crate::manifest::Manifest;
 use crate::platform;
+use crate::util::hash::ObjectHash;
 
 /// Errors that can occur during config evaluation.
 #[derive(Debug, thiserror::Error)]
@@ -35,6 +39,8 @@ pub enum EvalError {
 pub struct EvalOptions {
   /// Allow impure Lua libs (io, os). Breaks determinism but useful for tests.
   pub impure: bool,
+  /// Where to report evaluation and input resolution events.
+  pub events: Option<EventSender>,
 }
 
 /// Evaluate a Lua configuration file and return the resulting manifest.
@@ -65,72 +71,225 @@ pub struct EvalOptions {
 /// println!("Bindings: {}", manifest.bindings.len());
 /// ```
 pub fn evaluate_config(path: &Path, options: &EvalOptions) -> Result<Manifest, EvalError> {
+  evaluate(path, options, false).map(|(manifest, _)| manifest)
+}
+
+/// Evaluate a config like [`evaluate_config`], also recording where each build
+/// and bind was registered.
+///
+/// # Returns
+/// The manifest along with the Lua file and line of every `sys.build` and
+/// `sys.bind` call that registered an object in it.
+pub fn evaluate_config_with_sources(path: &Path, options: &EvalOptions) -> Result<(Manifest, SourceMap), EvalError> {
+  evaluate(path, options, true)
+}
+
+fn evaluate(path: &Path, options: &EvalOptions, track_sources: bool) -> Result<(Manifest, SourceMap), EvalError> {
+  let Some(events) = &options.events else {
+    return evaluate_manifest(path, options, track_sources);
+  };
+
+  events.emit(ExecuteEvent::EvalStarted {
+    config: path.to_path_buf(),
+  });
+  let start = Instant::now();
+  let result = evaluate_manifest(path, options, track_sources);
+  events.emit(ExecuteEvent::EvalFinished {
+    config: path.to_path_buf(),
+    duration: start.elapsed(),
+    error: result.as_ref().err().map(ToString::to_string),
+  });
+  result
+}
+
+fn evaluate_manifest(
+  path: &Path,
+  options: &EvalOptions,
+  track_sources: bool,
+) -> Result<(Manifest, SourceMap), EvalError> {
   let manifest = Rc::new(RefCell::new(Manifest::default()));
-  let config_dir = path.parent().unwrap_or(Path::new("."));
+
+  let source_map = {
+    let lua = runtime::create_runtime(manifest.clone(), options.impure)?;
+    if track_sources {
+      sources::enable_tracking(&lua);
+    }
+    let (setup, inputs_table) = prepare_config(&lua, path, options.events.as_ref())?;
+
+    // Call root config's setup(inputs) last
+    setup.call::<()>(inputs_table)?;
+
+    sources::take_sources(&lua)
+    // lua is dropped here, releasing its references to manifest
+  };
+
+  // Now we should have the only reference to manifest
+  let manifest = Rc::try_unwrap(manifest)
+    .expect("manifest still has references")
+    .into_inner();
+  Ok((manifest, source_map))
+}
+
+/// Evaluate package expressions in the context of a config's inputs.
+///
+/// The config is loaded and its inputs are resolved and set up exactly as in
+/// [`evaluate_config`], but the root `setup` function is never called. Instead,
+/// each expression is a dotted path into the `syslua` module (for example
+/// `pkgs.cli.ripgrep`). If the value it names has a `setup` function (or is a
+/// function itself) it is called with no arguments, and the result must be a
+/// `BuildRef`.
+///
+/// # Arguments
+/// * `path` - Path to the Lua configuration file providing inputs
+/// * `packages` - Package expressions to evaluate
+/// * `options` - Evaluation options
+///
+/// # Returns
+/// The manifest of everything registered while evaluating, along with the hash
+/// of the build each expression produced (in the same order as `packages`).
+pub fn evaluate_packages(
+  path: &Path,
+  packages: &[String],
+  options: &EvalOptions,
+) -> Result<(Manifest, Vec<ObjectHash>), EvalError> {
+  let manifest = Rc::new(RefCell::new(Manifest::default()));
+  let mut hashes = Vec::with_capacity(packages.len());
 
   {
     let lua = runtime::create_runtime(manifest.clone(), options.impure)?;
-    let config = runtime::load_file(&lua, path)?;
-
-    // Config should return a table with { inputs, setup }
-    if let LuaValue::Table(config_table) = config {
-      // Get the setup function
-      let setup: LuaFunction = config_table
-        .get("setup")
-        .map_err(|_| LuaError::external("config must return a table with a 'setup' function"))?;
-
-      // Extract raw inputs table (supports both simple URLs and extended syntax)
-      let input_decls = extract_raw_inputs(&config_table)?;
-
-      // Resolve inputs (fetch git repos, resolve paths) with transitive dependencies
-      let resolved = if input_decls.is_empty() {
-        info!("no inputs to resolve");
-        None
-      } else {
-        info!(
-          count = input_decls.len(),
-          "resolving inputs with transitive dependencies"
-        );
-        let result = resolve_inputs(&input_decls, config_dir, None)?;
-
-        // Save lock file if it changed
-        save_lock_file_if_changed(&result, config_dir)?;
-
-        // Update .luarc.json with resolved input paths for LuaLS
-        let system = platform::is_elevated();
-        let input_paths: Vec<_> = result.inputs.values().map(|i| i.path.as_path()).collect();
-        update_luarc_inputs(config_dir, input_paths, system);
-
-        Some(result.inputs)
-      };
+    prepare_config(&lua, path, options.events.as_ref())?;
 
-      // Build and set package.path from all lua/ directories
-      if let Some(ref inputs) = resolved {
-        let package_path = build_package_path(config_dir, inputs);
-        set_package_path(&lua, &package_path)?;
+    for expr in packages {
+      let hash = evaluate_package_expr(&lua, expr)?;
+      debug!(expr = %expr, hash = %hash.0, "evaluated package expression");
+      hashes.push(hash);
+    }
+  }
 
-        // Call input setup() functions in dependency order
-        call_input_setups(&lua, inputs)?;
-      }
+  let manifest = Rc::try_unwrap(manifest)
+    .expect("manifest still has references")
+    .into_inner();
+  Ok((manifest, hashes))
+}
 
-      // Build Lua inputs table for setup()
-      let inputs_table = build_inputs_table(&lua, resolved.as_ref())?;
+/// Resolve a dotted `syslua` path to a build and return its hash.
+fn evaluate_package_expr(lua: &Lua, expr: &str) -> LuaResult<ObjectHash> {
+  let require: LuaFunction = lua.globals().get("require")?;
+  let mut value: LuaValue = require.call("syslua")?;
+
+  for segment in expr.split('.').filter(|s| !s.is_empty()) {
+    let LuaValue::Table(table) = value else {
+      return Err(LuaError::external(format!(
+        "invalid package expression '{}': '{}' is not a table",
+        expr, segment
+      )));
+    };
+    value = table.get(segment)?;
+  }
 
-      // Call root config's setup(inputs) last
-      setup.call::<()>(inputs_table)?;
-    } else {
-      return Err(LuaError::external("config must return a table with 'inputs' and 'setup' fields").into());
+  let value = match value {
+    LuaValue::Function(f) => f.call::<LuaValue>(())?,
+    LuaValue::Table(ref table) if table.get::<LuaValue>("hash")?.is_nil() => {
+      let setup: LuaFunction = table.get("setup").map_err(|_| {
+        LuaError::external(format!(
+          "invalid package expression '{}': expected a package with a setup function",
+          expr
+        ))
+      })?;
+      setup.call::<LuaValue>(())?
     }
+    other => other,
+  };
 
-    // lua is dropped here, releasing its references to manifest
+  match value {
+    LuaValue::Table(table) => match table.get::<Option<String>>("hash")? {
+      Some(hash) => Ok(ObjectHash(hash)),
+      None => Err(LuaError::external(format!(
+        "package expression '{}' did not produce a BuildRef",
+        expr
+      ))),
+    },
+    _ => Err(LuaError::external(format!(
+      "package expression '{}' did not produce a BuildRef",
+      expr
+    ))),
   }
+}
 
-  // Now we should have the only reference to manifest
-  Ok(
-    Rc::try_unwrap(manifest)
-      .expect("manifest still has references")
-      .into_inner(),
-  )
+/// Load a config file and prepare the Lua state for its `setup` function.
+///
+/// Resolves inputs (saving the lock file and updating `.luarc.json`), extends
+/// package.path with every input's `lua/` directory and calls each input's
+/// `setup()` in dependency order. Every input the config declares is reported
+/// to `events` once it is resolved.
+///
+/// # Returns
+/// The root config's `setup` function and the inputs table to call it with.
+pub(crate) fn prepare_config(
+  lua: &Lua,
+  path: &Path,
+  events: Option<&EventSender>,
+) -> Result<(LuaFunction, LuaTable), EvalError> {
+  let config_dir = path.parent().unwrap_or(Path::new("."));
+  let config = runtime::load_file(lua, path)?;
+
+  // Config should return a table with { inputs, setup }
+  let LuaValue::Table(config_table) = config else {
+    return Err(LuaError::external("config must return a table with 'inputs' and 'setup' fields").into());
+  };
+
+  // Get the setup function
+  let setup: LuaFunction = config_table
+    .get("setup")
+    .map_err(|_| LuaError::external("config must return a table with a 'setup' function"))?;
+
+  // Extract raw inputs table (supports both simple URLs and extended syntax)
+  let input_decls = extract_raw_inputs(&config_table)?;
+
+  // Resolve inputs (fetch git repos, resolve paths) with transitive dependencies
+  let resolved = if input_decls.is_empty() {
+    info!("no inputs to resolve");
+    None
+  } else {
+    info!(
+      count = input_decls.len(),
+      "resolving inputs with transitive dependencies"
+    );
+    let result = resolve_inputs(&input_decls, config_dir, None)?;
+    if let Some(events) = events {
+      for (name, input) in &result.inputs {
+        events.emit(ExecuteEvent::InputResolved {
+          name: name.clone(),
+          rev: input.rev.clone(),
+          path: input.path.clone(),
+        });
+      }
+    }
+
+    // Save lock file if it changed
+    save_lock_file_if_changed(&result, config_dir)?;
+
+    // Update .luarc.json with resolved input paths for LuaLS
+    let system = platform::is_elevated();
+    let input_paths: Vec<_> = result.inputs.values().map(|i| i.path.as_path()).collect();
+    update_luarc_inputs(config_dir, input_paths, system);
+
+    Some(result.inputs)
+  };
+
+  // Build and set package.path from all lua/ directories
+  if let Some(ref inputs) = resolved {
+    let package_path = build_package_path(config_dir, inputs);
+    set_package_path(lua, &package_path)?;
+
+    // Call input setup() functions in dependency order
+    call_input_setups(lua, inputs)?;
+  }
+
+  // Build Lua inputs table for setup()
+  let inputs_table = build_inputs_table(lua, resolved.as_ref())?;
+
+  Ok((setup, inputs_table))
 }
 
 /// Build package.path from all lua/ directories.
@@ -617,6 +776,62 @@ mod tests {
     Ok(())
   }
 
+  #[test]
+  fn test_evaluate_packages_skips_root_setup() -> Result<(), EvalError> {
+    let temp_dir = TempDir::new().unwrap();
+    let config_dir = temp_dir.path();
+
+    let local_input = config_dir.join("my-syslua");
+    let lua_dir = local_input.join("lua").join("syslua");
+    fs::create_dir_all(&lua_dir).unwrap();
+    fs::write(local_input.join("init.lua"), "return {}").unwrap();
+    fs::write(
+      lua_dir.join("init.lua"),
+      r#"
+        return {
+          pkgs = {
+            hello = {
+              setup = function()
+                return sys.build({
+                  id = "hello",
+                  create = function(_, ctx)
+                    return { out = ctx.out }
+                  end,
+                })
+              end,
+            },
+          },
+        }
+      "#,
+    )
+    .unwrap();
+
+    let config_path = config_dir.join("init.lua");
+    fs::write(
+      &config_path,
+      r#"
+        return {
+          inputs = {
+            syslua = "path:./my-syslua",
+          },
+          setup = function(inputs)
+            error("root setup must not run")
+          end,
+        }
+      "#,
+    )
+    .unwrap();
+
+    let (manifest, hashes) = evaluate_packages(&config_path, &["pkgs.hello".to_string()], &EvalOptions::default())?;
+    assert_eq!(hashes.len(), 1);
+    assert_eq!(manifest.builds.len(), 1);
+    assert!(manifest.builds.contains_key(&hashes[0]));
+
+    let err = evaluate_packages(&config_path, &["pkgs.missing".to_string()], &EvalOptions::default());
+    assert!(err.is_err());
+    Ok(())
+  }
+
   #[test]
   fn test_require_submodule_from_lua_dir() -> Result<(), EvalError> {
     let temp_dir = TempDir::new().unwrap();
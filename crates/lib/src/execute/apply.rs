@@ -17,6 +17,7 @@
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Instant;
 
 use serde_json::Value as JsonValue;
 use thiserror::Error;
@@ -24,22 +25,42 @@ use tokio::sync::Semaphore;
 use tokio::task::JoinSet;
 use tracing::{debug, error, info, warn};
 
-use crate::bind::execute::{apply_bind, check_bind, destroy_bind, update_bind};
+use crate::bind::execute::{BindLogs, apply_bind, check_bind, destroy_bind, update_bind};
 use crate::bind::state::{BindState, BindStateError, load_bind_state, remove_bind_state, save_bind_state};
 use crate::bind::store::bind_dir_path;
 use crate::build::store::build_dir_path;
 use crate::eval::{EvalError, EvalOptions, evaluate_config};
 use crate::execute::execute_manifest;
 use crate::manifest::Manifest;
-use crate::platform::paths::store_dir;
+use crate::platform::paths::{plans_dir, store_dir};
 use crate::snapshot::{Snapshot, SnapshotError, SnapshotStore, StateDiff, compute_diff, generate_snapshot_id};
 use crate::store_lock::{LockMode, StoreLock, StoreLockError};
-use crate::util::hash::ObjectHash;
+use crate::util::hash::{Hashable, ObjectHash};
 
 use super::dag::{DagNode, ExecutionDag};
+use super::events::ExecuteEvent;
 use super::resolver::BindCtxResolver;
 use super::types::{BindResult, BuildResult, DagResult, DriftResult, ExecuteConfig, ExecuteError};
 
+/// File name of the manifest inside a plan directory.
+pub const PLAN_MANIFEST_FILENAME: &str = "manifest.json";
+
+/// File name of the [`PlanInfo`] inside a plan directory.
+pub const PLAN_INFO_FILENAME: &str = "plan.json";
+
+/// What a plan was made from, written by `sys plan` next to its manifest.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct PlanInfo {
+  /// Hash of the plan's manifest.
+  pub hash: ObjectHash,
+
+  /// The config file the manifest was evaluated from.
+  pub config_path: Option<PathBuf>,
+
+  /// The snapshot that was current when the plan was made.
+  pub base_snapshot: Option<String>,
+}
+
 /// Type alias for restore resolver data to reduce type complexity.
 type RestoreResolverData = (HashMap<ObjectHash, BuildResult>, HashMap<ObjectHash, BindResult>);
 
@@ -80,6 +101,10 @@ pub enum ApplyError {
   #[error("execution error: {0}")]
   Execute(#[from] ExecuteError),
 
+  /// Builds or binds failed; the result holds every failure and skipped node.
+  #[error("execution failed: {} build(s) and {} bind(s) failed", .0.build_failed.len(), .0.bind_failed.len())]
+  ExecutionFailed(Box<DagResult>),
+
   /// Bind state persistence failed.
   #[error("bind state error: {0}")]
   BindState(#[from] BindStateError),
@@ -108,6 +133,25 @@ pub enum ApplyError {
     source: Box<dyn std::error::Error + Send + Sync>,
   },
 
+  /// No plan exists for the given hash or path.
+  #[error("plan not found: {0}")]
+  PlanNotFound(String),
+
+  /// The plan manifest could not be read or parsed.
+  #[error("invalid plan {path}: {message}")]
+  InvalidPlan { path: PathBuf, message: String },
+
+  /// The plan manifest does not hash to the hash it was stored under.
+  #[error("plan hash mismatch: expected {expected}, got {actual}")]
+  PlanHashMismatch { expected: String, actual: String },
+
+  /// The current snapshot changed since the plan was made.
+  #[error(
+    "plan was made against snapshot {planned}, but the current snapshot is {current}; \
+     run `sys plan` again or apply it with --force"
+  )]
+  PlanOutdated { planned: String, current: String },
+
   /// Update phase failed.
   #[error("failed to update bind {old_hash} -> {new_hash}: {source}")]
   UpdateFailed {
@@ -146,6 +190,9 @@ pub struct ApplyOptions {
 
   /// Allow impure Lua libs (io, os). Breaks determinism.
   pub impure: bool,
+
+  /// Apply a plan even if the current snapshot changed since it was made.
+  pub force: bool,
 }
 
 /// Options for the destroy operation.
@@ -158,6 +205,38 @@ pub struct DestroyOptions {
   pub dry_run: bool,
 }
 
+/// Options for the rollback operation.
+#[derive(Debug, Clone, Default)]
+pub struct RollbackOptions {
+  /// Execution configuration (parallelism, etc.)
+  pub execute: ExecuteConfig,
+
+  /// Dry run mode - compute diff against the target but don't change anything.
+  pub dry_run: bool,
+}
+
+/// Result of a rollback operation.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct RollbackResult {
+  /// The snapshot that is now current (or would be, for dry runs).
+  pub snapshot: Snapshot,
+
+  /// The snapshot that was current before the rollback, if any.
+  pub previous_snapshot_id: Option<String>,
+
+  /// Diff from the previous state to the target snapshot.
+  pub diff: StateDiff,
+
+  /// Execution result details.
+  pub execution: DagResult,
+
+  /// Number of binds that were destroyed.
+  pub binds_destroyed: usize,
+
+  /// Number of binds that were updated in place.
+  pub binds_updated: usize,
+}
+
 /// Result of a destroy operation.
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct DestroyResult {
@@ -203,15 +282,14 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
   // 1. Load current state
   let snapshot_store = SnapshotStore::default_store();
   let current_snapshot = snapshot_store.load_current()?;
-  let current_manifest = current_snapshot.as_ref().map(|s| &s.manifest);
-
-  // Capture previous snapshot ID for potential rollback
-  let previous_snapshot_id = snapshot_store.current_id()?;
 
   debug!(has_current = current_snapshot.is_some(), "loaded current state");
 
   debug!("evaluating config");
-  let eval_options = EvalOptions { impure: options.impure };
+  let eval_options = EvalOptions {
+    impure: options.impure,
+    events: options.execute.events.clone(),
+  };
   let desired_manifest = evaluate_config(config_path, &eval_options)?;
 
   debug!(
@@ -220,6 +298,146 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
     "config evaluated"
   );
 
+  apply_manifest(
+    desired_manifest,
+    Some(config_path.to_path_buf()),
+    current_snapshot,
+    &snapshot_store,
+    options,
+  )
+  .await
+}
+
+/// Apply a plan previously written by `sys plan`, without evaluating Lua.
+///
+/// `plan` may be a plan hash (looked up under `plans_dir()`), a plan directory,
+/// or a path to a plan's `manifest.json`. The manifest's hash is recomputed and
+/// must match the hash the plan was stored under, so the applied state is
+/// exactly what was reviewed. Unless `options.force` is set, the current
+/// snapshot must still be the one the plan was made against, since the plan's
+/// diff was reviewed against it. The plan is then diffed against the current
+/// snapshot and applied with the same machinery as [`apply`]; the new snapshot
+/// records the config the plan was evaluated from.
+///
+/// # Arguments
+///
+/// * `plan` - Plan hash or path
+/// * `options` - Apply options (`impure` is ignored since no Lua is run)
+///
+/// # Returns
+///
+/// An [`ApplyResult`] containing the new snapshot and execution details.
+pub async fn apply_plan(plan: &str, options: &ApplyOptions) -> Result<ApplyResult, ApplyError> {
+  info!(plan = %plan, "starting plan apply");
+
+  let (info, desired_manifest) = load_plan(plan)?;
+
+  let _lock = StoreLock::acquire(LockMode::Exclusive, "apply")?;
+
+  let snapshot_store = SnapshotStore::default_store();
+  let current_snapshot = snapshot_store.load_current()?;
+
+  let current_id = current_snapshot.as_ref().map(|s| s.id.clone());
+  if current_id != info.base_snapshot {
+    let planned = info.base_snapshot.clone().unwrap_or_else(|| "none".to_string());
+    let current = current_id.unwrap_or_else(|| "none".to_string());
+    if !options.force {
+      return Err(ApplyError::PlanOutdated { planned, current });
+    }
+    warn!(planned = %planned, current = %current, "applying a plan made against another snapshot");
+  }
+
+  debug!(
+    has_current = current_snapshot.is_some(),
+    builds = desired_manifest.builds.len(),
+    binds = desired_manifest.bindings.len(),
+    "loaded plan"
+  );
+
+  apply_manifest(
+    desired_manifest,
+    info.config_path,
+    current_snapshot,
+    &snapshot_store,
+    options,
+  )
+  .await
+}
+
+/// Load and verify a stored plan manifest.
+///
+/// Returns the plan's [`PlanInfo`] along with the parsed manifest. The manifest
+/// must hash to the hash recorded in `plan.json` and, when the plan lives in a
+/// directory named after a hash (as written by `sys plan`), to that name.
+pub fn load_plan(plan: &str) -> Result<(PlanInfo, Manifest), ApplyError> {
+  let candidate = PathBuf::from(plan);
+  let manifest_path = if candidate.is_file() {
+    candidate
+  } else if candidate.is_dir() {
+    candidate.join(PLAN_MANIFEST_FILENAME)
+  } else {
+    plans_dir().join(plan).join(PLAN_MANIFEST_FILENAME)
+  };
+
+  if !manifest_path.is_file() {
+    return Err(ApplyError::PlanNotFound(plan.to_string()));
+  }
+
+  let content = std::fs::read_to_string(&manifest_path).map_err(|e| ApplyError::InvalidPlan {
+    path: manifest_path.clone(),
+    message: e.to_string(),
+  })?;
+  let manifest: Manifest = serde_json::from_str(&content).map_err(|e| ApplyError::InvalidPlan {
+    path: manifest_path.clone(),
+    message: e.to_string(),
+  })?;
+
+  let info_path = manifest_path.with_file_name(PLAN_INFO_FILENAME);
+  let info: PlanInfo = std::fs::read_to_string(&info_path)
+    .map_err(|e| e.to_string())
+    .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()))
+    .map_err(|message| ApplyError::InvalidPlan {
+      path: info_path.clone(),
+      message,
+    })?;
+
+  let actual = manifest.compute_hash().map_err(|e| ApplyError::InvalidPlan {
+    path: manifest_path.clone(),
+    message: e.to_string(),
+  })?;
+  let dir_hash = manifest_path
+    .parent()
+    .and_then(|dir| dir.file_name())
+    .and_then(|name| name.to_str())
+    .filter(|name| name.len() == actual.0.len() && name.chars().all(|c| c.is_ascii_hexdigit()));
+  for expected in std::iter::once(info.hash.0.as_str()).chain(dir_hash) {
+    if expected != actual.0 {
+      return Err(ApplyError::PlanHashMismatch {
+        expected: expected.to_string(),
+        actual: actual.0,
+      });
+    }
+  }
+
+  debug!(path = %manifest_path.display(), hash = %actual.0, "plan verified");
+  Ok((info, manifest))
+}
+
+/// Diff a desired manifest against the current state, apply it, and record a snapshot.
+///
+/// Shared tail of [`apply`] and [`apply_plan`]; the caller holds the store lock.
+async fn apply_manifest(
+  desired_manifest: Manifest,
+  config_path: Option<PathBuf>,
+  current_snapshot: Option<Snapshot>,
+  snapshot_store: &SnapshotStore,
+  options: &ApplyOptions,
+) -> Result<ApplyResult, ApplyError> {
+  let current_manifest = current_snapshot.as_ref().map(|s| &s.manifest);
+
+  // Capture previous snapshot ID for potential rollback
+  let previous_snapshot_id = current_snapshot.as_ref().map(|s| s.id.clone());
+
   // 3. Compute diff
   let store_path = store_dir();
   let diff = compute_diff(&desired_manifest, current_manifest, &store_path);
@@ -249,14 +467,11 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
     };
 
     // Still create a snapshot to record the state
-    let snapshot = Snapshot::new(
-      generate_snapshot_id(),
-      Some(config_path.to_path_buf()),
-      desired_manifest,
-    );
+    let snapshot = Snapshot::new(generate_snapshot_id(), config_path.clone(), desired_manifest);
 
     // Save snapshot and set as current
     snapshot_store.save_and_set_current(&snapshot)?;
+    snapshot_saved(&snapshot, &options.execute);
 
     if binds_repaired > 0 {
       debug!(binds_repaired = binds_repaired, "repaired drifted binds");
@@ -276,7 +491,7 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
   if options.dry_run {
     info!("dry run - not applying changes");
     return Ok(ApplyResult {
-      snapshot: Snapshot::new("dry-run".to_string(), Some(config_path.to_path_buf()), desired_manifest),
+      snapshot: Snapshot::new("dry-run".to_string(), config_path, desired_manifest),
       diff,
       execution: DagResult::default(),
       binds_destroyed: 0,
@@ -285,15 +500,161 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
     });
   }
 
+  let transition = transition_to_manifest(
+    &desired_manifest,
+    &diff,
+    current_snapshot.as_ref(),
+    previous_snapshot_id.as_deref(),
+    snapshot_store,
+    &options.execute,
+    options.repair,
+  )
+  .await?;
+
+  // 9. Create and save snapshot
+  let snapshot = Snapshot::new(generate_snapshot_id(), config_path, desired_manifest);
+
+  snapshot_store.save_and_set_current(&snapshot)?;
+  snapshot_saved(&snapshot, &options.execute);
+  debug!(snapshot_id = %snapshot.id, binds_repaired = transition.binds_repaired, "snapshot saved");
+
+  Ok(ApplyResult {
+    snapshot,
+    diff,
+    execution: transition.execution,
+    binds_destroyed: transition.binds_destroyed,
+    binds_updated: transition.binds_updated,
+    drift_results: transition.drift_results,
+  })
+}
+
+fn snapshot_saved(snapshot: &Snapshot, config: &ExecuteConfig) {
+  if let Some(events) = &config.events {
+    events.emit(ExecuteEvent::SnapshotSaved {
+      id: snapshot.id.clone(),
+    });
+  }
+}
+
+/// Roll the system back to a previously recorded snapshot.
+///
+/// This is the main entry point for `sys rollback`. It:
+/// 1. Resolves the target snapshot by ID or tag
+/// 2. Computes the diff between the target manifest and the current state
+/// 3. Destroys, updates and applies binds exactly as `apply` would,
+///    re-realizing any builds that were garbage collected since
+/// 4. Points the current snapshot at the target (no new snapshot is created)
+///
+/// No Lua is evaluated: the target snapshot's manifest is the desired state.
+///
+/// # Arguments
+///
+/// * `target` - Snapshot ID or tag to roll back to
+/// * `options` - Rollback options
+///
+/// # Returns
+///
+/// A [`RollbackResult`] describing the transition.
+pub async fn rollback(target: &str, options: &RollbackOptions) -> Result<RollbackResult, ApplyError> {
+  info!(target = %target, dry_run = options.dry_run, "starting rollback");
+
+  let _lock = StoreLock::acquire(LockMode::Exclusive, "rollback")?;
+
+  let snapshot_store = SnapshotStore::default_store();
+  let target_snapshot = snapshot_store.resolve_snapshot(target)?;
+  let current_snapshot = snapshot_store.load_current()?;
+  let previous_snapshot_id = current_snapshot.as_ref().map(|s| s.id.clone());
+
+  let store_path = store_dir();
+  let diff = compute_diff(
+    &target_snapshot.manifest,
+    current_snapshot.as_ref().map(|s| &s.manifest),
+    &store_path,
+  );
+
+  debug!(
+    snapshot_id = %target_snapshot.id,
+    builds_to_realize = diff.builds_to_realize.len(),
+    binds_to_apply = diff.binds_to_apply.len(),
+    binds_to_update = diff.binds_to_update.len(),
+    binds_to_destroy = diff.binds_to_destroy.len(),
+    "rollback diff computed"
+  );
+
+  if options.dry_run {
+    info!("dry run - not rolling back");
+    return Ok(RollbackResult {
+      snapshot: target_snapshot,
+      previous_snapshot_id,
+      diff,
+      execution: DagResult::default(),
+      binds_destroyed: 0,
+      binds_updated: 0,
+    });
+  }
+
+  let transition = transition_to_manifest(
+    &target_snapshot.manifest,
+    &diff,
+    current_snapshot.as_ref(),
+    previous_snapshot_id.as_deref(),
+    &snapshot_store,
+    &options.execute,
+    false,
+  )
+  .await?;
+
+  snapshot_store.set_current(&target_snapshot.id)?;
+  info!(snapshot_id = %target_snapshot.id, "rolled back");
+
+  Ok(RollbackResult {
+    snapshot: target_snapshot,
+    previous_snapshot_id,
+    diff,
+    execution: transition.execution,
+    binds_destroyed: transition.binds_destroyed,
+    binds_updated: transition.binds_updated,
+  })
+}
+
+/// Outcome of moving the system from its current state to a desired manifest.
+struct Transition {
+  execution: DagResult,
+  binds_destroyed: usize,
+  binds_updated: usize,
+  binds_repaired: usize,
+  drift_results: Vec<DriftResult>,
+}
+
+/// Execute a computed diff against the system.
+///
+/// Destroys removed binds, updates modified binds, realizes builds and applies
+/// new binds, then checks (and optionally repairs) unchanged binds. If execution
+/// fails, destroyed binds are restored and the current snapshot pointer is reset
+/// to `previous_snapshot_id` (or cleared when restoring fails).
+///
+/// Snapshot creation is left to the caller.
+#[allow(clippy::too_many_arguments)]
+async fn transition_to_manifest(
+  desired_manifest: &Manifest,
+  diff: &StateDiff,
+  current_snapshot: Option<&Snapshot>,
+  previous_snapshot_id: Option<&str>,
+  snapshot_store: &SnapshotStore,
+  config: &ExecuteConfig,
+  repair: bool,
+) -> Result<Transition, ApplyError> {
+  let current_manifest = current_snapshot.map(|s| &s.manifest);
+
   // 4. Destroy removed binds (state file cleanup is deferred until success)
-  let destroyed_hashes = match destroy_removed_binds(&diff.binds_to_destroy, current_manifest, &options.execute).await {
+  let destroyed_hashes = match destroy_removed_binds(&diff.binds_to_destroy, current_manifest, config).await {
     Ok(hashes) => hashes,
     Err(destroy_err) => {
       // Partial destroy failure - restore what we destroyed
       if !destroy_err.destroyed.is_empty()
-        && let Some(ref current_snapshot) = current_snapshot
+        && let Some(current_snapshot) = current_snapshot
       {
-        let _ = restore_destroyed_binds(&destroy_err.destroyed, &current_snapshot.manifest, &options.execute).await;
+        let _ = roll_back_destroyed_binds(&destroy_err.destroyed, &current_snapshot.manifest, config).await;
       }
       return Err(ApplyError::DestroyFailed {
         hash: destroy_err.failed_hash,
@@ -303,17 +664,11 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
   };
 
   // 5. Update modified binds (no rollback on failure - just fail with error)
-  let updated_hashes = update_modified_binds(
-    &diff.binds_to_update,
-    current_manifest,
-    &desired_manifest,
-    &options.execute,
-  )
-  .await?;
+  let updated_hashes = update_modified_binds(&diff.binds_to_update, current_manifest, desired_manifest, config).await?;
 
   // 6 & 7. Build execution manifest and execute (realize builds, apply new binds)
   // Filter to only include builds that need realization and binds that need applying
-  let execution_manifest = build_execution_manifest(&desired_manifest, &diff);
+  let execution_manifest = build_execution_manifest(desired_manifest, diff);
 
   debug!(
     builds = execution_manifest.builds.len(),
@@ -321,28 +676,28 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
     "executing manifest"
   );
 
-  let dag_result = execute_manifest(&execution_manifest, &options.execute).await?;
+  let dag_result = execute_manifest(&execution_manifest, config).await?;
 
   // Check for failures
   if !dag_result.is_success() {
     // Log the failure details
     error!("execution failed");
 
-    if let Some((hash, ref err)) = dag_result.build_failed {
+    for (hash, err) in &dag_result.build_failed {
       error!(build = %hash.0, error = %err, "build failed");
     }
-    if let Some((hash, ref err)) = dag_result.bind_failed {
+    for (hash, err) in &dag_result.bind_failed {
       error!(bind = %hash.0, error = %err, "bind failed");
     }
 
     // Execution failed - restore destroyed binds
     if !destroyed_hashes.is_empty()
-      && let Some(ref current_snapshot) = current_snapshot
+      && let Some(current_snapshot) = current_snapshot
     {
-      match restore_destroyed_binds(&destroyed_hashes, &current_snapshot.manifest, &options.execute).await {
+      match roll_back_destroyed_binds(&destroyed_hashes, &current_snapshot.manifest, config).await {
         Ok(_) => {
           // Restore succeeded - point snapshot back to previous
-          if let Some(ref prev_id) = previous_snapshot_id {
+          if let Some(prev_id) = previous_snapshot_id {
             let _ = snapshot_store.set_current(prev_id);
             info!(snapshot_id = %prev_id, "restored previous snapshot");
           }
@@ -358,11 +713,7 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
       }
     }
 
-    // Return the execution error
-    return Err(ApplyError::Execute(ExecuteError::CmdFailed {
-      cmd: "apply".to_string(),
-      code: Some(1),
-    }));
+    return Err(ApplyError::ExecutionFailed(Box::new(dag_result)));
   }
 
   // Save bind state for newly applied binds
@@ -376,31 +727,20 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
   cleanup_destroyed_bind_states(&destroyed_hashes)?;
 
   // 7. Check unchanged binds for drift
-  let drift_results = check_unchanged_binds(&diff.binds_unchanged, &desired_manifest, &options.execute).await?;
+  let drift_results = check_unchanged_binds(&diff.binds_unchanged, desired_manifest, config).await?;
 
   // 8. Repair drifted binds if requested
-  let binds_repaired = if options.repair {
-    repair_drifted_binds(&drift_results, &desired_manifest, &options.execute).await?
+  let binds_repaired = if repair {
+    repair_drifted_binds(&drift_results, desired_manifest, config).await?
   } else {
     0
   };
 
-  // 9. Create and save snapshot
-  let snapshot = Snapshot::new(
-    generate_snapshot_id(),
-    Some(config_path.to_path_buf()),
-    desired_manifest,
-  );
-
-  snapshot_store.save_and_set_current(&snapshot)?;
-  debug!(snapshot_id = %snapshot.id, binds_repaired = binds_repaired, "snapshot saved");
-
-  Ok(ApplyResult {
-    snapshot,
-    diff,
+  Ok(Transition {
     execution: dag_result,
     binds_destroyed: destroyed_hashes.len(),
     binds_updated: updated_hashes.len(),
+    binds_repaired,
     drift_results,
   })
 }
@@ -415,7 +755,7 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
 ///
 /// * `hashes` - List of unchanged bind hashes to check
 /// * `manifest` - The manifest containing bind definitions
-/// * `config` - Execution configuration (currently unused but kept for consistency)
+/// * `config` - Execution configuration; drifted binds are reported to its event observer
 ///
 /// # Returns
 ///
@@ -423,7 +763,7 @@ pub async fn apply(config_path: &Path, options: &ApplyOptions) -> Result<ApplyRe
 pub async fn check_unchanged_binds(
   hashes: &[ObjectHash],
   manifest: &Manifest,
-  _config: &ExecuteConfig,
+  config: &ExecuteConfig,
 ) -> Result<Vec<DriftResult>, ApplyError> {
   if hashes.is_empty() {
     return Ok(vec![]);
@@ -457,9 +797,26 @@ pub async fn check_unchanged_binds(
 
     let resolver = BindCtxResolver::new(&empty_builds, &empty_binds, manifest, String::new());
 
-    match check_bind(hash, bind_def, &bind_result, &resolver).await {
+    match check_bind(
+      hash,
+      bind_def,
+      &bind_result,
+      &resolver,
+      Some(&BindLogs::new(config.events.as_ref())),
+    )
+    .await
+    {
       Ok(Some(result)) => {
         debug!(hash = %hash.0, drifted = result.drifted, "drift check complete");
+        if result.drifted
+          && let Some(events) = &config.events
+        {
+          events.emit(ExecuteEvent::DriftDetected {
+            hash: hash.clone(),
+            id: bind_def.id.clone(),
+            message: result.message.clone(),
+          });
+        }
         drift_results.push(DriftResult {
           hash: hash.clone(),
           id: bind_def.id.clone(),
@@ -509,6 +866,7 @@ async fn repair_drifted_binds(
     let semaphore = semaphore.clone();
     let manifest = manifest.clone();
     let hash = hash.clone();
+    let events = config.events.clone();
 
     join_set.spawn(async move {
       let _permit = semaphore.acquire().await.unwrap();
@@ -518,7 +876,7 @@ async fn repair_drifted_binds(
 
       let resolver = BindCtxResolver::new(&empty_builds, &empty_binds, &manifest, String::new());
 
-      let result = apply_bind(&hash, &bind_def, &resolver)
+      let result = apply_bind(&hash, &bind_def, &resolver, Some(&BindLogs::new(events.as_ref())))
         .await
         .map_err(ApplyError::Execute)?;
 
@@ -545,6 +903,7 @@ async fn repair_drifted_binds(
         return Err(ApplyError::Execute(ExecuteError::CmdFailed {
           cmd: "repair".to_string(),
           code: None,
+          stderr: String::new(),
         }));
       }
     }
@@ -713,7 +1072,7 @@ fn build_execution_manifest(desired: &Manifest, diff: &StateDiff) -> Manifest {
 async fn destroy_removed_binds(
   hashes: &[ObjectHash],
   current_manifest: Option<&Manifest>,
-  _config: &ExecuteConfig,
+  config: &ExecuteConfig,
 ) -> Result<Vec<ObjectHash>, DestroyPhaseError> {
   if hashes.is_empty() {
     return Ok(Vec::new());
@@ -758,6 +1117,7 @@ async fn destroy_removed_binds(
           source: ExecuteError::CmdFailed {
             cmd: format!("load bind state for {}", hash.0),
             code: None,
+            stderr: String::new(),
           },
         });
       }
@@ -787,7 +1147,30 @@ async fn destroy_removed_binds(
 
     // Execute destroy
     debug!(bind = %hash.0, destroy_actions = bind_def.destroy_actions.len(), "destroying bind");
-    if let Err(e) = destroy_bind(hash, bind_def, &bind_result, &resolver).await {
+    let node = DagNode::Bind(hash.clone());
+    let start = Instant::now();
+    if let Some(events) = &config.events {
+      events.emit(ExecuteEvent::NodeStarted {
+        node: node.clone(),
+        id: bind_def.id.clone(),
+      });
+    }
+    let destroyed_bind = destroy_bind(
+      hash,
+      bind_def,
+      &bind_result,
+      &resolver,
+      Some(&BindLogs::new(config.events.as_ref())),
+    )
+    .await;
+    if let Some(events) = &config.events {
+      events.emit(ExecuteEvent::NodeFinished {
+        node,
+        duration: start.elapsed(),
+        error: destroyed_bind.as_ref().err().map(ToString::to_string),
+      });
+    }
+    if let Err(e) = destroyed_bind {
       error!(bind = %hash.0, error = %e, "failed to destroy bind");
       return Err(DestroyPhaseError {
         destroyed,
@@ -839,7 +1222,7 @@ async fn update_modified_binds(
   updates: &[(ObjectHash, ObjectHash)],
   _current: Option<&Manifest>,
   desired: &Manifest,
-  _config: &ExecuteConfig,
+  config: &ExecuteConfig,
 ) -> Result<Vec<ObjectHash>, ApplyError> {
   if updates.is_empty() {
     return Ok(Vec::new());
@@ -865,6 +1248,7 @@ async fn update_modified_binds(
           source: ExecuteError::CmdFailed {
             cmd: format!("load bind state for {}", old_hash.0),
             code: None,
+            stderr: String::new(),
           },
         });
       }
@@ -876,6 +1260,7 @@ async fn update_modified_binds(
           source: ExecuteError::CmdFailed {
             cmd: format!("load bind state for {}", old_hash.0),
             code: None,
+            stderr: String::new(),
           },
         });
       }
@@ -892,6 +1277,7 @@ async fn update_modified_binds(
           source: ExecuteError::CmdFailed {
             cmd: format!("find bind definition for {}", new_hash.0),
             code: None,
+            stderr: String::new(),
           },
         });
       }
@@ -908,7 +1294,16 @@ async fn update_modified_binds(
 
     // Execute update
     debug!(old_hash = %old_hash.0, new_hash = %new_hash.0, "updating bind");
-    let update_result = match update_bind(old_hash, new_hash, new_bind_def, &old_bind_result, &resolver).await {
+    let update_result = match update_bind(
+      old_hash,
+      new_hash,
+      new_bind_def,
+      &old_bind_result,
+      &resolver,
+      Some(&BindLogs::new(config.events.as_ref())),
+    )
+    .await
+    {
       Ok(result) => result,
       Err(e) => {
         error!(old_hash = %old_hash.0, new_hash = %new_hash.0, error = %e, "failed to update bind");
@@ -996,6 +1391,28 @@ fn build_restore_resolver_data(manifest: &Manifest) -> Result<RestoreResolverDat
   Ok((builds, binds))
 }
 
+/// Restore binds destroyed by a failed apply, reporting it as a rollback.
+async fn roll_back_destroyed_binds(
+  destroyed_hashes: &[ObjectHash],
+  manifest: &Manifest,
+  config: &ExecuteConfig,
+) -> Result<(), ApplyError> {
+  let start = Instant::now();
+  if let Some(events) = &config.events {
+    events.emit(ExecuteEvent::RollbackStarted {
+      binds: destroyed_hashes.len(),
+    });
+  }
+  let result = restore_destroyed_binds(destroyed_hashes, manifest, config).await;
+  if let Some(events) = &config.events {
+    events.emit(ExecuteEvent::RollbackFinished {
+      duration: start.elapsed(),
+      error: result.as_ref().err().map(ToString::to_string),
+    });
+  }
+  result
+}
+
 /// Restore previously destroyed binds using DAG ordering from the manifest.
 ///
 /// Uses parallel wave execution matching the normal apply flow.
@@ -1061,13 +1478,14 @@ async fn restore_destroyed_binds(
       let completed_binds = completed_binds.clone();
       let semaphore = semaphore.clone();
       let manifest = manifest.clone();
+      let events = config.events.clone();
 
       join_set.spawn(async move {
         let _permit = semaphore.acquire().await.unwrap();
 
         let resolver = BindCtxResolver::new(&completed_builds, &completed_binds, &manifest, "/tmp".to_string());
 
-        let result = apply_bind(&hash, &bind_def, &resolver)
+        let result = apply_bind(&hash, &bind_def, &resolver, Some(&BindLogs::new(events.as_ref())))
           .await
           .map_err(|e| ApplyError::RestoreFailed {
             hash: hash.clone(),
@@ -1114,15 +1532,22 @@ async fn restore_destroyed_binds(
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::execute::{EventSender, ExecuteObserver};
   use serial_test::serial;
   use tempfile::TempDir;
 
   fn test_options() -> ApplyOptions {
     ApplyOptions {
-      execute: ExecuteConfig { parallelism: 1 },
+      execute: ExecuteConfig {
+        parallelism: 1,
+        substituters: vec![],
+        keep_going: false,
+        events: None,
+      },
       dry_run: false,
       repair: false,
       impure: false,
+      force: false,
     }
   }
 
@@ -1156,6 +1581,7 @@ mod tests {
         inputs: None,
         create_actions: vec![],
         outputs: None,
+        sandbox: None,
       },
     );
     desired.builds.insert(
@@ -1165,6 +1591,7 @@ mod tests {
         inputs: None,
         create_actions: vec![],
         outputs: None,
+        sandbox: None,
       },
     );
 
@@ -1269,6 +1696,243 @@ mod tests {
     );
   }
 
+  /// Observer keeping every event it receives.
+  #[derive(Default)]
+  struct Recorder(std::sync::Mutex<Vec<ExecuteEvent>>);
+
+  impl ExecuteObserver for Recorder {
+    fn on_event(&self, event: &ExecuteEvent) {
+      self.0.lock().unwrap().push(event.clone());
+    }
+  }
+
+  #[test]
+  #[cfg(unix)]
+  #[serial]
+  fn apply_reports_lifecycle_events_to_observer() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("init.lua");
+    std::fs::write(
+      &config_path,
+      r#"
+      return {
+        inputs = {},
+        setup = function(_)
+          sys.build({
+            id = 'hello',
+            create = function(_, ctx)
+              ctx:exec({ bin = '/bin/sh', args = { '-c', 'echo hi' } })
+              return { out = ctx.out }
+            end,
+          })
+        end,
+      }
+      "#,
+    )
+    .unwrap();
+
+    let recorder = Arc::new(Recorder::default());
+    let mut options = test_options();
+    options.execute.events = Some(EventSender::new(recorder.clone()));
+
+    let result = temp_env::with_vars(
+      [
+        ("SYSLUA_STORE", Some(temp_dir.path().join("store"))),
+        ("SYSLUA_SNAPSHOTS", Some(temp_dir.path().join("snapshots"))),
+        ("XDG_DATA_HOME", Some(temp_dir.path().join("data"))),
+      ],
+      || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(apply(&config_path, &options)).unwrap()
+      },
+    );
+
+    let events = recorder.0.lock().unwrap();
+    let names: Vec<&str> = events
+      .iter()
+      .filter_map(|event| match event {
+        ExecuteEvent::EvalStarted { .. } => Some("eval_started"),
+        ExecuteEvent::EvalFinished { error: None, .. } => Some("eval_finished"),
+        ExecuteEvent::NodeScheduled { .. } => Some("scheduled"),
+        ExecuteEvent::NodeStarted { .. } => Some("started"),
+        ExecuteEvent::NodeFinished { error: None, .. } => Some("succeeded"),
+        ExecuteEvent::SnapshotSaved { .. } => Some("snapshot_saved"),
+        _ => None,
+      })
+      .collect();
+    assert_eq!(
+      names,
+      [
+        "eval_started",
+        "eval_finished",
+        "scheduled",
+        "started",
+        "succeeded",
+        "snapshot_saved"
+      ]
+    );
+    assert_eq!(
+      events.last(),
+      Some(&ExecuteEvent::SnapshotSaved {
+        id: result.snapshot.id.clone()
+      })
+    );
+  }
+
+  /// Write a plan directory the way `sys plan` does.
+  fn write_plan(plan_dir: &Path, manifest: &Manifest, info: &PlanInfo) {
+    std::fs::create_dir_all(plan_dir).unwrap();
+    std::fs::write(
+      plan_dir.join(PLAN_MANIFEST_FILENAME),
+      serde_json::to_string(manifest).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(plan_dir.join(PLAN_INFO_FILENAME), serde_json::to_string(info).unwrap()).unwrap();
+  }
+
+  fn plan_info(manifest: &Manifest, base_snapshot: Option<&str>) -> PlanInfo {
+    PlanInfo {
+      hash: manifest.compute_hash().unwrap(),
+      config_path: Some(PathBuf::from("/etc/syslua/init.lua")),
+      base_snapshot: base_snapshot.map(str::to_string),
+    }
+  }
+
+  #[test]
+  #[serial]
+  fn load_plan_by_hash_and_path() {
+    let temp_dir = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_PLANS", Some(temp_dir.path()), || {
+      let manifest = Manifest::default();
+      let info = plan_info(&manifest, None);
+      let plan_dir = temp_dir.path().join(&info.hash.0);
+      write_plan(&plan_dir, &manifest, &info);
+
+      let (loaded_info, loaded) = load_plan(&info.hash.0).unwrap();
+      assert_eq!(loaded_info, info);
+      assert_eq!(loaded, manifest);
+
+      let (_, loaded) = load_plan(plan_dir.to_str().unwrap()).unwrap();
+      assert_eq!(loaded, manifest);
+
+      assert!(matches!(
+        load_plan("ffffffffffffffffffff"),
+        Err(ApplyError::PlanNotFound(_))
+      ));
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn load_plan_rejects_hash_mismatch() {
+    let temp_dir = TempDir::new().unwrap();
+    let plan_dir = temp_dir.path().join("0123456789abcdef0123");
+    let manifest = Manifest::default();
+    write_plan(&plan_dir, &manifest, &plan_info(&manifest, None));
+
+    let result = load_plan(plan_dir.to_str().unwrap());
+    assert!(matches!(result, Err(ApplyError::PlanHashMismatch { .. })));
+
+    // Outside a hash-named directory, the hash in plan.json is still checked
+    let plan_dir = temp_dir.path().join("reviewed");
+    let mut info = plan_info(&manifest, None);
+    info.hash = ObjectHash("0123456789abcdef0123".to_string());
+    write_plan(&plan_dir, &manifest, &info);
+
+    let result = load_plan(plan_dir.to_str().unwrap());
+    assert!(matches!(result, Err(ApplyError::PlanHashMismatch { .. })));
+  }
+
+  #[test]
+  #[serial]
+  fn load_plan_requires_plan_info() {
+    let temp_dir = TempDir::new().unwrap();
+    let plan_dir = temp_dir.path().join("reviewed");
+    let manifest = Manifest::default();
+    write_plan(&plan_dir, &manifest, &plan_info(&manifest, None));
+    std::fs::remove_file(plan_dir.join(PLAN_INFO_FILENAME)).unwrap();
+
+    let result = load_plan(plan_dir.to_str().unwrap());
+    assert!(matches!(result, Err(ApplyError::InvalidPlan { .. })), "{:?}", result);
+  }
+
+  #[test]
+  #[serial]
+  fn apply_plan_requires_the_base_snapshot() {
+    with_temp_env(|temp_dir| {
+      temp_env::with_var("SYSLUA_SNAPSHOTS", Some(temp_dir.path().join("snapshots")), || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let store = SnapshotStore::default_store();
+        store
+          .save_and_set_current(&Snapshot::new("100".to_string(), None, Manifest::default()))
+          .unwrap();
+
+        let manifest = Manifest::default();
+        let plan_dir = temp_dir.path().join("plan");
+        write_plan(&plan_dir, &manifest, &plan_info(&manifest, Some("100")));
+        let plan = plan_dir.to_str().unwrap();
+
+        let result = rt.block_on(apply_plan(plan, &test_options())).unwrap();
+        assert_eq!(
+          result.snapshot.config_path,
+          Some(PathBuf::from("/etc/syslua/init.lua")),
+          "the snapshot records the config, not the plan"
+        );
+
+        // The plan was reviewed against snapshot 100, which is no longer current
+        let result = rt.block_on(apply_plan(plan, &test_options()));
+        assert!(matches!(result, Err(ApplyError::PlanOutdated { .. })), "{:?}", result);
+
+        let options = ApplyOptions {
+          force: true,
+          ..test_options()
+        };
+        assert!(rt.block_on(apply_plan(plan, &options)).is_ok());
+      })
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn rollback_unknown_target_fails() {
+    with_temp_env(|_temp_dir| {
+      let rt = tokio::runtime::Runtime::new().unwrap();
+      let result = rt.block_on(rollback("missing", &RollbackOptions::default()));
+      assert!(matches!(result, Err(ApplyError::Snapshot(SnapshotError::NotFound(_)))));
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn rollback_sets_target_as_current() {
+    with_temp_env(|_temp_dir| {
+      let store = SnapshotStore::default_store();
+      store
+        .save_snapshot(&Snapshot::new("100".to_string(), None, Manifest::default()))
+        .unwrap();
+      store
+        .save_and_set_current(&Snapshot::new("200".to_string(), None, Manifest::default()))
+        .unwrap();
+      store.set_snapshot_tags("100", vec!["known-good".to_string()]).unwrap();
+
+      let rt = tokio::runtime::Runtime::new().unwrap();
+
+      let dry_run = RollbackOptions {
+        dry_run: true,
+        ..Default::default()
+      };
+      let result = rt.block_on(rollback("known-good", &dry_run)).unwrap();
+      assert_eq!(result.snapshot.id, "100");
+      assert_eq!(store.current_id().unwrap(), Some("200".to_string()));
+
+      let result = rt
+        .block_on(rollback("known-good", &RollbackOptions::default()))
+        .unwrap();
+      assert_eq!(result.previous_snapshot_id, Some("200".to_string()));
+      assert_eq!(store.current_id().unwrap(), Some("100".to_string()));
+    });
+  }
+
   #[test]
   #[serial]
   fn cleanup_destroyed_bind_states_removes_state_files() {
@@ -1322,6 +1986,7 @@ mod tests {
           inputs: None,
           create_actions: vec![],
           outputs: None,
+          sandbox: None,
         },
       );
 
@@ -23,7 +23,7 @@ use super::types::ExecuteError;
 /// Represents either a build or bind that needs to be executed.
 /// Used for unified wave computation where builds and binds are
 /// interleaved based on their dependencies.
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum DagNode {
   /// A build to be realized.
   Build(ObjectHash),
@@ -31,6 +31,15 @@ pub enum DagNode {
   Bind(ObjectHash),
 }
 
+impl std::fmt::Display for DagNode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DagNode::Build(hash) => write!(f, "build:{}", hash.0),
+      DagNode::Bind(hash) => write!(f, "bind:{}", hash.0),
+    }
+  }
+}
+
 /// A DAG representing build and bind dependencies for execution planning.
 ///
 /// The DAG is constructed from a manifest and provides:
@@ -134,6 +143,14 @@ impl ExecutionDag {
     Ok(())
   }
 
+  /// Get all builds and binds in topological order.
+  ///
+  /// Dependencies come before their dependents.
+  pub fn topological_nodes(&self) -> Result<Vec<DagNode>, ExecuteError> {
+    let sorted = toposort(&self.graph, None).map_err(|_| ExecuteError::CycleDetected)?;
+    Ok(sorted.into_iter().map(|idx| self.graph[idx].clone()).collect())
+  }
+
   /// Get builds in topological order.
   ///
   /// Returns build hashes in an order where dependencies come before dependents.
@@ -238,6 +255,18 @@ impl ExecutionDag {
       .collect()
   }
 
+  /// Get the given builds and all of their transitive build dependencies.
+  pub fn build_closure(&self, roots: &[ObjectHash]) -> HashSet<ObjectHash> {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<ObjectHash> = roots.to_vec();
+    while let Some(hash) = stack.pop() {
+      if seen.insert(hash.clone()) {
+        stack.extend(self.build_dependencies(&hash));
+      }
+    }
+    seen
+  }
+
   /// Get the direct bind dependencies of a build.
   pub fn bind_dependencies(&self, hash: &ObjectHash) -> Vec<ObjectHash> {
     let Some(&idx) = self.build_nodes.get(hash) else {
@@ -271,6 +300,50 @@ impl ExecutionDag {
     self.build_nodes.keys().cloned().collect()
   }
 
+  /// Get the nodes that a build or bind directly depends on.
+  pub fn dependencies(&self, node: &DagNode) -> Vec<DagNode> {
+    let idx = match node {
+      DagNode::Build(hash) => self.build_nodes.get(hash),
+      DagNode::Bind(hash) => self.bind_nodes.get(hash),
+    };
+    let Some(&idx) = idx else {
+      return Vec::new();
+    };
+
+    self
+      .graph
+      .neighbors_directed(idx, Direction::Incoming)
+      .map(|dep_idx| self.graph[dep_idx].clone())
+      .collect()
+  }
+
+  /// Get the nodes that directly depend on a build or bind.
+  pub fn dependents(&self, node: &DagNode) -> Vec<DagNode> {
+    let idx = match node {
+      DagNode::Build(hash) => self.build_nodes.get(hash),
+      DagNode::Bind(hash) => self.bind_nodes.get(hash),
+    };
+    let Some(&idx) = idx else {
+      return Vec::new();
+    };
+
+    self
+      .graph
+      .neighbors_directed(idx, Direction::Outgoing)
+      .map(|dep_idx| self.graph[dep_idx].clone())
+      .collect()
+  }
+
+  /// Get every dependency edge as `(dependency, dependent)` pairs.
+  pub fn edges(&self) -> Vec<(DagNode, DagNode)> {
+    self
+      .graph
+      .edge_indices()
+      .filter_map(|edge| self.graph.edge_endpoints(edge))
+      .map(|(from, to)| (self.graph[from].clone(), self.graph[to].clone()))
+      .collect()
+  }
+
   /// Get the number of builds in the DAG.
   pub fn build_count(&self) -> usize {
     self.build_nodes.len()
@@ -420,7 +493,7 @@ impl ExecutionDag {
 ///
 /// Returns an error if any String value contains a `${{bind:...}}` placeholder,
 /// since builds cannot depend on binds.
-fn extract_build_dependencies(inputs: &BuildInputs) -> Result<Vec<ObjectHash>, ExecuteError> {
+pub(crate) fn extract_build_dependencies(inputs: &BuildInputs) -> Result<Vec<ObjectHash>, ExecuteError> {
   let mut deps = Vec::new();
   collect_build_dependencies(inputs, &mut deps)?;
   Ok(deps)
@@ -544,8 +617,12 @@ mod tests {
         args: Some(vec![id.to_string()]),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       outputs: None,
+      sandbox: None,
     }
   }
 
@@ -559,6 +636,9 @@ mod tests {
         args: Some(vec!["test".to_string()]),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
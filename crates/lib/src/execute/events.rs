@@ -0,0 +1,188 @@
+//! Lifecycle and progress events of an apply, plan or destroy.
+//!
+//! Setting [`ExecuteConfig::events`](super::ExecuteConfig::events) (and
+//! [`EvalOptions::events`](crate::eval::EvalOptions::events) for evaluation)
+//! reports what is happening while it happens: evaluation, input resolution,
+//! every build and bind the scheduler runs, drift, rollbacks and the snapshot
+//! that is saved in the end. Events go to an [`ExecuteObserver`]; the sending
+//! half of [`event_channel`] is one, for consumers running on their own task.
+
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use super::dag::DagNode;
+use super::types::FailedDependency;
+use crate::util::hash::ObjectHash;
+
+/// The stream a line of command output was written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputStream {
+  Stdout,
+  Stderr,
+}
+
+impl OutputStream {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      OutputStream::Stdout => "stdout",
+      OutputStream::Stderr => "stderr",
+    }
+  }
+}
+
+/// Something that happened while evaluating or executing a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecuteEvent {
+  /// Evaluation of a config started.
+  EvalStarted { config: PathBuf },
+  /// Evaluation of a config finished; `error` is set if it failed.
+  EvalFinished {
+    config: PathBuf,
+    duration: Duration,
+    error: Option<String>,
+  },
+  /// An input of the config was resolved to a revision.
+  InputResolved { name: String, rev: String, path: PathBuf },
+  /// A build or bind was queued for execution.
+  NodeScheduled { node: DagNode, id: Option<String> },
+  /// A build or bind started running.
+  NodeStarted { node: DagNode, id: Option<String> },
+  /// A build or bind finished; `error` is set if it failed.
+  NodeFinished {
+    node: DagNode,
+    duration: Duration,
+    error: Option<String>,
+  },
+  /// A build or bind was skipped because a dependency failed.
+  NodeSkipped {
+    node: DagNode,
+    dependency: FailedDependency,
+  },
+  /// A running command wrote a line of output.
+  Output {
+    node: DagNode,
+    stream: OutputStream,
+    line: String,
+  },
+  /// A download made progress. `total` is the expected size, if the server
+  /// sent one.
+  Download {
+    node: DagNode,
+    url: String,
+    downloaded: u64,
+    total: Option<u64>,
+  },
+  /// The check of an unchanged bind found its state had drifted.
+  DriftDetected {
+    hash: ObjectHash,
+    id: Option<String>,
+    message: Option<String>,
+  },
+  /// Binds applied (or destroyed) by a failed run are being undone.
+  RollbackStarted { binds: usize },
+  /// A rollback finished; `error` is set if some of it could not be undone.
+  RollbackFinished { duration: Duration, error: Option<String> },
+  /// A snapshot was saved and made current.
+  SnapshotSaved { id: String },
+}
+
+/// Receives events as they happen.
+///
+/// Events are delivered from the scheduler and from the tasks running
+/// actions, so `on_event` should return quickly; hand slow work to another
+/// task (see [`event_channel`]).
+pub trait ExecuteObserver: Send + Sync {
+  fn on_event(&self, event: &ExecuteEvent);
+}
+
+impl ExecuteObserver for UnboundedSender<ExecuteEvent> {
+  /// Events are dropped once the receiver is gone; progress reporting never
+  /// fails an execution.
+  fn on_event(&self, event: &ExecuteEvent) {
+    let _ = self.send(event.clone());
+  }
+}
+
+impl<T: ExecuteObserver + ?Sized> ExecuteObserver for Arc<T> {
+  fn on_event(&self, event: &ExecuteEvent) {
+    (**self).on_event(event);
+  }
+}
+
+/// Handle to the observer events are sent to.
+#[derive(Clone)]
+pub struct EventSender(Arc<dyn ExecuteObserver>);
+
+impl fmt::Debug for EventSender {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str("EventSender")
+  }
+}
+
+impl EventSender {
+  pub fn new(observer: impl ExecuteObserver + 'static) -> Self {
+    Self(Arc::new(observer))
+  }
+
+  pub fn emit(&self, event: ExecuteEvent) {
+    self.0.on_event(&event);
+  }
+
+  /// A sender passing every event to both `self` and `other`.
+  pub fn tee(self, other: EventSender) -> Self {
+    Self::new(Tee(self, other))
+  }
+
+  /// Events of a single build or bind.
+  pub fn for_node(&self, node: DagNode) -> NodeEvents {
+    NodeEvents {
+      sender: self.clone(),
+      node,
+    }
+  }
+}
+
+/// Create a channel for execution events.
+pub fn event_channel() -> (EventSender, UnboundedReceiver<ExecuteEvent>) {
+  let (tx, rx) = mpsc::unbounded_channel();
+  (EventSender::new(tx), rx)
+}
+
+struct Tee(EventSender, EventSender);
+
+impl ExecuteObserver for Tee {
+  fn on_event(&self, event: &ExecuteEvent) {
+    self.0.0.on_event(event);
+    self.1.0.on_event(event);
+  }
+}
+
+/// An [`EventSender`] tied to the build or bind whose actions are running.
+#[derive(Debug, Clone)]
+pub struct NodeEvents {
+  sender: EventSender,
+  node: DagNode,
+}
+
+impl NodeEvents {
+  pub fn output(&self, stream: OutputStream, line: &str) {
+    self.sender.emit(ExecuteEvent::Output {
+      node: self.node.clone(),
+      stream,
+      line: line.to_string(),
+    });
+  }
+
+  pub fn download(&self, url: &str, downloaded: u64, total: Option<u64>) {
+    self.sender.emit(ExecuteEvent::Download {
+      node: self.node.clone(),
+      url: url.to_string(),
+      downloaded,
+      total,
+    });
+  }
+}
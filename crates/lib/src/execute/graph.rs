@@ -0,0 +1,335 @@
+//! Dependency graph export.
+//!
+//! Turns the [`ExecutionDag`] of a manifest into a [`DependencyGraph`] whose
+//! nodes carry their `id`, hash and [`StateDiff`] category, and renders it as
+//! Graphviz DOT or Mermaid. The graph itself serializes to JSON.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use crate::manifest::Manifest;
+use crate::snapshot::StateDiff;
+use crate::util::hash::ObjectHash;
+
+use super::dag::{DagNode, ExecutionDag};
+use super::types::ExecuteError;
+
+/// Number of hash characters shown in node labels.
+const SHORT_HASH_LEN: usize = 12;
+
+/// Whether a node is a build or a bind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+  Build,
+  Bind,
+}
+
+/// What applying the manifest would do with a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeState {
+  /// Build not yet in the store.
+  ToRealize,
+  /// Build already in the store.
+  Cached,
+  /// Bind that will be applied.
+  ToApply,
+  /// Bind that will be updated in place.
+  ToUpdate,
+  /// Bind that will be destroyed.
+  ToDestroy,
+  /// Bind that is already applied.
+  Unchanged,
+}
+
+impl NodeState {
+  /// Fill colour used when rendering.
+  fn color(self) -> &'static str {
+    match self {
+      NodeState::ToRealize => "#fde68a",
+      NodeState::Cached => "#e5e7eb",
+      NodeState::ToApply => "#bbf7d0",
+      NodeState::ToUpdate => "#bfdbfe",
+      NodeState::ToDestroy => "#fecaca",
+      NodeState::Unchanged => "#f3f4f6",
+    }
+  }
+
+  fn as_str(self) -> &'static str {
+    match self {
+      NodeState::ToRealize => "to_realize",
+      NodeState::Cached => "cached",
+      NodeState::ToApply => "to_apply",
+      NodeState::ToUpdate => "to_update",
+      NodeState::ToDestroy => "to_destroy",
+      NodeState::Unchanged => "unchanged",
+    }
+  }
+}
+
+/// A build or bind in the graph.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphNode {
+  pub kind: NodeKind,
+  pub hash: ObjectHash,
+  pub id: Option<String>,
+  pub state: NodeState,
+}
+
+impl GraphNode {
+  /// Stable node key, unique across builds and binds.
+  pub fn key(&self) -> String {
+    node_key(self.kind, &self.hash)
+  }
+
+  /// Human-readable label: the `id` (or kind) followed by the short hash.
+  pub fn label(&self) -> String {
+    let name = match (&self.id, self.kind) {
+      (Some(id), _) => id.as_str(),
+      (None, NodeKind::Build) => "build",
+      (None, NodeKind::Bind) => "bind",
+    };
+    let len = self.hash.0.len().min(SHORT_HASH_LEN);
+    format!("{}\n{}", name, &self.hash.0[..len])
+  }
+}
+
+/// A dependency edge; `from` must be done before `to`.
+#[derive(Debug, Clone, Serialize)]
+pub struct GraphEdge {
+  pub from: String,
+  pub to: String,
+}
+
+/// The dependency graph of a manifest.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DependencyGraph {
+  pub nodes: Vec<GraphNode>,
+  pub edges: Vec<GraphEdge>,
+}
+
+impl DependencyGraph {
+  /// Build the graph of `manifest`, categorising nodes by `diff`.
+  ///
+  /// Binds the diff would destroy are not part of `manifest`; they are added
+  /// from `current` as nodes without edges.
+  ///
+  /// # Arguments
+  ///
+  /// * `manifest` - The desired manifest
+  /// * `current` - The manifest of the current snapshot, if any
+  /// * `diff` - Diff between `current` and `manifest`
+  pub fn from_manifest(
+    manifest: &Manifest,
+    current: Option<&Manifest>,
+    diff: &StateDiff,
+  ) -> Result<Self, ExecuteError> {
+    let dag = ExecutionDag::from_manifest(manifest)?;
+
+    let mut states: HashMap<&ObjectHash, NodeState> = HashMap::new();
+    states.extend(diff.builds_to_realize.iter().map(|h| (h, NodeState::ToRealize)));
+    states.extend(diff.builds_cached.iter().map(|h| (h, NodeState::Cached)));
+    states.extend(diff.binds_to_apply.iter().map(|h| (h, NodeState::ToApply)));
+    states.extend(diff.binds_to_update.iter().map(|(_, new)| (new, NodeState::ToUpdate)));
+    states.extend(diff.binds_unchanged.iter().map(|h| (h, NodeState::Unchanged)));
+
+    let mut graph = DependencyGraph::default();
+    for (hash, def) in &manifest.builds {
+      graph.nodes.push(GraphNode {
+        kind: NodeKind::Build,
+        hash: hash.clone(),
+        id: def.id.clone(),
+        state: states.get(hash).copied().unwrap_or(NodeState::ToRealize),
+      });
+    }
+    for (hash, def) in &manifest.bindings {
+      graph.nodes.push(GraphNode {
+        kind: NodeKind::Bind,
+        hash: hash.clone(),
+        id: def.id.clone(),
+        state: states.get(hash).copied().unwrap_or(NodeState::ToApply),
+      });
+    }
+    for hash in &diff.binds_to_destroy {
+      graph.nodes.push(GraphNode {
+        kind: NodeKind::Bind,
+        hash: hash.clone(),
+        id: current
+          .and_then(|m| m.bindings.get(hash))
+          .and_then(|def| def.id.clone()),
+        state: NodeState::ToDestroy,
+      });
+    }
+
+    let mut edges: Vec<GraphEdge> = dag
+      .edges()
+      .into_iter()
+      .map(|(from, to)| GraphEdge {
+        from: dag_node_key(&from),
+        to: dag_node_key(&to),
+      })
+      .collect();
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    graph.edges = edges;
+
+    Ok(graph)
+  }
+
+  /// Render the graph as Graphviz DOT.
+  pub fn to_dot(&self) -> String {
+    let mut out = String::new();
+    out.push_str("digraph syslua {\n");
+    out.push_str("  rankdir=LR;\n");
+    out.push_str("  node [style=\"rounded,filled\", fontname=\"monospace\"];\n");
+    for node in &self.nodes {
+      let shape = match node.kind {
+        NodeKind::Build => "box",
+        NodeKind::Bind => "ellipse",
+      };
+      let _ = writeln!(
+        out,
+        "  \"{}\" [label=\"{}\", shape={}, fillcolor=\"{}\", tooltip=\"{}\"];",
+        node.key(),
+        escape_dot(&node.label()),
+        shape,
+        node.state.color(),
+        node.state.as_str()
+      );
+    }
+    for edge in &self.edges {
+      let _ = writeln!(out, "  \"{}\" -> \"{}\";", edge.from, edge.to);
+    }
+    out.push_str("}\n");
+    out
+  }
+
+  /// Render the graph as a Mermaid flowchart.
+  pub fn to_mermaid(&self) -> String {
+    let mut out = String::new();
+    out.push_str("flowchart LR\n");
+    for node in &self.nodes {
+      let label = node.label().replace('\n', "<br/>").replace('"', "#quot;");
+      let (open, close) = match node.kind {
+        NodeKind::Build => ("[", "]"),
+        NodeKind::Bind => ("([", "])"),
+      };
+      let _ = writeln!(
+        out,
+        "  {}{}\"{}\"{}:::{}",
+        node.key(),
+        open,
+        label,
+        close,
+        node.state.as_str()
+      );
+    }
+    for edge in &self.edges {
+      let _ = writeln!(out, "  {} --> {}", edge.from, edge.to);
+    }
+    for state in [
+      NodeState::ToRealize,
+      NodeState::Cached,
+      NodeState::ToApply,
+      NodeState::ToUpdate,
+      NodeState::ToDestroy,
+      NodeState::Unchanged,
+    ] {
+      let _ = writeln!(out, "  classDef {} fill:{}", state.as_str(), state.color());
+    }
+    out
+  }
+}
+
+fn node_key(kind: NodeKind, hash: &ObjectHash) -> String {
+  match kind {
+    NodeKind::Build => format!("build_{}", hash.0),
+    NodeKind::Bind => format!("bind_{}", hash.0),
+  }
+}
+
+fn dag_node_key(node: &DagNode) -> String {
+  match node {
+    DagNode::Build(hash) => node_key(NodeKind::Build, hash),
+    DagNode::Bind(hash) => node_key(NodeKind::Bind, hash),
+  }
+}
+
+/// Escape a string for use inside a quoted DOT attribute.
+fn escape_dot(s: &str) -> String {
+  s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bind::{BindDef, BindInputsDef};
+  use crate::build::BuildDef;
+
+  fn test_manifest() -> Manifest {
+    let mut manifest = Manifest::default();
+    manifest.builds.insert(
+      ObjectHash("aaaa".to_string()),
+      BuildDef {
+        id: Some("tool".to_string()),
+        inputs: None,
+        create_actions: vec![],
+        outputs: None,
+        sandbox: None,
+      },
+    );
+    manifest.bindings.insert(
+      ObjectHash("bbbb".to_string()),
+      BindDef {
+        id: None,
+        inputs: Some(BindInputsDef::Build(ObjectHash("aaaa".to_string()))),
+        outputs: None,
+        create_actions: vec![],
+        update_actions: None,
+        destroy_actions: vec![],
+        check_actions: None,
+        check_outputs: None,
+      },
+    );
+    manifest
+  }
+
+  fn test_graph() -> DependencyGraph {
+    let manifest = test_manifest();
+    let diff = StateDiff {
+      builds_cached: vec![ObjectHash("aaaa".to_string())],
+      binds_to_apply: vec![ObjectHash("bbbb".to_string())],
+      ..Default::default()
+    };
+    DependencyGraph::from_manifest(&manifest, None, &diff).unwrap()
+  }
+
+  #[test]
+  fn graph_has_nodes_edges_and_states() {
+    let graph = test_graph();
+
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.nodes[0].state, NodeState::Cached);
+    assert_eq!(graph.nodes[1].state, NodeState::ToApply);
+    assert_eq!(graph.edges.len(), 1);
+    assert_eq!(graph.edges[0].from, "build_aaaa");
+    assert_eq!(graph.edges[0].to, "bind_bbbb");
+  }
+
+  #[test]
+  fn render_dot_and_mermaid() {
+    let graph = test_graph();
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph syslua {"));
+    assert!(dot.contains("\"build_aaaa\" [label=\"tool\\naaaa\", shape=box"));
+    assert!(dot.contains("\"build_aaaa\" -> \"bind_bbbb\";"));
+
+    let mermaid = graph.to_mermaid();
+    assert!(mermaid.starts_with("flowchart LR\n"));
+    assert!(mermaid.contains("build_aaaa[\"tool<br/>aaaa\"]:::cached"));
+    assert!(mermaid.contains("build_aaaa --> bind_bbbb"));
+  }
+}
@@ -3,48 +3,76 @@
 //! This module provides the main entry points for executing builds and binds from a manifest.
 //! It handles:
 //! - DAG-based dependency ordering
-//! - Parallel execution of independent nodes
+//! - Parallel execution of independent nodes, longest path first
 //! - Failure propagation and skip tracking
 //! - Atomic rollback of binds on failure
 
 pub mod apply;
 pub mod dag;
+pub mod events;
+pub mod graph;
+pub mod realize;
 pub mod resolver;
+pub mod schedule;
 pub mod types;
+pub mod why;
 
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use tokio::sync::Semaphore;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::{self, JoinSet};
 use tracing::{debug, error, info, warn};
 
 use crate::{
-  bind::execute::{apply_bind, destroy_bind},
+  bind::execute::{BindLogs, apply_bind, destroy_bind},
   manifest::Manifest,
   util::hash::ObjectHash,
 };
 
 use dag::DagNode;
 use resolver::BindCtxResolver;
+use schedule::BuildTimes;
 
 pub use apply::{
-  ApplyError, ApplyOptions, ApplyResult, DestroyOptions, DestroyResult, apply, check_unchanged_binds, destroy,
+  ApplyError, ApplyOptions, ApplyResult, DestroyOptions, DestroyResult, PLAN_INFO_FILENAME, PLAN_MANIFEST_FILENAME,
+  PlanInfo, RollbackOptions, RollbackResult, apply, apply_plan, check_unchanged_binds, destroy, load_plan, rollback,
 };
 pub use dag::ExecutionDag;
+pub use events::{EventSender, ExecuteEvent, ExecuteObserver, NodeEvents, OutputStream, event_channel};
+pub use graph::{DependencyGraph, GraphEdge, GraphNode, NodeKind, NodeState};
+pub use realize::{
+  BuildFailure, BuildSelector, RealizeError, RealizeOptions, RealizeResult, RealizedBuild, realize, select_build,
+};
 pub use types::{BindResult, BuildResult, DagResult, ExecuteConfig, ExecuteError, FailedDependency};
+pub use why::{MAX_WHY_PATHS, WhyError, WhyNode, WhyResult, explain, find_node};
 
-/// Type alias for build task JoinSet to reduce complexity.
-type BuildJoinSet = tokio::task::JoinSet<Result<(ObjectHash, Result<BuildResult, ExecuteError>), ExecuteError>>;
+/// Outcome of running one node, returned by its task.
+enum NodeOutcome {
+  Build(ObjectHash, Result<BuildResult, ExecuteError>),
+  Bind(ObjectHash, Result<BindResult, ExecuteError>),
+}
 
-/// Type alias for bind task JoinSet to reduce complexity.
-type BindJoinSet = tokio::task::JoinSet<Result<(ObjectHash, Result<BindResult, ExecuteError>), ExecuteError>>;
+/// Which nodes a scheduler run executes, and how.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunMode {
+  /// Builds only, realized with [`crate::build::execute::realize_build`].
+  /// A failure never stops independent builds.
+  Builds,
+  /// Builds and binds. A failure stops new work unless
+  /// [`ExecuteConfig::keep_going`] is set.
+  Manifest,
+}
 
 /// Execute all builds in a manifest.
 ///
 /// This is the main entry point for build execution. It:
 /// 1. Constructs a DAG from the manifest
-/// 2. Computes parallel execution waves
-/// 3. Executes builds wave by wave, with parallelism within each wave
-/// 4. Tracks failures and skips dependent builds
+/// 2. Starts each build as soon as its dependencies are realized, taking
+///    builds on the longest remaining path first (see [`schedule`])
+/// 3. Tracks failures and skips dependent builds
 ///
 /// # Arguments
 ///
@@ -57,76 +85,18 @@ type BindJoinSet = tokio::task::JoinSet<Result<(ObjectHash, Result<BindResult, E
 pub async fn execute_builds(manifest: &Manifest, config: &ExecuteConfig) -> Result<DagResult, ExecuteError> {
   info!(build_count = manifest.builds.len(), "starting build execution");
 
-  // Build the execution DAG
   let dag = ExecutionDag::from_manifest(manifest)?;
+  let nodes: Vec<DagNode> = dag
+    .topological_nodes()?
+    .into_iter()
+    .filter(|node| matches!(node, DagNode::Build(_)))
+    .collect();
 
-  // Get execution waves
-  let waves = dag.build_waves()?;
-
-  debug!(wave_count = waves.len(), "computed execution waves");
-
-  // Track results
-  let mut result = DagResult::default();
-  let mut failed_builds: HashSet<ObjectHash> = HashSet::new();
-
-  // Create semaphore for parallelism control
-  let semaphore = std::sync::Arc::new(Semaphore::new(config.parallelism));
-
-  // Execute waves in order
-  for (wave_idx, wave) in waves.iter().enumerate() {
-    debug!(wave = wave_idx, builds = wave.len(), "executing wave");
-
-    // Partition wave into ready and skipped
-    let mut ready_builds = Vec::new();
-    let mut wave_skipped = Vec::new();
-
-    for hash in wave {
-      // Check if any dependency failed
-      let deps = dag.build_dependencies(hash);
-      let failed_dep = deps.iter().find(|dep| failed_builds.contains(dep));
-
-      if let Some(failed_dep) = failed_dep {
-        wave_skipped.push((hash.clone(), FailedDependency::Build(failed_dep.clone())));
-      } else {
-        ready_builds.push(hash.clone());
-      }
-    }
-
-    // Record skipped builds
-    for (hash, failed_dep) in wave_skipped {
-      warn!(
-        build = %hash.0,
-        failed_dep = %failed_dep,
-        "skipping build due to failed dependency"
-      );
-      result.build_skipped.insert(hash.clone(), failed_dep);
-      failed_builds.insert(hash);
-    }
-
-    // Execute ready builds in parallel
-    if !ready_builds.is_empty() {
-      let wave_results = execute_wave(&ready_builds, manifest, config, &result.realized, semaphore.clone()).await;
-
-      // Process results
-      for (hash, build_result) in wave_results {
-        match build_result {
-          Ok(br) => {
-            debug!(build = %hash.0, "build succeeded");
-            result.realized.insert(hash, br);
-          }
-          Err(e) => {
-            error!(build = %hash.0, error = %e, "build failed");
-            failed_builds.insert(hash.clone());
-            result.build_failed = Some((hash, e));
-          }
-        }
-      }
-    }
-  }
+  let (result, _) = run_dag(&dag, nodes, manifest, config, RunMode::Builds).await;
 
   info!(
     realized = result.realized.len(),
-    failed = result.build_failed.is_some(),
+    failed = result.build_failed.len(),
     skipped = result.build_skipped.len(),
     "build execution complete"
   );
@@ -138,10 +108,10 @@ pub async fn execute_builds(manifest: &Manifest, config: &ExecuteConfig) -> Resu
 ///
 /// This is the main entry point for unified execution. It:
 /// 1. Constructs a DAG from the manifest
-/// 2. Computes unified execution waves (interleaved builds and binds)
-/// 3. Executes nodes wave by wave, with parallelism within each wave
-/// 4. Tracks failures and skips dependent nodes
-/// 5. On any failure, rolls back all successfully applied binds
+/// 2. Starts each build or bind as soon as its dependencies finish, taking
+///    nodes on the longest remaining path first (see [`schedule`])
+/// 3. Tracks failures and skips dependent nodes
+/// 4. On any failure, rolls back all successfully applied binds
 ///
 /// # Arguments
 ///
@@ -155,10 +125,15 @@ pub async fn execute_builds(manifest: &Manifest, config: &ExecuteConfig) -> Resu
 /// # Rollback Behavior
 ///
 /// If any build or bind fails:
+/// - No new nodes are started; nodes already running are allowed to finish
 /// - All already-completed builds remain (they're immutable in the store)
 /// - All already-applied binds are destroyed in reverse order
 /// - The failed node is recorded in `build_failed` or `bind_failed`
 /// - Dependent nodes are recorded in `build_skipped` or `bind_skipped`
+///
+/// With [`ExecuteConfig::keep_going`], execution continues past failures so
+/// that independent branches still run, and the rollback happens once every
+/// node is done.
 pub async fn execute_manifest(manifest: &Manifest, config: &ExecuteConfig) -> Result<DagResult, ExecuteError> {
   info!(
     build_count = manifest.builds.len(),
@@ -166,149 +141,242 @@ pub async fn execute_manifest(manifest: &Manifest, config: &ExecuteConfig) -> Re
     "starting manifest execution"
   );
 
-  // Build the execution DAG
   let dag = ExecutionDag::from_manifest(manifest)?;
+  let nodes = dag.topological_nodes()?;
 
-  // Get unified execution waves
-  let waves = dag.execution_waves()?;
+  let (result, applied_order) = run_dag(&dag, nodes, manifest, config, RunMode::Manifest).await;
 
-  debug!(wave_count = waves.len(), "computed execution waves");
+  if !result.is_success() {
+    rollback_binds(&applied_order, &result.applied, manifest, config).await;
+  }
 
-  // Track results
-  let mut result = DagResult::default();
-  let mut failed_nodes: HashSet<DagNode> = HashSet::new();
+  info!(
+    realized = result.realized.len(),
+    applied = result.applied.len(),
+    build_failed = result.build_failed.len(),
+    bind_failed = result.bind_failed.len(),
+    build_skipped = result.build_skipped.len(),
+    bind_skipped = result.bind_skipped.len(),
+    "manifest execution complete"
+  );
 
-  // Track applied binds in order for rollback
-  let mut applied_binds_order: Vec<ObjectHash> = Vec::new();
+  Ok(result)
+}
 
-  // Create semaphore for parallelism control
-  let semaphore = std::sync::Arc::new(Semaphore::new(config.parallelism));
+/// The ID a build or bind was given in the config, if any.
+fn node_id(manifest: &Manifest, node: &DagNode) -> Option<String> {
+  match node {
+    DagNode::Build(hash) => manifest.builds.get(hash).and_then(|def| def.id.clone()),
+    DagNode::Bind(hash) => manifest.bindings.get(hash).and_then(|def| def.id.clone()),
+  }
+}
 
-  // Execute waves in order
-  'waves: for (wave_idx, wave) in waves.iter().enumerate() {
-    debug!(wave = wave_idx, nodes = wave.len(), "executing wave");
+/// Run `nodes` as soon as their dependencies finish.
+///
+/// Ready nodes wait for a free slot (`config.parallelism` in total) and start
+/// in order of their critical-path priority. A node whose dependency failed or
+/// was skipped is skipped itself, recording that dependency. Durations of
+/// builds whose actions ran are recorded for future priorities.
+///
+/// # Arguments
+///
+/// * `dag` - The execution DAG
+/// * `nodes` - The nodes to run, in topological order
+/// * `manifest` - The manifest holding the definitions
+/// * `config` - Execution configuration
+/// * `mode` - Whether binds are run and whether failures stop new work
+///
+/// # Returns
+///
+/// The execution result and the applied binds in completion order, for rollback.
+async fn run_dag(
+  dag: &ExecutionDag,
+  nodes: Vec<DagNode>,
+  manifest: &Manifest,
+  config: &ExecuteConfig,
+  mode: RunMode,
+) -> (DagResult, Vec<ObjectHash>) {
+  let mut times = BuildTimes::load();
+  let priorities = schedule::critical_path(dag, &nodes, manifest, &times);
+  let scheduled: HashSet<&DagNode> = nodes.iter().collect();
+
+  // Number of unfinished dependencies of each node
+  let mut waiting: HashMap<DagNode, usize> = nodes
+    .iter()
+    .map(|node| {
+      let count = dag
+        .dependencies(node)
+        .iter()
+        .filter(|dep| scheduled.contains(dep))
+        .count();
+      (node.clone(), count)
+    })
+    .collect();
+  let mut ready: BinaryHeap<(u64, Reverse<DagNode>)> = nodes
+    .iter()
+    .filter(|node| waiting[*node] == 0)
+    .map(|node| (priorities[node], Reverse(node.clone())))
+    .collect();
+
+  debug!(nodes = nodes.len(), ready = ready.len(), "scheduling nodes");
+  if let Some(events) = &config.events {
+    for node in &nodes {
+      events.emit(ExecuteEvent::NodeScheduled {
+        node: node.clone(),
+        id: node_id(manifest, node),
+      });
+    }
+  }
 
-    // Separate builds and binds in this wave
-    let mut ready_builds = Vec::new();
-    let mut ready_binds = Vec::new();
-    let mut skipped_builds = Vec::new();
-    let mut skipped_binds = Vec::new();
+  let shared_manifest = Arc::new(manifest.clone());
+  let semaphore = Arc::new(Semaphore::new(config.parallelism.max(1)));
+  let mut running: JoinSet<(NodeOutcome, Duration)> = JoinSet::new();
+  let mut tasks: HashMap<task::Id, DagNode> = HashMap::new();
 
-    for node in wave {
-      // Check if any dependency failed
-      let failed_dep = find_failed_dependency(node, &dag, &failed_nodes);
+  let mut result = DagResult::default();
+  let mut failed_nodes: HashSet<DagNode> = HashSet::new();
+  let mut applied_order: Vec<ObjectHash> = Vec::new();
+  let mut stopped = false;
+  let mut timed = false;
+
+  loop {
+    // Start ready nodes while there are free slots
+    while !stopped && !ready.is_empty() {
+      let Ok(permit) = semaphore.clone().try_acquire_owned() else {
+        break;
+      };
+      let Some((priority, Reverse(node))) = ready.pop() else {
+        break;
+      };
+      debug!(node = %node, priority, "starting node");
+      if let Some(events) = &config.events {
+        events.emit(ExecuteEvent::NodeStarted {
+          node: node.clone(),
+          id: node_id(manifest, &node),
+        });
+      }
+      let handle = running.spawn(run_node(
+        node.clone(),
+        mode,
+        shared_manifest.clone(),
+        config.clone(),
+        result.realized.clone(),
+        result.applied.clone(),
+        permit,
+      ));
+      tasks.insert(handle.id(), node);
+    }
 
-      if let Some(dep) = failed_dep {
-        match node {
-          DagNode::Build(hash) => skipped_builds.push((hash.clone(), dep)),
-          DagNode::Bind(hash) => skipped_binds.push((hash.clone(), dep)),
-        }
-      } else {
-        match node {
-          DagNode::Build(hash) => ready_builds.push(hash.clone()),
-          DagNode::Bind(hash) => ready_binds.push(hash.clone()),
+    let Some(joined) = running.join_next_with_id().await else {
+      break;
+    };
+    let (node, outcome, elapsed) = match joined {
+      Ok((id, (outcome, elapsed))) => {
+        let node = tasks.remove(&id).expect("finished task was spawned by the scheduler");
+        if let NodeOutcome::Build(hash, Ok(build)) = &outcome
+          && !build.action_results.is_empty()
+          && let Some(def) = manifest.builds.get(hash)
+        {
+          times.record(hash, def, elapsed);
+          timed = true;
         }
+        (node, outcome, elapsed)
+      }
+      Err(e) => {
+        let node = tasks
+          .remove(&e.id())
+          .expect("finished task was spawned by the scheduler");
+        let err = ExecuteError::TaskFailed { message: e.to_string() };
+        let outcome = match &node {
+          DagNode::Build(hash) => NodeOutcome::Build(hash.clone(), Err(err)),
+          DagNode::Bind(hash) => NodeOutcome::Bind(hash.clone(), Err(err)),
+        };
+        (node, outcome, Duration::ZERO)
       }
+    };
+
+    if let Some(events) = &config.events {
+      let error = match &outcome {
+        NodeOutcome::Build(_, Err(e)) | NodeOutcome::Bind(_, Err(e)) => Some(e.to_string()),
+        _ => None,
+      };
+      events.emit(ExecuteEvent::NodeFinished {
+        node: node.clone(),
+        duration: elapsed,
+        error,
+      });
     }
 
-    // Record skipped nodes
-    for (hash, failed_dep) in skipped_builds {
-      warn!(
-        build = %hash.0,
-        failed_dep = %failed_dep,
-        "skipping build due to failed dependency"
-      );
-      failed_nodes.insert(DagNode::Build(hash.clone()));
-      result.build_skipped.insert(hash, failed_dep);
+    match outcome {
+      NodeOutcome::Build(hash, Ok(br)) => {
+        debug!(build = %hash.0, "build succeeded");
+        result.realized.insert(hash, br);
+      }
+      NodeOutcome::Build(hash, Err(e)) => {
+        error!(build = %hash.0, error = %e, "build failed");
+        failed_nodes.insert(node.clone());
+        result.build_failed.insert(hash, e);
+      }
+      NodeOutcome::Bind(hash, Ok(br)) => {
+        debug!(bind = %hash.0, "bind succeeded");
+        applied_order.push(hash.clone());
+        result.applied.insert(hash, br);
+      }
+      NodeOutcome::Bind(hash, Err(e)) => {
+        error!(bind = %hash.0, error = %e, "bind failed");
+        failed_nodes.insert(node.clone());
+        result.bind_failed.insert(hash, e);
+      }
     }
 
-    for (hash, failed_dep) in skipped_binds {
-      warn!(
-        bind = %hash.0,
-        failed_dep = %failed_dep,
-        "skipping bind due to failed dependency"
-      );
-      failed_nodes.insert(DagNode::Bind(hash.clone()));
-      result.bind_skipped.insert(hash, failed_dep);
+    if failed_nodes.contains(&node) && mode == RunMode::Manifest && !config.keep_going {
+      stopped = true;
     }
 
-    // Execute ready builds in parallel
-    if !ready_builds.is_empty() {
-      let build_results = execute_build_wave(
-        &ready_builds,
-        manifest,
-        config,
-        &result.realized,
-        &result.applied,
-        semaphore.clone(),
-      )
-      .await;
-
-      // Process build results
-      for (hash, build_result) in build_results {
-        match build_result {
-          Ok(br) => {
-            debug!(build = %hash.0, "build succeeded");
-            result.realized.insert(hash, br);
-          }
-          Err(e) => {
-            error!(build = %hash.0, error = %e, "build failed");
-            failed_nodes.insert(DagNode::Build(hash.clone()));
-            result.build_failed = Some((hash, e));
-
-            // Trigger rollback and stop
-            rollback_binds(&applied_binds_order, &result.applied, manifest, config).await;
-            break 'waves;
-          }
+    // Release dependents, skipping those that can no longer run
+    let mut finished = vec![node];
+    while let Some(done) = finished.pop() {
+      for dependent in dag.dependents(&done) {
+        let Some(count) = waiting.get_mut(&dependent) else {
+          continue;
+        };
+        *count -= 1;
+        if *count > 0 {
+          continue;
         }
-      }
-    }
 
-    // Execute ready binds in parallel
-    if !ready_binds.is_empty() {
-      let bind_results = execute_bind_wave(
-        &ready_binds,
-        manifest,
-        config,
-        &result.realized,
-        &result.applied,
-        semaphore.clone(),
-      )
-      .await;
-
-      // Process bind results
-      for (hash, bind_result) in bind_results {
-        match bind_result {
-          Ok(br) => {
-            debug!(bind = %hash.0, "bind succeeded");
-            applied_binds_order.push(hash.clone());
-            result.applied.insert(hash, br);
-          }
-          Err(e) => {
-            error!(bind = %hash.0, error = %e, "bind failed");
-            failed_nodes.insert(DagNode::Bind(hash.clone()));
-            result.bind_failed = Some((hash, e));
-
-            // Trigger rollback and stop
-            rollback_binds(&applied_binds_order, &result.applied, manifest, config).await;
-            break 'waves;
+        match find_failed_dependency(&dependent, dag, &failed_nodes) {
+          Some(failed_dep) => {
+            failed_nodes.insert(dependent.clone());
+            if let Some(events) = &config.events {
+              events.emit(ExecuteEvent::NodeSkipped {
+                node: dependent.clone(),
+                dependency: failed_dep.clone(),
+              });
+            }
+            match &dependent {
+              DagNode::Build(hash) => {
+                warn!(build = %hash.0, failed_dep = %failed_dep, "skipping build due to failed dependency");
+                result.build_skipped.insert(hash.clone(), failed_dep);
+              }
+              DagNode::Bind(hash) => {
+                warn!(bind = %hash.0, failed_dep = %failed_dep, "skipping bind due to failed dependency");
+                result.bind_skipped.insert(hash.clone(), failed_dep);
+              }
+            }
+            finished.push(dependent);
           }
+          None => ready.push((priorities[&dependent], Reverse(dependent))),
         }
       }
     }
   }
 
-  info!(
-    realized = result.realized.len(),
-    applied = result.applied.len(),
-    build_failed = result.build_failed.is_some(),
-    bind_failed = result.bind_failed.is_some(),
-    build_skipped = result.build_skipped.len(),
-    bind_skipped = result.bind_skipped.len(),
-    "manifest execution complete"
-  );
+  if timed && let Err(e) = times.save() {
+    warn!(error = %e, "failed to save build times");
+  }
 
-  Ok(result)
+  (result, applied_order)
 }
 
 /// Find a failed dependency for a node.
@@ -344,139 +412,60 @@ fn find_failed_dependency(
   }
 }
 
-/// Execute a wave of builds in parallel (unified execution version).
-async fn execute_build_wave(
-  builds: &[ObjectHash],
-  manifest: &Manifest,
-  config: &ExecuteConfig,
-  completed_builds: &HashMap<ObjectHash, BuildResult>,
-  completed_binds: &HashMap<ObjectHash, BindResult>,
-  semaphore: std::sync::Arc<Semaphore>,
-) -> Vec<(ObjectHash, Result<BuildResult, ExecuteError>)> {
-  use tokio::task::JoinSet;
-
-  let mut join_set = JoinSet::new();
-
-  for hash in builds {
-    let hash = hash.clone();
-    let manifest = manifest.clone();
-    let config = config.clone();
-    let completed_builds = completed_builds.clone();
-    let completed_binds = completed_binds.clone();
-    let semaphore = semaphore.clone();
-
-    join_set.spawn(async move {
-      let _permit = semaphore.acquire().await.unwrap();
-
-      let build_def = manifest
-        .builds
-        .get(&hash)
-        .ok_or_else(|| ExecuteError::BuildNotFound(hash.clone()))?;
-
-      // Build execution (builds can only reference other builds, not binds)
-      let result = crate::build::execute::realize_build_with_resolver(
-        &hash,
-        build_def,
-        &completed_builds,
-        &completed_binds,
-        &manifest,
-        &config,
-      )
-      .await;
-
-      Ok::<_, ExecuteError>((hash, result))
-    });
-  }
-
-  collect_join_results(join_set).await
-}
-
-/// Execute a wave of binds in parallel.
-async fn execute_bind_wave(
-  binds: &[ObjectHash],
-  manifest: &Manifest,
-  config: &ExecuteConfig,
-  completed_builds: &HashMap<ObjectHash, BuildResult>,
-  completed_binds: &HashMap<ObjectHash, BindResult>,
-  semaphore: std::sync::Arc<Semaphore>,
-) -> Vec<(ObjectHash, Result<BindResult, ExecuteError>)> {
-  use tokio::task::JoinSet;
-
-  let mut join_set = JoinSet::new();
-
-  for hash in binds {
-    let hash = hash.clone();
-    let manifest = manifest.clone();
-    let _config = config.clone();
-    let completed_builds = completed_builds.clone();
-    let completed_binds = completed_binds.clone();
-    let semaphore = semaphore.clone();
-
-    join_set.spawn(async move {
-      let _permit = semaphore.acquire().await.unwrap();
-
-      let bind_def = manifest
-        .bindings
-        .get(&hash)
-        .ok_or_else(|| ExecuteError::BindNotFound(hash.clone()))?;
-
-      // Create resolver with completed builds and binds
-      let resolver = BindCtxResolver::new(
-        &completed_builds,
-        &completed_binds,
-        &manifest,
-        "/tmp".to_string(), // Temporary; apply_bind creates its own working dir
-      );
-
-      let result = apply_bind(&hash, bind_def, &resolver).await;
-
-      Ok::<_, ExecuteError>((hash, result))
-    });
-  }
-
-  collect_bind_join_results(join_set).await
-}
-
-/// Collect results from a JoinSet of build tasks.
-async fn collect_join_results(mut join_set: BuildJoinSet) -> Vec<(ObjectHash, Result<BuildResult, ExecuteError>)> {
-  let mut results = Vec::new();
-
-  while let Some(join_result) = join_set.join_next().await {
-    match join_result {
-      Ok(Ok((hash, build_result))) => {
-        results.push((hash, build_result));
-      }
-      Ok(Err(e)) => {
-        error!(error = %e, "unexpected error in build task");
-      }
-      Err(e) => {
-        error!(error = %e, "build task panicked");
-      }
+/// Run a single build or bind against the results completed so far.
+///
+/// Holds its scheduler slot until it returns.
+async fn run_node(
+  node: DagNode,
+  mode: RunMode,
+  manifest: Arc<Manifest>,
+  config: ExecuteConfig,
+  completed_builds: HashMap<ObjectHash, BuildResult>,
+  completed_binds: HashMap<ObjectHash, BindResult>,
+  _permit: OwnedSemaphorePermit,
+) -> (NodeOutcome, Duration) {
+  let start = Instant::now();
+
+  let outcome = match node {
+    DagNode::Build(hash) => {
+      let result = match manifest.builds.get(&hash) {
+        None => Err(ExecuteError::BuildNotFound(hash.clone())),
+        // Builds can only reference other builds, not binds
+        Some(build_def) if mode == RunMode::Builds => {
+          crate::build::execute::realize_build(&hash, build_def, &completed_builds, &manifest, &config).await
+        }
+        Some(build_def) => {
+          crate::build::execute::realize_build_with_resolver(
+            &hash,
+            build_def,
+            &completed_builds,
+            &completed_binds,
+            &manifest,
+            &config,
+          )
+          .await
+        }
+      };
+      NodeOutcome::Build(hash, result)
     }
-  }
-
-  results
-}
-
-/// Collect results from a JoinSet of bind tasks.
-async fn collect_bind_join_results(mut join_set: BindJoinSet) -> Vec<(ObjectHash, Result<BindResult, ExecuteError>)> {
-  let mut results = Vec::new();
-
-  while let Some(join_result) = join_set.join_next().await {
-    match join_result {
-      Ok(Ok((hash, bind_result))) => {
-        results.push((hash, bind_result));
-      }
-      Ok(Err(e)) => {
-        error!(error = %e, "unexpected error in bind task");
-      }
-      Err(e) => {
-        error!(error = %e, "bind task panicked");
-      }
+    DagNode::Bind(hash) => {
+      let result = match manifest.bindings.get(&hash) {
+        None => Err(ExecuteError::BindNotFound(hash.clone())),
+        Some(bind_def) => {
+          let resolver = BindCtxResolver::new(
+            &completed_builds,
+            &completed_binds,
+            &manifest,
+            "/tmp".to_string(), // Temporary; apply_bind creates its own working dir
+          );
+          apply_bind(&hash, bind_def, &resolver, Some(&BindLogs::new(config.events.as_ref()))).await
+        }
+      };
+      NodeOutcome::Bind(hash, result)
     }
-  }
+  };
 
-  results
+  (outcome, start.elapsed())
 }
 
 /// Rollback applied binds in reverse order.
@@ -487,13 +476,20 @@ async fn rollback_binds(
   applied_order: &[ObjectHash],
   applied_results: &HashMap<ObjectHash, BindResult>,
   manifest: &Manifest,
-  _config: &ExecuteConfig,
+  config: &ExecuteConfig,
 ) {
   if applied_order.is_empty() {
     return;
   }
 
   info!(count = applied_order.len(), "rolling back applied binds");
+  let start = Instant::now();
+  if let Some(events) = &config.events {
+    events.emit(ExecuteEvent::RollbackStarted {
+      binds: applied_order.len(),
+    });
+  }
+  let mut failure = None;
 
   // Create an empty resolver for destroy operations
   // (destroy actions typically don't need to reference other completed nodes)
@@ -507,69 +503,29 @@ async fn rollback_binds(
       && let Some(bind_result) = applied_results.get(hash)
     {
       debug!(bind = %hash.0, "destroying bind during rollback");
-      if let Err(e) = destroy_bind(hash, bind_def, bind_result, &resolver).await {
+      if let Err(e) = destroy_bind(
+        hash,
+        bind_def,
+        bind_result,
+        &resolver,
+        Some(&BindLogs::new(config.events.as_ref())),
+      )
+      .await
+      {
         // Log but continue - we want to try to rollback as much as possible
         error!(bind = %hash.0, error = %e, "failed to destroy bind during rollback");
+        failure.get_or_insert_with(|| format!("failed to destroy bind {}: {}", hash.0, e));
       }
     }
   }
 
   debug!("rollback complete");
-}
-
-/// Execute a wave of builds in parallel.
-async fn execute_wave(
-  builds: &[ObjectHash],
-  manifest: &Manifest,
-  config: &ExecuteConfig,
-  completed: &HashMap<ObjectHash, BuildResult>,
-  semaphore: std::sync::Arc<Semaphore>,
-) -> Vec<(ObjectHash, Result<BuildResult, ExecuteError>)> {
-  use tokio::task::JoinSet;
-
-  let mut join_set = JoinSet::new();
-
-  for hash in builds {
-    let hash = hash.clone();
-    let manifest = manifest.clone();
-    let config = config.clone();
-    let completed = completed.clone();
-    let semaphore = semaphore.clone();
-
-    join_set.spawn(async move {
-      // Acquire semaphore permit inside the task
-      let _permit = semaphore.acquire().await.unwrap();
-
-      let build_def = manifest
-        .builds
-        .get(&hash)
-        .ok_or_else(|| ExecuteError::BuildNotFound(hash.clone()))?;
-
-      let result = crate::build::execute::realize_build(&hash, build_def, &completed, &manifest, &config).await;
-
-      Ok::<_, ExecuteError>((hash, result))
+  if let Some(events) = &config.events {
+    events.emit(ExecuteEvent::RollbackFinished {
+      duration: start.elapsed(),
+      error: failure,
     });
   }
-
-  let mut results = Vec::new();
-
-  while let Some(join_result) = join_set.join_next().await {
-    match join_result {
-      Ok(Ok((hash, build_result))) => {
-        results.push((hash, build_result));
-      }
-      Ok(Err(e)) => {
-        // This shouldn't happen as we handle errors in the task
-        error!(error = %e, "unexpected error in build task");
-      }
-      Err(e) => {
-        // Task panicked
-        error!(error = %e, "build task panicked");
-      }
-    }
-  }
-
-  results
 }
 
 /// Execute a single build by hash.
@@ -596,7 +552,7 @@ mod tests {
   use crate::{
     action::{Action, actions::exec::ExecOpts},
     bind::BindInputsDef,
-    build::{BuildDef, BuildInputs},
+    build::{BuildDef, BuildInputs, BuildSandbox},
     util::{
       hash::Hashable,
       testutil::{echo_msg, shell_cmd},
@@ -616,13 +572,22 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       outputs: None,
+      sandbox: None,
     }
   }
 
   fn test_config() -> ExecuteConfig {
-    ExecuteConfig { parallelism: 4 }
+    ExecuteConfig {
+      parallelism: 4,
+      substituters: vec![],
+      keep_going: false,
+      events: None,
+    }
   }
 
   /// Helper to set up a temp store and run a test.
@@ -772,6 +737,91 @@ mod tests {
     });
   }
 
+  /// A build running `script` through the shell, with no outputs.
+  ///
+  /// The scripts leave markers outside `$out`, so they run unsandboxed.
+  #[cfg(unix)]
+  fn script_build(script: &str, inputs: Option<BuildInputs>) -> BuildDef {
+    let (cmd, args) = shell_cmd(script);
+    BuildDef {
+      id: None,
+      inputs,
+      create_actions: vec![Action::Exec(ExecOpts {
+        bin: cmd.to_string(),
+        args: Some(args),
+        env: None,
+        cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
+      })],
+      outputs: None,
+      sandbox: Some(BuildSandbox::Disabled),
+    }
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn execute_starts_dependents_without_waiting_for_slow_builds() {
+    with_temp_store(|| async {
+      // slow and quick are independent; dependent only needs quick and must
+      // run while slow is still going
+      let markers = TempDir::new().unwrap();
+      let slow_done = markers.path().join("slow-done");
+
+      let slow = script_build(&format!("/bin/sleep 1 && /usr/bin/touch {}", slow_done.display()), None);
+      let quick = make_build("quick", None);
+      let quick_hash = quick.compute_hash().unwrap();
+      let dependent = script_build(
+        &format!("[ ! -e {} ]", slow_done.display()),
+        Some(BuildInputs::Build(quick_hash.clone())),
+      );
+
+      let mut manifest = Manifest::default();
+      for build in [slow, quick, dependent] {
+        manifest.builds.insert(build.compute_hash().unwrap(), build);
+      }
+
+      let result = execute_builds(&manifest, &test_config()).await.unwrap();
+      assert!(
+        result.is_success(),
+        "dependent ran after the slow build: {:?}",
+        result.build_failed
+      );
+      assert_eq!(result.realized.len(), 3);
+    });
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn execute_starts_longest_chain_first() {
+    with_temp_store(|| async {
+      let markers = TempDir::new().unwrap();
+      let log = markers.path().join("order.log");
+      let logging = |name: &str, inputs| script_build(&format!("echo {} >> {}", name, log.display()), inputs);
+
+      let head = logging("head", None);
+      let head_hash = head.compute_hash().unwrap();
+      let tail = logging("tail", Some(BuildInputs::Build(head_hash.clone())));
+      let single = logging("single", None);
+
+      let mut manifest = Manifest::default();
+      for build in [head, tail, single] {
+        manifest.builds.insert(build.compute_hash().unwrap(), build);
+      }
+
+      let config = ExecuteConfig {
+        parallelism: 1,
+        ..test_config()
+      };
+      let result = execute_builds(&manifest, &config).await.unwrap();
+      assert!(result.is_success());
+
+      let order = std::fs::read_to_string(&log).unwrap();
+      assert_eq!(order.lines().next(), Some("head"));
+    });
+  }
+
   #[test]
   fn execute_failing_build() {
     with_temp_store(|| async {
@@ -784,8 +834,12 @@ mod tests {
           args: Some(args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: None,
+        sandbox: None,
       };
       let hash = build.compute_hash().unwrap();
 
@@ -796,9 +850,8 @@ mod tests {
       let result = execute_builds(&manifest, &config).await.unwrap();
 
       assert!(!result.is_success());
-      assert!(result.build_failed.is_some());
-      let (failed_hash, _) = result.build_failed.as_ref().unwrap();
-      assert_eq!(failed_hash, &hash);
+      assert_eq!(result.build_failed.len(), 1);
+      assert!(result.build_failed.contains_key(&hash));
     });
   }
 
@@ -815,8 +868,12 @@ mod tests {
           args: Some(args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: None,
+        sandbox: None,
       };
       let hash_a = build_a.compute_hash().unwrap();
 
@@ -831,16 +888,74 @@ mod tests {
       let result = execute_builds(&manifest, &config).await.unwrap();
 
       assert!(!result.is_success());
-      assert!(result.build_failed.is_some());
+      assert_eq!(result.build_failed.len(), 1);
       assert_eq!(result.build_skipped.len(), 1);
 
-      let (failed_hash, _) = result.build_failed.as_ref().unwrap();
-      assert_eq!(failed_hash, &hash_a);
+      assert!(result.build_failed.contains_key(&hash_a));
       assert!(result.build_skipped.contains_key(&hash_b));
       assert_eq!(result.build_skipped[&hash_b], FailedDependency::Build(hash_a));
     });
   }
 
+  #[test]
+  #[cfg(unix)]
+  fn execute_reports_progress_events() {
+    with_temp_store(|| async {
+      let mut failing = script_build("echo boom && exit 1", None);
+      failing.id = Some("failing".to_string());
+      let failing_hash = failing.compute_hash().unwrap();
+      let dependent = make_build("dependent", Some(BuildInputs::Build(failing_hash.clone())));
+      let dependent_hash = dependent.compute_hash().unwrap();
+
+      let mut manifest = Manifest::default();
+      manifest.builds.insert(failing_hash.clone(), failing);
+      manifest.builds.insert(dependent_hash.clone(), dependent);
+
+      let (tx, mut rx) = event_channel();
+      let config = ExecuteConfig {
+        events: Some(tx),
+        ..test_config()
+      };
+      execute_builds(&manifest, &config).await.unwrap();
+      drop(config);
+
+      let mut events = Vec::new();
+      while let Some(event) = rx.recv().await {
+        events.push(event);
+      }
+
+      let node = DagNode::Build(failing_hash.clone());
+      assert!(
+        events[..2]
+          .iter()
+          .all(|event| matches!(event, ExecuteEvent::NodeScheduled { .. }))
+      );
+      assert_eq!(
+        events[2],
+        ExecuteEvent::NodeStarted {
+          node: node.clone(),
+          id: Some("failing".to_string()),
+        }
+      );
+      assert!(events.contains(&ExecuteEvent::Output {
+        node: node.clone(),
+        stream: OutputStream::Stdout,
+        line: "boom".to_string(),
+      }));
+      assert!(events.iter().any(|event| matches!(
+        event,
+        ExecuteEvent::NodeFinished { node: n, error: Some(_), .. } if *n == node
+      )));
+      assert_eq!(
+        events.last(),
+        Some(&ExecuteEvent::NodeSkipped {
+          node: DagNode::Build(dependent_hash),
+          dependency: FailedDependency::Build(failing_hash),
+        })
+      );
+    });
+  }
+
   #[test]
   fn execute_diamond_dependency() {
     with_temp_store(|| async {
@@ -895,6 +1010,9 @@ mod tests {
         args: Some(args),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       update_actions: None,
       destroy_actions: vec![],
@@ -971,12 +1089,16 @@ mod tests {
           args: Some(echo_args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: Some(
           [("bin".to_string(), JsonValue::String("$${{out}}/bin".to_string()))]
             .into_iter()
             .collect(),
         ),
+        sandbox: None,
       };
       let build_hash = build.compute_hash().unwrap();
 
@@ -992,6 +1114,9 @@ mod tests {
           args: Some(bind_args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         update_actions: None,
         destroy_actions: vec![],
@@ -1049,6 +1174,9 @@ mod tests {
           args: Some(touch_args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         update_actions: None,
         destroy_actions: vec![Action::Exec(ExecOpts {
@@ -1056,6 +1184,9 @@ mod tests {
           args: Some(rm_args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         check_actions: None,
         check_outputs: None,
@@ -1073,6 +1204,9 @@ mod tests {
           args: Some(exit_args),
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         update_actions: None,
         destroy_actions: vec![],
@@ -1098,15 +1232,14 @@ mod tests {
       eprintln!("=============================");
 
       assert!(!result.is_success());
-      assert!(result.bind_failed.is_some());
+      assert_eq!(result.bind_failed.len(), 1);
 
       // The failing bind should be hash_b (which depends on hash_a)
-      let (failed_hash, failed_err) = result.bind_failed.as_ref().unwrap();
+      let failed_err = result.bind_failed.get(&hash_b);
       eprintln!("=== DEBUG: Failed bind details ===");
-      eprintln!("failed_hash: {:?}", failed_hash);
       eprintln!("failed_err: {:?}", failed_err);
       eprintln!("==================================");
-      assert_eq!(failed_hash, &hash_b, "Bind B should have failed, not Bind A");
+      assert!(failed_err.is_some(), "Bind B should have failed, not Bind A");
 
       // Bind A should have been applied (before failure)
       assert!(
@@ -1119,6 +1252,78 @@ mod tests {
     });
   }
 
+  #[test]
+  fn manifest_keep_going_runs_independent_branches() {
+    // A fails, B depends on A, D fails; C and E (depends on C) are independent
+    with_temp_store(|| async {
+      let failing = |code: &str| {
+        let (cmd, args) = shell_cmd(&format!("exit {}", code));
+        BuildDef {
+          id: None,
+          inputs: None,
+          create_actions: vec![Action::Exec(ExecOpts {
+            bin: cmd.to_string(),
+            args: Some(args),
+            env: None,
+            cwd: None,
+            timeout: None,
+            memory_limit: None,
+            cpu_time_limit: None,
+          })],
+          outputs: None,
+          sandbox: None,
+        }
+      };
+      let build_a = failing("1");
+      let hash_a = build_a.compute_hash().unwrap();
+      let build_d = failing("2");
+      let hash_d = build_d.compute_hash().unwrap();
+      let build_b = make_build("b", Some(BuildInputs::Build(hash_a.clone())));
+      let hash_b = build_b.compute_hash().unwrap();
+      let build_c = make_build("c", None);
+      let hash_c = build_c.compute_hash().unwrap();
+      let build_e = make_build("e", Some(BuildInputs::Build(hash_c.clone())));
+      let hash_e = build_e.compute_hash().unwrap();
+
+      let mut manifest = Manifest::default();
+      for (hash, build) in [
+        (hash_a.clone(), build_a),
+        (hash_b.clone(), build_b),
+        (hash_c.clone(), build_c),
+        (hash_d.clone(), build_d),
+        (hash_e.clone(), build_e),
+      ] {
+        manifest.builds.insert(hash, build);
+      }
+
+      // A, C and D all start at once, so both failures are seen; whether E
+      // starts depends on C finishing first, so only the failures are checked
+      let stopped = execute_manifest(&manifest, &test_config()).await.unwrap();
+      assert_eq!(stopped.build_failed.len(), 2);
+      assert_eq!(
+        stopped.skipped_by(&FailedDependency::Build(hash_a.clone())),
+        vec![FailedDependency::Build(hash_b.clone())]
+      );
+
+      let config = ExecuteConfig {
+        keep_going: true,
+        ..test_config()
+      };
+      let result = execute_manifest(&manifest, &config).await.unwrap();
+
+      assert_eq!(result.build_failed.len(), 2);
+      assert!(result.build_failed.contains_key(&hash_a));
+      assert!(result.build_failed.contains_key(&hash_d));
+      assert!(result.realized.contains_key(&hash_c));
+      assert!(result.realized.contains_key(&hash_e));
+      assert_eq!(
+        result.skipped_by(&FailedDependency::Build(hash_a)),
+        vec![FailedDependency::Build(hash_b)]
+      );
+      assert!(result.skipped_by(&FailedDependency::Build(hash_d)).is_empty());
+    });
+  }
+
   #[test]
   fn manifest_build_failure_skips_binds() {
     // Build fails -> dependent bind should be skipped (not applied)
@@ -1131,8 +1336,12 @@ mod tests {
           args: None,
           env: None,
           cwd: None,
+          timeout: None,
+          memory_limit: None,
+          cpu_time_limit: None,
         })],
         outputs: None,
+        sandbox: None,
       };
       let build_hash = build.compute_hash().unwrap();
 
@@ -1151,9 +1360,8 @@ mod tests {
       let result = execute_manifest(&manifest, &config).await.unwrap();
 
       assert!(!result.is_success());
-      assert!(result.build_failed.is_some());
-      let (failed_hash, _) = result.build_failed.as_ref().unwrap();
-      assert_eq!(failed_hash, &build_hash);
+      assert_eq!(result.build_failed.len(), 1);
+      assert!(result.build_failed.contains_key(&build_hash));
 
       // No binds should have been applied (we break out of execution on failure)
       assert!(result.applied.is_empty(), "No binds should have been applied");
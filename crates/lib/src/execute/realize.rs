@@ -0,0 +1,356 @@
+//! Realizing individual builds from a config.
+//!
+//! This powers `sys build`: a config is evaluated, one build (or all of them)
+//! is selected together with its dependency closure, and the closure is
+//! realized in dependency order. Binds are never executed and no snapshot is
+//! recorded.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+use tracing::{debug, info, warn};
+
+use crate::build::execute::{realize_build, rebuild_build};
+use crate::eval::{EvalError, EvalOptions, evaluate_config};
+use crate::manifest::Manifest;
+use crate::store_lock::{LockMode, StoreLock, StoreLockError};
+use crate::util::hash::ObjectHash;
+
+use super::dag::ExecutionDag;
+use super::types::{BuildResult, ExecuteConfig, ExecuteError};
+
+/// Errors that can occur while realizing builds from a config.
+#[derive(Debug, Error)]
+pub enum RealizeError {
+  /// Config evaluation failed.
+  #[error("evaluation error: {0}")]
+  Eval(#[from] EvalError),
+
+  /// Execution setup failed (e.g. dependency cycle).
+  #[error("execution error: {0}")]
+  Execute(#[from] ExecuteError),
+
+  /// No build matched the selector.
+  #[error("no build matches {0}")]
+  NoMatchingBuild(BuildSelector),
+
+  /// More than one build matched the selector.
+  #[error("{selector} is ambiguous, matching builds: {}", candidates.join(", "))]
+  AmbiguousBuild {
+    selector: BuildSelector,
+    candidates: Vec<String>,
+  },
+
+  /// A build failed to realize.
+  #[error("build {hash} failed: {source}")]
+  BuildFailed {
+    hash: ObjectHash,
+    #[source]
+    source: ExecuteError,
+  },
+
+  /// Builds failed while keeping going; every failure is listed.
+  #[error("{} build(s) failed", .0.len())]
+  BuildsFailed(Vec<BuildFailure>),
+
+  /// Store lock acquisition failed.
+  #[error("failed to acquire store lock: {0}")]
+  Lock(#[from] StoreLockError),
+}
+
+/// A build that failed to realize, with the builds it kept from running.
+#[derive(Debug, Serialize)]
+pub struct BuildFailure {
+  /// The build hash.
+  pub hash: ObjectHash,
+
+  /// The build's `id`, if it has one.
+  pub id: Option<String>,
+
+  /// Why the build failed.
+  pub error: ExecuteError,
+
+  /// Builds skipped because they depend on this one, directly or not.
+  pub skipped: Vec<ObjectHash>,
+}
+
+/// Identifies a build in a manifest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildSelector {
+  /// The build's `id`.
+  Id(String),
+  /// A prefix of the build's hash.
+  HashPrefix(String),
+}
+
+impl std::fmt::Display for BuildSelector {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BuildSelector::Id(id) => write!(f, "id '{}'", id),
+      BuildSelector::HashPrefix(prefix) => write!(f, "hash prefix '{}'", prefix),
+    }
+  }
+}
+
+/// Options for realizing builds from a config.
+#[derive(Debug, Clone, Default)]
+pub struct RealizeOptions {
+  /// Execution configuration (parallelism, etc.)
+  pub execute: ExecuteConfig,
+
+  /// Allow impure Lua libs (io, os). Breaks determinism.
+  pub impure: bool,
+
+  /// Build to realize. All builds in the config are realized when `None`.
+  pub selector: Option<BuildSelector>,
+
+  /// Redo the selected builds even if a completed output is in the store.
+  /// Dependencies are still taken from the cache.
+  pub rebuild: bool,
+}
+
+/// A realized build and its resolved outputs.
+#[derive(Debug, Serialize)]
+pub struct RealizedBuild {
+  /// The build hash.
+  pub hash: ObjectHash,
+
+  /// The build's `id`, if it has one.
+  pub id: Option<String>,
+
+  /// Store path holding the build output.
+  pub store_path: PathBuf,
+
+  /// Resolved outputs (output name -> value).
+  pub outputs: HashMap<String, JsonValue>,
+}
+
+/// Result of realizing builds from a config.
+#[derive(Debug, Serialize)]
+pub struct RealizeResult {
+  /// The selected builds, in the order they were realized.
+  pub builds: Vec<RealizedBuild>,
+
+  /// Number of dependencies realized (or found in the store) along the way.
+  pub dependencies: usize,
+}
+
+/// Find the build in a manifest matching a selector.
+///
+/// Selecting by ID requires an exact match; a hash prefix must identify a
+/// single build.
+///
+/// # Arguments
+///
+/// * `manifest` - The manifest to search
+/// * `selector` - Which build to select
+///
+/// # Returns
+///
+/// The hash of the matching build.
+pub fn select_build(manifest: &Manifest, selector: &BuildSelector) -> Result<ObjectHash, RealizeError> {
+  let matches: Vec<&ObjectHash> = manifest
+    .builds
+    .iter()
+    .filter(|(hash, def)| match selector {
+      BuildSelector::Id(id) => def.id.as_deref() == Some(id.as_str()),
+      BuildSelector::HashPrefix(prefix) => hash.0.starts_with(prefix.as_str()),
+    })
+    .map(|(hash, _)| hash)
+    .collect();
+
+  match matches.as_slice() {
+    [] => Err(RealizeError::NoMatchingBuild(selector.clone())),
+    [hash] => Ok((*hash).clone()),
+    _ => Err(RealizeError::AmbiguousBuild {
+      selector: selector.clone(),
+      candidates: matches.iter().map(|h| h.0.clone()).collect(),
+    }),
+  }
+}
+
+/// Evaluate a config and realize the selected build with its dependencies.
+///
+/// Builds are realized one at a time in topological order through
+/// [`realize_build`], so completed builds in the store are reused. With
+/// `rebuild` set, the selected builds go through [`rebuild_build`] instead.
+/// The store is held with a shared lock throughout.
+///
+/// The first failure is returned as [`RealizeError::BuildFailed`]. With
+/// [`ExecuteConfig::keep_going`], builds that do not depend on a failed one
+/// are still realized and all failures are returned together as
+/// [`RealizeError::BuildsFailed`].
+///
+/// # Arguments
+///
+/// * `config_path` - Config to evaluate
+/// * `options` - Selection and execution options
+///
+/// # Returns
+///
+/// The selected builds with their resolved outputs.
+pub async fn realize(config_path: &Path, options: &RealizeOptions) -> Result<RealizeResult, RealizeError> {
+  info!(config = %config_path.display(), selector = ?options.selector, "realizing builds");
+
+  let eval_options = EvalOptions {
+    impure: options.impure,
+    events: options.execute.events.clone(),
+  };
+  let manifest = evaluate_config(config_path, &eval_options)?;
+
+  let selected: Vec<ObjectHash> = match &options.selector {
+    Some(selector) => vec![select_build(&manifest, selector)?],
+    None => manifest.builds.keys().cloned().collect(),
+  };
+
+  let dag = ExecutionDag::from_manifest(&manifest)?;
+  let closure = dag.build_closure(&selected);
+  let order: Vec<ObjectHash> = dag
+    .topological_builds()?
+    .into_iter()
+    .filter(|hash| closure.contains(hash))
+    .collect();
+
+  debug!(
+    selected = selected.len(),
+    closure = order.len(),
+    "build closure computed"
+  );
+
+  let _lock = StoreLock::acquire(LockMode::Shared, "build")?;
+
+  let targets: HashSet<&ObjectHash> = selected.iter().collect();
+  let mut completed: HashMap<ObjectHash, BuildResult> = HashMap::new();
+  let mut failures: Vec<BuildFailure> = Vec::new();
+  // Skipped build -> the failed build it traces back to
+  let mut skipped: HashMap<ObjectHash, ObjectHash> = HashMap::new();
+  for hash in &order {
+    let def = manifest
+      .builds
+      .get(hash)
+      .ok_or_else(|| ExecuteError::BuildNotFound(hash.clone()))?;
+
+    let failed_dep = dag.build_dependencies(hash).into_iter().find_map(|dep| {
+      if failures.iter().any(|f| f.hash == dep) {
+        Some(dep)
+      } else {
+        skipped.get(&dep).cloned()
+      }
+    });
+    if let Some(root) = failed_dep {
+      warn!(build = %hash.0, failed_dep = %root.0, "skipping build due to failed dependency");
+      skipped.insert(hash.clone(), root);
+      continue;
+    }
+
+    let result = if options.rebuild && targets.contains(hash) {
+      rebuild_build(hash, def, &completed, &manifest, &options.execute).await
+    } else {
+      realize_build(hash, def, &completed, &manifest, &options.execute).await
+    };
+    match result {
+      Ok(result) => {
+        completed.insert(hash.clone(), result);
+      }
+      Err(source) if !options.execute.keep_going => {
+        return Err(RealizeError::BuildFailed {
+          hash: hash.clone(),
+          source,
+        });
+      }
+      Err(error) => {
+        debug!(build = %hash.0, error = %error, "build failed, keeping going");
+        failures.push(BuildFailure {
+          hash: hash.clone(),
+          id: def.id.clone(),
+          error,
+          skipped: vec![],
+        });
+      }
+    }
+  }
+
+  if !failures.is_empty() {
+    for failure in &mut failures {
+      failure.skipped = order
+        .iter()
+        .filter(|hash| skipped.get(*hash) == Some(&failure.hash))
+        .cloned()
+        .collect();
+    }
+    return Err(RealizeError::BuildsFailed(failures));
+  }
+
+  let builds = order
+    .iter()
+    .filter(|hash| targets.contains(hash))
+    .filter_map(|hash| {
+      let result = completed.remove(hash)?;
+      Some(RealizedBuild {
+        hash: hash.clone(),
+        id: manifest.builds.get(hash).and_then(|def| def.id.clone()),
+        store_path: result.store_path,
+        outputs: result.outputs,
+      })
+    })
+    .collect();
+
+  Ok(RealizeResult {
+    builds,
+    dependencies: order.len() - selected.len(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::build::BuildDef;
+
+  fn build(id: Option<&str>) -> BuildDef {
+    BuildDef {
+      id: id.map(str::to_string),
+      inputs: None,
+      create_actions: vec![],
+      outputs: None,
+      sandbox: None,
+    }
+  }
+
+  fn test_manifest() -> Manifest {
+    let mut manifest = Manifest::default();
+    manifest
+      .builds
+      .insert(ObjectHash("abc111".to_string()), build(Some("ripgrep-14.0.0")));
+    manifest.builds.insert(ObjectHash("abc222".to_string()), build(None));
+    manifest
+  }
+
+  #[test]
+  fn select_build_by_id_and_prefix() {
+    let manifest = test_manifest();
+
+    let by_id = select_build(&manifest, &BuildSelector::Id("ripgrep-14.0.0".to_string())).unwrap();
+    assert_eq!(by_id.0, "abc111");
+
+    let by_prefix = select_build(&manifest, &BuildSelector::HashPrefix("abc2".to_string())).unwrap();
+    assert_eq!(by_prefix.0, "abc222");
+  }
+
+  #[test]
+  fn select_build_rejects_missing_and_ambiguous() {
+    let manifest = test_manifest();
+
+    assert!(matches!(
+      select_build(&manifest, &BuildSelector::Id("ripgrep".to_string())),
+      Err(RealizeError::NoMatchingBuild(_))
+    ));
+
+    match select_build(&manifest, &BuildSelector::HashPrefix("abc".to_string())) {
+      Err(RealizeError::AmbiguousBuild { candidates, .. }) => assert_eq!(candidates.len(), 2),
+      other => panic!("expected ambiguous match, got {:?}", other),
+    }
+  }
+}
@@ -0,0 +1,204 @@
+//! Critical-path priorities for DAG execution.
+//!
+//! The executor starts every node as soon as its dependencies finish. When
+//! more nodes are ready than there are free slots, the node heading the longest
+//! remaining path through the DAG goes first, so long chains start early
+//! instead of waiting behind short independent builds.
+//!
+//! Path lengths are estimated from how long each build took the last time its
+//! actions actually ran. Those durations are kept in [`BUILD_TIMES_FILE`] in
+//! the store, keyed by build ID (or hash for builds without one), so a new
+//! version of a package inherits the estimate of the previous one.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::build::BuildDef;
+use crate::manifest::Manifest;
+use crate::platform::paths::store_dir;
+use crate::util::hash::ObjectHash;
+
+use super::dag::{DagNode, ExecutionDag};
+
+/// File in the store recording past build durations.
+pub const BUILD_TIMES_FILE: &str = "build-times.json";
+
+/// Estimate for builds that have never run, in milliseconds.
+const DEFAULT_BUILD_ESTIMATE_MS: u64 = 1_000;
+
+/// Estimate for binds, in milliseconds. Binds are usually quick and their
+/// durations are not recorded.
+const BIND_ESTIMATE_MS: u64 = 100;
+
+/// Durations of past builds, in milliseconds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BuildTimes {
+  durations: BTreeMap<String, u64>,
+}
+
+impl BuildTimes {
+  /// Load the recorded durations from the store.
+  ///
+  /// A missing or unreadable file yields no estimates rather than an error;
+  /// the durations only affect scheduling order.
+  pub fn load() -> Self {
+    let path = build_times_path();
+    let content = match fs::read_to_string(&path) {
+      Ok(content) => content,
+      Err(_) => return Self::default(),
+    };
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+      debug!(path = %path.display(), error = %e, "ignoring unreadable build times");
+      Self::default()
+    })
+  }
+
+  /// Write the durations back to the store.
+  pub fn save(&self) -> io::Result<()> {
+    let path = build_times_path();
+    if let Some(parent) = path.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let content = serde_json::to_string_pretty(self).expect("failed to serialize build times");
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, content)?;
+    fs::rename(&tmp, &path)
+  }
+
+  /// Estimated duration of a build, in milliseconds.
+  pub fn estimate(&self, hash: &ObjectHash, def: &BuildDef) -> u64 {
+    self
+      .durations
+      .get(&timing_key(hash, def))
+      .copied()
+      .unwrap_or(DEFAULT_BUILD_ESTIMATE_MS)
+  }
+
+  /// Record how long a build's actions took to run.
+  pub fn record(&mut self, hash: &ObjectHash, def: &BuildDef, duration: Duration) {
+    let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+    self.durations.insert(timing_key(hash, def), millis);
+  }
+}
+
+/// Compute the priority of each node: its own estimate plus the longest
+/// estimated path through the nodes that depend on it.
+///
+/// # Arguments
+///
+/// * `dag` - The execution DAG
+/// * `order` - The nodes to schedule, in topological order
+/// * `manifest` - The manifest holding the build definitions
+/// * `times` - Recorded build durations
+///
+/// # Returns
+///
+/// The priority of every node in `order`; higher runs first.
+pub fn critical_path(
+  dag: &ExecutionDag,
+  order: &[DagNode],
+  manifest: &Manifest,
+  times: &BuildTimes,
+) -> HashMap<DagNode, u64> {
+  let scheduled: HashSet<&DagNode> = order.iter().collect();
+  let mut priorities: HashMap<DagNode, u64> = HashMap::with_capacity(order.len());
+
+  for node in order.iter().rev() {
+    let own = match node {
+      DagNode::Build(hash) => manifest
+        .builds
+        .get(hash)
+        .map_or(DEFAULT_BUILD_ESTIMATE_MS, |def| times.estimate(hash, def)),
+      DagNode::Bind(_) => BIND_ESTIMATE_MS,
+    };
+    let longest_dependent = dag
+      .dependents(node)
+      .iter()
+      .filter(|dependent| scheduled.contains(dependent))
+      .filter_map(|dependent| priorities.get(dependent))
+      .copied()
+      .max()
+      .unwrap_or(0);
+    priorities.insert(node.clone(), own.saturating_add(longest_dependent));
+  }
+
+  priorities
+}
+
+fn timing_key(hash: &ObjectHash, def: &BuildDef) -> String {
+  def.id.clone().unwrap_or_else(|| hash.0.clone())
+}
+
+fn build_times_path() -> PathBuf {
+  store_dir().join(BUILD_TIMES_FILE)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::build::BuildInputs;
+  use crate::util::hash::Hashable;
+
+  fn build(id: &str, inputs: Option<BuildInputs>) -> BuildDef {
+    BuildDef {
+      id: Some(id.to_string()),
+      inputs,
+      create_actions: vec![],
+      outputs: None,
+      sandbox: None,
+    }
+  }
+
+  #[test]
+  fn critical_path_prefers_long_chains() {
+    // slow -> tail is the long chain; quick stands alone
+    let slow = build("slow", None);
+    let slow_hash = slow.compute_hash().unwrap();
+    let tail = build("tail", Some(BuildInputs::Build(slow_hash.clone())));
+    let tail_hash = tail.compute_hash().unwrap();
+    let quick = build("quick", None);
+    let quick_hash = quick.compute_hash().unwrap();
+
+    let mut manifest = Manifest::default();
+    manifest.builds.insert(slow_hash.clone(), slow.clone());
+    manifest.builds.insert(tail_hash.clone(), tail.clone());
+    manifest.builds.insert(quick_hash.clone(), quick.clone());
+
+    let mut times = BuildTimes::default();
+    times.record(&slow_hash, &slow, Duration::from_secs(30));
+    times.record(&quick_hash, &quick, Duration::from_secs(5));
+
+    let dag = ExecutionDag::from_manifest(&manifest).unwrap();
+    let order = dag.topological_nodes().unwrap();
+    let priorities = critical_path(&dag, &order, &manifest, &times);
+
+    assert_eq!(priorities[&DagNode::Build(tail_hash)], DEFAULT_BUILD_ESTIMATE_MS);
+    assert_eq!(
+      priorities[&DagNode::Build(slow_hash)],
+      30_000 + DEFAULT_BUILD_ESTIMATE_MS
+    );
+    assert_eq!(priorities[&DagNode::Build(quick_hash)], 5_000);
+  }
+
+  #[test]
+  fn estimates_are_keyed_by_id() {
+    let old = build("tool", None);
+    let new = build("tool", Some(BuildInputs::String("v2".to_string())));
+
+    let mut times = BuildTimes::default();
+    times.record(&ObjectHash("old".to_string()), &old, Duration::from_millis(2_500));
+
+    assert_eq!(times.estimate(&ObjectHash("new".to_string()), &new), 2_500);
+    let anonymous = BuildDef { id: None, ..new };
+    assert_eq!(
+      times.estimate(&ObjectHash("new".to_string()), &anonymous),
+      DEFAULT_BUILD_ESTIMATE_MS
+    );
+  }
+}
@@ -9,6 +9,8 @@ use std::path::PathBuf;
 use serde_json::Value as JsonValue;
 use thiserror::Error;
 
+use crate::cache::{Substituter, substituters_from_env};
+use crate::execute::events::EventSender;
 use crate::placeholder::PlaceholderError;
 use crate::util::hash::{DirHashError, ObjectHash};
 
@@ -45,7 +47,7 @@ pub enum ExecuteError {
   #[error("fetch failed for {url}: {message}")]
   FetchFailed { url: String, message: String },
 
-  /// SHA256 hash mismatch after download.
+  /// Hash mismatch after download.
   #[error("hash mismatch for {url}: expected {expected}, got {actual}")]
   HashMismatch {
     url: String,
@@ -53,14 +55,42 @@ pub enum ExecuteError {
     actual: String,
   },
 
+  /// An archive could not be extracted.
+  #[error("unpack failed for {archive}: {message}")]
+  UnpackFailed { archive: String, message: String },
+
+  /// A file action (`write_file`, `copy`, ...) failed.
+  #[error("{action} failed: {message}")]
+  FileFailed { action: String, message: String },
+
+  /// The source copied by a Source action was not imported into the store.
+  #[error("source {name} ({sha256}) is not in the store; evaluate the config again to import it")]
+  SourceNotFound { name: String, sha256: String },
+
   /// Command execution failed.
-  #[error("command failed with exit code {code:?}: {cmd}")]
-  CmdFailed { cmd: String, code: Option<i32> },
+  ///
+  /// `stderr` holds the last lines the command wrote to stderr; the full
+  /// output is in the action log (see [`crate::action::log`]).
+  #[error("command failed with exit code {code:?}: {cmd}{}", indent_stderr(.stderr))]
+  CmdFailed {
+    cmd: String,
+    code: Option<i32>,
+    #[serde(default)]
+    stderr: String,
+  },
+
+  /// Command ran longer than its timeout and was killed.
+  #[error("command timed out after {}s: {cmd}", .elapsed.as_secs())]
+  Timeout { cmd: String, elapsed: std::time::Duration },
 
   /// Command produced output on stderr.
   #[error("command error: {message}")]
   CmdError { message: String },
 
+  /// A command could not be started in the build sandbox.
+  #[error("sandbox error: {message} (set `sandbox = false` on the build to run it unconfined)")]
+  Sandbox { message: String },
+
   /// I/O error during execution.
   #[error("io error: {message}")]
   Io { message: String },
@@ -69,6 +99,10 @@ pub enum ExecuteError {
   #[error("dependency failed: {0}")]
   DependencyFailed(ObjectHash),
 
+  /// The task running a build or bind panicked or was cancelled.
+  #[error("execution task failed: {message}")]
+  TaskFailed { message: String },
+
   /// Cycle detected in the dependency graph.
   #[error("dependency cycle detected")]
   CycleDetected,
@@ -158,8 +192,10 @@ pub struct DagResult {
   /// Successfully realized builds.
   pub realized: HashMap<ObjectHash, BuildResult>,
 
-  /// Build that failed during execution (at most one, stops execution).
-  pub build_failed: Option<(ObjectHash, ExecuteError)>,
+  /// Builds that failed during execution.
+  /// Unless [`ExecuteConfig::keep_going`] is set, execution stops after the
+  /// first wave with a failure.
+  pub build_failed: HashMap<ObjectHash, ExecuteError>,
 
   /// Builds that were skipped because a dependency failed.
   /// Maps skipped build hash -> the failed dependency.
@@ -169,8 +205,8 @@ pub struct DagResult {
   /// Successfully applied binds.
   pub applied: HashMap<ObjectHash, BindResult>,
 
-  /// Bind that failed during execution (at most one, triggers rollback).
-  pub bind_failed: Option<(ObjectHash, ExecuteError)>,
+  /// Binds that failed during execution. Any failure triggers rollback.
+  pub bind_failed: HashMap<ObjectHash, ExecuteError>,
 
   /// Binds that were skipped because a dependency failed.
   /// Maps skipped bind hash -> the failed dependency.
@@ -180,26 +216,64 @@ pub struct DagResult {
 impl DagResult {
   /// Returns true if all builds and binds succeeded.
   pub fn is_success(&self) -> bool {
-    self.build_failed.is_none()
+    self.build_failed.is_empty()
       && self.build_skipped.is_empty()
-      && self.bind_failed.is_none()
+      && self.bind_failed.is_empty()
       && self.bind_skipped.is_empty()
   }
 
   /// Returns the total number of builds processed.
   pub fn build_total(&self) -> usize {
-    self.realized.len() + self.build_failed.iter().count() + self.build_skipped.len()
+    self.realized.len() + self.build_failed.len() + self.build_skipped.len()
   }
 
   /// Returns the total number of binds processed.
   pub fn bind_total(&self) -> usize {
-    self.applied.len() + self.bind_failed.iter().count() + self.bind_skipped.len()
+    self.applied.len() + self.bind_failed.len() + self.bind_skipped.len()
   }
 
   /// Returns the total number of nodes (builds + binds) processed.
   pub fn total(&self) -> usize {
     self.build_total() + self.bind_total()
   }
+
+  /// Returns the skipped builds and binds that trace back to `failed`.
+  ///
+  /// A skipped node records the dependency that stopped it, which may itself
+  /// have been skipped; the chain is followed back to the node that actually
+  /// failed. The result is sorted for stable output.
+  pub fn skipped_by(&self, failed: &FailedDependency) -> Vec<FailedDependency> {
+    let builds = self
+      .build_skipped
+      .iter()
+      .map(|(hash, dep)| (FailedDependency::Build(hash.clone()), dep));
+    let binds = self
+      .bind_skipped
+      .iter()
+      .map(|(hash, dep)| (FailedDependency::Bind(hash.clone()), dep));
+
+    let mut skipped: Vec<FailedDependency> = builds
+      .chain(binds)
+      .filter(|(_, dep)| self.root_failure(dep) == failed)
+      .map(|(node, _)| node)
+      .collect();
+    skipped.sort_by_key(|node| node.to_string());
+    skipped
+  }
+
+  /// Follow a chain of skipped dependencies back to the node that failed.
+  fn root_failure<'a>(&'a self, mut dep: &'a FailedDependency) -> &'a FailedDependency {
+    loop {
+      let next = match dep {
+        FailedDependency::Build(hash) => self.build_skipped.get(hash),
+        FailedDependency::Bind(hash) => self.bind_skipped.get(hash),
+      };
+      match next {
+        Some(next) => dep = next,
+        None => return dep,
+      }
+    }
+  }
 }
 
 /// Configuration for build execution.
@@ -207,12 +281,28 @@ impl DagResult {
 pub struct ExecuteConfig {
   /// Maximum number of builds to execute in parallel.
   pub parallelism: usize,
+
+  /// Binary caches consulted before running a build's actions.
+  #[serde(default)]
+  pub substituters: Vec<Substituter>,
+
+  /// Keep executing independent parts of the DAG after a failure, so every
+  /// failure is reported instead of only the first.
+  #[serde(default)]
+  pub keep_going: bool,
+
+  /// Receives progress events while builds and binds run.
+  #[serde(skip)]
+  pub events: Option<EventSender>,
 }
 
 impl Default for ExecuteConfig {
   fn default() -> Self {
     Self {
       parallelism: num_cpus(),
+      substituters: substituters_from_env(),
+      keep_going: false,
+      events: None,
     }
   }
 }
@@ -222,6 +312,11 @@ fn num_cpus() -> usize {
   std::thread::available_parallelism().map(|p| p.get()).unwrap_or(4)
 }
 
+/// Render captured stderr as indented lines following an error message.
+fn indent_stderr(stderr: &str) -> String {
+  stderr.lines().map(|line| format!("\n    {}", line)).collect()
+}
+
 impl From<std::io::Error> for ExecuteError {
   fn from(err: std::io::Error) -> Self {
     ExecuteError::Io {
@@ -270,13 +365,14 @@ mod tests {
   #[test]
   fn dag_result_failure_with_build_failed() {
     let result = DagResult {
-      build_failed: Some((
+      build_failed: HashMap::from([(
         ObjectHash("abc123".to_string()),
         ExecuteError::CmdFailed {
           cmd: "make".to_string(),
           code: Some(1),
+          stderr: String::new(),
         },
-      )),
+      )]),
       ..Default::default()
     };
     assert!(!result.is_success());
@@ -287,13 +383,14 @@ mod tests {
   #[test]
   fn dag_result_failure_with_bind_failed() {
     let result = DagResult {
-      bind_failed: Some((
+      bind_failed: HashMap::from([(
         ObjectHash("def456".to_string()),
         ExecuteError::CmdFailed {
           cmd: "ln -s".to_string(),
           code: Some(1),
+          stderr: String::new(),
         },
-      )),
+      )]),
       ..Default::default()
     };
     assert!(!result.is_success());
@@ -337,6 +434,48 @@ mod tests {
     assert_eq!(result.total(), 1);
   }
 
+  #[test]
+  fn skipped_by_follows_chains_to_the_failure() {
+    let hash = |s: &str| ObjectHash(s.to_string());
+    let mut result = DagResult::default();
+    result.build_failed.insert(
+      hash("a"),
+      ExecuteError::CmdFailed {
+        cmd: "make".to_string(),
+        code: Some(1),
+        stderr: String::new(),
+      },
+    );
+    result.build_failed.insert(
+      hash("x"),
+      ExecuteError::CmdFailed {
+        cmd: "make".to_string(),
+        code: Some(2),
+        stderr: String::new(),
+      },
+    );
+    // a <- b <- bind c, and x <- y
+    result
+      .build_skipped
+      .insert(hash("b"), FailedDependency::Build(hash("a")));
+    result
+      .bind_skipped
+      .insert(hash("c"), FailedDependency::Build(hash("b")));
+    result
+      .build_skipped
+      .insert(hash("y"), FailedDependency::Build(hash("x")));
+
+    assert_eq!(
+      result.skipped_by(&FailedDependency::Build(hash("a"))),
+      vec![FailedDependency::Bind(hash("c")), FailedDependency::Build(hash("b"))]
+    );
+    assert_eq!(
+      result.skipped_by(&FailedDependency::Build(hash("x"))),
+      vec![FailedDependency::Build(hash("y"))]
+    );
+    assert_eq!(result.build_total(), 4);
+  }
+
   #[test]
   fn failed_dependency_display() {
     let build_dep = FailedDependency::Build(ObjectHash("abc123".to_string()));
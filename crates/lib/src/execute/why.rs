@@ -0,0 +1,287 @@
+//! Reverse dependency explanations for `sys why`.
+//!
+//! Given a build or bind, walks the [`ExecutionDag`] towards its dependents and
+//! reports every path from a root (a node nothing else depends on, usually a
+//! bind) down to it.
+
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::lua::sources::{SourceLocation, SourceMap};
+use crate::manifest::Manifest;
+use crate::util::hash::ObjectHash;
+
+use super::dag::{DagNode, ExecutionDag};
+use super::graph::NodeKind;
+use super::types::ExecuteError;
+
+/// Upper bound on the number of paths reported for one node.
+pub const MAX_WHY_PATHS: usize = 100;
+
+/// Errors that can occur while explaining a node.
+#[derive(Debug, Error)]
+pub enum WhyError {
+  /// The manifest could not be turned into a DAG.
+  #[error("execution error: {0}")]
+  Execute(#[from] ExecuteError),
+
+  /// No build or bind matched the query.
+  #[error("no build or bind matches '{0}'")]
+  NotFound(String),
+
+  /// More than one build or bind matched the query.
+  #[error("'{query}' is ambiguous, matching: {}", candidates.join(", "))]
+  Ambiguous { query: String, candidates: Vec<String> },
+}
+
+/// A build or bind on a dependency path.
+#[derive(Debug, Clone, Serialize)]
+pub struct WhyNode {
+  pub kind: NodeKind,
+  pub hash: ObjectHash,
+  pub id: Option<String>,
+  /// Where the node was registered, if known.
+  pub source: Option<SourceLocation>,
+}
+
+/// Every path through which a node is required.
+#[derive(Debug, Serialize)]
+pub struct WhyResult {
+  /// The node being explained.
+  pub target: WhyNode,
+
+  /// Paths from a root down to the target (both included). A target that
+  /// nothing depends on has a single path containing only itself.
+  pub paths: Vec<Vec<WhyNode>>,
+
+  /// Whether paths were omitted after reaching [`MAX_WHY_PATHS`].
+  pub truncated: bool,
+}
+
+/// Find the build or bind named by `query`.
+///
+/// An exact `id` match wins; otherwise `query` is treated as a hash prefix.
+pub fn find_node(manifest: &Manifest, query: &str) -> Result<DagNode, WhyError> {
+  let by_id: Vec<DagNode> = manifest
+    .builds
+    .iter()
+    .filter(|(_, def)| def.id.as_deref() == Some(query))
+    .map(|(hash, _)| DagNode::Build(hash.clone()))
+    .chain(
+      manifest
+        .bindings
+        .iter()
+        .filter(|(_, def)| def.id.as_deref() == Some(query))
+        .map(|(hash, _)| DagNode::Bind(hash.clone())),
+    )
+    .collect();
+
+  let matches = if by_id.is_empty() {
+    manifest
+      .builds
+      .keys()
+      .filter(|hash| hash.0.starts_with(query))
+      .map(|hash| DagNode::Build(hash.clone()))
+      .chain(
+        manifest
+          .bindings
+          .keys()
+          .filter(|hash| hash.0.starts_with(query))
+          .map(|hash| DagNode::Bind(hash.clone())),
+      )
+      .collect()
+  } else {
+    by_id
+  };
+
+  match matches.as_slice() {
+    [] => Err(WhyError::NotFound(query.to_string())),
+    [node] => Ok(node.clone()),
+    _ => Err(WhyError::Ambiguous {
+      query: query.to_string(),
+      candidates: matches.iter().map(|n| n.to_string()).collect(),
+    }),
+  }
+}
+
+/// Explain why the build or bind named by `query` is part of `manifest`.
+///
+/// # Arguments
+///
+/// * `manifest` - The evaluated manifest
+/// * `sources` - Source locations recorded during evaluation
+/// * `query` - An `id` or hash prefix
+///
+/// # Returns
+///
+/// Every path from a root down to the node, in a stable order.
+pub fn explain(manifest: &Manifest, sources: &SourceMap, query: &str) -> Result<WhyResult, WhyError> {
+  let target = find_node(manifest, query)?;
+  let dag = ExecutionDag::from_manifest(manifest)?;
+
+  let mut raw_paths: Vec<Vec<DagNode>> = Vec::new();
+  let mut truncated = false;
+  let mut stack: Vec<Vec<DagNode>> = vec![vec![target.clone()]];
+
+  while let Some(path) = stack.pop() {
+    let last = path.last().expect("paths are never empty");
+    let mut dependents = dag.dependents(last);
+
+    if dependents.is_empty() {
+      if raw_paths.len() == MAX_WHY_PATHS {
+        truncated = true;
+        break;
+      }
+      raw_paths.push(path);
+      continue;
+    }
+
+    // Push in reverse so paths come out in sorted order.
+    dependents.sort_by_key(|n| n.to_string());
+    for dependent in dependents.into_iter().rev() {
+      let mut next = path.clone();
+      next.push(dependent);
+      stack.push(next);
+    }
+  }
+
+  let describe = |node: &DagNode| describe_node(manifest, sources, node);
+  let paths = raw_paths
+    .iter()
+    .map(|path| path.iter().rev().map(describe).collect())
+    .collect();
+
+  Ok(WhyResult {
+    target: describe(&target),
+    paths,
+    truncated,
+  })
+}
+
+fn describe_node(manifest: &Manifest, sources: &SourceMap, node: &DagNode) -> WhyNode {
+  match node {
+    DagNode::Build(hash) => WhyNode {
+      kind: NodeKind::Build,
+      hash: hash.clone(),
+      id: manifest.builds.get(hash).and_then(|d| d.id.clone()),
+      source: sources.builds.get(hash).cloned(),
+    },
+    DagNode::Bind(hash) => WhyNode {
+      kind: NodeKind::Bind,
+      hash: hash.clone(),
+      id: manifest.bindings.get(hash).and_then(|d| d.id.clone()),
+      source: sources.binds.get(hash).cloned(),
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::bind::{BindDef, BindInputsDef};
+  use crate::build::{BuildDef, BuildInputs};
+
+  fn build(id: &str, deps: &[&str]) -> BuildDef {
+    let inputs = (!deps.is_empty()).then(|| {
+      BuildInputs::Table(
+        deps
+          .iter()
+          .map(|d| (d.to_string(), BuildInputs::Build(ObjectHash(d.to_string()))))
+          .collect(),
+      )
+    });
+    BuildDef {
+      id: Some(id.to_string()),
+      inputs,
+      create_actions: vec![],
+      outputs: None,
+      sandbox: None,
+    }
+  }
+
+  fn bind(id: &str, dep: &str) -> BindDef {
+    BindDef {
+      id: Some(id.to_string()),
+      inputs: Some(BindInputsDef::Build(ObjectHash(dep.to_string()))),
+      outputs: None,
+      create_actions: vec![],
+      update_actions: None,
+      destroy_actions: vec![],
+      check_actions: None,
+      check_outputs: None,
+    }
+  }
+
+  /// `lib` is used by `app` and `tool`; `app` is used by two binds.
+  fn test_manifest() -> Manifest {
+    let mut manifest = Manifest::default();
+    manifest
+      .builds
+      .insert(ObjectHash("aa01".to_string()), build("lib", &[]));
+    manifest
+      .builds
+      .insert(ObjectHash("bb01".to_string()), build("app", &["aa01"]));
+    manifest
+      .builds
+      .insert(ObjectHash("cc01".to_string()), build("tool", &["aa01"]));
+    manifest
+      .bindings
+      .insert(ObjectHash("dd01".to_string()), bind("link-app", "bb01"));
+    manifest
+      .bindings
+      .insert(ObjectHash("ee01".to_string()), bind("env-app", "bb01"));
+    manifest
+  }
+
+  fn ids(path: &[WhyNode]) -> Vec<&str> {
+    path.iter().map(|n| n.id.as_deref().unwrap()).collect()
+  }
+
+  #[test]
+  fn explain_lists_every_path_from_roots() {
+    let manifest = test_manifest();
+    let mut sources = SourceMap::default();
+    sources.binds.insert(
+      ObjectHash("dd01".to_string()),
+      SourceLocation {
+        file: "init.lua".to_string(),
+        line: Some(12),
+      },
+    );
+
+    let result = explain(&manifest, &sources, "lib").unwrap();
+    assert_eq!(result.target.id.as_deref(), Some("lib"));
+    assert!(!result.truncated);
+
+    let paths: Vec<Vec<&str>> = result.paths.iter().map(|p| ids(p)).collect();
+    assert_eq!(
+      paths,
+      vec![
+        vec!["link-app", "app", "lib"],
+        vec!["env-app", "app", "lib"],
+        vec!["tool", "lib"],
+      ]
+    );
+    assert_eq!(result.paths[0][0].source.as_ref().unwrap().line, Some(12));
+  }
+
+  #[test]
+  fn root_node_has_single_path() {
+    let manifest = test_manifest();
+    let result = explain(&manifest, &SourceMap::default(), "ee0").unwrap();
+    assert_eq!(result.paths.len(), 1);
+    assert_eq!(ids(&result.paths[0]), vec!["env-app"]);
+  }
+
+  #[test]
+  fn find_node_errors() {
+    let manifest = test_manifest();
+    assert!(matches!(find_node(&manifest, "missing"), Err(WhyError::NotFound(_))));
+
+    let mut manifest = manifest;
+    manifest
+      .builds
+      .insert(ObjectHash("aa02".to_string()), build("lib2", &[]));
+    assert!(matches!(find_node(&manifest, "aa"), Err(WhyError::Ambiguous { .. })));
+  }
+}
@@ -1,3 +1,5 @@
+pub mod roots;
+
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::{fs, io};
@@ -6,9 +8,17 @@ use thiserror::Error;
 use tracing::{debug, info, warn};
 use walkdir::WalkDir;
 
+use crate::action::Action;
+use crate::action::actions::fetch_url::{DOWNLOADS_DIR, Integrity, LOCK_SUFFIX, PARTIAL_SUFFIX};
+use crate::action::log::LOG_DIR;
 use crate::build::execute::BUILD_COMPLETE_MARKER;
-use crate::platform::paths::{cache_dir, store_dir};
+use crate::execute::PLAN_MANIFEST_FILENAME;
+use crate::manifest::Manifest;
+use crate::platform::paths::{cache_dir, plans_dir, store_dir};
 use crate::snapshot::SnapshotStore;
+use crate::source::SOURCES_DIR;
+
+pub use roots::TempGcRoot;
 
 #[derive(Debug, Error)]
 pub enum GcError {
@@ -33,15 +43,21 @@ pub struct GcStats {
   pub inputs_scanned: usize,
   pub inputs_deleted: usize,
   pub inputs_bytes_freed: u64,
+  pub sources_scanned: usize,
+  pub sources_deleted: usize,
+  pub sources_bytes_freed: u64,
+  pub downloads_scanned: usize,
+  pub downloads_deleted: usize,
+  pub downloads_bytes_freed: u64,
 }
 
 impl GcStats {
   pub fn total_deleted(&self) -> usize {
-    self.builds_deleted + self.inputs_deleted
+    self.builds_deleted + self.inputs_deleted + self.sources_deleted + self.downloads_deleted
   }
 
   pub fn total_bytes_freed(&self) -> u64 {
-    self.builds_bytes_freed + self.inputs_bytes_freed
+    self.builds_bytes_freed + self.inputs_bytes_freed + self.sources_bytes_freed + self.downloads_bytes_freed
   }
 }
 
@@ -51,8 +67,38 @@ pub struct GcResult {
   pub deleted_paths: Vec<PathBuf>,
 }
 
-fn collect_live_hashes(snapshot_store: &SnapshotStore) -> Result<HashSet<String>, GcError> {
-  let mut live = HashSet::new();
+/// Store objects referenced by a snapshot or a saved plan.
+#[derive(Debug, Default)]
+struct LiveSet {
+  /// Hashes of builds and binds.
+  hashes: HashSet<String>,
+  /// Content hashes of imported sources used by `Source` actions.
+  sources: HashSet<String>,
+  /// Download cache keys of `FetchUrl` actions.
+  downloads: HashSet<String>,
+}
+
+impl LiveSet {
+  /// Keep the imported sources and downloads used by `actions`.
+  fn add_actions(&mut self, actions: &[Action]) {
+    for action in actions {
+      match action {
+        Action::Source { sha256, .. } => {
+          self.sources.insert(sha256.clone());
+        }
+        Action::FetchUrl(opts) => {
+          if let Ok(integrity) = Integrity::parse(&opts.hash) {
+            self.downloads.insert(integrity.cache_key());
+          }
+        }
+        _ => {}
+      }
+    }
+  }
+}
+
+fn collect_live(snapshot_store: &SnapshotStore) -> Result<LiveSet, GcError> {
+  let mut live = LiveSet::default();
 
   let snapshots = snapshot_store
     .list()
@@ -61,12 +107,13 @@ fn collect_live_hashes(snapshot_store: &SnapshotStore) -> Result<HashSet<String>
   for meta in snapshots {
     match snapshot_store.load_snapshot(&meta.id) {
       Ok(snapshot) => {
-        for hash in snapshot.manifest.builds.keys() {
-          live.insert(hash.0.clone());
+        for (hash, build) in &snapshot.manifest.builds {
+          live.hashes.insert(hash.0.clone());
+          live.add_actions(&build.create_actions);
         }
 
         for hash in snapshot.manifest.bindings.keys() {
-          live.insert(hash.0.clone());
+          live.hashes.insert(hash.0.clone());
         }
       }
       Err(e) => {
@@ -75,7 +122,31 @@ fn collect_live_hashes(snapshot_store: &SnapshotStore) -> Result<HashSet<String>
     }
   }
 
-  debug!(count = live.len(), "collected live hashes from snapshots");
+  // `sys.source` imports while a config is evaluated, so a saved plan's
+  // sources have to survive until it is applied
+  if let Ok(entries) = fs::read_dir(plans_dir()) {
+    for entry in entries.flatten() {
+      let path = entry.path().join(PLAN_MANIFEST_FILENAME);
+      let Ok(content) = fs::read_to_string(&path) else {
+        continue;
+      };
+      match serde_json::from_str::<Manifest>(&content) {
+        Ok(manifest) => {
+          for build in manifest.builds.values() {
+            live.add_actions(&build.create_actions);
+          }
+        }
+        Err(e) => warn!(path = %path.display(), error = %e, "skipping unreadable plan"),
+      }
+    }
+  }
+
+  debug!(
+    count = live.hashes.len(),
+    sources = live.sources.len(),
+    downloads = live.downloads.len(),
+    "collected live hashes from snapshots"
+  );
   Ok(live)
 }
 
@@ -95,7 +166,12 @@ fn is_complete_build(path: &std::path::Path) -> bool {
 
 pub fn collect_garbage(dry_run: bool) -> Result<GcResult, GcError> {
   let snapshot_store = SnapshotStore::default_store();
-  let live_hashes = collect_live_hashes(&snapshot_store)?;
+  let LiveSet {
+    hashes: mut live_hashes,
+    sources: live_sources,
+    downloads: live_downloads,
+  } = collect_live(&snapshot_store)?;
+  live_hashes.extend(roots::collect_temp_roots(dry_run));
 
   let mut stats = GcStats::default();
   let mut deleted_paths = Vec::new();
@@ -110,9 +186,26 @@ pub fn collect_garbage(dry_run: bool) -> Result<GcResult, GcError> {
     sweep_inputs_cache(&inputs_cache, &live_hashes, dry_run, &mut stats, &mut deleted_paths)?;
   }
 
+  let sources_dir = store_dir().join(SOURCES_DIR);
+  if sources_dir.exists() {
+    sweep_sources(&sources_dir, &live_sources, dry_run, &mut stats, &mut deleted_paths)?;
+  }
+
+  let downloads_dir = store_dir().join(DOWNLOADS_DIR);
+  if downloads_dir.exists() {
+    sweep_downloads(&downloads_dir, &live_downloads, dry_run, &mut stats, &mut deleted_paths)?;
+  }
+
+  let log_dir = store_dir().join(LOG_DIR);
+  if log_dir.exists() && !dry_run {
+    sweep_logs(&log_dir, &live_hashes)?;
+  }
+
   info!(
     builds_deleted = stats.builds_deleted,
     inputs_deleted = stats.inputs_deleted,
+    sources_deleted = stats.sources_deleted,
+    downloads_deleted = stats.downloads_deleted,
     bytes_freed = stats.total_bytes_freed(),
     dry_run,
     "garbage collection complete"
@@ -231,6 +324,131 @@ fn sweep_inputs_cache(
   Ok(())
 }
 
+/// Remove imported sources that no build in a snapshot copies.
+///
+/// Sources are named by their content hash, which `Source` actions record, so
+/// an import made by an evaluation that was never applied is removed. The
+/// staging directories of imports in progress start with a dot and are left
+/// alone.
+fn sweep_sources(
+  sources_dir: &std::path::Path,
+  live_sources: &HashSet<String>,
+  dry_run: bool,
+  stats: &mut GcStats,
+  deleted_paths: &mut Vec<PathBuf>,
+) -> Result<(), GcError> {
+  for entry in fs::read_dir(sources_dir)?.flatten() {
+    let path = entry.path();
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+      continue;
+    };
+    if name.starts_with('.') {
+      continue;
+    }
+
+    stats.sources_scanned += 1;
+    if live_sources.contains(name) {
+      continue;
+    }
+
+    let size = dir_size(&path);
+    debug!(path = %path.display(), "removing unreferenced source");
+
+    let removed = if dry_run {
+      Ok(())
+    } else if path.is_dir() {
+      fs::remove_dir_all(&path)
+    } else {
+      fs::remove_file(&path)
+    };
+    match removed {
+      Ok(()) => {
+        stats.sources_deleted += 1;
+        stats.sources_bytes_freed += size;
+        deleted_paths.push(path);
+      }
+      Err(e) => {
+        warn!(path = %path.display(), error = %e, "failed to delete imported source");
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Remove cached downloads that no build in a snapshot fetches.
+///
+/// Partial downloads and lock files go along with the download they belong
+/// to. Fetches run under the shared store lock, so none is in progress while
+/// garbage is collected.
+fn sweep_downloads(
+  downloads_dir: &std::path::Path,
+  live_downloads: &HashSet<String>,
+  dry_run: bool,
+  stats: &mut GcStats,
+  deleted_paths: &mut Vec<PathBuf>,
+) -> Result<(), GcError> {
+  for entry in fs::read_dir(downloads_dir)?.flatten() {
+    let path = entry.path();
+    if !path.is_file() {
+      continue;
+    }
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+      continue;
+    };
+
+    stats.downloads_scanned += 1;
+    let key = name
+      .strip_suffix(PARTIAL_SUFFIX)
+      .or_else(|| name.strip_suffix(LOCK_SUFFIX))
+      .unwrap_or(name);
+    if live_downloads.contains(key) {
+      continue;
+    }
+
+    let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+    debug!(path = %path.display(), "removing unreferenced download");
+
+    let removed = if dry_run { Ok(()) } else { fs::remove_file(&path) };
+    match removed {
+      Ok(()) => {
+        stats.downloads_deleted += 1;
+        stats.downloads_bytes_freed += size;
+        deleted_paths.push(path);
+      }
+      Err(e) => {
+        warn!(path = %path.display(), error = %e, "failed to delete cached download");
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Remove the action logs of builds and binds no snapshot references.
+///
+/// Logs are not counted in the stats; they only exist to explain what a build
+/// or bind did, so they go along with the objects they describe.
+fn sweep_logs(log_dir: &std::path::Path, live_hashes: &HashSet<String>) -> Result<(), GcError> {
+  for entry in fs::read_dir(log_dir)?.flatten() {
+    let path = entry.path();
+    let is_live = path
+      .file_name()
+      .and_then(|n| n.to_str())
+      .is_some_and(|name| live_hashes.contains(name));
+    if is_live || !path.is_dir() {
+      continue;
+    }
+
+    debug!(path = %path.display(), "removing unreferenced action logs");
+    if let Err(e) = fs::remove_dir_all(&path) {
+      warn!(path = %path.display(), error = %e, "failed to delete action logs");
+    }
+  }
+
+  Ok(())
+}
+
 fn extract_hash_from_cache_name(name: &str) -> String {
   if let Some(pos) = name.rfind('-') {
     name[pos + 1..].to_string()
@@ -242,6 +460,13 @@ fn extract_hash_from_cache_name(name: &str) -> String {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use crate::action::actions::fetch_url::FetchUrlOpts;
+  use crate::build::BuildDef;
+  use crate::manifest::Manifest;
+  use crate::snapshot::Snapshot;
+  use crate::util::hash::Hashable;
+  use serial_test::serial;
+  use tempfile::TempDir;
 
   #[test]
   fn test_extract_hash_from_cache_name() {
@@ -262,9 +487,142 @@ mod tests {
       inputs_scanned: 5,
       inputs_deleted: 2,
       inputs_bytes_freed: 500,
+      sources_scanned: 4,
+      sources_deleted: 1,
+      sources_bytes_freed: 200,
+      downloads_scanned: 3,
+      downloads_deleted: 1,
+      downloads_bytes_freed: 300,
     };
 
-    assert_eq!(stats.total_deleted(), 5);
-    assert_eq!(stats.total_bytes_freed(), 1500);
+    assert_eq!(stats.total_deleted(), 7);
+    assert_eq!(stats.total_bytes_freed(), 2000);
+  }
+
+  /// Run `f` with the store, snapshots and caches in a temp directory.
+  fn with_temp_dirs(f: impl FnOnce()) {
+    let temp = TempDir::new().unwrap();
+    let root = temp.path();
+    temp_env::with_vars(
+      [
+        ("SYSLUA_ROOT", Some(root.join("root"))),
+        ("SYSLUA_STORE", Some(root.join("store"))),
+        ("SYSLUA_SNAPSHOTS", Some(root.join("snapshots"))),
+        ("XDG_CACHE_HOME", Some(root.join("cache"))),
+        ("LOCALAPPDATA", Some(root.join("cache"))),
+      ],
+      f,
+    );
+  }
+
+  /// Save a snapshot with a single build running `actions`.
+  fn save_snapshot(actions: Vec<Action>) {
+    let build = BuildDef {
+      id: None,
+      inputs: None,
+      create_actions: actions,
+      outputs: None,
+      sandbox: None,
+    };
+    let mut manifest = Manifest::default();
+    manifest.builds.insert(build.compute_hash().unwrap(), build);
+    SnapshotStore::default_store()
+      .save_snapshot(&Snapshot::new(SnapshotStore::generate_id(), None, manifest))
+      .unwrap();
+  }
+
+  #[test]
+  #[serial]
+  fn sweep_keeps_sources_of_snapshot_builds() {
+    with_temp_dirs(|| {
+      let sources = store_dir().join(SOURCES_DIR);
+      for name in ["live", "dead", ".import-abc"] {
+        fs::create_dir_all(sources.join(name)).unwrap();
+        fs::write(sources.join(name).join("file"), name).unwrap();
+      }
+      save_snapshot(vec![Action::Source {
+        name: "src".to_string(),
+        sha256: "live".to_string(),
+      }]);
+
+      let dry = collect_garbage(true).unwrap();
+      assert_eq!(dry.stats.sources_deleted, 1);
+      assert!(sources.join("dead").exists());
+
+      let result = collect_garbage(false).unwrap();
+      assert_eq!(result.stats.sources_scanned, 2);
+      assert_eq!(result.deleted_paths, vec![sources.join("dead")]);
+      assert!(sources.join("live").exists());
+      assert!(sources.join(".import-abc").exists());
+      assert!(!sources.join("dead").exists());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn sweep_keeps_sources_of_saved_plans() {
+    with_temp_dirs(|| {
+      let sources = store_dir().join(SOURCES_DIR);
+      for name in ["planned", "dead"] {
+        fs::create_dir_all(sources.join(name)).unwrap();
+      }
+      let build = BuildDef {
+        id: None,
+        inputs: None,
+        create_actions: vec![Action::Source {
+          name: "src".to_string(),
+          sha256: "planned".to_string(),
+        }],
+        outputs: None,
+        sandbox: None,
+      };
+      let mut manifest = Manifest::default();
+      manifest.builds.insert(build.compute_hash().unwrap(), build);
+      let plan_dir = plans_dir().join(manifest.compute_hash().unwrap().0);
+      fs::create_dir_all(&plan_dir).unwrap();
+      fs::write(
+        plan_dir.join(PLAN_MANIFEST_FILENAME),
+        serde_json::to_string(&manifest).unwrap(),
+      )
+      .unwrap();
+
+      let result = collect_garbage(false).unwrap();
+      assert_eq!(result.deleted_paths, vec![sources.join("dead")]);
+      assert!(sources.join("planned").exists());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn sweep_keeps_downloads_of_snapshot_builds() {
+    with_temp_dirs(|| {
+      let live = "a".repeat(64);
+      let dead = "b".repeat(64);
+      let downloads = store_dir().join(DOWNLOADS_DIR);
+      fs::create_dir_all(&downloads).unwrap();
+      for name in [
+        live.clone(),
+        format!("{}{}", live, LOCK_SUFFIX),
+        dead.clone(),
+        format!("{}{}", dead, PARTIAL_SUFFIX),
+        format!("{}{}", dead, LOCK_SUFFIX),
+      ] {
+        fs::write(downloads.join(name), "data").unwrap();
+      }
+      save_snapshot(vec![Action::FetchUrl(FetchUrlOpts::new(
+        "https://example.com/a.tar.gz",
+        &live,
+      ))]);
+
+      let result = collect_garbage(false).unwrap();
+
+      assert_eq!(result.stats.downloads_scanned, 5);
+      assert_eq!(result.stats.downloads_deleted, 3);
+      assert_eq!(result.stats.downloads_bytes_freed, 12);
+      assert!(downloads.join(&live).exists());
+      assert!(downloads.join(format!("{}{}", live, LOCK_SUFFIX)).exists());
+      assert!(!downloads.join(&dead).exists());
+      assert!(!downloads.join(format!("{}{}", dead, PARTIAL_SUFFIX)).exists());
+    });
   }
 }
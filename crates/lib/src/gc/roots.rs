@@ -0,0 +1,199 @@
+//! Temporary GC roots.
+//!
+//! Short-lived processes such as `sys shell` use store objects that are not
+//! referenced by any snapshot. A [`TempGcRoot`] records those hashes in a file
+//! under `{store}/gcroots/` and holds a shared lock on it for as long as the root
+//! is alive. Garbage collection treats every locked root file as live; a root
+//! file that can be locked exclusively belongs to a process that has exited and
+//! is removed.
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, warn};
+
+use crate::platform::paths::store_dir;
+use crate::store_lock::{LockMode, try_lock};
+use crate::util::hash::ObjectHash;
+
+/// Directory (relative to the store) holding temporary root files.
+const GC_ROOTS_DIR: &str = "gcroots";
+
+/// Returns the directory holding temporary GC root files.
+pub fn gc_roots_dir() -> PathBuf {
+  store_dir().join(GC_ROOTS_DIR)
+}
+
+/// A set of store hashes protected from garbage collection until dropped.
+#[derive(Debug)]
+pub struct TempGcRoot {
+  file: Option<File>,
+  path: PathBuf,
+}
+
+impl TempGcRoot {
+  /// Register `hashes` as a temporary root.
+  ///
+  /// The root stays in effect until the returned value is dropped or the
+  /// process exits.
+  ///
+  /// The file is written and locked under a name garbage collection ignores
+  /// and only then renamed into place, so a collection never sees the root
+  /// unlocked or half written.
+  pub fn register(hashes: &[ObjectHash]) -> io::Result<Self> {
+    let dir = gc_roots_dir();
+    fs::create_dir_all(&dir)?;
+
+    let name = format!(
+      "{}-{}.json",
+      std::process::id(),
+      crate::snapshot::generate_snapshot_id()
+    );
+    let path = dir.join(&name);
+    let staging = dir.join(format!(".{}", name));
+
+    let mut file = OpenOptions::new()
+      .read(true)
+      .write(true)
+      .create_new(true)
+      .open(&staging)?;
+    let written = try_lock(&file, LockMode::Shared).and_then(|()| {
+      let names: Vec<&str> = hashes.iter().map(|h| h.0.as_str()).collect();
+      serde_json::to_writer(&mut file, &names).map_err(io::Error::other)?;
+      file.flush()?;
+      fs::rename(&staging, &path)
+    });
+    if let Err(e) = written {
+      drop(file);
+      let _ = fs::remove_file(&staging);
+      return Err(e);
+    }
+
+    debug!(path = %path.display(), count = hashes.len(), "registered temporary gc root");
+    Ok(Self { file: Some(file), path })
+  }
+
+  /// Path of the root file.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempGcRoot {
+  fn drop(&mut self) {
+    // Close the handle first so removal also works on Windows.
+    drop(self.file.take());
+    if let Err(e) = fs::remove_file(&self.path) {
+      warn!(path = %self.path.display(), error = %e, "failed to remove temporary gc root");
+    }
+  }
+}
+
+/// Collect hashes from all temporary roots still held by a running process.
+///
+/// Root files left behind by exited processes are deleted unless `dry_run` is set.
+pub(crate) fn collect_temp_roots(dry_run: bool) -> HashSet<String> {
+  let mut live = HashSet::new();
+
+  let Ok(entries) = fs::read_dir(gc_roots_dir()) else {
+    return live;
+  };
+
+  for entry in entries.flatten() {
+    let path = entry.path();
+    // Roots that are still being registered
+    if entry.file_name().to_string_lossy().starts_with('.') {
+      continue;
+    }
+    let Ok(mut file) = File::open(&path) else {
+      continue;
+    };
+
+    if try_lock(&file, LockMode::Exclusive).is_ok() {
+      drop(file);
+      if !dry_run {
+        debug!(path = %path.display(), "removing stale gc root");
+        let _ = fs::remove_file(&path);
+      }
+      continue;
+    }
+
+    let mut contents = String::new();
+    if file.read_to_string(&mut contents).is_err() {
+      continue;
+    }
+    match serde_json::from_str::<Vec<String>>(&contents) {
+      Ok(hashes) => live.extend(hashes),
+      Err(e) => warn!(path = %path.display(), error = %e, "ignoring unreadable gc root"),
+    }
+  }
+
+  debug!(count = live.len(), "collected temporary gc roots");
+  live
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  #[test]
+  #[serial]
+  fn temp_root_is_live_until_dropped() {
+    let temp_dir = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_STORE", Some(temp_dir.path()), || {
+      let root = TempGcRoot::register(&[ObjectHash("abc123".to_string())]).unwrap();
+      assert!(root.path().exists());
+      assert!(collect_temp_roots(false).contains("abc123"));
+
+      let path = root.path().to_path_buf();
+      drop(root);
+      assert!(!path.exists());
+      assert!(collect_temp_roots(false).is_empty());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn stale_root_is_removed() {
+    let temp_dir = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_STORE", Some(temp_dir.path()), || {
+      let dir = gc_roots_dir();
+      fs::create_dir_all(&dir).unwrap();
+      let stale = dir.join("1-1.json");
+      fs::write(&stale, r#"["deadbeef"]"#).unwrap();
+
+      assert!(collect_temp_roots(true).is_empty());
+      assert!(stale.exists(), "dry run keeps stale roots");
+
+      assert!(collect_temp_roots(false).is_empty());
+      assert!(!stale.exists());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn roots_being_registered_are_ignored() {
+    let temp_dir = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_STORE", Some(temp_dir.path()), || {
+      let dir = gc_roots_dir();
+      fs::create_dir_all(&dir).unwrap();
+      let staging = dir.join(".1-1.json");
+      fs::write(&staging, "").unwrap();
+
+      assert!(collect_temp_roots(false).is_empty());
+      assert!(staging.exists(), "unlocked staging files are not treated as stale");
+
+      let root = TempGcRoot::register(&[ObjectHash("abc123".to_string())]).unwrap();
+      let names: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+      assert_eq!(names.len(), 2, "{:?}", names);
+      assert!(root.path().exists());
+    });
+  }
+}
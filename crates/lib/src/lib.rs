@@ -9,6 +9,7 @@
 pub mod action;
 pub mod bind;
 pub mod build;
+pub mod cache;
 pub mod consts;
 pub mod eval;
 pub mod execute;
@@ -20,7 +21,11 @@ pub mod manifest;
 pub mod outputs;
 pub mod placeholder;
 pub mod platform;
+pub mod repl;
+pub mod shell;
 pub mod snapshot;
+pub mod source;
 pub mod store_lock;
+pub mod store_verify;
 pub mod update;
 pub mod util;
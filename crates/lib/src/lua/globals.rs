@@ -5,7 +5,9 @@
 //! - `sys.os` - Operating system name (e.g., "darwin", "linux", "windows")
 //! - `sys.arch` - CPU architecture (e.g., "x86_64", "aarch64")
 //! - `sys.path` - Path manipulation utilities
+//! - `sys.json`, `sys.toml`, `sys.yaml` - Deterministic encoding and decoding
 //! - `sys.build{}` - Define a build
+//! - `sys.source()` - Import a local file or directory into the store
 //! - `sys.bind{}` - Define a bind
 //! - `sys.register_build_ctx_method()` - Register a custom BuildCtx method
 //! - `sys.register_bind_ctx_method()` - Register a custom BindCtx method
@@ -23,6 +25,7 @@ use crate::bind::lua::register_sys_bind;
 use crate::build::lua::register_sys_build;
 use crate::manifest::Manifest;
 use crate::platform::{self, Platform};
+use crate::source::lua::register_sys_source;
 
 /// Register the `sys` global table in the Lua runtime.
 ///
@@ -43,6 +46,11 @@ pub fn register_globals(lua: &Lua, manifest: Rc<RefCell<Manifest>>) -> LuaResult
   let path = helpers::path::create_path_helpers(lua)?;
   sys.set("path", path)?;
 
+  // Serialization formats
+  sys.set("json", helpers::codec::create_json_codec(lua)?)?;
+  sys.set("toml", helpers::codec::create_toml_codec(lua)?)?;
+  sys.set("yaml", helpers::codec::create_yaml_codec(lua)?)?;
+
   // Environment variable placeholder (resolves at execution time)
   let getenv = lua.create_function(|_, name: String| Ok(format!("$${{{{env:{}}}}}", name)))?;
   sys.set("getenv", getenv)?;
@@ -86,6 +94,9 @@ pub fn register_globals(lua: &Lua, manifest: Rc<RefCell<Manifest>>) -> LuaResult
   // Register sys.build{}
   register_sys_build(lua, &sys, manifest.clone())?;
 
+  // Register sys.source()
+  register_sys_source(lua, &sys, manifest.clone())?;
+
   // Register sys.bind{}
   register_sys_bind(lua, &sys, manifest)?;
 
@@ -416,6 +427,143 @@ mod tests {
     }
   }
 
+  mod codecs {
+    use super::*;
+
+    #[test]
+    fn json_encode_sorts_keys() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result: String = lua
+        .load(r#"return sys.json.encode({ zeta = 1, alpha = { 3, 2.5, "é" }, mid = { b = true, a = false } })"#)
+        .eval()?;
+      assert_eq!(result, r#"{"alpha":[3,2.5,"é"],"mid":{"a":false,"b":true},"zeta":1}"#);
+      Ok(())
+    }
+
+    #[test]
+    fn json_encode_is_stable_across_insertion_order() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let same: bool = lua
+        .load(
+          r#"
+          local a, b = {}, {}
+          for i = 1, 50 do a["k" .. i] = i / 3 end
+          for i = 50, 1, -1 do b["k" .. i] = i / 3 end
+          return sys.json.encode(a, { pretty = true }) == sys.json.encode(b, { pretty = true })
+          "#,
+        )
+        .eval()?;
+      assert!(same);
+      Ok(())
+    }
+
+    #[test]
+    fn json_round_trips_numbers_and_unicode() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result: String = lua
+        .load(
+          r#"
+          local v = sys.json.decode('{"big": 9007199254740993, "f": 0.1, "s": "é😀"}')
+          assert(math.type(v.big) == "integer")
+          return sys.json.encode(v)
+          "#,
+        )
+        .eval()?;
+      assert_eq!(result, r#"{"big":9007199254740993,"f":0.1,"s":"é😀"}"#);
+      Ok(())
+    }
+
+    #[test]
+    fn json_decode_reports_errors() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let err = lua.load(r#"return sys.json.decode("{")"#).exec().unwrap_err();
+      assert!(err.to_string().contains("json decode"), "unexpected error: {}", err);
+      Ok(())
+    }
+
+    #[test]
+    fn json_encode_rejects_circular_tables() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let err: String = lua
+        .load(
+          r#"
+          local t = {}
+          t.t = t
+          local ok, err = pcall(sys.json.encode, t)
+          assert(not ok)
+          return tostring(err)
+          "#,
+        )
+        .eval()?;
+      assert!(err.contains("circular reference"), "unexpected error: {}", err);
+
+      // The same table twice, but not inside itself, is fine
+      let result: String = lua
+        .load(r#"local shared = { 1 } return sys.json.encode({ a = shared, b = shared })"#)
+        .eval()?;
+      assert_eq!(result, r#"{"a":[1],"b":[1]}"#);
+      Ok(())
+    }
+
+    #[test]
+    fn json_encode_turns_sparse_tables_into_objects() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result: String = lua
+        .load(r#"return sys.json.encode({ huge = { [2^40] = true }, holes = { 1, nil, 3 }, seq = { 1, 2 } })"#)
+        .eval()?;
+      assert_eq!(
+        result,
+        r#"{"holes":{"1":1,"3":3},"huge":{"1099511627776":true},"seq":[1,2]}"#
+      );
+      Ok(())
+    }
+
+    #[test]
+    fn toml_round_trip() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result: String = lua
+        .load(
+          r#"
+          local doc = sys.toml.decode('title = "x"\nwhen = 1979-05-27T07:32:00Z\n[server]\nport = 8080\n')
+          assert(doc.server.port == 8080)
+          assert(doc.when == "1979-05-27T07:32:00Z")
+          return sys.toml.encode({ server = { port = 8080, hosts = { "a", "b" } }, title = "x" })
+          "#,
+        )
+        .eval()?;
+      assert_eq!(
+        result,
+        "title = \"x\"\n\n[server]\nhosts = [\"a\", \"b\"]\nport = 8080\n"
+      );
+      Ok(())
+    }
+
+    #[test]
+    fn toml_encode_requires_table() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let err = lua.load(r#"return sys.toml.encode({ 1, 2 })"#).exec().unwrap_err();
+      assert!(err.to_string().contains("top level"), "unexpected error: {}", err);
+      Ok(())
+    }
+
+    #[test]
+    fn yaml_round_trip() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result: String = lua
+        .load(
+          r#"
+          local doc = sys.yaml.decode("name: app\nports:\n  - 80\n  - 443\n1: one\nversion: '1.0'\n")
+          assert(doc.ports[2] == 443)
+          assert(doc["1"] == "one")
+          return sys.yaml.encode(doc)
+          "#,
+        )
+        .eval()?;
+      assert_eq!(result, "'1': one\nname: app\nports:\n- 80\n- 443\nversion: '1.0'\n");
+      Ok(())
+    }
+  }
+
   mod ctx_method_registration {
     use super::*;
 
@@ -524,6 +672,23 @@ mod tests {
       Ok(())
     }
 
+    #[test]
+    fn cannot_override_builtin_remove_in_bind() -> LuaResult<()> {
+      let lua = create_test_lua()?;
+      let result = lua
+        .load(
+          r#"
+        sys.register_bind_ctx_method("remove", function(ctx) return "hacked" end)
+        "#,
+        )
+        .exec();
+
+      assert!(result.is_err());
+      let err = result.unwrap_err().to_string();
+      assert!(err.contains("cannot override built-in bind ctx method 'remove'"));
+      Ok(())
+    }
+
     #[test]
     fn cannot_override_builtin_fetch_url_in_build() -> LuaResult<()> {
       let lua = create_test_lua()?;
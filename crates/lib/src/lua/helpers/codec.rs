@@ -0,0 +1,179 @@
+//! `sys.json`, `sys.toml` and `sys.yaml`: serde-backed encoders and decoders.
+//!
+//! Values go through the same Lua/JSON conversion as build outputs, so object
+//! keys are always encoded in sorted order and floats use serde's shortest
+//! round-trip formatting. Generated config files are byte-for-byte stable
+//! between runs and don't change the hashes of the builds that write them.
+//!
+//! Tables whose keys are exactly `1..n` encode as arrays, any other table
+//! (including an empty or sparse one) as an object, and tables that contain
+//! themselves are an error. `nil` and JSON `null` are the same value, so nulls
+//! in decoded objects are dropped.
+
+use mlua::prelude::*;
+use serde_json::Value as JsonValue;
+
+use crate::outputs::lua::{json_to_lua_value, lua_value_to_json};
+
+/// Whether the options table passed to `encode` asks for pretty output.
+fn pretty(opts: Option<LuaTable>) -> LuaResult<bool> {
+  match opts {
+    Some(opts) => Ok(opts.get::<Option<bool>>("pretty")?.unwrap_or(false)),
+    None => Ok(false),
+  }
+}
+
+/// Create the `sys.json` table.
+///
+/// - `encode(value, { pretty? })` - Encode a value as JSON, compact by default
+/// - `decode(str)` - Decode a JSON document
+pub fn create_json_codec(lua: &Lua) -> LuaResult<LuaTable> {
+  let json = lua.create_table()?;
+
+  json.set(
+    "encode",
+    lua.create_function(|_, (value, opts): (LuaValue, Option<LuaTable>)| {
+      let value = lua_value_to_json(value)?;
+      let encoded = if pretty(opts)? {
+        serde_json::to_string_pretty(&value)
+      } else {
+        serde_json::to_string(&value)
+      };
+      encoded.map_err(|e| LuaError::external(format!("json encode: {}", e)))
+    })?,
+  )?;
+
+  json.set(
+    "decode",
+    lua.create_function(|lua, s: LuaString| {
+      let value: JsonValue =
+        serde_json::from_slice(&s.as_bytes()).map_err(|e| LuaError::external(format!("json decode: {}", e)))?;
+      json_to_lua_value(lua, &value)
+    })?,
+  )?;
+
+  Ok(json)
+}
+
+/// Create the `sys.toml` table.
+///
+/// - `encode(table, { pretty? })` - Encode a table as a TOML document
+/// - `decode(str)` - Decode a TOML document; dates and times become strings
+pub fn create_toml_codec(lua: &Lua) -> LuaResult<LuaTable> {
+  let toml = lua.create_table()?;
+
+  toml.set(
+    "encode",
+    lua.create_function(|_, (value, opts): (LuaValue, Option<LuaTable>)| {
+      let value = lua_value_to_json(value)?;
+      if !value.is_object() {
+        return Err(LuaError::external(
+          "toml encode: the top level value must be a table with string keys",
+        ));
+      }
+      let encoded = if pretty(opts)? {
+        toml::to_string_pretty(&value)
+      } else {
+        toml::to_string(&value)
+      };
+      encoded.map_err(|e| LuaError::external(format!("toml encode: {}", e)))
+    })?,
+  )?;
+
+  toml.set(
+    "decode",
+    lua.create_function(|lua, s: String| {
+      let table: toml::Table = toml::from_str(&s).map_err(|e| LuaError::external(format!("toml decode: {}", e)))?;
+      json_to_lua_value(lua, &toml_to_json(toml::Value::Table(table))?)
+    })?,
+  )?;
+
+  Ok(toml)
+}
+
+fn toml_to_json(value: toml::Value) -> LuaResult<JsonValue> {
+  Ok(match value {
+    toml::Value::String(s) => JsonValue::String(s),
+    toml::Value::Integer(i) => JsonValue::Number(i.into()),
+    toml::Value::Float(f) => JsonValue::Number(
+      serde_json::Number::from_f64(f)
+        .ok_or_else(|| LuaError::external(format!("toml decode: {} is not a finite number", f)))?,
+    ),
+    toml::Value::Boolean(b) => JsonValue::Bool(b),
+    toml::Value::Datetime(dt) => JsonValue::String(dt.to_string()),
+    toml::Value::Array(arr) => JsonValue::Array(arr.into_iter().map(toml_to_json).collect::<LuaResult<_>>()?),
+    toml::Value::Table(table) => JsonValue::Object(
+      table
+        .into_iter()
+        .map(|(k, v)| Ok((k, toml_to_json(v)?)))
+        .collect::<LuaResult<_>>()?,
+    ),
+  })
+}
+
+/// Create the `sys.yaml` table.
+///
+/// - `encode(value)` - Encode a value as a YAML document
+/// - `decode(str)` - Decode a single YAML document; tags are ignored and
+///   scalar keys become strings
+pub fn create_yaml_codec(lua: &Lua) -> LuaResult<LuaTable> {
+  let yaml = lua.create_table()?;
+
+  yaml.set(
+    "encode",
+    lua.create_function(|_, value: LuaValue| {
+      serde_yaml_ng::to_string(&lua_value_to_json(value)?)
+        .map_err(|e| LuaError::external(format!("yaml encode: {}", e)))
+    })?,
+  )?;
+
+  yaml.set(
+    "decode",
+    lua.create_function(|lua, s: LuaString| {
+      let value: serde_yaml_ng::Value =
+        serde_yaml_ng::from_slice(&s.as_bytes()).map_err(|e| LuaError::external(format!("yaml decode: {}", e)))?;
+      json_to_lua_value(lua, &yaml_to_json(value)?)
+    })?,
+  )?;
+
+  Ok(yaml)
+}
+
+fn yaml_to_json(value: serde_yaml_ng::Value) -> LuaResult<JsonValue> {
+  use serde_yaml_ng::Value;
+
+  Ok(match value {
+    Value::Null => JsonValue::Null,
+    Value::Bool(b) => JsonValue::Bool(b),
+    Value::Number(n) => {
+      if let Some(i) = n.as_i64() {
+        JsonValue::Number(i.into())
+      } else if let Some(f) = n.as_f64().and_then(serde_json::Number::from_f64) {
+        JsonValue::Number(f)
+      } else {
+        return Err(LuaError::external(format!("yaml decode: {} is not a finite number", n)));
+      }
+    }
+    Value::String(s) => JsonValue::String(s),
+    Value::Sequence(seq) => JsonValue::Array(seq.into_iter().map(yaml_to_json).collect::<LuaResult<_>>()?),
+    Value::Mapping(mapping) => {
+      let mut map = serde_json::Map::new();
+      for (k, v) in mapping {
+        let key = match k {
+          Value::String(s) => s,
+          Value::Number(n) => n.to_string(),
+          Value::Bool(b) => b.to_string(),
+          other => {
+            return Err(LuaError::external(format!(
+              "yaml decode: unsupported mapping key {:?}",
+              other
+            )));
+          }
+        };
+        map.insert(key, yaml_to_json(v)?);
+      }
+      JsonValue::Object(map)
+    }
+    Value::Tagged(tagged) => yaml_to_json(tagged.value)?,
+  })
+}
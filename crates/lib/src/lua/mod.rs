@@ -10,8 +10,10 @@
 //! - [`globals`] - Global Lua functions (`build()`, `bind()`, `input()`, etc.)
 //! - [`helpers`] - Lua helper modules exposed to user scripts
 //! - [`runtime`] - Low-level Lua VM management
+//! - [`sources`] - Source locations of registered builds and binds
 
 pub mod entrypoint;
 pub mod globals;
 pub mod helpers;
 pub mod runtime;
+pub mod sources;
@@ -0,0 +1,156 @@
+//! Source locations of registered builds and binds.
+//!
+//! When tracking is enabled on a Lua state, `sys.build` and `sys.bind` record
+//! the file and line they were called from. Locations are kept outside the
+//! manifest so they never affect object hashes.
+
+use std::collections::BTreeMap;
+
+use mlua::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::util::hash::ObjectHash;
+
+/// Where a build or bind was registered.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceLocation {
+  /// Lua file (or chunk name) that called `sys.build`/`sys.bind`.
+  pub file: String,
+  /// Line of the call, if known.
+  pub line: Option<usize>,
+}
+
+impl std::fmt::Display for SourceLocation {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self.line {
+      Some(line) => write!(f, "{}:{}", self.file, line),
+      None => write!(f, "{}", self.file),
+    }
+  }
+}
+
+/// Source locations keyed by object hash.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SourceMap {
+  pub builds: BTreeMap<ObjectHash, SourceLocation>,
+  pub binds: BTreeMap<ObjectHash, SourceLocation>,
+}
+
+/// Start recording source locations on `lua`.
+pub fn enable_tracking(lua: &Lua) {
+  lua.set_app_data(SourceMap::default());
+}
+
+/// Stop recording and return everything recorded so far.
+pub fn take_sources(lua: &Lua) -> SourceMap {
+  lua.remove_app_data::<SourceMap>().unwrap_or_default()
+}
+
+/// Record the caller of the running Rust function as the source of a build.
+pub(crate) fn record_build(lua: &Lua, hash: &ObjectHash) {
+  record(lua, hash, |map| &mut map.builds);
+}
+
+/// Record the caller of the running Rust function as the source of a bind.
+pub(crate) fn record_bind(lua: &Lua, hash: &ObjectHash) {
+  record(lua, hash, |map| &mut map.binds);
+}
+
+fn record(
+  lua: &Lua,
+  hash: &ObjectHash,
+  target: impl FnOnce(&mut SourceMap) -> &mut BTreeMap<ObjectHash, SourceLocation>,
+) {
+  let Some(mut map) = lua.app_data_mut::<SourceMap>() else {
+    return;
+  };
+  let entries = target(&mut map);
+  if entries.contains_key(hash) {
+    return;
+  }
+
+  // Level 0 is the Rust function itself; level 1 is its Lua caller.
+  let location = lua.inspect_stack(1, |debug| {
+    let source = debug.source();
+    let file = source
+      .source
+      .as_deref()
+      .map(|s| {
+        s.strip_prefix('@')
+          .or_else(|| s.strip_prefix('='))
+          .unwrap_or(s)
+          .to_string()
+      })
+      .unwrap_or_else(|| "?".to_string());
+    SourceLocation {
+      file,
+      line: debug.current_line(),
+    }
+  });
+
+  if let Some(location) = location {
+    entries.insert(hash.clone(), location);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  use super::*;
+  use crate::lua::runtime::create_runtime;
+  use crate::manifest::Manifest;
+
+  #[test]
+  fn records_calling_line() -> LuaResult<()> {
+    let manifest = Rc::new(RefCell::new(Manifest::default()));
+    let lua = create_runtime(manifest.clone(), false)?;
+    enable_tracking(&lua);
+
+    lua
+      .load(
+        r#"
+local b = sys.build({
+  id = "traced",
+  create = function(_, ctx) return { out = ctx.out } end,
+})
+sys.bind({
+  inputs = { b = b },
+  create = function(_, ctx) ctx:exec("true") end,
+  destroy = function(_, ctx) ctx:exec("true") end,
+})
+"#,
+      )
+      .set_name("=config.lua")
+      .exec()?;
+
+    let sources = take_sources(&lua);
+    let build_hash = manifest.borrow().builds.keys().next().unwrap().clone();
+    let bind_hash = manifest.borrow().bindings.keys().next().unwrap().clone();
+
+    assert_eq!(
+      sources.builds[&build_hash],
+      SourceLocation {
+        file: "config.lua".to_string(),
+        line: Some(2)
+      }
+    );
+    assert_eq!(sources.binds[&bind_hash].line, Some(6));
+    Ok(())
+  }
+
+  #[test]
+  fn untracked_state_records_nothing() -> LuaResult<()> {
+    let manifest = Rc::new(RefCell::new(Manifest::default()));
+    let lua = create_runtime(manifest, false)?;
+
+    lua
+      .load(r#"sys.build({ create = function(_, ctx) return { out = ctx.out } end })"#)
+      .exec()?;
+
+    let sources = take_sources(&lua);
+    assert!(sources.builds.is_empty());
+    Ok(())
+  }
+}
@@ -3,8 +3,20 @@ use std::collections::BTreeMap;
 use mlua::prelude::*;
 use serde_json::Value as JsonValue;
 
+/// How deeply tables may be nested before conversion gives up.
+const MAX_DEPTH: usize = 256;
+
 /// Convert a Lua value to a serde_json::Value.
-fn lua_value_to_json(value: LuaValue) -> LuaResult<JsonValue> {
+///
+/// Tables whose keys are exactly `1..n` become arrays, all other tables
+/// objects, whose keys serde_json keeps sorted. Tables that contain themselves
+/// are an error.
+pub(crate) fn lua_value_to_json(value: LuaValue) -> LuaResult<JsonValue> {
+  to_json(value, &mut Vec::new())
+}
+
+/// Convert `value`, where `parents` are the tables currently being converted.
+fn to_json(value: LuaValue, parents: &mut Vec<*const std::ffi::c_void>) -> LuaResult<JsonValue> {
   match value {
     LuaValue::Nil => Ok(JsonValue::Null),
     LuaValue::Boolean(b) => Ok(JsonValue::Bool(b)),
@@ -14,58 +26,76 @@ fn lua_value_to_json(value: LuaValue) -> LuaResult<JsonValue> {
       if n.is_finite() {
         Ok(serde_json::Number::from_f64(n).map_or(JsonValue::Null, JsonValue::Number))
       } else {
-        Err(LuaError::external(
-          "output numbers must be finite (not NaN or Infinity)",
-        ))
+        Err(LuaError::external("numbers must be finite (not NaN or Infinity)"))
       }
     }
     LuaValue::String(s) => Ok(JsonValue::String(s.to_str()?.to_string())),
     LuaValue::Table(t) => {
-      // Check if this is an array (sequential integer keys starting at 1) or object
-      let mut is_array = true;
-      let mut max_index = 0;
-      for pair in t.clone().pairs::<LuaValue, LuaValue>() {
-        let (k, _) = pair?;
-        match k {
-          LuaValue::Integer(i) if i > 0 => {
-            max_index = max_index.max(i as usize);
-          }
-          _ => {
-            is_array = false;
-            break;
-          }
-        }
+      let pointer = t.to_pointer();
+      if parents.contains(&pointer) {
+        return Err(LuaError::external("circular reference"));
+      }
+      if parents.len() >= MAX_DEPTH {
+        return Err(LuaError::external(format!(
+          "tables are nested more than {} deep",
+          MAX_DEPTH
+        )));
       }
 
-      if is_array && max_index > 0 {
-        // Convert as array
-        let mut arr = Vec::with_capacity(max_index);
-        for i in 1..=max_index {
-          let v: LuaValue = t.get(i)?;
-          arr.push(lua_value_to_json(v)?);
-        }
-        Ok(JsonValue::Array(arr))
-      } else {
-        // Convert as object
-        let mut map = serde_json::Map::new();
-        for pair in t.pairs::<String, LuaValue>() {
-          let (k, v) = pair?;
-          map.insert(k, lua_value_to_json(v)?);
+      parents.push(pointer);
+      let result = table_to_json(t, parents);
+      parents.pop();
+      result
+    }
+    LuaValue::Function(_) => Err(LuaError::external("functions cannot be serialized")),
+    LuaValue::Thread(_) => Err(LuaError::external("threads cannot be serialized")),
+    LuaValue::UserData(_) => Err(LuaError::external("userdata cannot be serialized")),
+    LuaValue::LightUserData(_) => Err(LuaError::external("light userdata cannot be serialized")),
+    LuaValue::Error(e) => Err(LuaError::external(format!("errors cannot be serialized: {}", e))),
+    _ => Err(LuaError::external("unsupported value type")),
+  }
+}
+
+fn table_to_json(t: LuaTable, parents: &mut Vec<*const std::ffi::c_void>) -> LuaResult<JsonValue> {
+  let pairs = t.pairs::<LuaValue, LuaValue>().collect::<LuaResult<Vec<_>>>()?;
+
+  // Keys are distinct, so positive integers no larger than their count are exactly `1..n`
+  let is_array = !pairs.is_empty()
+    && pairs
+      .iter()
+      .all(|(k, _)| matches!(k, LuaValue::Integer(i) if *i > 0 && *i as usize <= pairs.len()));
+
+  if is_array {
+    let mut arr = vec![JsonValue::Null; pairs.len()];
+    for (k, v) in pairs {
+      let LuaValue::Integer(i) = k else {
+        unreachable!("array keys are integers")
+      };
+      arr[i as usize - 1] = to_json(v, parents)?;
+    }
+    Ok(JsonValue::Array(arr))
+  } else {
+    let mut map = serde_json::Map::new();
+    for (k, v) in pairs {
+      let key = match k {
+        LuaValue::String(s) => s.to_str()?.to_string(),
+        LuaValue::Integer(i) => i.to_string(),
+        LuaValue::Number(n) => n.to_string(),
+        other => {
+          return Err(LuaError::external(format!(
+            "table keys must be strings or numbers, got {}",
+            other.type_name()
+          )));
         }
-        Ok(JsonValue::Object(map))
-      }
+      };
+      map.insert(key, to_json(v, parents)?);
     }
-    LuaValue::Function(_) => Err(LuaError::external("output values cannot be functions")),
-    LuaValue::Thread(_) => Err(LuaError::external("output values cannot be threads")),
-    LuaValue::UserData(_) => Err(LuaError::external("output values cannot be userdata")),
-    LuaValue::LightUserData(_) => Err(LuaError::external("output values cannot be light userdata")),
-    LuaValue::Error(e) => Err(LuaError::external(format!("output values cannot be errors: {}", e))),
-    _ => Err(LuaError::external("unsupported output value type")),
+    Ok(JsonValue::Object(map))
   }
 }
 
 /// Convert a JSON value to a Lua value.
-fn json_to_lua_value(lua: &Lua, value: &JsonValue) -> LuaResult<LuaValue> {
+pub(crate) fn json_to_lua_value(lua: &Lua, value: &JsonValue) -> LuaResult<LuaValue> {
   match value {
     JsonValue::Null => Ok(LuaValue::Nil),
     JsonValue::Bool(b) => Ok(LuaValue::Boolean(*b)),
@@ -75,7 +105,7 @@ fn json_to_lua_value(lua: &Lua, value: &JsonValue) -> LuaResult<LuaValue> {
       } else if let Some(f) = n.as_f64() {
         Ok(LuaValue::Number(f))
       } else {
-        Err(LuaError::external("invalid number in output"))
+        Err(LuaError::external("invalid number"))
       }
     }
     JsonValue::String(s) => Ok(LuaValue::String(lua.create_string(s)?)),
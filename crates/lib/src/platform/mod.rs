@@ -7,6 +7,7 @@ pub mod immutable;
 pub mod link;
 pub mod os;
 pub mod paths;
+pub mod sandbox;
 
 use arch::Arch;
 use os::Os;
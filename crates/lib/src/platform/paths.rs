@@ -123,6 +123,18 @@ pub fn snapshots_dir() -> PathBuf {
     .unwrap_or_else(|_| root_dir().join("snapshots"))
 }
 
+/// Returns the file listing public keys trusted to sign substituted builds.
+pub fn trusted_keys_path() -> PathBuf {
+  std::env::var("SYSLUA_TRUSTED_KEYS")
+    .map(PathBuf::from)
+    .unwrap_or_else(|_| root_dir().join("trusted-keys"))
+}
+
+/// Returns the default directory for generated signing keys.
+pub fn keys_dir() -> PathBuf {
+  root_dir().join("keys")
+}
+
 pub fn plans_dir() -> PathBuf {
   std::env::var("SYSLUA_PLANS")
     .map(PathBuf::from)
@@ -0,0 +1,493 @@
+//! Build sandbox.
+//!
+//! On Linux, the `exec` actions of a build run in fresh user, mount and
+//! network namespaces. The command starts on an empty root filesystem where
+//! only the paths listed in its [`Sandbox`] are mounted, each at its host
+//! location:
+//!
+//! - writable paths, i.e. the build's output directory (which holds `$TMPDIR`)
+//! - read-only paths: the store paths of dependency builds and the host path
+//!   allowlist, [`DEFAULT_SANDBOX_PATHS`] unless the build sets its own
+//! - the device nodes in [`SANDBOX_DEVICES`]
+//!
+//! The network namespace only has a loopback interface, which is down, so
+//! downloads must go through `fetch_url`, which syslua runs itself.
+//!
+//! Namespaces are created by the child process between `fork` and `exec`, so
+//! everything it needs is prepared beforehand and the child only makes system
+//! calls. No privileges are required, but some hosts block unprivileged user
+//! namespaces. The first sandboxed command probes for this once, and builds
+//! then fail with an error naming the kernel setting instead of a bare `EPERM`.
+//!
+//! ## Platform Behavior
+//!
+//! - **Linux**: Commands run confined as described above
+//! - **macOS / Windows**: The sandbox is ignored and commands run unconfined
+
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::sync::OnceLock;
+
+/// Host paths visible to sandboxed builds that don't list their own.
+pub const DEFAULT_SANDBOX_PATHS: &[&str] = &["/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64"];
+
+/// Device nodes available in every sandbox.
+pub const SANDBOX_DEVICES: &[&str] = &["/dev/null", "/dev/zero", "/dev/full", "/dev/random", "/dev/urandom"];
+
+/// Kernel settings that keep unprivileged processes from using user
+/// namespaces, with the value that does.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const USERNS_SETTINGS: &[(&str, &str)] = &[
+  ("kernel.unprivileged_userns_clone", "0"),
+  ("kernel.apparmor_restrict_unprivileged_userns", "1"),
+  ("user.max_user_namespaces", "0"),
+];
+
+/// Result of probing whether sandboxes can be created on this host.
+#[cfg(target_os = "linux")]
+static AVAILABLE: OnceLock<Result<(), String>> = OnceLock::new();
+
+/// The host paths a sandboxed command can see.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sandbox {
+  read_only: Vec<PathBuf>,
+  writable: Vec<PathBuf>,
+}
+
+impl Sandbox {
+  /// Create a sandbox in which no host paths are visible.
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Make `path` visible read-only. Paths that don't exist are skipped.
+  pub fn with_read_only(mut self, path: impl Into<PathBuf>) -> Self {
+    self.read_only.push(path.into());
+    self
+  }
+
+  /// Make `path` visible and writable.
+  pub fn with_writable(mut self, path: impl Into<PathBuf>) -> Self {
+    self.writable.push(path.into());
+    self
+  }
+
+  pub fn read_only(&self) -> &[PathBuf] {
+    &self.read_only
+  }
+
+  pub fn writable(&self) -> &[PathBuf] {
+    &self.writable
+  }
+
+  /// Arrange for `command` to run inside this sandbox, starting in `cwd`.
+  ///
+  /// The returned directory is the mount point of the sandbox root and must
+  /// be kept until the command has exited.
+  ///
+  /// # Errors
+  ///
+  /// `PermissionDenied` if the host doesn't allow unprivileged processes to
+  /// create the namespaces a sandbox needs.
+  #[cfg(target_os = "linux")]
+  pub(crate) fn confine(
+    &self,
+    command: &mut tokio::process::Command,
+    cwd: &Path,
+  ) -> std::io::Result<tempfile::TempDir> {
+    check_available().map_err(|message| std::io::Error::new(std::io::ErrorKind::PermissionDenied, message))?;
+
+    let root = tempfile::Builder::new().prefix("syslua-sandbox-").tempdir()?;
+    let plan = linux::Plan::new(self, root.path(), cwd)?;
+
+    // SAFETY: the closure runs between fork and exec. It only makes system
+    // calls on data prepared above and never allocates.
+    unsafe {
+      command.pre_exec(move || plan.enter().map_err(std::io::Error::from));
+    }
+    Ok(root)
+  }
+
+  /// Every host path mounted in the sandbox and whether it is writable.
+  #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+  fn mounts(&self) -> impl Iterator<Item = (&Path, bool)> {
+    let devices = SANDBOX_DEVICES.iter().map(|dev| (Path::new(dev), true));
+    let read_only = self.read_only.iter().map(|path| (path.as_path(), false));
+    let writable = self.writable.iter().map(|path| (path.as_path(), true));
+    devices.chain(read_only).chain(writable)
+  }
+}
+
+/// Check that sandboxes can be created on this host, probing only once.
+#[cfg(target_os = "linux")]
+pub(crate) fn check_available() -> Result<(), &'static str> {
+  AVAILABLE
+    .get_or_init(probe)
+    .as_ref()
+    .map(|_| ())
+    .map_err(String::as_str)
+}
+
+/// Whether tests that create sandboxes can run here; logs why not.
+#[cfg(all(test, target_os = "linux"))]
+pub(crate) fn sandboxes_work() -> bool {
+  match check_available() {
+    Ok(()) => true,
+    Err(message) => {
+      eprintln!("skipping sandbox test: {}", message);
+      false
+    }
+  }
+}
+
+/// Enter an empty sandbox in a child process that then executes a file that
+/// doesn't exist. `ENOENT` means the sandbox was set up; any other error came
+/// from setting it up.
+#[cfg(target_os = "linux")]
+fn probe() -> Result<(), String> {
+  use std::os::unix::process::CommandExt;
+
+  let root = tempfile::Builder::new()
+    .prefix("syslua-sandbox-")
+    .tempdir()
+    .map_err(|e| e.to_string())?;
+  let plan = linux::Plan::new(&Sandbox::new(), root.path(), Path::new("/")).map_err(|e| e.to_string())?;
+  let mut command = std::process::Command::new(root.path().join("probe"));
+  // SAFETY: as in `confine`, the closure only makes system calls.
+  unsafe {
+    command.pre_exec(move || plan.enter().map_err(std::io::Error::from));
+  }
+
+  match command.spawn() {
+    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+    Ok(mut child) => {
+      let _ = child.wait();
+      Ok(())
+    }
+    Err(e) => {
+      let blocking = USERNS_SETTINGS.iter().map(|(name, _)| *name).find(|name| {
+        let path = Path::new("/proc/sys").join(name.replace('.', "/"));
+        std::fs::read_to_string(path).is_ok_and(|value| {
+          USERNS_SETTINGS
+            .iter()
+            .any(|(n, blocked)| n == name && value.trim() == *blocked)
+        })
+      });
+      tracing::warn!(error = %e, setting = ?blocking, "build sandbox is unavailable");
+      Err(unavailable_message(&e, blocking))
+    }
+  }
+}
+
+/// Explain that sandboxes can't be created, naming the kernel setting that
+/// blocks them if it is known.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn unavailable_message(error: &std::io::Error, blocking: Option<&str>) -> String {
+  let fix = match blocking {
+    Some(setting) => {
+      let (_, blocked) = USERNS_SETTINGS
+        .iter()
+        .find(|(name, _)| *name == setting)
+        .expect("blocking setting is listed");
+      format!("`{}` is set to {}; change it with sysctl", setting, blocked)
+    }
+    None => {
+      let settings: Vec<String> = USERNS_SETTINGS.iter().map(|(name, _)| format!("`{}`", name)).collect();
+      format!("check the kernel settings {}", settings.join(", "))
+    }
+  };
+  format!(
+    "this host does not allow unprivileged user namespaces ({}): {}",
+    error, fix
+  )
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+  use std::collections::HashSet;
+  use std::ffi::{CStr, CString};
+  use std::io;
+  use std::os::unix::ffi::OsStrExt;
+  use std::path::{Component, Path, PathBuf};
+
+  use rustix::fs::{Mode, OFlags, StatVfsMountFlags, mkdir, open, statvfs};
+  use rustix::io::Errno;
+  use rustix::mount::{
+    MountFlags, MountPropagationFlags, UnmountFlags, mount, mount_bind_recursive, mount_change, mount_remount, unmount,
+  };
+  use rustix::process::{chdir, getgid, getuid, pivot_root};
+  use rustix::thread::{UnshareFlags, unshare_unsafe};
+  use tracing::debug;
+
+  use super::Sandbox;
+
+  /// Mount flags the kernel refuses to clear when remounting inside a user
+  /// namespace; read-only remounts must carry them over from the host mount.
+  const LOCKED_FLAGS: StatVfsMountFlags = StatVfsMountFlags::NOSUID
+    .union(StatVfsMountFlags::NODEV)
+    .union(StatVfsMountFlags::NOEXEC)
+    .union(StatVfsMountFlags::NOATIME)
+    .union(StatVfsMountFlags::NODIRATIME)
+    .union(StatVfsMountFlags::RELATIME);
+
+  struct BindMount {
+    source: CString,
+    target: CString,
+    /// Flags to remount with, for read-only mounts.
+    remount: Option<MountFlags>,
+  }
+
+  /// Everything the child needs to set up the sandbox, as C strings.
+  pub(super) struct Plan {
+    root: CString,
+    dirs: Vec<CString>,
+    files: Vec<CString>,
+    mounts: Vec<BindMount>,
+    uid_map: Vec<u8>,
+    gid_map: Vec<u8>,
+    cwd: CString,
+  }
+
+  impl Plan {
+    pub(super) fn new(sandbox: &Sandbox, root: &Path, cwd: &Path) -> io::Result<Self> {
+      let mut plan = Plan {
+        root: c_path(root)?,
+        dirs: Vec::new(),
+        files: Vec::new(),
+        mounts: Vec::new(),
+        uid_map: format!("{0} {0} 1", getuid().as_raw()).into_bytes(),
+        gid_map: format!("{0} {0} 1", getgid().as_raw()).into_bytes(),
+        cwd: c_path(cwd)?,
+      };
+
+      // Parents are mounted before the paths beneath them
+      let mut mounts: Vec<(&Path, bool)> = sandbox.mounts().collect();
+      mounts.sort_by_key(|(path, _)| path.components().count());
+
+      let mut seen = HashSet::new();
+      for (path, writable) in mounts {
+        if !path.is_absolute() {
+          return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("sandbox path must be absolute: {}", path.display()),
+          ));
+        }
+        let metadata = match std::fs::metadata(path) {
+          Ok(metadata) => metadata,
+          Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            debug!(path = ?path, "sandbox path does not exist, skipping");
+            continue;
+          }
+          Err(e) => return Err(e),
+        };
+
+        let target = root.join(path.strip_prefix("/").unwrap_or(path));
+        let mut dirs: Vec<PathBuf> = target
+          .ancestors()
+          .skip(1)
+          .take_while(|dir| *dir != root)
+          .map(Path::to_path_buf)
+          .collect();
+        dirs.reverse();
+        if metadata.is_dir() {
+          dirs.push(target.clone());
+        } else {
+          plan.files.push(c_path(&target)?);
+        }
+        for dir in dirs {
+          if seen.insert(dir.clone()) {
+            plan.dirs.push(c_path(&dir)?);
+          }
+        }
+
+        let remount = if writable {
+          None
+        } else {
+          let host_flags = statvfs(path)?.f_flag & LOCKED_FLAGS;
+          let flags = MountFlags::from_bits_truncate(host_flags.bits() as u32);
+          Some(flags | MountFlags::BIND | MountFlags::RDONLY)
+        };
+        plan.mounts.push(BindMount {
+          source: c_path(path)?,
+          target: c_path(&target)?,
+          remount,
+        });
+      }
+
+      Ok(plan)
+    }
+
+    /// Enter the sandbox. Runs in the child between fork and exec.
+    pub(super) fn enter(&self) -> rustix::io::Result<()> {
+      // SAFETY: the child is single-threaded and no file descriptor table is
+      // unshared.
+      unsafe { unshare_unsafe(UnshareFlags::NEWUSER | UnshareFlags::NEWNS | UnshareFlags::NEWNET)? };
+      write_file(c"/proc/self/setgroups", b"deny")?;
+      write_file(c"/proc/self/uid_map", &self.uid_map)?;
+      write_file(c"/proc/self/gid_map", &self.gid_map)?;
+
+      // Keep our mounts from propagating back to the host
+      mount_change(c"/", MountPropagationFlags::REC | MountPropagationFlags::PRIVATE)?;
+      mount(
+        c"tmpfs",
+        &self.root,
+        c"tmpfs",
+        MountFlags::NOSUID | MountFlags::NODEV,
+        None::<&CStr>,
+      )?;
+
+      for dir in &self.dirs {
+        match mkdir(dir, Mode::from_raw_mode(0o755)) {
+          Ok(()) | Err(Errno::EXIST) => {}
+          Err(e) => return Err(e),
+        }
+      }
+      for file in &self.files {
+        open(
+          file,
+          OFlags::CREATE | OFlags::WRONLY | OFlags::CLOEXEC,
+          Mode::from_raw_mode(0o644),
+        )?;
+      }
+      for bind in &self.mounts {
+        mount_bind_recursive(&bind.source, &bind.target)?;
+        if let Some(flags) = bind.remount {
+          mount_remount(&bind.target, flags, c"")?;
+        }
+      }
+
+      // Swap the root and drop every other host mount
+      chdir(&self.root)?;
+      pivot_root(c".", c".")?;
+      unmount(c".", UnmountFlags::DETACH)?;
+      mount_remount(c"/", MountFlags::RDONLY | MountFlags::NOSUID | MountFlags::NODEV, c"")?;
+      chdir(&self.cwd)
+    }
+  }
+
+  fn write_file(path: &CStr, content: &[u8]) -> rustix::io::Result<()> {
+    let fd = open(path, OFlags::WRONLY | OFlags::CLOEXEC, Mode::empty())?;
+    rustix::io::write(&fd, content)?;
+    Ok(())
+  }
+
+  fn c_path(path: &Path) -> io::Result<CString> {
+    // Normalize away `.` components so targets line up with their sources
+    let normalized: PathBuf = path.components().filter(|c| *c != Component::CurDir).collect();
+    CString::new(normalized.as_os_str().as_bytes()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+  }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+  use std::time::Duration;
+
+  use tempfile::TempDir;
+  use tokio::process::Command;
+
+  use super::*;
+
+  /// Run `script` with `/bin/sh` inside `sandbox`.
+  async fn run(sandbox: &Sandbox, cwd: &Path, script: &str) -> std::process::Output {
+    let mut command = Command::new("/bin/sh");
+    command.args(["-c", script]).current_dir(cwd).env_clear();
+    let _root = sandbox.confine(&mut command, cwd).unwrap();
+    tokio::time::timeout(Duration::from_secs(30), command.output())
+      .await
+      .unwrap()
+      .unwrap()
+  }
+
+  #[test]
+  fn unavailable_sandbox_names_the_setting_and_escape_hatch() {
+    let eperm = std::io::Error::from_raw_os_error(1);
+
+    let message = unavailable_message(&eperm, Some("kernel.apparmor_restrict_unprivileged_userns"));
+    assert!(
+      message.contains("`kernel.apparmor_restrict_unprivileged_userns` is set to 1"),
+      "{}",
+      message
+    );
+
+    let message = unavailable_message(&eperm, None);
+    for (setting, _) in USERNS_SETTINGS {
+      assert!(message.contains(setting), "{}", message);
+    }
+
+    let error = crate::execute::types::ExecuteError::Sandbox { message }.to_string();
+    assert!(error.contains("sandbox = false"), "{}", error);
+  }
+
+  #[test]
+  fn probe_succeeds_where_sandboxes_work() {
+    if !sandboxes_work() {
+      return;
+    }
+    assert_eq!(probe(), Ok(()));
+  }
+
+  fn default_sandbox(out: &Path) -> Sandbox {
+    DEFAULT_SANDBOX_PATHS
+      .iter()
+      .fold(Sandbox::new(), |sandbox, path| sandbox.with_read_only(path))
+      .with_writable(out)
+  }
+
+  #[tokio::test]
+  async fn only_listed_paths_are_visible() {
+    if !sandboxes_work() {
+      return;
+    }
+    let out = TempDir::new().unwrap();
+    let hidden = TempDir::new().unwrap();
+    std::fs::write(hidden.path().join("secret"), "hidden").unwrap();
+
+    let script = format!(
+      "/bin/echo built > result && /bin/cat {} ; /bin/ls /",
+      hidden.path().join("secret").display()
+    );
+    let output = run(&default_sandbox(out.path()), out.path(), &script).await;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert_eq!(std::fs::read_to_string(out.path().join("result")).unwrap(), "built\n");
+    assert!(stdout.contains("usr"), "allowed directory missing: {}", stdout);
+    assert!(!stdout.contains("hidden"), "host file leaked: {}", stdout);
+    assert!(!stdout.contains("home"), "unlisted directory visible: {}", stdout);
+  }
+
+  #[tokio::test]
+  async fn read_only_paths_cannot_be_written() {
+    if !sandboxes_work() {
+      return;
+    }
+    let out = TempDir::new().unwrap();
+    let dep = TempDir::new().unwrap();
+    std::fs::write(dep.path().join("lib"), "dependency").unwrap();
+
+    let sandbox = default_sandbox(out.path()).with_read_only(dep.path());
+    let script = format!("/bin/cat {0}/lib && /bin/echo changed > {0}/lib", dep.path().display());
+    let output = run(&sandbox, out.path(), &script).await;
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "dependency");
+    assert!(!output.status.success());
+    assert_eq!(std::fs::read_to_string(dep.path().join("lib")).unwrap(), "dependency");
+  }
+
+  #[tokio::test]
+  async fn network_is_unavailable() {
+    if !sandboxes_work() || !Path::new("/bin/bash").exists() {
+      return;
+    }
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let out = TempDir::new().unwrap();
+
+    // The host's loopback is reachable outside the sandbox but not inside
+    let script = format!("/bin/bash -c 'echo > /dev/tcp/127.0.0.1/{}'", port);
+    let unconfined = Command::new("/bin/sh").args(["-c", &script]).output().await.unwrap();
+    assert!(unconfined.status.success());
+
+    let output = run(&default_sandbox(out.path()), out.path(), &script).await;
+    assert!(!output.status.success(), "connected to the host from the sandbox");
+  }
+}
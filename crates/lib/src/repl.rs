@@ -0,0 +1,313 @@
+//! Interactive Lua sessions for `sys repl`.
+//!
+//! A [`ReplSession`] sets up the same Lua state that [`evaluate_config`] would
+//! (the `sys` global, resolved inputs, `package.path` and input `setup()`
+//! calls) but does not run the config's own `setup`. Chunks are then evaluated
+//! one at a time, and the builds and binds each chunk registers are reported.
+//!
+//! [`evaluate_config`]: crate::eval::evaluate_config
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+use mlua::prelude::*;
+
+use crate::bind::BIND_REF_TYPE;
+use crate::build::BUILD_REF_TYPE;
+use crate::eval::{EvalError, EvalOptions, prepare_config};
+use crate::lua::runtime;
+use crate::manifest::Manifest;
+use crate::util::hash::ObjectHash;
+
+/// Global holding the config's inputs table inside the session.
+pub const INPUTS_GLOBAL: &str = "inputs";
+
+/// Maximum table nesting shown when formatting values.
+const MAX_FORMAT_DEPTH: usize = 4;
+
+/// The outcome of evaluating one chunk.
+#[derive(Debug, Default)]
+pub struct ReplOutput {
+  /// Formatted values returned by the chunk.
+  pub values: Vec<String>,
+
+  /// Builds registered by the chunk.
+  pub builds: Vec<ObjectHash>,
+
+  /// Binds registered by the chunk.
+  pub binds: Vec<ObjectHash>,
+}
+
+/// A Lua session with a config's inputs loaded.
+pub struct ReplSession {
+  lua: Lua,
+  manifest: Rc<RefCell<Manifest>>,
+  setup: Option<LuaFunction>,
+}
+
+impl ReplSession {
+  /// Create a session, optionally loading the inputs of a config.
+  ///
+  /// With a config, its inputs are resolved and set up exactly as during
+  /// evaluation and exposed through the `inputs` global. The config's `setup`
+  /// is kept aside and only runs through [`ReplSession::run_setup`].
+  ///
+  /// # Arguments
+  ///
+  /// * `config_path` - Config to take inputs from, if any
+  /// * `options` - Evaluation options
+  pub fn new(config_path: Option<&Path>, options: &EvalOptions) -> Result<Self, EvalError> {
+    let manifest = Rc::new(RefCell::new(Manifest::default()));
+    let lua = runtime::create_runtime(manifest.clone(), options.impure)?;
+
+    let setup = match config_path {
+      Some(path) => {
+        let (setup, inputs_table) = prepare_config(&lua, path, options.events.as_ref())?;
+        lua.globals().set(INPUTS_GLOBAL, inputs_table)?;
+        Some(setup)
+      }
+      None => {
+        lua.globals().set(INPUTS_GLOBAL, lua.create_table()?)?;
+        None
+      }
+    };
+
+    Ok(Self { lua, manifest, setup })
+  }
+
+  /// Evaluate a chunk of Lua.
+  ///
+  /// The chunk is first tried as an expression (so `sys.os` prints its value)
+  /// and otherwise run as statements.
+  pub fn eval(&self, source: &str) -> LuaResult<ReplOutput> {
+    let func = match self
+      .lua
+      .load(format!("return {}", source))
+      .set_name("=repl")
+      .into_function()
+    {
+      Ok(func) => func,
+      Err(expr_err) => match self.lua.load(source).set_name("=repl").into_function() {
+        Ok(func) => func,
+        // `1 +` is only unfinished when read as an expression
+        Err(_) if is_incomplete_input(&expr_err) => return Err(expr_err),
+        Err(e) => return Err(e),
+      },
+    };
+
+    self.track(|| {
+      let values: LuaMultiValue = func.call(())?;
+      Ok(values.iter().map(format_value).collect())
+    })
+  }
+
+  /// Run the config's `setup(inputs)` function, as `sys apply` would.
+  pub fn run_setup(&self) -> LuaResult<ReplOutput> {
+    let Some(setup) = &self.setup else {
+      return Err(LuaError::external("no config loaded"));
+    };
+    let inputs: LuaValue = self.lua.globals().get(INPUTS_GLOBAL)?;
+    self.track(|| {
+      setup.call::<()>(inputs)?;
+      Ok(vec![])
+    })
+  }
+
+  /// Whether a config (and so a `setup` function) was loaded.
+  pub fn has_config(&self) -> bool {
+    self.setup.is_some()
+  }
+
+  /// The manifest of everything registered so far.
+  pub fn manifest(&self) -> Manifest {
+    self.manifest.borrow().clone()
+  }
+
+  /// Run `f` and report the builds and binds registered while it ran.
+  fn track(&self, f: impl FnOnce() -> LuaResult<Vec<String>>) -> LuaResult<ReplOutput> {
+    let (builds_before, binds_before) = {
+      let manifest = self.manifest.borrow();
+      let builds: BTreeSet<ObjectHash> = manifest.builds.keys().cloned().collect();
+      let binds: BTreeSet<ObjectHash> = manifest.bindings.keys().cloned().collect();
+      (builds, binds)
+    };
+
+    let values = f()?;
+
+    let manifest = self.manifest.borrow();
+    Ok(ReplOutput {
+      values,
+      builds: manifest
+        .builds
+        .keys()
+        .filter(|h| !builds_before.contains(*h))
+        .cloned()
+        .collect(),
+      binds: manifest
+        .bindings
+        .keys()
+        .filter(|h| !binds_before.contains(*h))
+        .cloned()
+        .collect(),
+    })
+  }
+}
+
+/// Whether a Lua error means the chunk is unfinished and more lines are needed.
+pub fn is_incomplete_input(err: &LuaError) -> bool {
+  matches!(
+    err,
+    LuaError::SyntaxError {
+      incomplete_input: true,
+      ..
+    }
+  )
+}
+
+/// Format a Lua value for display.
+///
+/// `BuildRef` and `BindRef` tables are labelled with their type; other tables
+/// are printed with sorted keys, up to a limited depth.
+pub fn format_value(value: &LuaValue) -> String {
+  let mut out = String::new();
+  write_value(&mut out, value, 0);
+  out
+}
+
+fn write_value(out: &mut String, value: &LuaValue, depth: usize) {
+  match value {
+    LuaValue::Nil => out.push_str("nil"),
+    LuaValue::Boolean(b) => out.push_str(&b.to_string()),
+    LuaValue::Integer(i) => out.push_str(&i.to_string()),
+    LuaValue::Number(n) => out.push_str(&n.to_string()),
+    LuaValue::String(s) => {
+      let _ = write!(out, "{:?}", s.to_string_lossy());
+    }
+    LuaValue::Table(table) => write_table(out, table, depth),
+    LuaValue::Function(_) => out.push_str("<function>"),
+    LuaValue::UserData(_) | LuaValue::LightUserData(_) => out.push_str("<userdata>"),
+    other => out.push_str(&format!("<{}>", other.type_name())),
+  }
+}
+
+fn write_table(out: &mut String, table: &LuaTable, depth: usize) {
+  let type_name = table
+    .metatable()
+    .and_then(|mt| mt.get::<String>("__type").ok())
+    .filter(|t| t == BUILD_REF_TYPE || t == BIND_REF_TYPE);
+  if let Some(type_name) = type_name {
+    out.push_str(&type_name);
+    out.push(' ');
+  }
+
+  if depth >= MAX_FORMAT_DEPTH {
+    out.push_str("{ ... }");
+    return;
+  }
+
+  let mut entries: Vec<(String, LuaValue)> = table
+    .pairs::<LuaValue, LuaValue>()
+    .filter_map(Result::ok)
+    .map(|(k, v)| {
+      let key = match &k {
+        LuaValue::String(s) => s.to_string_lossy().to_string(),
+        other => format!("[{}]", format_value(other)),
+      };
+      (key, v)
+    })
+    .collect();
+  entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+  if entries.is_empty() {
+    out.push_str("{}");
+    return;
+  }
+
+  let indent = "  ".repeat(depth + 1);
+  out.push_str("{\n");
+  for (key, value) in entries {
+    let _ = write!(out, "{}{} = ", indent, key);
+    write_value(out, &value, depth + 1);
+    out.push_str(",\n");
+  }
+  out.push_str(&"  ".repeat(depth));
+  out.push('}');
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn eval_expression_and_statement() {
+    let session = ReplSession::new(None, &EvalOptions::default()).unwrap();
+
+    let output = session.eval("1 + 2").unwrap();
+    assert_eq!(output.values, vec!["3"]);
+
+    let output = session.eval("x = { b = 'two', a = 1 }").unwrap();
+    assert!(output.values.is_empty());
+
+    let output = session.eval("x").unwrap();
+    assert_eq!(output.values, vec!["{\n  a = 1,\n  b = \"two\",\n}"]);
+  }
+
+  #[test]
+  fn eval_reports_registered_builds() {
+    let session = ReplSession::new(None, &EvalOptions::default()).unwrap();
+
+    let output = session
+      .eval(
+        r#"sys.build({
+          id = "repl-test",
+          create = function(_, ctx)
+            return { out = ctx.out }
+          end,
+        })"#,
+      )
+      .unwrap();
+
+    assert_eq!(output.builds.len(), 1);
+    assert!(output.binds.is_empty());
+    assert!(output.values[0].starts_with("BuildRef {"));
+    assert!(output.values[0].contains("id = \"repl-test\""));
+    assert_eq!(session.manifest().builds.len(), 1);
+
+    // Re-registering the same build is deduplicated and reports nothing new
+    let again = session
+      .eval(
+        r#"sys.build({
+          id = "repl-test",
+          create = function(_, ctx)
+            return { out = ctx.out }
+          end,
+        })"#,
+      )
+      .unwrap();
+    assert!(again.builds.is_empty());
+  }
+
+  #[test]
+  fn incomplete_input_is_detected() {
+    let session = ReplSession::new(None, &EvalOptions::default()).unwrap();
+
+    let err = session.eval("function f()").unwrap_err();
+    assert!(is_incomplete_input(&err));
+
+    let err = session.eval("1 +").unwrap_err();
+    assert!(is_incomplete_input(&err));
+
+    let err = session.eval("1 +* 2").unwrap_err();
+    assert!(!is_incomplete_input(&err));
+  }
+
+  #[test]
+  fn run_setup_requires_config() {
+    let session = ReplSession::new(None, &EvalOptions::default()).unwrap();
+    assert!(!session.has_config());
+    assert!(session.run_setup().is_err());
+  }
+}
@@ -0,0 +1,313 @@
+//! Ephemeral build environments for `sys shell`.
+//!
+//! A shell environment realizes builds from a config (or from individual package
+//! expressions) and exposes their `bin`/`man` outputs through `PATH` and
+//! `MANPATH`. No binds are executed and no snapshot is recorded; the realized
+//! builds are protected from garbage collection by a [`TempGcRoot`] for as long
+//! as the environment is alive.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+use thiserror::Error;
+use tracing::{debug, info};
+
+use crate::eval::{EvalError, EvalOptions, evaluate_config, evaluate_packages};
+use crate::execute::{BuildResult, ExecuteConfig, ExecuteError, ExecutionDag, execute_builds};
+use crate::gc::TempGcRoot;
+use crate::manifest::Manifest;
+use crate::store_lock::{LockMode, StoreLock, StoreLockError};
+use crate::util::hash::ObjectHash;
+
+/// Environment variable set inside a shell so prompts and scripts can detect it.
+pub const SHELL_MARKER_VAR: &str = "SYSLUA_SHELL";
+
+/// Errors that can occur while preparing a shell environment.
+#[derive(Debug, Error)]
+pub enum ShellError {
+  /// Config or package evaluation failed.
+  #[error("evaluation error: {0}")]
+  Eval(#[from] EvalError),
+
+  /// Execution setup failed (e.g. invalid manifest).
+  #[error("execution error: {0}")]
+  Execute(#[from] ExecuteError),
+
+  /// A build failed to realize.
+  #[error("build {hash} failed: {source}")]
+  BuildFailed {
+    hash: ObjectHash,
+    #[source]
+    source: ExecuteError,
+  },
+
+  /// Store lock acquisition failed.
+  #[error("failed to acquire store lock: {0}")]
+  Lock(#[from] StoreLockError),
+
+  /// Registering the temporary GC root failed.
+  #[error("failed to register gc root: {0}")]
+  GcRoot(#[source] io::Error),
+}
+
+/// Options for preparing a shell environment.
+#[derive(Debug, Clone, Default)]
+pub struct ShellOptions {
+  /// Execution configuration (parallelism, etc.)
+  pub execute: ExecuteConfig,
+
+  /// Allow impure Lua libs (io, os). Breaks determinism.
+  pub impure: bool,
+
+  /// Package expressions (e.g. `pkgs.cli.ripgrep`) to realize instead of the
+  /// config's own builds. The config then only provides inputs.
+  pub packages: Vec<String>,
+}
+
+/// A realized shell environment.
+#[derive(Debug)]
+pub struct ShellEnvironment {
+  /// Builds exposed in the environment, keyed by hash.
+  pub builds: HashMap<ObjectHash, BuildResult>,
+
+  /// Directories prepended to `PATH`.
+  pub path: Vec<PathBuf>,
+
+  /// Directories prepended to `MANPATH`.
+  pub manpath: Vec<PathBuf>,
+
+  /// Keeps the realized builds alive until the environment is dropped.
+  pub gc_root: TempGcRoot,
+}
+
+impl ShellEnvironment {
+  /// Environment variables for the shell process.
+  ///
+  /// `PATH` and `MANPATH` are prefixed with the build directories, keeping
+  /// whatever the current process already has after them.
+  pub fn env_vars(&self) -> Vec<(String, OsString)> {
+    let mut vars = vec![("PATH".to_string(), prepend_paths(&self.path, std::env::var_os("PATH")))];
+
+    if !self.manpath.is_empty() {
+      // An empty trailing component keeps the default man path searched as well.
+      let existing = std::env::var_os("MANPATH").unwrap_or_default();
+      vars.push(("MANPATH".to_string(), prepend_paths(&self.manpath, Some(existing))));
+    }
+
+    vars.push((SHELL_MARKER_VAR.to_string(), OsString::from("1")));
+    vars
+  }
+}
+
+/// Evaluate a config (or package expressions) and realize a shell environment.
+///
+/// Only builds are executed, through [`execute_builds`]. Binds in the config are
+/// ignored. The store is held with a shared lock while realizing, and all builds
+/// in the evaluated closure are registered as a temporary GC root before any of
+/// them are realized.
+///
+/// # Arguments
+///
+/// * `config_path` - Config to evaluate (or to take inputs from, with packages)
+/// * `options` - Shell options
+///
+/// # Returns
+///
+/// The realized [`ShellEnvironment`].
+pub async fn prepare_shell(config_path: &Path, options: &ShellOptions) -> Result<ShellEnvironment, ShellError> {
+  info!(config = %config_path.display(), packages = ?options.packages, "preparing shell environment");
+
+  let eval_options = EvalOptions {
+    impure: options.impure,
+    events: options.execute.events.clone(),
+  };
+  let (manifest, selected) = if options.packages.is_empty() {
+    let mut manifest = evaluate_config(config_path, &eval_options)?;
+    manifest.bindings.clear();
+    let selected: Vec<ObjectHash> = manifest.builds.keys().cloned().collect();
+    (manifest, selected)
+  } else {
+    let (manifest, requested) = evaluate_packages(config_path, &options.packages, &eval_options)?;
+    (build_closure(&manifest, &requested)?, requested)
+  };
+
+  debug!(
+    builds = manifest.builds.len(),
+    selected = selected.len(),
+    "shell manifest ready"
+  );
+
+  let _lock = StoreLock::acquire(LockMode::Shared, "shell")?;
+  let all_hashes: Vec<ObjectHash> = manifest.builds.keys().cloned().collect();
+  let gc_root = TempGcRoot::register(&all_hashes).map_err(ShellError::GcRoot)?;
+
+  let mut result = execute_builds(&manifest, &options.execute).await?;
+  if let Some((hash, source)) = result.build_failed.drain().next() {
+    return Err(ShellError::BuildFailed { hash, source });
+  }
+
+  let mut builds = HashMap::new();
+  let mut path = Vec::new();
+  let mut manpath = Vec::new();
+  for hash in selected {
+    let Some(build) = result.realized.remove(&hash) else {
+      continue;
+    };
+    collect_search_dirs(&build, &mut path, &mut manpath);
+    builds.insert(hash, build);
+  }
+
+  Ok(ShellEnvironment {
+    builds,
+    path,
+    manpath,
+    gc_root,
+  })
+}
+
+/// Restrict a manifest to the given builds and everything they depend on.
+fn build_closure(manifest: &Manifest, roots: &[ObjectHash]) -> Result<Manifest, ExecuteError> {
+  let dag = ExecutionDag::from_manifest(manifest)?;
+
+  let mut closure = Manifest::default();
+  for hash in dag.build_closure(roots) {
+    let def = manifest
+      .builds
+      .get(&hash)
+      .ok_or_else(|| ExecuteError::BuildNotFound(hash.clone()))?;
+    closure.builds.insert(hash, def.clone());
+  }
+  Ok(closure)
+}
+
+/// Add a build's binary and man page directories to the search lists.
+///
+/// `bin`/`man` outputs may name either a directory or a single file, in which
+/// case the containing directory is used (for man pages, the directory above a
+/// `manN` section). Without a `bin` output, `$out/bin` is used if it exists.
+fn collect_search_dirs(build: &BuildResult, path: &mut Vec<PathBuf>, manpath: &mut Vec<PathBuf>) {
+  let bin = output_path(build, "bin")
+    .map(|p| containing_dir(&p))
+    .or_else(|| Some(build.store_path.join("bin")).filter(|p| p.is_dir()));
+  if let Some(dir) = bin
+    && !path.contains(&dir)
+  {
+    path.push(dir);
+  }
+
+  let man = output_path(build, "man")
+    .map(|p| {
+      let dir = containing_dir(&p);
+      let is_section = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with("man") && n.len() > 3);
+      match dir.parent() {
+        Some(parent) if is_section => parent.to_path_buf(),
+        _ => dir,
+      }
+    })
+    .or_else(|| Some(build.store_path.join("share").join("man")).filter(|p| p.is_dir()));
+  if let Some(dir) = man
+    && !manpath.contains(&dir)
+  {
+    manpath.push(dir);
+  }
+}
+
+fn output_path(build: &BuildResult, name: &str) -> Option<PathBuf> {
+  match build.outputs.get(name) {
+    Some(JsonValue::String(s)) => Some(PathBuf::from(s)),
+    _ => None,
+  }
+}
+
+fn containing_dir(path: &Path) -> PathBuf {
+  if path.is_file() {
+    path
+      .parent()
+      .map(Path::to_path_buf)
+      .unwrap_or_else(|| path.to_path_buf())
+  } else {
+    path.to_path_buf()
+  }
+}
+
+fn prepend_paths(dirs: &[PathBuf], existing: Option<OsString>) -> OsString {
+  let mut all: Vec<PathBuf> = dirs.to_vec();
+  if let Some(existing) = existing {
+    all.extend(std::env::split_paths(&existing));
+  }
+  std::env::join_paths(all).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn build_with_outputs(store_path: &Path, outputs: &[(&str, PathBuf)]) -> BuildResult {
+    BuildResult {
+      store_path: store_path.to_path_buf(),
+      outputs: outputs
+        .iter()
+        .map(|(k, v)| (k.to_string(), JsonValue::String(v.to_string_lossy().to_string())))
+        .collect(),
+      action_results: vec![],
+    }
+  }
+
+  #[test]
+  fn search_dirs_from_file_outputs() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let out = temp.path();
+    std::fs::create_dir_all(out.join("share/man/man1")).unwrap();
+    std::fs::write(out.join("rg"), "").unwrap();
+    std::fs::write(out.join("share/man/man1/rg.1"), "").unwrap();
+
+    let build = build_with_outputs(
+      out,
+      &[("bin", out.join("rg")), ("man", out.join("share/man/man1/rg.1"))],
+    );
+
+    let mut path = Vec::new();
+    let mut manpath = Vec::new();
+    collect_search_dirs(&build, &mut path, &mut manpath);
+
+    assert_eq!(path, vec![out.to_path_buf()]);
+    assert_eq!(manpath, vec![out.join("share/man")]);
+  }
+
+  #[test]
+  #[cfg(unix)]
+  fn prepend_paths_keeps_existing_entries() {
+    let dirs = vec![PathBuf::from("/store/a/bin")];
+    assert_eq!(
+      prepend_paths(&dirs, Some(OsString::from("/usr/bin:/bin"))),
+      OsString::from("/store/a/bin:/usr/bin:/bin")
+    );
+    assert_eq!(
+      prepend_paths(&dirs, Some(OsString::new())),
+      OsString::from("/store/a/bin:")
+    );
+    assert_eq!(prepend_paths(&dirs, None), OsString::from("/store/a/bin"));
+  }
+
+  #[test]
+  fn search_dirs_fall_back_to_out_bin() {
+    let temp = tempfile::TempDir::new().unwrap();
+    let out = temp.path();
+    std::fs::create_dir_all(out.join("bin")).unwrap();
+
+    let build = build_with_outputs(out, &[]);
+
+    let mut path = Vec::new();
+    let mut manpath = Vec::new();
+    collect_search_dirs(&build, &mut path, &mut manpath);
+
+    assert_eq!(path, vec![out.join("bin")]);
+    assert!(manpath.is_empty());
+  }
+}
@@ -213,6 +213,7 @@ mod tests {
       inputs: None,
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     }
   }
 
@@ -243,6 +244,9 @@ mod tests {
         args: Some(vec!["update".to_string()]),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })]),
       destroy_actions: vec![],
       check_actions: None,
@@ -532,6 +536,7 @@ mod tests {
       inputs: None,
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let base_v1_hash = base_v1.compute_hash().unwrap();
 
@@ -541,6 +546,7 @@ mod tests {
       inputs: None,
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let base_v2_hash = base_v2.compute_hash().unwrap();
 
@@ -556,6 +562,7 @@ mod tests {
       inputs: Some(BuildInputs::Build(base_v1_hash.clone())),
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let dep_v1_hash = dependent_on_v1.compute_hash().unwrap();
 
@@ -565,6 +572,7 @@ mod tests {
       inputs: Some(BuildInputs::Build(base_v2_hash.clone())),
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let dep_v2_hash = dependent_on_v2.compute_hash().unwrap();
 
@@ -589,6 +597,7 @@ mod tests {
       inputs: None,
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let hash_v1 = build_v1.compute_hash().unwrap();
 
@@ -606,6 +615,7 @@ mod tests {
       inputs: None,
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let hash_v2 = build_v2.compute_hash().unwrap();
 
@@ -635,8 +645,12 @@ mod tests {
         args: Some(vec!["hello".to_string()]),
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       outputs: None,
+      sandbox: None,
     };
     let hash1 = build_action1.compute_hash().unwrap();
 
@@ -649,8 +663,12 @@ mod tests {
         args: Some(vec!["world".to_string()]), // Different argument
         env: None,
         cwd: None,
+        timeout: None,
+        memory_limit: None,
+        cpu_time_limit: None,
       })],
       outputs: None,
+      sandbox: None,
     };
     let hash2 = build_action2.compute_hash().unwrap();
 
@@ -670,6 +688,7 @@ mod tests {
       inputs: Some(BuildInputs::String("foo".to_string())),
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let hash1 = build_input1.compute_hash().unwrap();
 
@@ -679,6 +698,7 @@ mod tests {
       inputs: Some(BuildInputs::String("bar".to_string())),
       create_actions: vec![],
       outputs: None,
+      sandbox: None,
     };
     let hash2 = build_input2.compute_hash().unwrap();
 
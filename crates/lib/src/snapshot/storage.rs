@@ -137,6 +137,19 @@ impl SnapshotStore {
     Ok(snapshot)
   }
 
+  /// Load a snapshot by ID or tag.
+  ///
+  /// IDs take precedence over tags; if several snapshots share a tag, the
+  /// most recent one is loaded.
+  pub fn resolve_snapshot(&self, id_or_tag: &str) -> Result<Snapshot, SnapshotError> {
+    let index = self.load_index()?;
+    let id = index
+      .find(id_or_tag)
+      .map(|m| m.id.clone())
+      .ok_or_else(|| SnapshotError::NotFound(id_or_tag.to_string()))?;
+    self.load_snapshot(&id)
+  }
+
   /// Save a snapshot.
   ///
   /// Writes the snapshot file and updates the index.
@@ -295,6 +308,21 @@ mod tests {
     assert!(matches!(result, Err(SnapshotError::NotFound(_))));
   }
 
+  #[test]
+  fn resolve_snapshot_by_id_or_tag() {
+    let (_temp, store) = temp_store();
+    store.save_snapshot(&make_snapshot("100")).unwrap();
+    store.save_snapshot(&make_snapshot("200")).unwrap();
+    store.set_snapshot_tags("100", vec!["stable".to_string()]).unwrap();
+
+    assert_eq!(store.resolve_snapshot("200").unwrap().id, "200");
+    assert_eq!(store.resolve_snapshot("stable").unwrap().id, "100");
+    assert!(matches!(
+      store.resolve_snapshot("missing"),
+      Err(SnapshotError::NotFound(_))
+    ));
+  }
+
   #[test]
   fn save_updates_index() {
     let (_temp, store) = temp_store();
@@ -173,6 +173,20 @@ impl SnapshotIndex {
     self.snapshots.iter().find(|s| s.id == id)
   }
 
+  /// Find snapshot metadata by ID or tag.
+  ///
+  /// An exact ID match takes precedence. Otherwise the most recent snapshot
+  /// carrying the tag is returned.
+  pub fn find(&self, id_or_tag: &str) -> Option<&SnapshotMetadata> {
+    self.get(id_or_tag).or_else(|| {
+      self
+        .snapshots
+        .iter()
+        .rev()
+        .find(|s| s.tags.iter().any(|t| t == id_or_tag))
+    })
+  }
+
   /// Get the current snapshot metadata.
   pub fn get_current(&self) -> Option<&SnapshotMetadata> {
     self.current.as_ref().and_then(|id| self.get(id))
@@ -274,6 +288,7 @@ mod tests {
         inputs: None,
         create_actions: vec![],
         outputs: None,
+        sandbox: None,
       },
     );
 
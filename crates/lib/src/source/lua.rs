@@ -0,0 +1,245 @@
+//! Lua bindings for `sys.source`.
+//!
+//! `sys.source` imports a file or directory while the config is evaluated and
+//! returns a BuildRef for it, like `sys.build{}`:
+//!
+//! ```lua
+//! local nvim = sys.source { path = "./nvim", exclude = { "*.swp", ".git" } }
+//! -- nvim.outputs.out is the imported directory
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use mlua::prelude::*;
+use serde_json::Value as JsonValue;
+
+use crate::action::Action;
+use crate::build::{BuildDef, BuildRef};
+use crate::inputs::fetch::resolve_path;
+use crate::lua::sources;
+use crate::manifest::Manifest;
+
+use super::{EntryKind, SourceError, SourceFilter, import_source};
+
+/// Options of `sys.source`.
+struct SourceSpec {
+  path: String,
+  name: Option<String>,
+  include: Vec<String>,
+  exclude: Vec<String>,
+  filter: Option<LuaFunction>,
+}
+
+impl SourceSpec {
+  fn from_lua(value: LuaValue) -> LuaResult<Self> {
+    match value {
+      LuaValue::String(path) => Ok(Self {
+        path: path.to_str()?.to_string(),
+        name: None,
+        include: Vec::new(),
+        exclude: Vec::new(),
+        filter: None,
+      }),
+      LuaValue::Table(t) => Ok(Self {
+        path: t
+          .get::<Option<String>>("path")?
+          .ok_or_else(|| LuaError::external("sys.source requires 'path'"))?,
+        name: t.get("name")?,
+        include: t.get::<Option<Vec<String>>>("include")?.unwrap_or_default(),
+        exclude: t.get::<Option<Vec<String>>>("exclude")?.unwrap_or_default(),
+        filter: t.get("filter")?,
+      }),
+      other => Err(LuaError::external(format!(
+        "sys.source expects a path or a table, got {}",
+        other.type_name()
+      ))),
+    }
+  }
+}
+
+/// Directory relative paths are resolved against: `sys.dir`, or the working
+/// directory when no file is being loaded.
+fn config_dir(lua: &Lua) -> LuaResult<PathBuf> {
+  let sys: LuaTable = lua.globals().get("sys")?;
+  match sys.get::<Option<String>>("dir")? {
+    Some(dir) => Ok(PathBuf::from(dir)),
+    None => std::env::current_dir().map_err(LuaError::external),
+  }
+}
+
+/// Name of the copy in the build output: the last component of the path.
+fn default_name(path: &Path) -> String {
+  path
+    .file_name()
+    .map(|name| name.to_string_lossy().to_string())
+    .unwrap_or_else(|| "source".to_string())
+}
+
+/// Register the `sys.source` function on the sys table.
+///
+/// The `sys.source` function:
+/// 1. Resolves the path against `sys.dir`
+/// 2. Hashes the selected entries and imports them into the store
+/// 3. Creates a BuildDef with a single Source action for the imported content
+/// 4. Adds it to the manifest and returns its BuildRef
+pub fn register_sys_source(lua: &Lua, sys_table: &LuaTable, manifest: Rc<RefCell<Manifest>>) -> LuaResult<()> {
+  let source_fn = lua.create_function(move |lua, value: LuaValue| {
+    let spec = SourceSpec::from_lua(value)?;
+    let path = resolve_path(&spec.path, &config_dir(lua)?).map_err(LuaError::external)?;
+    let name = spec.name.unwrap_or_else(|| default_name(&path));
+    if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+      return Err(LuaError::external(format!("invalid sys.source name '{}'", name)));
+    }
+
+    let filter = SourceFilter::new(&spec.include, &spec.exclude).map_err(LuaError::external)?;
+    let mut keep = |rel: &str, kind: EntryKind| -> Result<bool, SourceError> {
+      let Some(f) = &spec.filter else {
+        return Ok(true);
+      };
+      f.call::<LuaValue>((rel, kind.as_str()))
+        .map(|keep| !matches!(keep, LuaValue::Nil | LuaValue::Boolean(false)))
+        .map_err(|e| SourceError::Filter {
+          path: rel.to_string(),
+          message: e.to_string(),
+        })
+    };
+    let imported = import_source(&path, &filter, &mut keep).map_err(LuaError::external)?;
+
+    let build_def = BuildDef {
+      id: None,
+      inputs: None,
+      outputs: Some(BTreeMap::from([(
+        "out".to_string(),
+        JsonValue::String("$${{action:0}}".to_string()),
+      )])),
+      create_actions: vec![Action::Source {
+        name,
+        sha256: imported.sha256,
+      }],
+      sandbox: None,
+    };
+    let build_ref = BuildRef::from_def(&build_def)?;
+
+    manifest
+      .borrow_mut()
+      .builds
+      .entry(build_ref.hash.clone())
+      .or_insert(build_def);
+    sources::record_build(lua, &build_ref.hash);
+
+    lua.pack(build_ref)
+  })?;
+
+  sys_table.set("source", source_fn)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::lua::globals::register_globals;
+  use serial_test::serial;
+  use std::fs;
+  use tempfile::TempDir;
+
+  fn eval_in_store<T>(temp: &TempDir, f: impl FnOnce(&Lua, &Rc<RefCell<Manifest>>) -> T) -> T {
+    temp_env::with_var("SYSLUA_STORE", Some(temp.path().join("store")), || {
+      let lua = crate::lua::runtime::create_lua(false).unwrap();
+      let manifest = Rc::new(RefCell::new(Manifest::default()));
+      register_globals(&lua, manifest.clone()).unwrap();
+      let sys: LuaTable = lua.globals().get("sys").unwrap();
+      sys.set("dir", temp.path().to_string_lossy().to_string()).unwrap();
+      f(&lua, &manifest)
+    })
+  }
+
+  fn source_hash(lua: &Lua, code: &str) -> String {
+    let build: LuaTable = lua.load(code).eval().unwrap();
+    build.get("hash").unwrap()
+  }
+
+  #[test]
+  #[serial]
+  fn hash_depends_only_on_content() {
+    let temp = TempDir::new().unwrap();
+    for dir in ["a", "b"] {
+      fs::create_dir_all(temp.path().join(dir).join("nvim")).unwrap();
+      fs::write(temp.path().join(dir).join("nvim/init.lua"), "return {}").unwrap();
+    }
+
+    eval_in_store(&temp, |lua, manifest| {
+      let a = source_hash(lua, r#"return sys.source("a/nvim")"#);
+      let b = source_hash(lua, r#"return sys.source { path = "b/nvim" }"#);
+      assert_eq!(a, b);
+      assert_eq!(manifest.borrow().builds.len(), 1);
+
+      fs::write(temp.path().join("b/nvim/init.lua"), "return { changed = true }").unwrap();
+      let changed = source_hash(lua, r#"return sys.source { path = "b/nvim" }"#);
+      assert_ne!(a, changed);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn records_source_action() {
+    let temp = TempDir::new().unwrap();
+    fs::write(temp.path().join("gitconfig"), "[user]\n").unwrap();
+
+    eval_in_store(&temp, |lua, manifest| {
+      let hash = source_hash(lua, r#"return sys.source { path = "gitconfig", name = "config" }"#);
+
+      let manifest = manifest.borrow();
+      let (build_hash, def) = manifest.builds.iter().next().unwrap();
+      assert_eq!(build_hash.0, hash);
+      let Action::Source { name, sha256 } = &def.create_actions[0] else {
+        panic!("expected a Source action, got {:?}", def.create_actions);
+      };
+      assert_eq!(name, "config");
+      assert!(crate::source::source_path(sha256).is_file());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn filter_function_skips_entries() {
+    let temp = TempDir::new().unwrap();
+    fs::create_dir_all(temp.path().join("dots/private")).unwrap();
+    fs::write(temp.path().join("dots/bashrc"), "").unwrap();
+    fs::write(temp.path().join("dots/private/token"), "secret").unwrap();
+
+    eval_in_store(&temp, |lua, manifest| {
+      lua
+        .load(
+          r#"return sys.source {
+            path = "dots",
+            filter = function(path, kind) return not (kind == "directory" and path == "private") end,
+          }"#,
+        )
+        .exec()
+        .unwrap();
+
+      let manifest = manifest.borrow();
+      let def = manifest.builds.values().next().unwrap();
+      let Action::Source { sha256, .. } = &def.create_actions[0] else {
+        panic!("expected a Source action");
+      };
+      let imported = crate::source::source_path(sha256);
+      assert!(imported.join("bashrc").exists());
+      assert!(!imported.join("private").exists());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn missing_path_is_an_error() {
+    let temp = TempDir::new().unwrap();
+
+    eval_in_store(&temp, |lua, _| {
+      let err = lua.load(r#"sys.source("missing")"#).exec().unwrap_err();
+      assert!(err.to_string().contains("missing"), "unexpected error: {}", err);
+    });
+  }
+}
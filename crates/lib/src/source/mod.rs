@@ -0,0 +1,571 @@
+//! Local files and directories imported into the store by `sys.source`.
+//!
+//! A source is hashed while the config is evaluated and copied into a
+//! content-addressed directory of the store:
+//!
+//! ```text
+//! <store>/sources/<sha256>    # The imported file or directory
+//! ```
+//!
+//! The hash covers the content of every file, whether it is executable, the
+//! directory structure and symlink targets, but not timestamps or ownership,
+//! so the same content always imports to the same path. Imported files get
+//! mode `0644` (`0755` if executable) and directories `0755`.
+//!
+//! `sys.source` registers a build whose only action,
+//! [`Action::Source`](crate::action::Action::Source), copies the import into
+//! the build's output. The build hash depends on nothing but the content and
+//! name of the source.
+
+pub mod lua;
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tracing::{debug, info};
+use walkdir::WalkDir;
+
+use crate::platform::paths::store_dir;
+use crate::util::hash::hash_file;
+
+/// Directory in the store holding imported sources.
+pub const SOURCES_DIR: &str = "sources";
+
+/// Errors that can occur while importing a source.
+#[derive(Debug, Error)]
+pub enum SourceError {
+  /// An include or exclude pattern is not a valid glob.
+  #[error("invalid glob '{pattern}': {message}")]
+  InvalidGlob { pattern: String, message: String },
+
+  /// The source could not be read.
+  #[error("failed to read '{path}': {message}")]
+  Read { path: PathBuf, message: String },
+
+  /// The source could not be copied into the store.
+  #[error("failed to import '{path}' into the store: {source}")]
+  Import {
+    path: PathBuf,
+    #[source]
+    source: io::Error,
+  },
+
+  /// The source changed while it was imported.
+  #[error("'{0}' changed while it was imported into the store")]
+  Changed(PathBuf),
+
+  /// The `filter` function failed.
+  #[error("source filter failed for '{path}': {message}")]
+  Filter { path: String, message: String },
+}
+
+/// The kind of an entry of a source directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+  File,
+  Directory,
+  Symlink,
+}
+
+impl EntryKind {
+  pub fn as_str(self) -> &'static str {
+    match self {
+      EntryKind::File => "file",
+      EntryKind::Directory => "directory",
+      EntryKind::Symlink => "symlink",
+    }
+  }
+}
+
+/// Include and exclude globs selecting the entries of a source directory.
+///
+/// Patterns containing a `/` match the path relative to the source root;
+/// patterns without one match the name of an entry at any depth, like
+/// `.gitignore`. An excluded directory is skipped with everything in it. With
+/// include patterns, only matching files and links (and everything inside
+/// matching directories) are imported.
+#[derive(Debug, Default)]
+pub struct SourceFilter {
+  include: Option<Patterns>,
+  exclude: Option<Patterns>,
+}
+
+#[derive(Debug)]
+struct Patterns {
+  paths: GlobSet,
+  names: GlobSet,
+}
+
+impl Patterns {
+  fn new(patterns: &[String]) -> Result<Option<Self>, SourceError> {
+    if patterns.is_empty() {
+      return Ok(None);
+    }
+
+    let mut paths = GlobSetBuilder::new();
+    let mut names = GlobSetBuilder::new();
+    for pattern in patterns {
+      let glob = compile_glob(pattern.trim_start_matches('/')).map_err(|message| SourceError::InvalidGlob {
+        pattern: pattern.clone(),
+        message,
+      })?;
+      if pattern.contains('/') {
+        paths.add(glob);
+      } else {
+        names.add(glob);
+      }
+    }
+
+    let build = |set: GlobSetBuilder| {
+      set.build().map_err(|e| SourceError::InvalidGlob {
+        pattern: patterns.join(", "),
+        message: e.to_string(),
+      })
+    };
+    Ok(Some(Self {
+      paths: build(paths)?,
+      names: build(names)?,
+    }))
+  }
+
+  fn matches(&self, rel: &str) -> bool {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    self.paths.is_match(rel) || self.names.is_match(name)
+  }
+}
+
+fn compile_glob(pattern: &str) -> Result<Glob, String> {
+  GlobBuilder::new(pattern)
+    .literal_separator(true)
+    .build()
+    .map_err(|e| e.to_string())
+}
+
+impl SourceFilter {
+  /// Create a filter from include and exclude globs.
+  pub fn new(include: &[String], exclude: &[String]) -> Result<Self, SourceError> {
+    Ok(Self {
+      include: Patterns::new(include)?,
+      exclude: Patterns::new(exclude)?,
+    })
+  }
+
+  fn is_excluded(&self, rel: &str) -> bool {
+    self.exclude.as_ref().is_some_and(|p| p.matches(rel))
+  }
+
+  fn is_included(&self, rel: &str) -> bool {
+    self.include.as_ref().is_none_or(|p| p.matches(rel))
+  }
+}
+
+/// An entry of a source directory, relative to its root with `/` separators.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+  rel: String,
+  kind: EntryKind,
+}
+
+/// A source imported into the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSource {
+  /// Hash of the source's content.
+  pub sha256: String,
+  /// Where the source is in the store.
+  pub path: PathBuf,
+}
+
+/// Path of an imported source in the store.
+pub fn source_path(sha256: &str) -> PathBuf {
+  store_dir().join(SOURCES_DIR).join(sha256)
+}
+
+/// Hash `path` and import it into the store, unless it already is.
+///
+/// # Arguments
+///
+/// * `path` - The file or directory to import
+/// * `filter` - Globs selecting the entries of a directory
+/// * `keep` - Called with the relative path and kind of every entry that
+///   passed `filter`; returning `false` leaves it (and, for a directory,
+///   everything in it) out
+///
+/// # Returns
+///
+/// The hash of the imported content and its path in the store.
+pub fn import_source(
+  path: &Path,
+  filter: &SourceFilter,
+  keep: &mut dyn FnMut(&str, EntryKind) -> Result<bool, SourceError>,
+) -> Result<ImportedSource, SourceError> {
+  let entries = collect_entries(path, filter, keep)?;
+  let sha256 = hash_source(path, entries.as_deref())?;
+  let dest = source_path(&sha256);
+
+  if fs::symlink_metadata(&dest).is_ok() {
+    debug!(path = %path.display(), sha256, "source already in store");
+    return Ok(ImportedSource { sha256, path: dest });
+  }
+
+  info!(path = %path.display(), sha256, "importing source into store");
+  let import_err = |source| SourceError::Import {
+    path: path.to_path_buf(),
+    source,
+  };
+  let sources_dir = store_dir().join(SOURCES_DIR);
+  fs::create_dir_all(&sources_dir).map_err(import_err)?;
+  let staging = tempfile::Builder::new()
+    .prefix(".import-")
+    .tempdir_in(&sources_dir)
+    .map_err(import_err)?;
+  let copy = staging.path().join("source");
+  copy_source(path, &copy, entries.as_deref()).map_err(import_err)?;
+
+  // The copy is what ends up in the store, so it must hash the same
+  if hash_source(&copy, entries.as_deref())? != sha256 {
+    return Err(SourceError::Changed(path.to_path_buf()));
+  }
+
+  if let Err(e) = fs::rename(&copy, &dest) {
+    // Imported by someone else in the meantime
+    if fs::symlink_metadata(&dest).is_err() {
+      return Err(import_err(e));
+    }
+  }
+  Ok(ImportedSource { sha256, path: dest })
+}
+
+/// The entries of a source directory that pass the filters, sorted by path.
+///
+/// Returns `None` if `root` is a file.
+fn collect_entries(
+  root: &Path,
+  filter: &SourceFilter,
+  keep: &mut dyn FnMut(&str, EntryKind) -> Result<bool, SourceError>,
+) -> Result<Option<Vec<Entry>>, SourceError> {
+  let read_err = |path: &Path, message: String| SourceError::Read {
+    path: path.to_path_buf(),
+    message,
+  };
+
+  let metadata = fs::metadata(root).map_err(|e| read_err(root, e.to_string()))?;
+  if !metadata.is_dir() {
+    return Ok(None);
+  }
+
+  let mut entries = Vec::new();
+  // Directories inside an included directory are included as a whole
+  let mut included_dirs: Vec<String> = Vec::new();
+  let mut walker = WalkDir::new(root).min_depth(1).sort_by_file_name().into_iter();
+  while let Some(entry) = walker.next() {
+    let entry = entry.map_err(|e| read_err(root, e.to_string()))?;
+    let rel = relative_path(root, entry.path());
+    let file_type = entry.file_type();
+    let kind = if file_type.is_symlink() {
+      EntryKind::Symlink
+    } else if file_type.is_dir() {
+      EntryKind::Directory
+    } else if file_type.is_file() {
+      EntryKind::File
+    } else {
+      // Sockets, devices and the like can't be imported
+      continue;
+    };
+
+    if filter.is_excluded(&rel) || !keep(&rel, kind)? {
+      if kind == EntryKind::Directory {
+        walker.skip_current_dir();
+      }
+      continue;
+    }
+
+    let parent_included = included_dirs
+      .iter()
+      .any(|dir| rel.len() > dir.len() && rel.starts_with(dir.as_str()) && rel.as_bytes()[dir.len()] == b'/');
+    let included = parent_included || filter.is_included(&rel);
+    if kind == EntryKind::Directory && included {
+      included_dirs.push(rel.clone());
+    }
+    if included || kind == EntryKind::Directory {
+      entries.push(Entry { rel, kind });
+    }
+  }
+
+  if filter.include.is_some() {
+    // Drop directories that only exist to reach entries that were filtered out
+    let needed: Vec<bool> = entries
+      .iter()
+      .map(|entry| {
+        entry.kind != EntryKind::Directory
+          || included_dirs.contains(&entry.rel)
+          || entries.iter().any(|other| {
+            other.kind != EntryKind::Directory
+              && other.rel.starts_with(entry.rel.as_str())
+              && other.rel.as_bytes().get(entry.rel.len()) == Some(&b'/')
+          })
+      })
+      .collect();
+    let mut needed = needed.into_iter();
+    entries.retain(|_| needed.next().unwrap_or(true));
+  }
+
+  Ok(Some(entries))
+}
+
+fn relative_path(root: &Path, path: &Path) -> String {
+  let rel = path.strip_prefix(root).unwrap_or(path);
+  rel
+    .components()
+    .map(|c| c.as_os_str().to_string_lossy())
+    .collect::<Vec<_>>()
+    .join("/")
+}
+
+/// Hash a source: a single file if `entries` is `None`, else the listed
+/// entries of the directory `root`.
+fn hash_source(root: &Path, entries: Option<&[Entry]>) -> Result<String, SourceError> {
+  let read_err = |path: &Path, message: String| SourceError::Read {
+    path: path.to_path_buf(),
+    message,
+  };
+  let file_line = |path: &Path| -> Result<String, SourceError> {
+    let content = hash_file(path).map_err(|e| read_err(path, e.to_string()))?;
+    let executable = is_executable(path).map_err(|e| read_err(path, e.to_string()))?;
+    Ok(format!("{}:{}", if executable { "x" } else { "-" }, content.0))
+  };
+
+  let mut hasher = Sha256::new();
+  match entries {
+    None => hasher.update(format!("F::{}\n", file_line(root)?)),
+    Some(entries) => {
+      hasher.update("D:\n");
+      for entry in entries {
+        let path = root.join(&entry.rel);
+        let line = match entry.kind {
+          EntryKind::File => format!("F:{}:{}", entry.rel, file_line(&path)?),
+          EntryKind::Directory => format!("D:{}", entry.rel),
+          EntryKind::Symlink => {
+            let target = fs::read_link(&path).map_err(|e| read_err(&path, e.to_string()))?;
+            format!("L:{}:{}", entry.rel, target.to_string_lossy())
+          }
+        };
+        hasher.update(line);
+        hasher.update("\n");
+      }
+    }
+  }
+  Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Copy a source to `dest` with normalized permissions.
+fn copy_source(root: &Path, dest: &Path, entries: Option<&[Entry]>) -> io::Result<()> {
+  let Some(entries) = entries else {
+    return copy_file(root, dest);
+  };
+
+  fs::create_dir(dest)?;
+  set_mode(dest, 0o755)?;
+  for entry in entries {
+    let (src, dest) = (root.join(&entry.rel), dest.join(&entry.rel));
+    match entry.kind {
+      EntryKind::File => copy_file(&src, &dest)?,
+      EntryKind::Directory => {
+        fs::create_dir(&dest)?;
+        set_mode(&dest, 0o755)?;
+      }
+      EntryKind::Symlink => copy_link(&src, &dest)?,
+    }
+  }
+  Ok(())
+}
+
+fn copy_file(src: &Path, dest: &Path) -> io::Result<()> {
+  let executable = is_executable(src)?;
+  fs::copy(src, dest)?;
+  set_mode(dest, if executable { 0o755 } else { 0o644 })
+}
+
+#[cfg(unix)]
+fn copy_link(src: &Path, dest: &Path) -> io::Result<()> {
+  std::os::unix::fs::symlink(fs::read_link(src)?, dest)
+}
+
+#[cfg(windows)]
+fn copy_link(src: &Path, dest: &Path) -> io::Result<()> {
+  let target = fs::read_link(src)?;
+  if fs::metadata(src).is_ok_and(|m| m.is_dir()) {
+    std::os::windows::fs::symlink_dir(target, dest)
+  } else {
+    std::os::windows::fs::symlink_file(target, dest)
+  }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> io::Result<bool> {
+  use std::os::unix::fs::PermissionsExt;
+  Ok(fs::metadata(path)?.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> io::Result<bool> {
+  Ok(false)
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+  use std::os::unix::fs::PermissionsExt;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> io::Result<()> {
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  fn keep_all(_: &str, _: EntryKind) -> Result<bool, SourceError> {
+    Ok(true)
+  }
+
+  fn with_temp_store<T>(f: impl FnOnce(&TempDir) -> T) -> T {
+    let temp = TempDir::new().unwrap();
+    temp_env::with_var("SYSLUA_STORE", Some(temp.path().join("store")), || f(&temp))
+  }
+
+  fn write(root: &Path, rel: &str, content: &str) {
+    let path = root.join(rel);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+  }
+
+  fn rel_paths(root: &Path, filter: &SourceFilter) -> Vec<String> {
+    collect_entries(root, filter, &mut keep_all)
+      .unwrap()
+      .unwrap()
+      .into_iter()
+      .map(|e| e.rel)
+      .collect()
+  }
+
+  #[test]
+  #[serial]
+  fn same_content_imports_to_same_path() {
+    with_temp_store(|temp| {
+      let (a, b) = (temp.path().join("a"), temp.path().join("b"));
+      for root in [&a, &b] {
+        write(root, "init.lua", "return {}\n");
+        write(root, "lua/plugins.lua", "-- plugins\n");
+      }
+
+      let first = import_source(&a, &SourceFilter::default(), &mut keep_all).unwrap();
+      let second = import_source(&b, &SourceFilter::default(), &mut keep_all).unwrap();
+      assert_eq!(first, second);
+      assert_eq!(
+        fs::read_to_string(first.path.join("lua/plugins.lua")).unwrap(),
+        "-- plugins\n"
+      );
+
+      write(&b, "init.lua", "return { changed = true }\n");
+      let changed = import_source(&b, &SourceFilter::default(), &mut keep_all).unwrap();
+      assert_ne!(changed.sha256, first.sha256);
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn imports_single_file() {
+    with_temp_store(|temp| {
+      write(temp.path(), "gitconfig", "[user]\n");
+
+      let imported = import_source(&temp.path().join("gitconfig"), &SourceFilter::default(), &mut keep_all).unwrap();
+
+      assert!(imported.path.is_file());
+      assert_eq!(fs::read_to_string(&imported.path).unwrap(), "[user]\n");
+    });
+  }
+
+  #[cfg(unix)]
+  #[test]
+  #[serial]
+  fn executable_bit_is_part_of_the_hash() {
+    use std::os::unix::fs::PermissionsExt;
+
+    with_temp_store(|temp| {
+      let script = temp.path().join("run.sh");
+      fs::write(&script, "#!/bin/sh\n").unwrap();
+      fs::set_permissions(&script, fs::Permissions::from_mode(0o644)).unwrap();
+      let plain = import_source(&script, &SourceFilter::default(), &mut keep_all).unwrap();
+
+      fs::set_permissions(&script, fs::Permissions::from_mode(0o700)).unwrap();
+      let executable = import_source(&script, &SourceFilter::default(), &mut keep_all).unwrap();
+
+      assert_ne!(plain.sha256, executable.sha256);
+      let mode = fs::metadata(&executable.path).unwrap().permissions().mode();
+      assert_eq!(mode & 0o777, 0o755);
+    });
+  }
+
+  #[test]
+  fn exclude_globs_skip_files_and_directories() {
+    let temp = TempDir::new().unwrap();
+    write(temp.path(), "init.lua", "");
+    write(temp.path(), "init.lua.swp", "");
+    write(temp.path(), "lua/.git/HEAD", "");
+    write(temp.path(), "lua/plugins.lua", "");
+    write(temp.path(), "cache/data", "");
+
+    let filter = SourceFilter::new(&[], &["*.swp".to_string(), ".git".to_string(), "/cache".to_string()]).unwrap();
+
+    assert_eq!(
+      rel_paths(temp.path(), &filter),
+      vec!["init.lua", "lua", "lua/plugins.lua"]
+    );
+  }
+
+  #[test]
+  fn include_globs_keep_matching_entries_and_their_directories() {
+    let temp = TempDir::new().unwrap();
+    write(temp.path(), "README.md", "");
+    write(temp.path(), "lua/plugins.lua", "");
+    write(temp.path(), "lua/notes.txt", "");
+    write(temp.path(), "docs/guide/index.md", "");
+    write(temp.path(), "snippets/rust.json", "");
+
+    let filter = SourceFilter::new(&["*.lua".to_string(), "snippets".to_string()], &[]).unwrap();
+
+    assert_eq!(
+      rel_paths(temp.path(), &filter),
+      vec!["lua", "lua/plugins.lua", "snippets", "snippets/rust.json"]
+    );
+  }
+
+  #[test]
+  fn keep_callback_prunes_directories() {
+    let temp = TempDir::new().unwrap();
+    write(temp.path(), "a/one", "");
+    write(temp.path(), "b/two", "");
+
+    let mut keep = |rel: &str, kind: EntryKind| Ok(!(rel == "b" && kind == EntryKind::Directory));
+    let entries = collect_entries(temp.path(), &SourceFilter::default(), &mut keep)
+      .unwrap()
+      .unwrap();
+
+    let rels: Vec<_> = entries.iter().map(|e| e.rel.as_str()).collect();
+    assert_eq!(rels, vec!["a", "a/one"]);
+  }
+
+  #[test]
+  fn invalid_glob_is_an_error() {
+    let result = SourceFilter::new(&["[".to_string()], &[]);
+    assert!(matches!(result, Err(SourceError::InvalidGlob { .. })));
+  }
+}
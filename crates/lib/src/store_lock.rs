@@ -159,7 +159,7 @@ impl StoreLock {
 }
 
 #[cfg(unix)]
-fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
+pub(crate) fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
   use rustix::fs::{FlockOperation, flock};
   use std::os::unix::io::AsFd;
 
@@ -172,7 +172,7 @@ fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
 }
 
 #[cfg(windows)]
-fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
+pub(crate) fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
   use std::os::windows::io::AsRawHandle;
   use windows_sys::Win32::Foundation::HANDLE;
   use windows_sys::Win32::Storage::FileSystem::{LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY, LockFileEx};
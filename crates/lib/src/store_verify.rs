@@ -0,0 +1,453 @@
+//! Store integrity checks for `sys store verify`.
+//!
+//! Every entry under `<store>/build/` is re-hashed and compared with the
+//! `output_hash` recorded in its [`BuildMarker`]. Reused builds are already
+//! checked this way just before they are used; this module checks the whole
+//! store at once and can optionally rebuild corrupted outputs that a snapshot
+//! still references.
+//!
+//! [`BuildMarker`]: crate::build::execute::BuildMarker
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::Serialize;
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::build::execute::{compute_build_hash, read_build_marker, realize_build, rebuild_build};
+use crate::execute::ExecutionDag;
+use crate::execute::types::{BuildResult, ExecuteConfig, ExecuteError};
+use crate::manifest::Manifest;
+use crate::platform::paths::store_dir;
+use crate::snapshot::SnapshotStore;
+use crate::store_lock::{LockMode, StoreLock, StoreLockError};
+use crate::util::hash::ObjectHash;
+
+/// Errors that can occur while verifying the store.
+#[derive(Debug, Error)]
+pub enum VerifyError {
+  #[error("failed to read store directory: {0}")]
+  ReadStore(#[from] std::io::Error),
+
+  #[error("failed to list snapshots: {0}")]
+  ListSnapshots(String),
+
+  #[error("failed to acquire store lock: {0}")]
+  Lock(#[from] StoreLockError),
+
+  #[error("verification task failed: {0}")]
+  Task(String),
+}
+
+/// Outcome of checking one store entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryStatus {
+  /// Outputs match the marker's hash.
+  Ok,
+  /// Outputs differ from the marker's hash, or could not be hashed.
+  Corrupted,
+  /// No completion marker; the build never finished.
+  Incomplete,
+  /// Marker predates output hashes, so there is nothing to compare against.
+  Unverified,
+  /// Not a build directory, or its marker is unreadable.
+  Unknown,
+}
+
+/// A checked store entry.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyEntry {
+  /// Path of the entry in the store.
+  pub path: PathBuf,
+
+  /// Build hash, taken from the directory name.
+  pub hash: ObjectHash,
+
+  pub status: EntryStatus,
+
+  /// Whether any snapshot references this build.
+  pub referenced: bool,
+
+  /// Hash recorded in the marker.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub expected: Option<String>,
+
+  /// Hash of the outputs as found.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub actual: Option<String>,
+
+  /// Why the entry could not be checked.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub message: Option<String>,
+}
+
+/// A corrupted build that could not be re-realized.
+#[derive(Debug, Clone, Serialize)]
+pub struct RepairFailure {
+  pub hash: ObjectHash,
+  pub error: String,
+}
+
+/// Number of entries in each [`EntryStatus`].
+#[derive(Debug, Default, Serialize)]
+pub struct VerifySummary {
+  pub scanned: usize,
+  pub ok: usize,
+  pub corrupted: usize,
+  pub incomplete: usize,
+  pub unverified: usize,
+  pub unknown: usize,
+}
+
+/// The result of verifying the store.
+#[derive(Debug, Default, Serialize)]
+pub struct VerifyReport {
+  pub summary: VerifySummary,
+
+  /// Every entry that is not [`EntryStatus::Ok`], sorted by path.
+  pub problems: Vec<VerifyEntry>,
+
+  /// Corrupted builds that were re-realized.
+  pub repaired: Vec<ObjectHash>,
+
+  /// Corrupted builds whose re-realization failed.
+  pub repair_failed: Vec<RepairFailure>,
+}
+
+impl VerifyReport {
+  /// Whether the store is free of corrupted builds that were not repaired.
+  pub fn is_clean(&self) -> bool {
+    self
+      .problems
+      .iter()
+      .filter(|e| e.status == EntryStatus::Corrupted)
+      .all(|e| self.repaired.contains(&e.hash))
+  }
+}
+
+/// Options for [`verify_store`].
+#[derive(Debug, Clone, Default)]
+pub struct VerifyOptions {
+  /// Re-realize corrupted builds that a snapshot references.
+  pub repair: bool,
+
+  /// Controls how many entries are hashed at once, and repair execution.
+  pub execute: ExecuteConfig,
+}
+
+/// Verify every build in the store against its completion marker.
+///
+/// Entries are hashed in parallel while a shared store lock is held. With
+/// [`VerifyOptions::repair`], corrupted builds referenced by a snapshot are
+/// rebuilt from that snapshot's manifest; unreferenced ones are left for
+/// `sys gc`.
+///
+/// # Arguments
+///
+/// * `options` - Repair and parallelism options
+///
+/// # Returns
+///
+/// A report of every entry that is not intact, plus any repairs made.
+pub async fn verify_store(options: &VerifyOptions) -> Result<VerifyReport, VerifyError> {
+  let _lock = StoreLock::acquire(LockMode::Shared, "verify")?;
+
+  let manifests = load_snapshot_manifests()?;
+  let build_dir = store_dir().join("build");
+
+  let mut paths = Vec::new();
+  if build_dir.exists() {
+    for entry in std::fs::read_dir(&build_dir)? {
+      paths.push(entry?.path());
+    }
+  }
+  info!(entries = paths.len(), store = %build_dir.display(), "verifying store");
+
+  let semaphore = Arc::new(Semaphore::new(options.execute.parallelism.max(1)));
+  let mut tasks = JoinSet::new();
+  for path in paths {
+    let semaphore = semaphore.clone();
+    tasks.spawn(async move {
+      let _permit = semaphore.acquire_owned().await;
+      tokio::task::spawn_blocking(move || check_entry(path)).await
+    });
+  }
+
+  let mut report = VerifyReport::default();
+  while let Some(joined) = tasks.join_next().await {
+    let mut entry = joined
+      .map_err(|e| VerifyError::Task(e.to_string()))?
+      .map_err(|e| VerifyError::Task(e.to_string()))?;
+    entry.referenced = manifests.values().any(|m| m.builds.contains_key(&entry.hash));
+
+    let summary = &mut report.summary;
+    summary.scanned += 1;
+    match entry.status {
+      EntryStatus::Ok => summary.ok += 1,
+      EntryStatus::Corrupted => summary.corrupted += 1,
+      EntryStatus::Incomplete => summary.incomplete += 1,
+      EntryStatus::Unverified => summary.unverified += 1,
+      EntryStatus::Unknown => summary.unknown += 1,
+    }
+    if entry.status != EntryStatus::Ok {
+      report.problems.push(entry);
+    }
+  }
+  report.problems.sort_by(|a, b| a.path.cmp(&b.path));
+
+  if options.repair {
+    let targets: Vec<ObjectHash> = report
+      .problems
+      .iter()
+      .filter(|e| e.status == EntryStatus::Corrupted && e.referenced)
+      .map(|e| e.hash.clone())
+      .collect();
+
+    for hash in targets {
+      let manifest = manifests
+        .values()
+        .find(|m| m.builds.contains_key(&hash))
+        .expect("referenced builds come from a snapshot");
+      match repair_build(&hash, manifest, &options.execute).await {
+        Ok(()) => {
+          info!(hash = %hash.0, "repaired corrupted build");
+          report.repaired.push(hash);
+        }
+        Err(e) => {
+          warn!(hash = %hash.0, error = %e, "failed to repair build");
+          report.repair_failed.push(RepairFailure {
+            hash,
+            error: e.to_string(),
+          });
+        }
+      }
+    }
+  }
+
+  info!(
+    scanned = report.summary.scanned,
+    corrupted = report.summary.corrupted,
+    incomplete = report.summary.incomplete,
+    unknown = report.summary.unknown,
+    repaired = report.repaired.len(),
+    "store verification complete"
+  );
+
+  Ok(report)
+}
+
+/// Load the manifest of every readable snapshot, keyed by snapshot id.
+fn load_snapshot_manifests() -> Result<BTreeMap<String, Manifest>, VerifyError> {
+  let store = SnapshotStore::default_store();
+  let snapshots = store.list().map_err(|e| VerifyError::ListSnapshots(e.to_string()))?;
+
+  let mut manifests = BTreeMap::new();
+  for meta in snapshots {
+    match store.load_snapshot(&meta.id) {
+      Ok(snapshot) => {
+        manifests.insert(meta.id, snapshot.manifest);
+      }
+      Err(e) => {
+        warn!(id = %meta.id, error = %e, "skipping unreadable snapshot");
+      }
+    }
+  }
+  Ok(manifests)
+}
+
+/// Classify a single entry of `<store>/build/`.
+fn check_entry(path: PathBuf) -> VerifyEntry {
+  let name = path
+    .file_name()
+    .map(|n| n.to_string_lossy().to_string())
+    .unwrap_or_default();
+  let mut entry = VerifyEntry {
+    path: path.clone(),
+    hash: ObjectHash(name.clone()),
+    status: EntryStatus::Unknown,
+    referenced: false,
+    expected: None,
+    actual: None,
+    message: None,
+  };
+
+  if !is_hash_name(&name) {
+    entry.message = Some("not a build hash".to_string());
+    return entry;
+  }
+  if !path.is_dir() {
+    entry.message = Some("not a directory".to_string());
+    return entry;
+  }
+
+  let marker = match read_build_marker(&path) {
+    Ok(Some(marker)) => marker,
+    Ok(None) => {
+      entry.status = EntryStatus::Incomplete;
+      return entry;
+    }
+    Err(e) => {
+      entry.message = Some(e.to_string());
+      return entry;
+    }
+  };
+
+  let Some(expected) = marker.output_hash else {
+    entry.status = EntryStatus::Unverified;
+    return entry;
+  };
+
+  entry.status = match compute_build_hash(&path) {
+    Ok(actual) if actual.0 == expected => EntryStatus::Ok,
+    Ok(actual) => {
+      entry.actual = Some(actual.0);
+      EntryStatus::Corrupted
+    }
+    Err(e) => {
+      entry.message = Some(e.to_string());
+      EntryStatus::Corrupted
+    }
+  };
+  if entry.status == EntryStatus::Corrupted {
+    entry.expected = Some(expected);
+  }
+
+  debug!(path = %path.display(), status = ?entry.status, "checked build");
+  entry
+}
+
+fn is_hash_name(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+}
+
+/// Rebuild `hash` from `manifest`, realizing its dependencies from the cache.
+async fn repair_build(hash: &ObjectHash, manifest: &Manifest, config: &ExecuteConfig) -> Result<(), ExecuteError> {
+  let dag = ExecutionDag::from_manifest(manifest)?;
+  let closure = dag.build_closure(std::slice::from_ref(hash));
+
+  let mut completed: HashMap<ObjectHash, BuildResult> = HashMap::new();
+  for dep in dag.topological_builds()? {
+    if !closure.contains(&dep) {
+      continue;
+    }
+    let def = manifest
+      .builds
+      .get(&dep)
+      .ok_or_else(|| ExecuteError::BuildNotFound(dep.clone()))?;
+
+    let result = if &dep == hash {
+      rebuild_build(&dep, def, &completed, manifest, config).await?
+    } else {
+      realize_build(&dep, def, &completed, manifest, config).await?
+    };
+    completed.insert(dep, result);
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use serial_test::serial;
+  use tempfile::TempDir;
+
+  use super::*;
+  use crate::build::execute::BUILD_COMPLETE_MARKER;
+
+  fn write_build(dir: &Path, name: &str, content: &str, with_hash: bool) -> PathBuf {
+    let path = dir.join(name);
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(path.join("out.txt"), content).unwrap();
+    let marker = if with_hash {
+      let hash = compute_build_hash(&path).unwrap();
+      format!(r#"{{"version":1,"status":"complete","output_hash":"{}"}}"#, hash.0)
+    } else {
+      r#"{"version":1,"status":"complete"}"#.to_string()
+    };
+    std::fs::write(path.join(BUILD_COMPLETE_MARKER), marker).unwrap();
+    path
+  }
+
+  fn with_temp_store<T>(f: impl FnOnce(&Path) -> T) -> T {
+    let temp = TempDir::new().unwrap();
+    let store = temp.path().join("store");
+    let snapshots = temp.path().join("snapshots");
+    temp_env::with_vars(
+      [
+        ("SYSLUA_STORE", Some(store.to_str().unwrap())),
+        ("SYSLUA_SNAPSHOTS", Some(snapshots.to_str().unwrap())),
+      ],
+      || f(&store.join("build")),
+    )
+  }
+
+  fn run(options: &VerifyOptions) -> VerifyReport {
+    tokio::runtime::Builder::new_current_thread()
+      .enable_all()
+      .build()
+      .unwrap()
+      .block_on(verify_store(options))
+      .unwrap()
+  }
+
+  #[test]
+  #[serial]
+  fn classifies_store_entries() {
+    with_temp_store(|build_dir| {
+      write_build(build_dir, "aa11", "fine", true);
+      let corrupted = write_build(build_dir, "bb22", "original", true);
+      std::fs::write(corrupted.join("out.txt"), "tampered").unwrap();
+      write_build(build_dir, "cc33", "legacy", false);
+      std::fs::create_dir_all(build_dir.join("dd44")).unwrap();
+      std::fs::write(build_dir.join("stray.txt"), "?").unwrap();
+
+      let report = run(&VerifyOptions::default());
+
+      assert_eq!(report.summary.scanned, 5);
+      assert_eq!(report.summary.ok, 1);
+      let statuses: Vec<(&str, EntryStatus)> = report.problems.iter().map(|e| (e.hash.0.as_str(), e.status)).collect();
+      assert_eq!(
+        statuses,
+        vec![
+          ("bb22", EntryStatus::Corrupted),
+          ("cc33", EntryStatus::Unverified),
+          ("dd44", EntryStatus::Incomplete),
+          ("stray.txt", EntryStatus::Unknown),
+        ]
+      );
+
+      let entry = &report.problems[0];
+      assert_eq!(entry.path, corrupted);
+      assert!(entry.expected.is_some());
+      assert_ne!(entry.expected, entry.actual);
+      assert!(!entry.referenced);
+      assert!(!report.is_clean());
+    });
+  }
+
+  #[test]
+  #[serial]
+  fn repair_skips_unreferenced_builds() {
+    with_temp_store(|build_dir| {
+      let corrupted = write_build(build_dir, "bb22", "original", true);
+      std::fs::write(corrupted.join("out.txt"), "tampered").unwrap();
+
+      let report = run(&VerifyOptions {
+        repair: true,
+        ..Default::default()
+      });
+
+      assert_eq!(report.summary.corrupted, 1);
+      assert!(report.repaired.is_empty());
+      assert!(report.repair_failed.is_empty());
+      assert!(corrupted.join("out.txt").exists());
+    });
+  }
+}
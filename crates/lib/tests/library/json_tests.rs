@@ -0,0 +1,54 @@
+//! Tests for the syslua.json compatibility module.
+
+use mlua::prelude::*;
+
+use super::common::create_test_runtime;
+
+#[test]
+fn encodes_like_sys_json() -> LuaResult<()> {
+  let (lua, _) = create_test_runtime()?;
+
+  let result: String = lua
+    .load(r#"return require('syslua.json').encode({ b = { 1, 2 }, a = 'x' })"#)
+    .eval()?;
+  assert_eq!(result, r#"{"a":"x","b":[1,2]}"#);
+  Ok(())
+}
+
+#[test]
+fn empty_and_sparse_tables_encode_as_objects() -> LuaResult<()> {
+  let (lua, _) = create_test_runtime()?;
+
+  // The pure-Lua encoder this replaced wrote `[]` and raised errors for these
+  let result: String = lua
+    .load(
+      r#"
+        local json = require('syslua.json')
+        return json.encode({ empty = {}, sparse = { [1] = 'a', [3] = 'c' }, mixed = { 1, x = 2 } })
+      "#,
+    )
+    .eval()?;
+  assert_eq!(
+    result,
+    r#"{"empty":{},"mixed":{"1":1,"x":2},"sparse":{"1":"a","3":"c"}}"#
+  );
+  Ok(())
+}
+
+#[test]
+fn circular_tables_raise_catchable_errors() -> LuaResult<()> {
+  let (lua, _) = create_test_runtime()?;
+
+  lua
+    .load(
+      r#"
+        local json = require('syslua.json')
+        local t = {}
+        t[1] = t
+        local ok, err = pcall(json.encode, t)
+        assert(not ok and tostring(err):find('circular reference'), tostring(err))
+      "#,
+    )
+    .exec()?;
+  Ok(())
+}
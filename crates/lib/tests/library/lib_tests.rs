@@ -1,6 +1,7 @@
 //! Tests for syslua.lib.* functions.
 
 use mlua::prelude::*;
+use syslua_lib::action::Action;
 
 use super::common::create_test_runtime;
 
@@ -73,4 +74,96 @@ mod fetch_url {
     assert_eq!(m.builds.len(), 1, "fetch_url should create exactly one build");
     Ok(())
   }
+
+  #[test]
+  fn passes_mirrors_and_sri_hash_to_action() -> LuaResult<()> {
+    let (lua, manifest) = create_test_runtime()?;
+
+    lua
+      .load(
+        r#"
+            local syslua = require('syslua')
+            syslua.lib.fetch_url({
+                url = 'https://example.com/file.tar.gz',
+                hash = 'sha512-abc',
+                mirrors = { 'https://mirror.example.com/file.tar.gz' },
+                retries = 4,
+            })
+        "#,
+      )
+      .exec()?;
+
+    let m = manifest.borrow();
+    let build = m.builds.values().next().expect("fetch_url should create a build");
+    match &build.create_actions[..] {
+      [Action::FetchUrl(opts)] => {
+        assert_eq!(opts.hash, "sha512-abc");
+        assert_eq!(opts.mirrors, vec!["https://mirror.example.com/file.tar.gz".to_string()]);
+        assert_eq!(opts.retries, Some(4));
+        assert_eq!(opts.retry_delay, None);
+      }
+      actions => panic!("expected a single fetch_url action, got {:?}", actions),
+    }
+    Ok(())
+  }
+}
+
+mod fetch_git {
+  use super::*;
+
+  #[test]
+  fn records_fetch_git_action() -> LuaResult<()> {
+    let (lua, manifest) = create_test_runtime()?;
+
+    lua
+      .load(
+        r#"
+            local syslua = require('syslua')
+            syslua.lib.fetch_git({
+                url = 'https://example.com/repo.git',
+                rev = '0123456789abcdef0123456789abcdef01234567',
+                sha256 = string.rep('ab', 32),
+            })
+        "#,
+      )
+      .exec()?;
+
+    let m = manifest.borrow();
+    let build = m.builds.values().next().expect("fetch_git should create a build");
+    assert_eq!(
+      build.create_actions,
+      vec![Action::FetchGit {
+        url: "https://example.com/repo.git".to_string(),
+        rev: "0123456789abcdef0123456789abcdef01234567".to_string(),
+        sha256: "ab".repeat(32),
+      }]
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn rejects_branch_names() -> LuaResult<()> {
+    let (lua, _) = create_test_runtime()?;
+
+    let result = lua
+      .load(
+        r#"
+            local syslua = require('syslua')
+            syslua.lib.fetch_git({
+                url = 'https://example.com/repo.git',
+                rev = 'main',
+                sha256 = string.rep('ab', 32),
+            })
+        "#,
+      )
+      .exec();
+
+    let err_msg = result.unwrap_err().to_string();
+    assert!(
+      err_msg.contains("must be a full commit hash"),
+      "Expected error about the revision, got: {}",
+      err_msg
+    );
+    Ok(())
+  }
 }
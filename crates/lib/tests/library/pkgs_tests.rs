@@ -1,6 +1,8 @@
 //! Tests for syslua.pkgs.* packages.
 
 use mlua::prelude::*;
+use syslua_lib::action::Action;
+use syslua_lib::action::actions::unpack::ArchiveFormat;
 
 use super::common::create_test_runtime;
 
@@ -22,6 +24,32 @@ mod extract {
 
     Ok(())
   }
+
+  #[test]
+  fn uses_builtin_unpack() -> LuaResult<()> {
+    let (lua, manifest) = create_test_runtime()?;
+
+    lua
+      .load(
+        r#"
+            local lib = require('syslua.lib')
+            lib.extract({ archive = '/tmp/rg.tar.gz', format = 'tar.gz', strip_components = 1 })
+        "#,
+      )
+      .exec()?;
+
+    let m = manifest.borrow();
+    let build = m.builds.values().next().expect("extract should create a build");
+    assert_eq!(
+      build.create_actions,
+      vec![Action::Unpack {
+        archive: "/tmp/rg.tar.gz".to_string(),
+        format: Some(ArchiveFormat::TarGz),
+        strip_components: 1,
+      }]
+    );
+    Ok(())
+  }
 }
 
 mod cli_category {
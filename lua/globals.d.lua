@@ -5,17 +5,53 @@
 ---@field args? string[] Optional: arguments to pass to the binary
 ---@field env? table<string,string> Optional: environment variables
 ---@field cwd? string Optional: working directory
+---@field timeout? integer Optional: seconds before the command and its process group are killed
+---@field memory_limit? integer Optional: maximum address space in bytes (Unix only)
+---@field cpu_time_limit? integer Optional: maximum CPU time in seconds (Unix only)
+
+---@class FetchUrlOpts
+---@field url string URL to download
+---@field hash string Expected hash: SHA-256 hex or SRI (`sha256-…`, `sha512-…`)
+---@field mirrors? string[] Optional: URLs tried in order when `url` fails
+---@field retries? integer Optional: retries of each URL after a transient failure (default 2)
+---@field retry_delay? integer Optional: seconds before the first retry, doubled for each further one (default 1)
+
+---@class FetchGitOpts
+---@field url string Repository URL
+---@field rev string Full commit hash
+---@field sha256 string Hash of the checked-out tree, without `.git`
+
+---@class UnpackOpts
+---@field format? "tar" | "tar.gz" | "tar.xz" | "zip" Optional: archive format, detected from the archive if omitted
+---@field strip_components? integer Optional: leading path components removed from every entry
+
+---@class FileModeOpts
+---@field mode? integer | string Optional: Unix permissions, e.g. 420 or "644"
 
 ---@class BuildCtx
 ---@field out string returns the store path placeholder
 ---@field action_count number returns the number of actions performed so far
----@field fetch_url fun(self: BuildCtx, url: string, sha256: string): string Fetches a URL and returns the store path
+---@field fetch_url fun(self: BuildCtx, opts: string | FetchUrlOpts, hash?: string): string Fetches a URL and returns the store path
+---@field fetch_git fun(self: BuildCtx, opts: string | FetchGitOpts, rev?: string, sha256?: string): string Checks out a git commit into the output directory and returns its path
+---@field unpack fun(self: BuildCtx, archive: string, opts?: UnpackOpts): string Extracts an archive into the output directory and returns its path
 ---@field exec fun(self: BuildCtx, opts: string | ExecOpts, args?: string[]): string Performs a command during application, returns stdout
+---@field write_file fun(self: BuildCtx, path: string, content: string, opts?: FileModeOpts): string Writes a file and returns its path
+---@field copy fun(self: BuildCtx, src: string, dest: string): string Copies a file or directory tree and returns `dest`
+---@field symlink fun(self: BuildCtx, target: string, link: string): string Creates (or replaces) a symlink at `link` and returns it
+---@field mkdir fun(self: BuildCtx, path: string, opts?: FileModeOpts): string Creates a directory and its parents and returns its path
+---@field chmod fun(self: BuildCtx, path: string, mode: integer | string): string Changes the mode of a path and returns it
+---@field remove fun(self: BuildCtx, path: string): string Removes a file, symlink or directory tree if it exists
 
 ---@class BindCtx
 ---@field out string returns the store path placeholder
 ---@field action_count number returns the number of actions performed so far
 ---@field exec fun(self: BindCtx, opts: string | ExecOpts, args?: string[]): string Performs a command during application, returns stdout
+---@field write_file fun(self: BindCtx, path: string, content: string, opts?: FileModeOpts): string Writes a file and returns its path
+---@field copy fun(self: BindCtx, src: string, dest: string): string Copies a file or directory tree and returns `dest`
+---@field symlink fun(self: BindCtx, target: string, link: string): string Creates (or replaces) a symlink at `link` and returns it
+---@field mkdir fun(self: BindCtx, path: string, opts?: FileModeOpts): string Creates a directory and its parents and returns its path
+---@field chmod fun(self: BindCtx, path: string, mode: integer | string): string Changes the mode of a path and returns it
+---@field remove fun(self: BindCtx, path: string): string Removes a file, symlink or directory tree if it exists
 
 ---@class BuildRef
 ---@field id? string Build id
@@ -26,8 +62,19 @@
 ---@class BuildSpec
 ---@field id? string Required: build id, must be unique
 ---@field inputs? table|fun(): table Optional: input data
+---@field sandbox? boolean|BuildSandbox Optional: false runs exec actions unconfined (Linux builds are sandboxed by default)
 ---@field create fun(inputs: table, ctx: BuildCtx): table Required: build logic, returns outputs
 
+---@class SourceOpts
+---@field path string Required: file or directory, relative to `sys.dir`
+---@field name? string Optional: name of the copy in the build output, defaults to the last path component
+---@field include? string[] Optional: globs of entries to import; everything else is left out
+---@field exclude? string[] Optional: globs of entries to leave out
+---@field filter? fun(path: string, kind: "file" | "directory" | "symlink"): boolean Optional: return false to leave an entry out
+
+---@class BuildSandbox
+---@field paths string[] Absolute host paths visible read-only in the sandbox, replacing the defaults
+
 ---@class BindRef
 ---@field id? string Binding id
 ---@field inputs? table All inputs to the binding
@@ -58,6 +105,21 @@
 ---@field split fun(path: string): table<string> Splits the path into its components
 ---@field canonicalize fun(path: string): string Returns the canonical filesystem path (resolves symlinks, Windows 8.3 names). Throws if path doesn't exist.
 
+---@class CodecEncodeOpts
+---@field pretty? boolean Optional: indent the output for reading
+
+---@class JsonCodec
+---@field encode fun(value: any, opts?: CodecEncodeOpts): string Encodes a value as JSON with sorted keys
+---@field decode fun(str: string): any Decodes a JSON document
+
+---@class TomlCodec
+---@field encode fun(tbl: table, opts?: CodecEncodeOpts): string Encodes a table as TOML with sorted keys
+---@field decode fun(str: string): table Decodes a TOML document; dates and times become strings
+
+---@class YamlCodec
+---@field encode fun(value: any): string Encodes a value as YAML with sorted keys
+---@field decode fun(str: string): any Decodes a single YAML document
+
 ---@alias Platform "x86_64-windows" | "aarch64-windows" | "x86_64-linux" | "aarch64-linux" | "i386-linux" | "x86_64-darwin" | "aarch64-darwin"
 ---@alias Os "windows" | "linux" | "darwin"
 ---@alias Arch "x86_64" | "aarch64" | "i386"
@@ -69,7 +131,11 @@
 ---@field arch Arch System architecture
 ---@field is_elevated boolean Whether the process has elevated privileges
 ---@field path PathHelpers File path utilities
+---@field json JsonCodec JSON encoding and decoding
+---@field toml TomlCodec TOML encoding and decoding
+---@field yaml YamlCodec YAML encoding and decoding
 ---@field build fun(spec: BuildSpec): BuildRef Creates a build within the store
+---@field source fun(opts: string | SourceOpts): BuildRef Imports a local file or directory into the store by content
 ---@field bind fun(spec: BindSpec): BindRef Creates a binding to the active system
 ---@field getenv fun(name: string): string Returns a placeholder that resolves to the environment variable at execution time
 ---@field register_build_ctx_method fun(name: string, fn: fun(ctx: BuildCtx, ...: any): any) Registers a custom method on BuildCtx
@@ -5,7 +5,7 @@ local f = require('syslua.interpolation')
 local M = {}
 
 ---@class syslua.environment.files.FileOptions
----@field source? syslua.Option<string> Path to the source file or directory
+---@field source? syslua.Option<string> Path to the source file or directory, or `sys.source(path).outputs.out` to track it by content
 ---@field content? syslua.MergeableOption<string> Content to write to the target file (if source is not provided)
 ---@field mutable? syslua.Option<boolean> Whether the target should be mutable (default: false)
 
@@ -46,37 +46,9 @@ M.setup = function(provided_opts)
         },
         create = function(inputs, ctx)
           if inputs.source then
-            if sys.os == 'windows' then
-              ctx:exec({
-                bin = 'powershell.exe',
-                args = {
-                  '-NoProfile',
-                  '-Command',
-                  f('Copy-Item -Recurse -Path "{{source}}" -Destination "{{target}}"', inputs),
-                },
-              })
-            else
-              ctx:exec({
-                bin = '/bin/sh',
-                args = { '-c', f('cp -r "{{source}}" "{{target}}"', inputs) },
-              })
-            end
+            ctx:copy(inputs.source, inputs.target)
           else
-            if sys.os == 'windows' then
-              ctx:exec({
-                bin = 'powershell.exe',
-                args = {
-                  '-NoProfile',
-                  '-Command',
-                  f('Set-Content -Path "{{target}}" -Value "{{content}}"', inputs),
-                },
-              })
-            else
-              ctx:exec({
-                bin = '/bin/sh',
-                args = { '-c', f('echo "{{content}}" > "{{target}}"', inputs) },
-              })
-            end
+            ctx:write_file(inputs.target, inputs.content)
           end
 
           return {
@@ -84,22 +56,17 @@ M.setup = function(provided_opts)
           }
         end,
         destroy = function(outputs, ctx)
-          if sys.os == 'windows' then
-            ctx:exec({
-              bin = 'powershell.exe',
-              args = {
-                '-NoProfile',
-                '-Command',
-                f('Remove-Item -Path "{{target}}" -Recurse -Force -ErrorAction SilentlyContinue', outputs),
-              },
-            })
-          else
-            ctx:exec({ bin = '/bin/sh', args = { '-c', f('rm -rf "{{target}}"', outputs) } })
-          end
+          ctx:remove(outputs.target)
         end,
       })
     else
       local basename = sys.path.basename(target)
+      -- The copy reads a host source, which the build has to declare; the
+      -- outputs of other builds are readable without it
+      local sandbox = nil
+      if file_opts.source and sys.path.is_absolute(file_opts.source) then
+        sandbox = { paths = { file_opts.source } }
+      end
       local build = sys.build({
         id = basename .. '-file',
         inputs = {
@@ -107,49 +74,13 @@ M.setup = function(provided_opts)
           content = file_opts.content,
           mutable = file_opts.mutable,
         },
+        sandbox = sandbox,
         create = function(inputs, ctx)
           local out_path = f('{{out}}/{{basename}}', { out = ctx.out, basename = basename })
           if inputs.source then
-            if sys.os == 'windows' then
-              ctx:exec({
-                bin = 'powershell.exe',
-                args = {
-                  '-NoProfile',
-                  '-Command',
-                  f('Copy-Item -Recurse -Path "{{source}}" -Destination "{{out_path}}"', {
-                    source = inputs.source,
-                    out_path = out_path,
-                  }),
-                },
-              })
-            else
-              ctx:exec({
-                bin = '/bin/sh',
-                args = { '-c', f('cp -r "{{source}}" "{{out_path}}"', { source = inputs.source, out_path = out_path }) },
-              })
-            end
+            ctx:copy(inputs.source, out_path)
           else
-            if sys.os == 'windows' then
-              ctx:exec({
-                bin = 'powershell.exe',
-                args = {
-                  '-NoProfile',
-                  '-Command',
-                  f('Set-Content -Path "{{out_path}}" -Value "{{content}}"', {
-                    out_path = out_path,
-                    content = inputs.content,
-                  }),
-                },
-              })
-            else
-              ctx:exec({
-                bin = '/bin/sh',
-                args = {
-                  '-c',
-                  f('echo "{{content}}" > "{{out_path}}"', { content = inputs.content, out_path = out_path }),
-                },
-              })
-            end
+            ctx:write_file(out_path, inputs.content)
           end
 
           return {
@@ -164,46 +95,14 @@ M.setup = function(provided_opts)
           target = target,
         },
         create = function(inputs, ctx)
-          local build_path = inputs.build.outputs.path
-          if sys.os == 'windows' then
-            ctx:exec({
-              bin = 'powershell.exe',
-              args = {
-                '-NoProfile',
-                '-Command',
-                f('New-Item -ItemType SymbolicLink -Path "{{target}}" -Target "{{build_path}}"', {
-                  target = inputs.target,
-                  build_path = build_path,
-                }),
-              },
-            })
-          else
-            ctx:exec({
-              bin = '/bin/sh',
-              args = {
-                '-c',
-                f('ln -s "{{build_path}}" "{{target}}"', { build_path = build_path, target = inputs.target }),
-              },
-            })
-          end
+          ctx:symlink(inputs.build.outputs.path, inputs.target)
 
           return {
             link = inputs.target,
           }
         end,
         destroy = function(outputs, ctx)
-          if sys.os == 'windows' then
-            ctx:exec({
-              bin = 'powershell.exe',
-              args = {
-                '-NoProfile',
-                '-Command',
-                f('Remove-Item -Path "{{link}}" -Recurse -Force', outputs),
-              },
-            })
-          else
-            ctx:exec({ bin = '/bin/sh', args = { '-c', f('rm -rf "{{link}}"', outputs) } })
-          end
+          ctx:remove(outputs.link)
         end,
       })
     end
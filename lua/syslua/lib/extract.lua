@@ -1,23 +1,8 @@
-local PATHS = {
-  windows = {
-    powershell = 'powershell.exe',
-    tar = 'tar.exe',
-  },
-  darwin = {
-    tar = '/usr/bin/tar',
-    ditto = '/usr/bin/ditto',
-  },
-  linux = {
-    tar = '/usr/bin/tar',
-    unzip = '/usr/bin/unzip',
-  },
-}
-
----@alias syslua.lib.extract.ArchiveFormat "zip" | "tar.gz" | "tar.xz"
+---@alias syslua.lib.extract.ArchiveFormat "zip" | "tar" | "tar.gz" | "tar.xz"
 
 ---@class syslua.lib.extract.Options
 ---@field archive string Path to archive file (typically from lib.fetch_url)
----@field format syslua.lib.extract.ArchiveFormat Archive format
+---@field format? syslua.lib.extract.ArchiveFormat Archive format, detected from the archive if omitted
 ---@field strip_components? number Number of leading path components to strip
 
 ---@param opts syslua.lib.extract.Options
@@ -26,9 +11,6 @@ local function extract(opts)
   if not opts.archive then
     error("extract requires an 'archive' option")
   end
-  if not opts.format then
-    error("extract requires a 'format' option")
-  end
 
   return sys.build({
     inputs = {
@@ -37,41 +19,10 @@ local function extract(opts)
       strip_components = opts.strip_components or 0,
     },
     create = function(inputs, ctx)
-      local paths = PATHS[sys.os]
-      local archive = inputs.archive
-      local format = inputs.format
-      local strip = inputs.strip_components
-
-      if format == 'zip' then
-        if sys.os == 'windows' then
-          ctx:exec({
-            bin = paths.powershell,
-            args = {
-              '-NoProfile',
-              '-Command',
-              string.format('Expand-Archive -Path "%s" -DestinationPath "%s" -Force', archive, ctx.out),
-            },
-          })
-        elseif sys.os == 'darwin' then
-          ctx:exec({ bin = paths.ditto, args = { '-xk', archive, ctx.out } })
-        else
-          ctx:exec({ bin = paths.unzip, args = { '-q', archive, '-d', ctx.out } })
-        end
-      elseif format == 'tar.gz' then
-        local args = { '-xzf', archive, '-C', ctx.out }
-        if strip > 0 then
-          table.insert(args, '--strip-components=' .. strip)
-        end
-        ctx:exec({ bin = paths.tar, args = args })
-      elseif format == 'tar.xz' then
-        local args = { '-xJf', archive, '-C', ctx.out }
-        if strip > 0 then
-          table.insert(args, '--strip-components=' .. strip)
-        end
-        ctx:exec({ bin = paths.tar, args = args })
-      else
-        error('Unsupported archive format: ' .. tostring(format))
-      end
+      ctx:unpack(inputs.archive, {
+        format = inputs.format,
+        strip_components = inputs.strip_components,
+      })
 
       return {
         out = ctx.out,
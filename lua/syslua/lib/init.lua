@@ -29,26 +29,74 @@ end
 
 ---@class syslua.lib.fetch_url.Options
 ---@field url string
----@field sha256 string
+---@field sha256? string SHA-256 hex (or an SRI hash)
+---@field hash? string SRI hash (`sha256-…`, `sha512-…`), instead of `sha256`
+---@field mirrors? string[] URLs tried in order when `url` fails
+---@field retries? integer Retries of each URL after a transient failure
+---@field retry_delay? integer Seconds before the first retry, doubled for each further one
 
----Fetches a file from a URL and verifies its SHA256 checksum.
+---Fetches a file from a URL and verifies its checksum.
 ---@param opts syslua.lib.fetch_url.Options
 ---@return BuildRef
 function M.fetch_url(opts)
   if not opts.url then
     error("fetch_url requires a 'url' option")
   end
+  if not opts.sha256 and not opts.hash then
+    error("fetch_url requires a 'sha256' or 'hash' option")
+  end
+
+  return sys.build({
+    inputs = {
+      url = opts.url,
+      sha256 = opts.sha256,
+      hash = opts.hash,
+      mirrors = opts.mirrors,
+      retries = opts.retries,
+      retry_delay = opts.retry_delay,
+    },
+    create = function(inputs, ctx)
+      local result = ctx:fetch_url({
+        url = inputs.url,
+        hash = inputs.hash or inputs.sha256,
+        mirrors = inputs.mirrors,
+        retries = inputs.retries,
+        retry_delay = inputs.retry_delay,
+      })
+      return {
+        out = result,
+      }
+    end,
+  })
+end
+
+---@class syslua.lib.fetch_git.Options
+---@field url string
+---@field rev string Full commit hash
+---@field sha256 string Hash of the checked-out tree
+
+---Checks out a git commit and verifies the hash of its tree.
+---@param opts syslua.lib.fetch_git.Options
+---@return BuildRef
+function M.fetch_git(opts)
+  if not opts.url then
+    error("fetch_git requires a 'url' option")
+  end
+  if not opts.rev then
+    error("fetch_git requires a 'rev' option")
+  end
   if not opts.sha256 then
-    error("fetch_url requires a 'sha256' option")
+    error("fetch_git requires a 'sha256' option")
   end
 
   return sys.build({
     inputs = {
       url = opts.url,
+      rev = opts.rev,
       sha256 = opts.sha256,
     },
     create = function(inputs, ctx)
-      local result = ctx:fetch_url(inputs.url, inputs.sha256)
+      local result = ctx:fetch_git(inputs.url, inputs.rev, inputs.sha256)
       return {
         out = result,
       }